@@ -0,0 +1,314 @@
+use std::io::{ErrorKind, Read};
+
+use super::{decoder, lookup, CharReader, Decoder, BUFFER_SIZE};
+
+/// A [CharReader] that decodes a byte stream of a XML entity in the encoding detected as
+/// [XML 1.0 Appendix F](https://www.w3.org/TR/xml/#sec-guessing).
+///
+/// The encoding family is detected from the byte order mark, or from the first bytes `<?xml` if there is no BOM: UTF-8
+/// and the other encodings compatible with ASCII, UTF-16 or UTF-32 in big or little endian. Then the `encoding`
+/// pseudo-attribute of the XML or text declaration, read in that family, selects the decoder of the rest of the
+/// stream. A stream that has neither a BOM nor a declaration is also detected as UTF-16 or UTF-32 by the zero bytes
+/// of its first characters, since U+0000 is not allowed in XML, and as UTF-8 otherwise. The BOM itself is decoded as
+/// U+FEFF, which the parser skips.
+///
+/// It's an error if the declared encoding doesn't belong to the detected family, such as `UTF-16` declared in a
+/// stream that starts with `<?xml` in single bytes, or if the encoding is not supported.
+///
+/// ```
+/// use xenolith::io::{CharReader, DecodingReader};
+///
+/// let mut reader = DecodingReader::new(&b"<?xml version='1.0' encoding='ISO-8859-1'?><a>\xE9</a>"[..]);
+/// let mut buffer = ['\0'; 64];
+/// let length = reader.read(&mut buffer).unwrap();
+/// assert!(buffer[..length].iter().collect::<String>().ends_with("<a>\u{E9}</a>"));
+/// assert_eq!("ISO-8859-1", reader.encoding().unwrap());
+/// ```
+///
+pub struct DecodingReader<R: Read> {
+  reader: R,
+  bytes: Vec<u8>,
+  /// The offset of the first byte in `bytes` from the beginning of the stream.
+  offset: u64,
+  eof: bool,
+  /// The decoder of the encoding, which is `None` until the encoding is detected.
+  decoder: Option<Box<dyn Decoder>>,
+  /// The numbers of bytes of the characters last read, unless the encoding is UTF-8.
+  lengths: Vec<usize>,
+  /// The number of bytes decoded to no character yet, such as an escape sequence, which are counted in the next one.
+  pending: usize,
+}
+
+/// The encoding detected from the first bytes, and the layout of the ASCII characters in it.
+struct Detected {
+  encoding: &'static str,
+  /// The number of bytes of a code unit: 1, 2 or 4.
+  width: usize,
+  big_endian: bool,
+  /// The length of the byte order mark.
+  bom: usize,
+}
+
+/// The maximum number of characters read to find the end of the XML declaration.
+const DECLARATION_LIMIT: usize = 512;
+
+impl<R: Read> DecodingReader<R> {
+  /// Creates a reader that detects the encoding from the stream.
+  pub fn new(reader: R) -> DecodingReader<R> {
+    let bytes = Vec::with_capacity(BUFFER_SIZE);
+    DecodingReader { reader, bytes, offset: 0, eof: false, decoder: None, lengths: Vec::new(), pending: 0 }
+  }
+
+  /// Creates a reader that decodes the stream in the `encoding` specified externally, such as by the `charset` of
+  /// the content type, instead of detecting it. An error is returned if the encoding is not supported.
+  pub fn with_encoding(reader: R, encoding: &str) -> crate::Result<DecodingReader<R>> {
+    let mut reader = Self::new(reader);
+    reader.decoder = Some(supported(encoding)?);
+    Ok(reader)
+  }
+
+  /// Creates a reader that decodes the stream with the `decoder`, such as one configured with a mapping other than
+  /// the default of its encoding, instead of detecting the encoding.
+  pub fn with_decoder(reader: R, decoder: Box<dyn Decoder>) -> DecodingReader<R> {
+    let mut reader = Self::new(reader);
+    reader.decoder = Some(decoder);
+    reader
+  }
+
+  /// The name of the encoding of the stream. The encoding is detected if it hasn't been yet, which reads the first
+  /// bytes of the stream.
+  pub fn encoding(&mut self) -> crate::Result<&str> {
+    if self.decoder.is_none() {
+      self.detect()?;
+    }
+    Ok(self.decoder.as_ref().expect("the decoder must have been detected").encoding())
+  }
+
+  fn detect(&mut self) -> crate::Result<()> {
+    while self.bytes.len() < 4 && !self.eof {
+      self.fill()?;
+    }
+    let detected = detect_family(&self.bytes)?;
+    let declared = loop {
+      match declared_encoding(&self.bytes, &detected) {
+        Some(declared) => break declared,
+        None if self.eof => break None,
+        None => self.fill()?,
+      }
+    };
+    let encoding = match declared {
+      None => detected.encoding.to_string(),
+      Some(declared) => {
+        let (width, big_endian) = layout(&declared);
+        // an alias is compared by the name of its encoding, and an unknown one fails as unsupported below
+        let name = lookup(&declared).map(|encoding| encoding.name().to_string());
+        let conflicts = width != detected.width
+          || big_endian.map(|big_endian| big_endian != detected.big_endian).unwrap_or(false)
+          || (detected.bom > 0 && width == 1 && name.is_some_and(|name| !name.eq_ignore_ascii_case(detected.encoding)));
+        if conflicts {
+          let message = format!("the encoding {} is declared in a stream detected as {}", declared, detected.encoding);
+          return Err(std::io::Error::new(ErrorKind::InvalidData, message).into());
+        }
+        // the byte order of UTF-16 or UTF-32 is the detected one
+        if width > 1 {
+          detected.encoding.to_string()
+        } else {
+          declared
+        }
+      }
+    };
+    self.decoder = Some(supported(&encoding)?);
+    Ok(())
+  }
+
+  fn fill(&mut self) -> std::io::Result<()> {
+    let mut buf = [0u8; BUFFER_SIZE];
+    let len = loop {
+      match self.reader.read(&mut buf) {
+        Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+        result => break result?,
+      }
+    };
+    if len == 0 {
+      self.eof = true;
+    } else {
+      self.bytes.extend_from_slice(&buf[..len]);
+    }
+    Ok(())
+  }
+
+  /// Reads characters one at a time to keep the number of bytes of each of them in an encoding other than UTF-8.
+  fn read_each(&mut self, buffer: &mut [char]) -> crate::Result<usize> {
+    let mut consumed = 0;
+    let mut written = 0;
+    let result = loop {
+      if written == buffer.len() {
+        break Ok(());
+      }
+      let decoder = self.decoder.as_mut().expect("the decoder must have been detected");
+      match decoder.decode(&self.bytes[consumed..], &mut buffer[written..written + 1], self.eof) {
+        Ok((0, 0)) if written > 0 || self.eof => break Ok(()),
+        Ok((0, 0)) => {
+          self.bytes.drain(..consumed);
+          self.offset += consumed as u64;
+          consumed = 0;
+          self.fill()?;
+        }
+        Ok((read, n)) => {
+          consumed += read;
+          self.pending += read;
+          if n > 0 {
+            self.lengths.push(self.pending);
+            self.pending = 0;
+            written += 1;
+          }
+        }
+        Err(_) => {
+          let offset = self.offset + consumed as u64;
+          let message = format!("malformed {} sequence at byte offset {}", decoder.encoding(), offset);
+          break Err(std::io::Error::new(ErrorKind::InvalidData, message));
+        }
+      }
+    };
+    self.bytes.drain(..consumed);
+    self.offset += consumed as u64;
+    match result {
+      Err(err) if written == 0 => Err(err.into()),
+      _ => Ok(written),
+    }
+  }
+}
+
+impl<R: Read> CharReader for DecodingReader<R> {
+  fn read(&mut self, buffer: &mut [char]) -> crate::Result<usize> {
+    if self.decoder.is_none() {
+      self.detect()?;
+    }
+    self.lengths.clear();
+    if self.decoder.as_ref().expect("the decoder must have been detected").encoding() != "UTF-8" {
+      return self.read_each(buffer);
+    }
+    loop {
+      let decoder = self.decoder.as_mut().expect("the decoder must have been detected");
+      match decoder.decode(&self.bytes, buffer, self.eof) {
+        Ok((read, written)) => {
+          self.bytes.drain(..read);
+          self.offset += read as u64;
+          if written > 0 || (read == 0 && self.eof) {
+            return Ok(written);
+          } else if read == 0 {
+            self.fill()?;
+          }
+        }
+        Err(_) => {
+          let message = format!("malformed {} sequence at byte offset {}", decoder.encoding(), self.offset);
+          return Err(std::io::Error::new(ErrorKind::InvalidData, message).into());
+        }
+      }
+    }
+  }
+
+  fn byte_lengths(&self) -> Option<&[usize]> {
+    match self.decoder.as_ref() {
+      Some(decoder) if decoder.encoding() != "UTF-8" => Some(&self.lengths),
+      _ => None,
+    }
+  }
+}
+
+fn supported(encoding: &str) -> crate::Result<Box<dyn Decoder>> {
+  decoder(encoding).ok_or_else(|| {
+    let message = format!("unsupported encoding: {}", encoding);
+    std::io::Error::new(ErrorKind::Unsupported, message).into()
+  })
+}
+
+/// Detects the encoding family from the first 4 bytes as the table of
+/// [Appendix F.1](https://www.w3.org/TR/xml/#sec-guessing-no-ext-info).
+fn detect_family(bytes: &[u8]) -> crate::Result<Detected> {
+  let detected = |encoding, width, big_endian, bom| Ok(Detected { encoding, width, big_endian, bom });
+  let mut head = [0xFFu8; 4];
+  head[..bytes.len().min(4)].copy_from_slice(&bytes[..bytes.len().min(4)]);
+  let unsupported = match head {
+    [0x00, 0x00, 0xFF, 0xFE] | [0xFE, 0xFF, 0x00, 0x00] | [0x00, 0x00, 0x3C, 0x00] | [0x00, 0x3C, 0x00, 0x00] => {
+      "UCS-4 in the unusual octet order"
+    }
+    [0x00, 0x00, 0xFE, 0xFF] => return detected("UTF-32BE", 4, true, 4),
+    [0xFF, 0xFE, 0x00, 0x00] => return detected("UTF-32LE", 4, false, 4),
+    [0xFE, 0xFF, ..] => return detected("UTF-16BE", 2, true, 2),
+    [0xFF, 0xFE, ..] => return detected("UTF-16LE", 2, false, 2),
+    [0xEF, 0xBB, 0xBF, _] => return detected("UTF-8", 1, true, 3),
+    // U+0000 is not allowed in XML, so the zero bytes tell the width of an ASCII character other than `<?`
+    [0x00, 0x00, 0x00, a] if a != 0 => return detected("UTF-32BE", 4, true, 0),
+    [a, 0x00, 0x00, 0x00] if a != 0 => return detected("UTF-32LE", 4, false, 0),
+    [0x00, a, 0x00, b] if a != 0 && b != 0 => return detected("UTF-16BE", 2, true, 0),
+    [a, 0x00, b, 0x00] if a != 0 && b != 0 => return detected("UTF-16LE", 2, false, 0),
+    [0x4C, 0x6F, 0xA7, 0x94] => "EBCDIC",
+    _ => return detected("UTF-8", 1, true, 0),
+  };
+  let message = format!("unsupported encoding: {}", unsupported);
+  Err(std::io::Error::new(ErrorKind::Unsupported, message).into())
+}
+
+/// Returns the value of the `encoding` pseudo-attribute of the XML declaration at the beginning of `bytes`, whose
+/// characters are read as ASCII in the `detected` layout. `None` is returned if more bytes are needed to find the end
+/// of the declaration, and `Some(None)` if there is no declaration or it has no encoding.
+fn declared_encoding(bytes: &[u8], detected: &Detected) -> Option<Option<String>> {
+  let units = bytes[detected.bom..].chunks(detected.width);
+  let mut declaration = String::new();
+  for unit in units.take(DECLARATION_LIMIT) {
+    if unit.len() < detected.width {
+      return None;
+    }
+    let (ch, zeros) = if detected.big_endian { unit.split_last() } else { unit.split_first() }.unwrap();
+    if !ch.is_ascii() || zeros.iter().any(|b| *b != 0) {
+      return Some(None);
+    }
+    declaration.push(*ch as char);
+    let started = "<?xml ".starts_with(&declaration[..declaration.len().min(5)])
+      && (declaration.len() <= 5 || declaration.as_bytes()[5].is_ascii_whitespace());
+    if !started {
+      return Some(None);
+    }
+    if declaration.ends_with("?>") {
+      return Some(encoding_pseudo_attribute(&declaration));
+    }
+  }
+  if declaration.len() < DECLARATION_LIMIT {
+    None
+  } else {
+    Some(None)
+  }
+}
+
+/// Returns the value of `encoding="..."` in the declaration.
+fn encoding_pseudo_attribute(declaration: &str) -> Option<String> {
+  let (_, rest) = declaration.split_once("encoding")?;
+  let rest = rest.trim_start_matches(|ch: char| ch.is_ascii_whitespace()).strip_prefix('=')?;
+  let rest = rest.trim_start_matches(|ch: char| ch.is_ascii_whitespace());
+  let quote = rest.chars().next().filter(|ch| *ch == '"' || *ch == '\'')?;
+  let (value, _) = rest[1..].split_once(quote)?;
+  Some(value.to_string())
+}
+
+/// Returns the width of the code unit of the `encoding` and its byte order if the name specifies it. An alias in the
+/// registry is resolved to the name of its encoding.
+fn layout(encoding: &str) -> (usize, Option<bool>) {
+  let encoding = lookup(encoding).map(|encoding| encoding.name().to_string()).unwrap_or(encoding.to_string());
+  let encoding = encoding.to_ascii_uppercase();
+  let width = if ["UTF-16", "UTF16", "UCS-2", "ISO-10646-UCS-2", "UNICODE"].iter().any(|n| encoding.starts_with(n)) {
+    2
+  } else if ["UTF-32", "UTF32", "UCS-4", "ISO-10646-UCS-4"].iter().any(|n| encoding.starts_with(n)) {
+    4
+  } else {
+    return (1, None);
+  };
+  let big_endian = if encoding.ends_with("BE") {
+    Some(true)
+  } else if encoding.ends_with("LE") {
+    Some(false)
+  } else {
+    None
+  };
+  (width, big_endian)
+}
@@ -0,0 +1,119 @@
+use std::io::ErrorKind;
+
+use crate::io::{CharReader, DecodingReader};
+use crate::Error;
+
+/// Reads all characters a few at a time, so that the characters split by the buffer boundary are tested.
+fn read_all(bytes: &[u8]) -> crate::Result<(String, String)> {
+  let mut reader = DecodingReader::new(bytes);
+  let mut buffer = ['\0'; 3];
+  let mut text = String::new();
+  loop {
+    let length = reader.read(&mut buffer)?;
+    if length == 0 {
+      break;
+    }
+    text.extend(&buffer[..length]);
+  }
+  Ok((reader.encoding()?.to_string(), text))
+}
+
+fn error_kind(result: crate::Result<(String, String)>) -> ErrorKind {
+  match result {
+    Err(Error::IO(err)) => err.kind(),
+    result => panic!("unexpected result: {:?}", result.map_err(|err| err.to_string())),
+  }
+}
+
+#[test]
+fn detect_encoding() {
+  let utf8 = "<?xml version='1.0'?><a>\u{3042}\u{1F600}</a>";
+  assert_eq!(("UTF-8".to_string(), utf8.to_string()), read_all(utf8.as_bytes()).unwrap());
+  let bom = "\u{FEFF}<a>\u{3042}</a>";
+  assert_eq!(("UTF-8".to_string(), bom.to_string()), read_all(bom.as_bytes()).unwrap());
+  let alias = "\u{FEFF}<?xml version='1.0' encoding='utf8'?><a>\u{3042}</a>";
+  assert_eq!(("UTF-8".to_string(), alias.to_string()), read_all(alias.as_bytes()).unwrap());
+  assert_eq!(("UTF-8".to_string(), "<".to_string()), read_all(b"<").unwrap());
+  assert_eq!(("UTF-8".to_string(), String::new()), read_all(b"").unwrap());
+
+  // the declaration switches the decoder
+  let latin1 = b"<?xml version=\"1.0\" encoding = \"iso-8859-1\" ?>\n<a>\xE9\xFF</a>";
+  let (encoding, text) = read_all(latin1).unwrap();
+  assert_eq!(("ISO-8859-1", true), (encoding.as_str(), text.ends_with("<a>\u{E9}\u{FF}</a>")));
+  let (encoding, _) = read_all(b"<?xml encoding='US-ASCII'?><a/>").unwrap();
+  assert_eq!("US-ASCII", encoding);
+  let (encoding, _) = read_all(b"<?xml-stylesheet encoding='ISO-8859-1'?><a/>").unwrap();
+  assert_eq!("UTF-8", encoding);
+}
+
+#[test]
+fn conflicting_or_unsupported_encoding() {
+  assert_eq!(ErrorKind::InvalidData, error_kind(read_all(b"<?xml version='1.0' encoding='UTF-16'?><a/>")));
+  assert_eq!(ErrorKind::InvalidData, error_kind(read_all(b"\xEF\xBB\xBF<?xml version='1.0' encoding='US-ASCII'?>")));
+  let utf16le = |text: &str| text.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect::<Vec<_>>();
+  let utf16 = utf16le("\u{FEFF}<?xml encoding='UTF-8'?>");
+  assert_eq!(ErrorKind::InvalidData, error_kind(read_all(&utf16)));
+  let utf16 = utf16le("<?xml encoding='UTF-16BE'?>");
+  assert_eq!(ErrorKind::InvalidData, error_kind(read_all(&utf16)));
+  assert_eq!(ErrorKind::Unsupported, error_kind(read_all(b"<?xml version='1.0' encoding='X-UNKNOWN'?>")));
+  assert_eq!(ErrorKind::Unsupported, error_kind(read_all(b"\x4C\x6F\xA7\x94\x93\x40")));
+  assert!(DecodingReader::with_encoding(&b""[..], "X-UNKNOWN").is_err());
+}
+
+#[test]
+fn malformed_sequence() {
+  let result = read_all(b"<a>\xE3\x81</a>");
+  assert_eq!("malformed UTF-8 sequence at byte offset 3", result.unwrap_err().to_string());
+  let result = read_all(b"<a>\xE3\x81");
+  assert_eq!("malformed UTF-8 sequence at byte offset 3", result.unwrap_err().to_string());
+  let result = read_all(b"<?xml version='1.0' encoding='US-ASCII'?><a>\x80</a>");
+  assert_eq!("malformed US-ASCII sequence at byte offset 44", result.unwrap_err().to_string());
+}
+
+#[test]
+fn utf16_and_utf32() {
+  let text = |family: &str| format!("<?xml version='1.0' encoding='{}'?><a>\u{3042}\u{1F600}</a>", family);
+  let utf16 = |big_endian: bool| -> Vec<u8> {
+    let units = text("UTF-16").encode_utf16().collect::<Vec<_>>();
+    units.into_iter().flat_map(|unit| if big_endian { unit.to_be_bytes() } else { unit.to_le_bytes() }).collect()
+  };
+  let utf32 = |big_endian: bool| -> Vec<u8> {
+    let chars = text("UTF-32").chars().map(|ch| ch as u32).collect::<Vec<_>>();
+    chars.into_iter().flat_map(|ch| if big_endian { ch.to_be_bytes() } else { ch.to_le_bytes() }).collect()
+  };
+  for (bytes, encoding, family) in [
+    (utf16(true), "UTF-16BE", "UTF-16"),
+    (utf16(false), "UTF-16LE", "UTF-16"),
+    ([&[0xFE, 0xFF][..], &utf16(true)].concat(), "UTF-16BE", "UTF-16"),
+    ([&[0xFF, 0xFE][..], &utf16(false)].concat(), "UTF-16LE", "UTF-16"),
+    (utf32(true), "UTF-32BE", "UTF-32"),
+    (utf32(false), "UTF-32LE", "UTF-32"),
+    ([&[0x00, 0x00, 0xFE, 0xFF][..], &utf32(true)].concat(), "UTF-32BE", "UTF-32"),
+    ([&[0xFF, 0xFE, 0x00, 0x00][..], &utf32(false)].concat(), "UTF-32LE", "UTF-32"),
+  ] {
+    let (detected, decoded) = read_all(&bytes).unwrap();
+    assert_eq!(encoding, detected);
+    assert_eq!(text(family), decoded.trim_start_matches('\u{FEFF}'));
+  }
+
+  // without a BOM nor a declaration
+  let bytes = "<a>\u{1F600}</a>".encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect::<Vec<_>>();
+  assert_eq!(("UTF-16LE".to_string(), "<a>\u{1F600}</a>".to_string()), read_all(&bytes).unwrap());
+  let mut reader = DecodingReader::with_encoding(&bytes[..], "utf-16le").unwrap();
+  assert_eq!("UTF-16LE", reader.encoding().unwrap());
+}
+
+#[test]
+fn malformed_utf16_and_utf32() {
+  for (bytes, message) in [
+    (&b"\xFF\xFE<\0a\0>\0\x00\xDC</\0"[..], "malformed UTF-16LE sequence at byte offset 8"),
+    (&b"\xFE\xFF\0<\0a\0>\xD8\x3D\0<"[..], "malformed UTF-16BE sequence at byte offset 8"),
+    (&b"\xFE\xFF\0<\0a\0>\xD8\x3D"[..], "malformed UTF-16BE sequence at byte offset 8"),
+    (&b"\xFE\xFF\0<\0a\0>\0"[..], "malformed UTF-16BE sequence at byte offset 8"),
+    (&b"<\0\0\0\0\xD8\0\0"[..], "malformed UTF-32LE sequence at byte offset 4"),
+    (&b"\0\0\0<\0\x11\0\0"[..], "malformed UTF-32BE sequence at byte offset 4"),
+    (&b"\0\0\0<\0\0"[..], "malformed UTF-32BE sequence at byte offset 4"),
+  ] {
+    assert_eq!(message, read_all(bytes).unwrap_err().to_string());
+  }
+}
@@ -0,0 +1,434 @@
+/// A decoder that converts the bytes of a character encoding into characters. A decoder may keep a state between
+/// calls, such as the shift state of a stateful encoding, so a new one is needed for each stream.
+///
+pub trait Decoder {
+  /// The name of the encoding, such as `UTF-8`.
+  fn encoding(&self) -> &str;
+
+  /// Decodes the bytes at the beginning of `bytes` into `chars`, and returns the numbers of bytes read and characters
+  /// written. A sequence that is incomplete at the end of `bytes` is left unread unless `last` is `true`, in which
+  /// case it's malformed.
+  ///
+  /// If a malformed sequence follows the characters decoded, they are returned first, and [Malformed] is returned by
+  /// the next call whose `bytes` begin with that sequence. A sequence that only changes the state, such as an escape
+  /// sequence of ISO-2022-JP, is read without writing a character. `(0, 0)` is returned only if `bytes` has no
+  /// complete sequence and `last` is `false`, or `bytes` is empty.
+  ///
+  fn decode(&mut self, bytes: &[u8], chars: &mut [char], last: bool) -> std::result::Result<(usize, usize), Malformed>;
+}
+
+/// The error of [Decoder::decode()] that indicates the bytes at the beginning are not a valid sequence of the encoding.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Malformed;
+
+/// An encoder that converts characters into the bytes of a character encoding. A stateful encoder, such as of
+/// ISO-2022-JP, keeps its shift state between calls, so a new one is needed for each stream.
+///
+pub trait Encoder {
+  /// The name of the encoding, such as `UTF-8`.
+  fn encoding(&self) -> &str;
+
+  /// Returns `true` if the `ch` can be represented in the encoding.
+  fn can_encode(&self, ch: char) -> bool;
+
+  /// Encodes the `text` and appends the bytes to `out`. If a character of the `text` cannot be represented in the
+  /// encoding, the characters before it are encoded and [Unmappable] is returned.
+  ///
+  fn encode(&mut self, text: &str, out: &mut Vec<u8>) -> std::result::Result<(), Unmappable>;
+
+  /// Appends the bytes that end the stream, such as the escape sequence that returns a stateful encoding to its
+  /// initial state.
+  fn finish(&mut self, _out: &mut Vec<u8>) {}
+}
+
+/// The error of [Encoder::encode()] with the character that cannot be represented in the encoding.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unmappable(pub char);
+
+/// The result of decoding the sequence at the beginning of the bytes by [decode_each()].
+pub(super) enum Decoded {
+  /// A character and the length of its sequence.
+  Char(char, usize),
+  /// The length of a sequence that only changes the state of the decoder.
+  Shift(usize),
+  /// The sequence continues beyond the bytes.
+  Incomplete,
+}
+
+/// Decodes the sequences of `bytes` one by one with the `next` function, which decodes the sequence at the beginning
+/// of the bytes passed, into `chars` as [Decoder::decode()].
+pub(super) fn decode_each<F>(
+  bytes: &[u8], chars: &mut [char], last: bool, mut next: F,
+) -> std::result::Result<(usize, usize), Malformed>
+where
+  F: FnMut(&[u8]) -> std::result::Result<Decoded, Malformed>,
+{
+  let mut read = 0;
+  let mut written = 0;
+  while written < chars.len() && read < bytes.len() {
+    match next(&bytes[read..]) {
+      Ok(Decoded::Char(ch, length)) => {
+        chars[written] = ch;
+        written += 1;
+        read += length;
+      }
+      Ok(Decoded::Shift(length)) => read += length,
+      Ok(Decoded::Incomplete) if !last => break,
+      _ if read == 0 => return Err(Malformed),
+      _ => break,
+    }
+  }
+  Ok((read, written))
+}
+
+/// Encodes the characters of `text` one by one with the `next` function, which appends the bytes of a character to
+/// `out` and returns `false` if the character cannot be represented.
+pub(super) fn encode_each<F>(text: &str, out: &mut Vec<u8>, mut next: F) -> std::result::Result<(), Unmappable>
+where
+  F: FnMut(char, &mut Vec<u8>) -> bool,
+{
+  match text.chars().find(|ch| !next(*ch, out)) {
+    Some(ch) => Err(Unmappable(ch)),
+    None => Ok(()),
+  }
+}
+
+/// The [Decoder] of UTF-8.
+///
+pub struct Utf8Decoder;
+
+impl Decoder for Utf8Decoder {
+  fn encoding(&self) -> &str {
+    "UTF-8"
+  }
+
+  fn decode(&mut self, bytes: &[u8], chars: &mut [char], last: bool) -> std::result::Result<(usize, usize), Malformed> {
+    // a character is at most 4 bytes, so the bytes beyond them are not examined
+    let limit = bytes.len().min(chars.len().saturating_mul(4));
+    let (text, malformed) = match std::str::from_utf8(&bytes[..limit]) {
+      Ok(text) => (text, false),
+      Err(err) => {
+        let text = std::str::from_utf8(&bytes[..err.valid_up_to()]).expect("the bytes must be valid up to there");
+        (text, err.error_len().is_some() || (last && limit == bytes.len()))
+      }
+    };
+    let mut read = 0;
+    let mut written = 0;
+    for ch in text.chars().take(chars.len()) {
+      chars[written] = ch;
+      written += 1;
+      read += ch.len_utf8();
+    }
+    if written == 0 && malformed {
+      return Err(Malformed);
+    }
+    Ok((read, written))
+  }
+}
+
+/// The [Decoder] of UTF-16 in big or little endian. A supplementary character is decoded from a surrogate pair, and
+/// an unpaired surrogate is malformed.
+///
+/// If the byte order is not specified, as the encoding `UTF-16`, it's determined by the byte order mark at the
+/// beginning, or big endian without a BOM as [RFC 2781](https://www.rfc-editor.org/rfc/rfc2781). The BOM is decoded as
+/// U+FEFF like the other characters.
+///
+pub struct Utf16Decoder {
+  big_endian: Option<bool>,
+}
+
+impl Utf16Decoder {
+  pub fn new(big_endian: Option<bool>) -> Utf16Decoder {
+    Utf16Decoder { big_endian }
+  }
+}
+
+impl Decoder for Utf16Decoder {
+  fn encoding(&self) -> &str {
+    match self.big_endian {
+      Some(true) => "UTF-16BE",
+      Some(false) => "UTF-16LE",
+      None => "UTF-16",
+    }
+  }
+
+  fn decode(&mut self, bytes: &[u8], chars: &mut [char], last: bool) -> std::result::Result<(usize, usize), Malformed> {
+    let big_endian = match (self.big_endian, bytes) {
+      (Some(big_endian), _) => big_endian,
+      (None, [a, b, ..]) => *self.big_endian.insert(!(*a == 0xFF && *b == 0xFE)),
+      (None, _) => return if last && !bytes.is_empty() { Err(Malformed) } else { Ok((0, 0)) },
+    };
+    let unit = |i: usize| {
+      let unit = [bytes[i], bytes[i + 1]];
+      if big_endian {
+        u16::from_be_bytes(unit)
+      } else {
+        u16::from_le_bytes(unit)
+      }
+    };
+    let mut read = 0;
+    let mut written = 0;
+    while written < chars.len() && read + 2 <= bytes.len() {
+      let high = unit(read);
+      let (ch, length) = match high {
+        0xD800..=0xDBFF if read + 4 > bytes.len() => break,
+        0xD800..=0xDBFF => match unit(read + 2) {
+          low @ 0xDC00..=0xDFFF => {
+            let code = 0x10000 + (((high as u32) - 0xD800) << 10) + ((low as u32) - 0xDC00);
+            (char::from_u32(code), 4)
+          }
+          _ => (None, 0),
+        },
+        _ => (char::from_u32(high as u32), 2),
+      };
+      match ch {
+        Some(ch) => {
+          chars[written] = ch;
+          written += 1;
+          read += length;
+        }
+        None if written == 0 => return Err(Malformed),
+        None => break,
+      }
+    }
+    if written == 0 && last && read < bytes.len() && !chars.is_empty() {
+      return Err(Malformed);
+    }
+    Ok((read, written))
+  }
+}
+
+/// The [Decoder] of UTF-32 in big or little endian, in which a code point that is a surrogate or above U+10FFFF is
+/// malformed. The byte order is determined as [Utf16Decoder] if not specified.
+///
+pub struct Utf32Decoder {
+  big_endian: Option<bool>,
+}
+
+impl Utf32Decoder {
+  pub fn new(big_endian: Option<bool>) -> Utf32Decoder {
+    Utf32Decoder { big_endian }
+  }
+}
+
+impl Decoder for Utf32Decoder {
+  fn encoding(&self) -> &str {
+    match self.big_endian {
+      Some(true) => "UTF-32BE",
+      Some(false) => "UTF-32LE",
+      None => "UTF-32",
+    }
+  }
+
+  fn decode(&mut self, bytes: &[u8], chars: &mut [char], last: bool) -> std::result::Result<(usize, usize), Malformed> {
+    let big_endian = match (self.big_endian, bytes) {
+      (Some(big_endian), _) => big_endian,
+      (None, [a, b, c, d, ..]) => *self.big_endian.insert(![*a, *b, *c, *d].eq(&[0xFF, 0xFE, 0x00, 0x00])),
+      (None, _) => return if last && !bytes.is_empty() { Err(Malformed) } else { Ok((0, 0)) },
+    };
+    let mut written = 0;
+    for unit in bytes.chunks_exact(4).take(chars.len()) {
+      let unit = [unit[0], unit[1], unit[2], unit[3]];
+      let code = if big_endian { u32::from_be_bytes(unit) } else { u32::from_le_bytes(unit) };
+      match char::from_u32(code) {
+        Some(ch) => chars[written] = ch,
+        None if written == 0 => return Err(Malformed),
+        None => break,
+      }
+      written += 1;
+    }
+    if written == 0 && last && !bytes.is_empty() && !chars.is_empty() {
+      return Err(Malformed);
+    }
+    Ok((written * 4, written))
+  }
+}
+
+/// The [Decoder] of US-ASCII, in which a byte above `#x7F` is malformed.
+///
+pub struct AsciiDecoder;
+
+impl Decoder for AsciiDecoder {
+  fn encoding(&self) -> &str {
+    "US-ASCII"
+  }
+
+  fn decode(
+    &mut self, bytes: &[u8], chars: &mut [char], _last: bool,
+  ) -> std::result::Result<(usize, usize), Malformed> {
+    let length = bytes.iter().take(chars.len()).take_while(|b| b.is_ascii()).count();
+    if length == 0 && !bytes.is_empty() && !chars.is_empty() {
+      return Err(Malformed);
+    }
+    for (ch, b) in chars.iter_mut().zip(&bytes[..length]) {
+      *ch = *b as char;
+    }
+    Ok((length, length))
+  }
+}
+
+/// The [Decoder] of ISO-8859-1, whose bytes are the first 256 code points of Unicode.
+///
+pub struct Latin1Decoder;
+
+impl Decoder for Latin1Decoder {
+  fn encoding(&self) -> &str {
+    "ISO-8859-1"
+  }
+
+  fn decode(
+    &mut self, bytes: &[u8], chars: &mut [char], _last: bool,
+  ) -> std::result::Result<(usize, usize), Malformed> {
+    let length = bytes.len().min(chars.len());
+    for (ch, b) in chars.iter_mut().zip(&bytes[..length]) {
+      *ch = *b as char;
+    }
+    Ok((length, length))
+  }
+}
+
+/// The [Encoder] of UTF-8.
+///
+pub struct Utf8Encoder;
+
+impl Encoder for Utf8Encoder {
+  fn encoding(&self) -> &str {
+    "UTF-8"
+  }
+
+  fn can_encode(&self, _ch: char) -> bool {
+    true
+  }
+
+  fn encode(&mut self, text: &str, out: &mut Vec<u8>) -> std::result::Result<(), Unmappable> {
+    out.extend_from_slice(text.as_bytes());
+    Ok(())
+  }
+}
+
+/// The [Encoder] of UTF-16 in big or little endian. If the byte order is not specified, as the encoding `UTF-16`, the
+/// stream is written in big endian with the byte order mark at the beginning.
+///
+pub struct Utf16Encoder {
+  big_endian: Option<bool>,
+  started: bool,
+}
+
+impl Utf16Encoder {
+  pub fn new(big_endian: Option<bool>) -> Utf16Encoder {
+    Utf16Encoder { big_endian, started: false }
+  }
+}
+
+impl Encoder for Utf16Encoder {
+  fn encoding(&self) -> &str {
+    match self.big_endian {
+      Some(true) => "UTF-16BE",
+      Some(false) => "UTF-16LE",
+      None => "UTF-16",
+    }
+  }
+
+  fn can_encode(&self, _ch: char) -> bool {
+    true
+  }
+
+  fn encode(&mut self, text: &str, out: &mut Vec<u8>) -> std::result::Result<(), Unmappable> {
+    let big_endian = self.big_endian.unwrap_or(true);
+    if !std::mem::replace(&mut self.started, true) && self.big_endian.is_none() {
+      out.extend_from_slice(&[0xFE, 0xFF]);
+    }
+    for unit in text.encode_utf16() {
+      out.extend_from_slice(&if big_endian { unit.to_be_bytes() } else { unit.to_le_bytes() });
+    }
+    Ok(())
+  }
+}
+
+/// The [Encoder] of UTF-32 in big or little endian, which writes the byte order mark as [Utf16Encoder] if the byte
+/// order is not specified.
+///
+pub struct Utf32Encoder {
+  big_endian: Option<bool>,
+  started: bool,
+}
+
+impl Utf32Encoder {
+  pub fn new(big_endian: Option<bool>) -> Utf32Encoder {
+    Utf32Encoder { big_endian, started: false }
+  }
+}
+
+impl Encoder for Utf32Encoder {
+  fn encoding(&self) -> &str {
+    match self.big_endian {
+      Some(true) => "UTF-32BE",
+      Some(false) => "UTF-32LE",
+      None => "UTF-32",
+    }
+  }
+
+  fn can_encode(&self, _ch: char) -> bool {
+    true
+  }
+
+  fn encode(&mut self, text: &str, out: &mut Vec<u8>) -> std::result::Result<(), Unmappable> {
+    let big_endian = self.big_endian.unwrap_or(true);
+    if !std::mem::replace(&mut self.started, true) && self.big_endian.is_none() {
+      out.extend_from_slice(&[0x00, 0x00, 0xFE, 0xFF]);
+    }
+    for ch in text.chars() {
+      let code = ch as u32;
+      out.extend_from_slice(&if big_endian { code.to_be_bytes() } else { code.to_le_bytes() });
+    }
+    Ok(())
+  }
+}
+
+/// The [Encoder] of US-ASCII.
+///
+pub struct AsciiEncoder;
+
+impl Encoder for AsciiEncoder {
+  fn encoding(&self) -> &str {
+    "US-ASCII"
+  }
+
+  fn can_encode(&self, ch: char) -> bool {
+    ch.is_ascii()
+  }
+
+  fn encode(&mut self, text: &str, out: &mut Vec<u8>) -> std::result::Result<(), Unmappable> {
+    encode_each(text, out, |ch, out| {
+      if ch.is_ascii() {
+        out.push(ch as u8);
+      }
+      ch.is_ascii()
+    })
+  }
+}
+
+/// The [Encoder] of ISO-8859-1.
+///
+pub struct Latin1Encoder;
+
+impl Encoder for Latin1Encoder {
+  fn encoding(&self) -> &str {
+    "ISO-8859-1"
+  }
+
+  fn can_encode(&self, ch: char) -> bool {
+    (ch as u32) <= 0xFF
+  }
+
+  fn encode(&mut self, text: &str, out: &mut Vec<u8>) -> std::result::Result<(), Unmappable> {
+    encode_each(text, out, |ch, out| {
+      if (ch as u32) <= 0xFF {
+        out.push(ch as u8);
+      }
+      (ch as u32) <= 0xFF
+    })
+  }
+}
@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use super::jis_table::{JIS0208, WINDOWS_31J_ROWS, WINDOWS_31J_SYMBOLS};
+use super::{decode_each, encode_each, Decoded, Decoder, Encoder, Malformed, Unmappable};
+
+/// The mapping between the characters of JIS X 0208 and Unicode that the Japanese encodings use. The vendors differ
+/// in some symbols and in the characters they add to the unassigned rows.
+///
+/// The single bytes below `#x80` are ASCII in any mapping, since `\` and `~` are more common in the documents than
+/// `¥` and `‾` of JIS X 0201, except in ISO-2022-JP after `ESC ( J`.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum JisMapping {
+  /// The mapping of `JIS0208.TXT` of the Unicode Consortium, such as U+301C WAVE DASH for 1-33.
+  #[default]
+  Jis,
+  /// The mapping of Microsoft Windows-31J (CP932), such as U+FF5E FULLWIDTH TILDE for 1-33, with the NEC special
+  /// characters in the row 13 and the NEC-selected IBM extensions in the rows 89 to 92. Shift_JIS also has the
+  /// user-defined area mapped to U+E000-U+E757 and the IBM extensions after the row 94.
+  Windows31J,
+}
+
+/// The number of the cells in a row of JIS X 0208.
+const CELLS: usize = 94;
+
+/// The rows of JIS X 0208, and those that Shift_JIS of Windows-31J extends to.
+const ROWS: usize = 94;
+const EXTENDED_ROWS: usize = 120;
+
+/// The rows of the user-defined area of Windows-31J.
+const USER_DEFINED_ROWS: std::ops::RangeInclusive<usize> = 95..=114;
+
+/// The characters of a [JisMapping] indexed by the pointer `(row - 1) * 94 + (cell - 1)`, and the pointers of the
+/// characters to encode them.
+struct Table {
+  chars: Vec<char>,
+  /// The pointers within the 94 rows, for EUC-JP and ISO-2022-JP.
+  pointers: HashMap<char, usize>,
+  /// The pointers within the extended rows, for Shift_JIS.
+  extended_pointers: HashMap<char, usize>,
+}
+
+impl JisMapping {
+  fn table(self) -> &'static Table {
+    static JIS: OnceLock<Table> = OnceLock::new();
+    static WINDOWS_31J: OnceLock<Table> = OnceLock::new();
+    match self {
+      JisMapping::Jis => JIS.get_or_init(|| Table::new(self)),
+      JisMapping::Windows31J => WINDOWS_31J.get_or_init(|| Table::new(self)),
+    }
+  }
+}
+
+impl Table {
+  fn new(mapping: JisMapping) -> Table {
+    let mut chars = vec!['\0'; EXTENDED_ROWS * CELLS];
+    let mut put_row = |row: usize, text: &str| {
+      for (i, ch) in text.chars().enumerate() {
+        chars[(row - 1) * CELLS + i] = ch;
+      }
+    };
+    for (i, text) in JIS0208.iter().enumerate() {
+      put_row(i + 1, text);
+    }
+    if mapping == JisMapping::Windows31J {
+      for (row, text) in WINDOWS_31J_ROWS {
+        put_row(row, text);
+      }
+      for (row, cell, ch) in WINDOWS_31J_SYMBOLS {
+        chars[(row - 1) * CELLS + cell - 1] = ch;
+      }
+      for (i, pointer) in ((USER_DEFINED_ROWS.start() - 1) * CELLS..USER_DEFINED_ROWS.end() * CELLS).enumerate() {
+        chars[pointer] = char::from_u32(0xE000 + i as u32).expect("the user-defined area must be in the PUA");
+      }
+    }
+
+    // a character in several cells is encoded to the first of them in the order of the JIS X 0208 rows, the NEC
+    // special characters, the IBM extensions and the NEC-selected IBM extensions, as Windows and WHATWG do
+    let standard = (1..=88).filter(|row| *row != 13);
+    let order = standard.chain([13]).chain(115..=EXTENDED_ROWS).chain(89..=ROWS).chain(USER_DEFINED_ROWS);
+    let mut pointers = HashMap::new();
+    let mut extended_pointers = HashMap::new();
+    for row in order {
+      for (pointer, ch) in chars.iter().enumerate().skip((row - 1) * CELLS).take(CELLS) {
+        if *ch != '\0' {
+          if row <= ROWS {
+            pointers.entry(*ch).or_insert(pointer);
+          }
+          extended_pointers.entry(*ch).or_insert(pointer);
+        }
+      }
+    }
+    Table { chars, pointers, extended_pointers }
+  }
+
+  fn char(&self, pointer: usize) -> Option<char> {
+    self.chars.get(pointer).copied().filter(|ch| *ch != '\0')
+  }
+}
+
+/// Returns the halfwidth katakana U+FF61-U+FF9F of the JIS X 0201 byte `#xA1`-`#xDF`.
+fn katakana(b: u8) -> char {
+  char::from_u32(0xFF61 + (b - 0xA1) as u32).expect("the byte must be a halfwidth katakana")
+}
+
+/// Returns the JIS X 0201 byte `#xA1`-`#xDF` of the halfwidth katakana.
+fn katakana_byte(ch: char) -> Option<u8> {
+  matches!(ch, '\u{FF61}'..='\u{FF9F}').then(|| (ch as u32 - 0xFF61) as u8 + 0xA1)
+}
+
+/// The [Decoder] of Shift_JIS, whose single bytes are ASCII and the halfwidth katakana, and whose double bytes are
+/// JIS X 0208 in the [JisMapping]. The encoding is named `Windows-31J` with [JisMapping::Windows31J].
+///
+pub struct ShiftJisDecoder {
+  mapping: JisMapping,
+}
+
+impl ShiftJisDecoder {
+  pub fn new(mapping: JisMapping) -> ShiftJisDecoder {
+    ShiftJisDecoder { mapping }
+  }
+}
+
+impl Decoder for ShiftJisDecoder {
+  fn encoding(&self) -> &str {
+    shift_jis_name(self.mapping)
+  }
+
+  fn decode(&mut self, bytes: &[u8], chars: &mut [char], last: bool) -> std::result::Result<(usize, usize), Malformed> {
+    let table = self.mapping.table();
+    decode_each(bytes, chars, last, |bytes| match bytes {
+      [b @ 0x00..=0x7F, ..] => Ok(Decoded::Char(*b as char, 1)),
+      [b @ 0xA1..=0xDF, ..] => Ok(Decoded::Char(katakana(*b), 1)),
+      [0x81..=0x9F | 0xE0..=0xFC] => Ok(Decoded::Incomplete),
+      [lead @ (0x81..=0x9F | 0xE0..=0xFC), trail @ (0x40..=0x7E | 0x80..=0xFC), ..] => {
+        let lead = (*lead - if *lead < 0xA0 { 0x81 } else { 0xC1 }) as usize;
+        let trail = (*trail - if *trail < 0x7F { 0x40 } else { 0x41 }) as usize;
+        let ch = table.char(lead * 2 * CELLS + trail).ok_or(Malformed)?;
+        Ok(Decoded::Char(ch, 2))
+      }
+      _ => Err(Malformed),
+    })
+  }
+}
+
+/// The [Encoder] of Shift_JIS, the counterpart of [ShiftJisDecoder].
+///
+pub struct ShiftJisEncoder {
+  mapping: JisMapping,
+}
+
+impl ShiftJisEncoder {
+  pub fn new(mapping: JisMapping) -> ShiftJisEncoder {
+    ShiftJisEncoder { mapping }
+  }
+}
+
+impl Encoder for ShiftJisEncoder {
+  fn encoding(&self) -> &str {
+    shift_jis_name(self.mapping)
+  }
+
+  fn can_encode(&self, ch: char) -> bool {
+    ch.is_ascii() || katakana_byte(ch).is_some() || self.mapping.table().extended_pointers.contains_key(&ch)
+  }
+
+  fn encode(&mut self, text: &str, out: &mut Vec<u8>) -> std::result::Result<(), Unmappable> {
+    let table = self.mapping.table();
+    encode_each(text, out, |ch, out| {
+      if ch.is_ascii() {
+        out.push(ch as u8);
+      } else if let Some(b) = katakana_byte(ch) {
+        out.push(b);
+      } else if let Some(pointer) = table.extended_pointers.get(&ch) {
+        let (lead, trail) = ((pointer / (2 * CELLS)) as u8, (pointer % (2 * CELLS)) as u8);
+        out.push(lead + if lead < 0x1F { 0x81 } else { 0xC1 });
+        out.push(trail + if trail < 0x3F { 0x40 } else { 0x41 });
+      } else {
+        return false;
+      }
+      true
+    })
+  }
+}
+
+fn shift_jis_name(mapping: JisMapping) -> &'static str {
+  match mapping {
+    JisMapping::Jis => "Shift_JIS",
+    JisMapping::Windows31J => "Windows-31J",
+  }
+}
+
+/// The [Decoder] of EUC-JP, whose single bytes are ASCII, and whose double bytes are JIS X 0208 in the [JisMapping]
+/// and the halfwidth katakana after `#x8E`. The three bytes of JIS X 0212 after `#x8F` are not supported, and
+/// decoded as malformed.
+///
+pub struct EucJpDecoder {
+  mapping: JisMapping,
+}
+
+impl EucJpDecoder {
+  pub fn new(mapping: JisMapping) -> EucJpDecoder {
+    EucJpDecoder { mapping }
+  }
+}
+
+impl Decoder for EucJpDecoder {
+  fn encoding(&self) -> &str {
+    "EUC-JP"
+  }
+
+  fn decode(&mut self, bytes: &[u8], chars: &mut [char], last: bool) -> std::result::Result<(usize, usize), Malformed> {
+    let table = self.mapping.table();
+    decode_each(bytes, chars, last, |bytes| match bytes {
+      [b @ 0x00..=0x7F, ..] => Ok(Decoded::Char(*b as char, 1)),
+      [0x8E | 0xA1..=0xFE] => Ok(Decoded::Incomplete),
+      [0x8E, b @ 0xA1..=0xDF, ..] => Ok(Decoded::Char(katakana(*b), 2)),
+      [lead @ 0xA1..=0xFE, trail @ 0xA1..=0xFE, ..] => {
+        let ch = table.char((*lead - 0xA1) as usize * CELLS + (*trail - 0xA1) as usize).ok_or(Malformed)?;
+        Ok(Decoded::Char(ch, 2))
+      }
+      _ => Err(Malformed),
+    })
+  }
+}
+
+/// The [Encoder] of EUC-JP, the counterpart of [EucJpDecoder].
+///
+pub struct EucJpEncoder {
+  mapping: JisMapping,
+}
+
+impl EucJpEncoder {
+  pub fn new(mapping: JisMapping) -> EucJpEncoder {
+    EucJpEncoder { mapping }
+  }
+}
+
+impl Encoder for EucJpEncoder {
+  fn encoding(&self) -> &str {
+    "EUC-JP"
+  }
+
+  fn can_encode(&self, ch: char) -> bool {
+    ch.is_ascii() || katakana_byte(ch).is_some() || self.mapping.table().pointers.contains_key(&ch)
+  }
+
+  fn encode(&mut self, text: &str, out: &mut Vec<u8>) -> std::result::Result<(), Unmappable> {
+    let table = self.mapping.table();
+    encode_each(text, out, |ch, out| {
+      if ch.is_ascii() {
+        out.push(ch as u8);
+      } else if let Some(b) = katakana_byte(ch) {
+        out.extend_from_slice(&[0x8E, b]);
+      } else if let Some(pointer) = table.pointers.get(&ch) {
+        out.extend_from_slice(&[(pointer / CELLS) as u8 + 0xA1, (pointer % CELLS) as u8 + 0xA1]);
+      } else {
+        return false;
+      }
+      true
+    })
+  }
+}
+
+/// The character sets of ISO-2022-JP designated by the escape sequences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Designation {
+  /// `ESC ( B`
+  Ascii,
+  /// `ESC ( J`, JIS X 0201 Roman that has `¥` and `‾` in place of `\` and `~`.
+  Roman,
+  /// `ESC ( I`, JIS X 0201 katakana, which is not a part of ISO-2022-JP but is accepted as some encoders write it.
+  Katakana,
+  /// `ESC $ @` or `ESC $ B`
+  Jis0208,
+}
+
+/// The [Decoder] of ISO-2022-JP ([RFC 1468](https://www.rfc-editor.org/rfc/rfc1468)), a stateful 7-bit encoding
+/// that switches ASCII, JIS X 0201 Roman and JIS X 0208 in the [JisMapping] by the escape sequences.
+///
+pub struct Iso2022JpDecoder {
+  mapping: JisMapping,
+  designation: Designation,
+}
+
+impl Iso2022JpDecoder {
+  pub fn new(mapping: JisMapping) -> Iso2022JpDecoder {
+    Iso2022JpDecoder { mapping, designation: Designation::Ascii }
+  }
+}
+
+impl Decoder for Iso2022JpDecoder {
+  fn encoding(&self) -> &str {
+    "ISO-2022-JP"
+  }
+
+  fn decode(&mut self, bytes: &[u8], chars: &mut [char], last: bool) -> std::result::Result<(usize, usize), Malformed> {
+    let table = self.mapping.table();
+    let designation = &mut self.designation;
+    decode_each(bytes, chars, last, |bytes| {
+      let ch = match (*designation, bytes) {
+        (_, [0x1B] | [0x1B, b'(' | b'$']) => return Ok(Decoded::Incomplete),
+        (_, [0x1B, a, b, ..]) => {
+          *designation = match (a, b) {
+            (b'(', b'B') => Designation::Ascii,
+            (b'(', b'J') => Designation::Roman,
+            (b'(', b'I') => Designation::Katakana,
+            (b'$', b'@' | b'B') => Designation::Jis0208,
+            _ => return Err(Malformed),
+          };
+          return Ok(Decoded::Shift(3));
+        }
+        (_, [0x1B, ..] | [0x80..=0xFF, ..]) => return Err(Malformed),
+        (Designation::Ascii, [b, ..]) => *b as char,
+        (Designation::Roman, [0x5C, ..]) => '\u{A5}',
+        (Designation::Roman, [0x7E, ..]) => '\u{203E}',
+        (Designation::Roman, [b, ..]) => *b as char,
+        (Designation::Katakana, [b @ 0x21..=0x5F, ..]) => katakana(*b + 0x80),
+        (Designation::Jis0208, [0x21..=0x7E]) => return Ok(Decoded::Incomplete),
+        (Designation::Jis0208, [lead @ 0x21..=0x7E, trail @ 0x21..=0x7E, ..]) => {
+          let ch = table.char((*lead - 0x21) as usize * CELLS + (*trail - 0x21) as usize).ok_or(Malformed)?;
+          return Ok(Decoded::Char(ch, 2));
+        }
+        _ => return Err(Malformed),
+      };
+      Ok(Decoded::Char(ch, 1))
+    })
+  }
+}
+
+/// The [Encoder] of ISO-2022-JP, which writes ASCII and JIS X 0208 in the [JisMapping], and returns to ASCII at the
+/// end of the stream by [Encoder::finish()]. The halfwidth katakana cannot be encoded.
+///
+pub struct Iso2022JpEncoder {
+  mapping: JisMapping,
+  designation: Designation,
+}
+
+impl Iso2022JpEncoder {
+  pub fn new(mapping: JisMapping) -> Iso2022JpEncoder {
+    Iso2022JpEncoder { mapping, designation: Designation::Ascii }
+  }
+}
+
+impl Encoder for Iso2022JpEncoder {
+  fn encoding(&self) -> &str {
+    "ISO-2022-JP"
+  }
+
+  fn can_encode(&self, ch: char) -> bool {
+    ch.is_ascii() || self.mapping.table().pointers.contains_key(&ch)
+  }
+
+  fn encode(&mut self, text: &str, out: &mut Vec<u8>) -> std::result::Result<(), Unmappable> {
+    let table = self.mapping.table();
+    let designation = &mut self.designation;
+    encode_each(text, out, |ch, out| {
+      if ch.is_ascii() {
+        designate(designation, Designation::Ascii, out);
+        out.push(ch as u8);
+      } else if let Some(pointer) = table.pointers.get(&ch) {
+        designate(designation, Designation::Jis0208, out);
+        out.extend_from_slice(&[(pointer / CELLS) as u8 + 0x21, (pointer % CELLS) as u8 + 0x21]);
+      } else {
+        return false;
+      }
+      true
+    })
+  }
+
+  fn finish(&mut self, out: &mut Vec<u8>) {
+    designate(&mut self.designation, Designation::Ascii, out);
+  }
+}
+
+/// Writes the escape sequence of the `designation` if it's not the current one.
+fn designate(current: &mut Designation, designation: Designation, out: &mut Vec<u8>) {
+  if *current != designation {
+    out.extend_from_slice(if designation == Designation::Ascii { b"\x1B(B" } else { b"\x1B$B" });
+    *current = designation;
+  }
+}
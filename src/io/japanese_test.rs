@@ -0,0 +1,135 @@
+use crate::io::{
+  decoder, encoder, CharReader, Decoder, DecodingReader, Encoder, EucJpDecoder, EucJpEncoder, Iso2022JpDecoder,
+  Iso2022JpEncoder, JisMapping, Malformed, ShiftJisDecoder, ShiftJisEncoder, Unmappable,
+};
+
+/// Decodes all bytes a few characters at a time, so that the sequences split by the buffer boundary are tested.
+fn decode(decoder: &mut dyn Decoder, mut bytes: &[u8]) -> Result<String, Malformed> {
+  let mut buffer = ['\0'; 3];
+  let mut text = String::new();
+  while !bytes.is_empty() {
+    let (read, written) = decoder.decode(bytes, &mut buffer, true)?;
+    text.extend(&buffer[..written]);
+    bytes = &bytes[read..];
+  }
+  Ok(text)
+}
+
+#[test]
+fn encode_and_decode_japanese() {
+  let text = "<a>日本語 ｱｲｳ A\\~</a>";
+  let sjis = b"<a>\x93\xFA\x96\x7B\x8C\xEA \xB1\xB2\xB3 A\\~</a>";
+  let euc = b"<a>\xC6\xFC\xCB\xDC\xB8\xEC \x8E\xB1\x8E\xB2\x8E\xB3 A\\~</a>";
+  for (name, bytes) in [("Shift_JIS", &sjis[..]), ("EUC-JP", &euc[..])] {
+    let mut out = Vec::new();
+    encoder(name).unwrap().encode(text, &mut out).unwrap();
+    assert_eq!(bytes, out, "{}", name);
+    assert_eq!(Ok(text.to_string()), decode(decoder(name).unwrap().as_mut(), bytes), "{}", name);
+  }
+
+  // ISO-2022-JP switches to JIS X 0208 and back, and can't encode the halfwidth katakana
+  let mut iso2022jp = encoder("iso-2022-jp").unwrap();
+  let mut out = Vec::new();
+  assert_eq!(Err(Unmappable('ｱ')), iso2022jp.encode(text, &mut out));
+  assert_eq!(b"<a>\x1B$B\x46\x7C\x4B\x5C\x38\x6C\x1B(B ", &out[..]);
+  out.clear();
+  iso2022jp.encode("語", &mut out).unwrap();
+  iso2022jp.finish(&mut out);
+  assert_eq!(b"\x1B$B\x38\x6C\x1B(B", &out[..]);
+  let bytes = b"\x1B$@\x46\x7C\x1B(J\\~\x1B(I\x31\x1B(B\\";
+  assert_eq!(Ok("日¥‾ｱ\\".to_string()), decode(&mut Iso2022JpDecoder::new(JisMapping::Jis), bytes));
+
+  // every character of the tables is encoded back to the bytes it's decoded from, unless it's duplicated
+  for mapping in [JisMapping::Jis, JisMapping::Windows31J] {
+    let mut decoder = ShiftJisDecoder::new(mapping);
+    let mut encoder = ShiftJisEncoder::new(mapping);
+    for lead in (0x81..=0x9F).chain(0xE0..=0xFC) {
+      for trail in (0x40..=0x7E).chain(0x80..=0xFC) {
+        let Ok(text) = decode(&mut decoder, &[lead, trail]) else { continue };
+        let mut out = Vec::new();
+        encoder.encode(&text, &mut out).unwrap();
+        assert_eq!(Ok(text), decode(&mut decoder, &out));
+      }
+    }
+    let mut decoder = EucJpDecoder::new(mapping);
+    let mut encoder = EucJpEncoder::new(mapping);
+    let mut iso2022jp = Iso2022JpEncoder::new(mapping);
+    for lead in 0xA1..=0xFE {
+      for trail in 0xA1..=0xFE {
+        let Ok(text) = decode(&mut decoder, &[lead, trail]) else { continue };
+        let mut out = Vec::new();
+        encoder.encode(&text, &mut out).unwrap();
+        assert_eq!(Ok(text.clone()), decode(&mut decoder, &out));
+        out.clear();
+        iso2022jp.encode(&text, &mut out).unwrap();
+        iso2022jp.finish(&mut out);
+        assert_eq!(Ok(text), decode(&mut Iso2022JpDecoder::new(mapping), &out));
+      }
+    }
+  }
+}
+
+#[test]
+fn vendor_mappings() {
+  let decode_sjis = |mapping, bytes: &[u8]| decode(&mut ShiftJisDecoder::new(mapping), bytes);
+  assert_eq!(Ok("\u{301C}\u{2212}".to_string()), decode_sjis(JisMapping::Jis, b"\x81\x60\x81\x7C"));
+  assert_eq!(Ok("\u{FF5E}\u{FF0D}".to_string()), decode_sjis(JisMapping::Windows31J, b"\x81\x60\x81\x7C"));
+  assert_eq!(Err(Malformed), decode_sjis(JisMapping::Jis, b"\x87\x40"));
+  assert_eq!(Ok("①ⅰⅰ\u{E000}".to_string()), decode_sjis(JisMapping::Windows31J, b"\x87\x40\xEE\xEF\xFA\x40\xF0\x40"));
+  assert_eq!("Windows-31J", decoder("cp932").unwrap().encoding());
+
+  // a duplicated character is encoded to the JIS X 0208 rows, the NEC special characters, then the IBM extensions
+  let mut out = Vec::new();
+  ShiftJisEncoder::new(JisMapping::Windows31J).encode("\u{FFE2}∵Ⅰⅰ纊", &mut out).unwrap();
+  assert_eq!(b"\x81\xCA\x81\xE6\x87\x54\xFA\x40\xFA\x5C", &out[..]);
+  assert!(!ShiftJisEncoder::new(JisMapping::Jis).can_encode('①'));
+
+  // EUC-JP of Windows-31J has the rows up to 94 only
+  let mut euc = EucJpEncoder::new(JisMapping::Windows31J);
+  out.clear();
+  euc.encode("～ⅰ", &mut out).unwrap();
+  assert_eq!(b"\xA1\xC1\xFC\xF1", &out[..]);
+  assert!(!euc.can_encode('\u{E000}'));
+}
+
+#[test]
+fn malformed_japanese() {
+  assert_eq!(Err(Malformed), decode(&mut EucJpDecoder::new(JisMapping::Jis), b"\x8F\xA2\xAF"));
+  assert_eq!(Err(Malformed), decode(&mut ShiftJisDecoder::new(JisMapping::Jis), b"\x80"));
+  assert_eq!(Err(Malformed), decode(&mut Iso2022JpDecoder::new(JisMapping::Jis), b"\x1B$(D"));
+  assert_eq!(Err(Malformed), decode(&mut Iso2022JpDecoder::new(JisMapping::Jis), b"\x1B$B\x46"));
+
+  // the characters before a malformed sequence are decoded first, and an incomplete one waits for the next bytes
+  let mut chars = ['\0'; 8];
+  assert_eq!(Ok((3, 2)), ShiftJisDecoder::new(JisMapping::Jis).decode(b"a\x93\xFA\x93", &mut chars, false));
+  assert_eq!(Ok((3, 0)), Iso2022JpDecoder::new(JisMapping::Jis).decode(b"\x1B$B\x46", &mut chars, false));
+  assert_eq!(Ok((1, 1)), EucJpDecoder::new(JisMapping::Jis).decode(b"a\x8F", &mut chars, false));
+}
+
+#[test]
+fn detect_japanese_declaration() {
+  for (bytes, encoding) in [
+    (&b"<?xml version='1.0' encoding='Shift_JIS'?><a>\x93\xFA</a>"[..], "Shift_JIS"),
+    (&b"<?xml version='1.0' encoding='euc-jp'?><a>\xC6\xFC</a>"[..], "EUC-JP"),
+    (&b"<?xml version='1.0' encoding='ISO-2022-JP'?><a>\x1B$B\x46\x7C\x1B(B</a>"[..], "ISO-2022-JP"),
+  ] {
+    let mut reader = DecodingReader::new(bytes);
+    let mut buffer = ['\0'; 2];
+    let mut text = String::new();
+    loop {
+      match reader.read(&mut buffer).unwrap() {
+        0 => break,
+        length => text.extend(&buffer[..length]),
+      }
+    }
+    assert!(text.ends_with("<a>日</a>"), "{}: {}", encoding, text);
+    assert_eq!(encoding, reader.encoding().unwrap());
+  }
+
+  // the mapping is chosen with the decoder
+  let mut reader =
+    DecodingReader::with_decoder(&b"\x81\x60"[..], Box::new(ShiftJisDecoder::new(JisMapping::Windows31J)));
+  let mut buffer = ['\0'; 2];
+  assert_eq!(1, reader.read(&mut buffer).unwrap());
+  assert_eq!('\u{FF5E}', buffer[0]);
+}
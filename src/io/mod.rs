@@ -1,6 +1,137 @@
+//! This module provides character streams that the XML parser reads from, the decoders that convert byte streams
+//! into them, and the encoders that convert characters back into bytes.
+//!
+use std::io::{ErrorKind, Read};
+
+pub use decoding_reader::*;
+pub use encoding::*;
+pub use japanese::*;
+pub use registry::*;
+pub use single_byte::*;
+
+mod decoding_reader;
+mod encoding;
+mod japanese;
+mod jis_table;
+mod registry;
+mod single_byte;
+mod single_byte_table;
+
+#[cfg(test)]
+mod decoding_reader_test;
+#[cfg(test)]
+mod japanese_test;
+#[cfg(test)]
+mod registry_test;
+
+const BUFFER_SIZE: usize = 8 * 1024;
+
 /// An abstracted character stream that can read characters via buffer.
 ///
-
 pub trait CharReader {
-  fn read(&mut self, buffer: &[char]) -> usize;
+  /// Reads characters into the specified buffer and returns the number of characters read. 0 is returned only when
+  /// the end of the stream has been reached.
+  ///
+  fn read(&mut self, buffer: &mut [char]) -> crate::Result<usize>;
+
+  /// The numbers of bytes in the stream that the characters returned by the last [CharReader::read()] were decoded
+  /// from, one for each of them, or `None` if they are the lengths of the characters in UTF-8. The parser counts the
+  /// byte offsets of its locations with them.
+  ///
+  fn byte_lengths(&self) -> Option<&[usize]> {
+    None
+  }
+}
+
+/// A [CharReader] that reads characters from an in-memory string.
+///
+pub struct StringReader {
+  text: String,
+  position: usize,
+}
+
+impl StringReader {
+  pub fn new(text: &str) -> StringReader {
+    StringReader { text: text.to_string(), position: 0 }
+  }
+}
+
+impl From<String> for StringReader {
+  fn from(text: String) -> Self {
+    StringReader { text, position: 0 }
+  }
+}
+
+impl CharReader for StringReader {
+  fn read(&mut self, buffer: &mut [char]) -> crate::Result<usize> {
+    let mut length = 0;
+    for ch in self.text[self.position..].chars().take(buffer.len()) {
+      buffer[length] = ch;
+      length += 1;
+      self.position += ch.len_utf8();
+    }
+    Ok(length)
+  }
+}
+
+/// A [CharReader] that decodes UTF-8 byte stream such as a file.
+///
+pub struct Utf8Reader<R: Read> {
+  reader: R,
+  bytes: Vec<u8>,
+  offset: u64,
+  eof: bool,
+}
+
+impl<R: Read> Utf8Reader<R> {
+  pub fn new(reader: R) -> Utf8Reader<R> {
+    Utf8Reader { reader, bytes: Vec::with_capacity(BUFFER_SIZE), offset: 0, eof: false }
+  }
+
+  fn fill(&mut self) -> std::io::Result<()> {
+    let mut buf = [0u8; BUFFER_SIZE];
+    let len = loop {
+      match self.reader.read(&mut buf) {
+        Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+        result => break result?,
+      }
+    };
+    if len == 0 {
+      self.eof = true;
+    } else {
+      self.bytes.extend_from_slice(&buf[..len]);
+    }
+    Ok(())
+  }
+}
+
+impl<R: Read> CharReader for Utf8Reader<R> {
+  fn read(&mut self, buffer: &mut [char]) -> crate::Result<usize> {
+    while !self.eof && self.bytes.len() < 4 {
+      self.fill()?;
+    }
+    let valid = match std::str::from_utf8(&self.bytes) {
+      Ok(text) => text,
+      Err(err) if err.error_len().is_none() && !self.eof => {
+        // the last character is split by the buffer boundary
+        std::str::from_utf8(&self.bytes[..err.valid_up_to()]).unwrap()
+      }
+      Err(err) if err.valid_up_to() == 0 => {
+        let offset = self.offset;
+        let msg = format!("malformed UTF-8 sequence at byte offset {}", offset);
+        return Err(std::io::Error::new(ErrorKind::InvalidData, msg).into());
+      }
+      Err(err) => std::str::from_utf8(&self.bytes[..err.valid_up_to()]).unwrap(),
+    };
+    let mut length = 0;
+    let mut consumed = 0;
+    for ch in valid.chars().take(buffer.len()) {
+      buffer[length] = ch;
+      length += 1;
+      consumed += ch.len_utf8();
+    }
+    self.bytes.drain(..consumed);
+    self.offset += consumed as u64;
+    Ok(length)
+  }
 }
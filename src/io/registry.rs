@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, PoisonError, RwLock};
+
+use super::single_byte_table::*;
+use super::{
+  AsciiDecoder, AsciiEncoder, Decoder, Encoder, EucJpDecoder, EucJpEncoder, Iso2022JpDecoder, Iso2022JpEncoder,
+  JisMapping, Latin1Decoder, Latin1Encoder, ShiftJisDecoder, ShiftJisEncoder, SingleByteEncoding, Utf16Decoder,
+  Utf16Encoder, Utf32Decoder, Utf32Encoder, Utf8Decoder, Utf8Encoder,
+};
+
+/// A character encoding in the registry, which creates the decoders and encoders of the streams in it.
+///
+/// The registry resolves the labels of the encodings to them, as the `encoding` declaration of a XML document or the
+/// encoding of [LSSerializer](crate::xml::dom::LSSerializer). The labels are compared case-insensitively and
+/// without the surrounding whitespace, and include the name and the aliases in the
+/// [IANA Character Sets](https://www.iana.org/assignments/character-sets/character-sets.xhtml) and the labels of the
+/// [WHATWG Encoding Standard](https://encoding.spec.whatwg.org/#names-and-labels). The built-in encodings are:
+///
+/// * UTF-8, UTF-16, UTF-16BE, UTF-16LE, UTF-32, UTF-32BE and UTF-32LE,
+/// * US-ASCII, ISO-8859-1 to ISO-8859-16 except ISO-8859-12 that doesn't exist, windows-1250 to windows-1258, KOI8-R
+///   and KOI8-U,
+/// * Shift_JIS, Windows-31J, EUC-JP and ISO-2022-JP.
+///
+/// WHATWG resolves the labels of US-ASCII, ISO-8859-1, ISO-8859-9 and ISO-8859-11 to the Windows code pages that
+/// extend them, but they are the encodings of those names here. Also, the labels of UTF-16 without the byte order
+/// detect it from the BOM, and those of Shift_JIS use [JisMapping::Jis] except `windows-31j` and the Microsoft code
+/// page names.
+///
+/// An encoding added by [register()] replaces the one that has the same label.
+///
+pub trait Encoding: Send + Sync {
+  /// The name of the encoding, such as `UTF-8`.
+  fn name(&self) -> &str;
+
+  /// Creates a new decoder of a stream.
+  fn decoder(&self) -> Box<dyn Decoder>;
+
+  /// Creates a new encoder of a stream.
+  fn encoder(&self) -> Box<dyn Encoder>;
+}
+
+/// Adds the `encoding` to the registry with its name and the `labels`.
+///
+pub fn register(encoding: Arc<dyn Encoding>, labels: &[&str]) {
+  let mut encodings = registry().write().unwrap_or_else(PoisonError::into_inner);
+  insert(&mut encodings, encoding, labels);
+}
+
+/// Returns the [Encoding] of the `label` in the registry, or `None` if there isn't.
+///
+pub fn lookup(label: &str) -> Option<Arc<dyn Encoding>> {
+  let encodings = registry().read().unwrap_or_else(PoisonError::into_inner);
+  encodings.get(&normalize(label)).cloned()
+}
+
+/// Returns a new decoder of the encoding of the `label` in the registry, or `None` if the encoding is not supported.
+///
+pub fn decoder(label: &str) -> Option<Box<dyn Decoder>> {
+  lookup(label).map(|encoding| encoding.decoder())
+}
+
+/// Returns a new encoder of the encoding of the `label` in the registry, or `None` if the encoding is not supported.
+///
+pub fn encoder(label: &str) -> Option<Box<dyn Encoder>> {
+  lookup(label).map(|encoding| encoding.encoder())
+}
+
+fn normalize(label: &str) -> String {
+  label.trim_matches(|ch: char| ch.is_ascii_whitespace()).to_ascii_lowercase()
+}
+
+fn insert(encodings: &mut HashMap<String, Arc<dyn Encoding>>, encoding: Arc<dyn Encoding>, labels: &[&str]) {
+  for label in labels.iter().copied().chain([encoding.name()]) {
+    encodings.insert(normalize(label), encoding.clone());
+  }
+}
+
+/// A built-in encoding whose decoders and encoders are created by the functions.
+struct Builtin<D, E> {
+  name: &'static str,
+  decoder: D,
+  encoder: E,
+}
+
+impl<D, E, DE, EN> Encoding for Builtin<D, E>
+where
+  D: Fn() -> DE + Send + Sync,
+  E: Fn() -> EN + Send + Sync,
+  DE: Decoder + 'static,
+  EN: Encoder + 'static,
+{
+  fn name(&self) -> &str {
+    self.name
+  }
+
+  fn decoder(&self) -> Box<dyn Decoder> {
+    Box::new((self.decoder)())
+  }
+
+  fn encoder(&self) -> Box<dyn Encoder> {
+    Box::new((self.encoder)())
+  }
+}
+
+fn registry() -> &'static RwLock<HashMap<String, Arc<dyn Encoding>>> {
+  static REGISTRY: OnceLock<RwLock<HashMap<String, Arc<dyn Encoding>>>> = OnceLock::new();
+  REGISTRY.get_or_init(|| RwLock::new(builtins()))
+}
+
+fn builtins() -> HashMap<String, Arc<dyn Encoding>> {
+  let mut encodings = HashMap::new();
+  let mut add = |encoding: Arc<dyn Encoding>, labels: &str| {
+    insert(&mut encodings, encoding, &labels.split_whitespace().collect::<Vec<_>>());
+  };
+
+  let utf8 = Builtin { name: "UTF-8", decoder: || Utf8Decoder, encoder: || Utf8Encoder };
+  add(Arc::new(utf8), "utf8 csutf8 unicode-1-1-utf-8 unicode11utf8 unicode20utf8 x-unicode20utf8");
+  for (name, big_endian, labels) in [
+    ("UTF-16", None, "utf16 csutf16 ucs-2 iso-10646-ucs-2 csunicode unicode unicodefeff"),
+    ("UTF-16BE", Some(true), "csutf16be unicodefffe"),
+    ("UTF-16LE", Some(false), "csutf16le"),
+  ] {
+    let encoding =
+      Builtin { name, decoder: move || Utf16Decoder::new(big_endian), encoder: move || Utf16Encoder::new(big_endian) };
+    add(Arc::new(encoding), labels);
+  }
+  for (name, big_endian, labels) in [
+    ("UTF-32", None, "utf32 csutf32 ucs-4 iso-10646-ucs-4 csucs4"),
+    ("UTF-32BE", Some(true), "csutf32be"),
+    ("UTF-32LE", Some(false), "csutf32le"),
+  ] {
+    let encoding =
+      Builtin { name, decoder: move || Utf32Decoder::new(big_endian), encoder: move || Utf32Encoder::new(big_endian) };
+    add(Arc::new(encoding), labels);
+  }
+
+  let ascii = Builtin { name: "US-ASCII", decoder: || AsciiDecoder, encoder: || AsciiEncoder };
+  let labels = "ascii us iso-ir-6 ansi_x3.4-1968 ansi_x3.4-1986 iso_646.irv:1991 iso646-us ibm367 cp367 csascii";
+  add(Arc::new(ascii), labels);
+  let latin1 = Builtin { name: "ISO-8859-1", decoder: || Latin1Decoder, encoder: || Latin1Encoder };
+  let labels = "iso_8859-1:1987 iso-ir-100 iso_8859-1 iso8859-1 iso88591 latin1 l1 ibm819 cp819 csisolatin1";
+  add(Arc::new(latin1), labels);
+  for (name, table, labels) in SINGLE_BYTE_ENCODINGS {
+    add(Arc::new(SingleByteEncoding::from_table(name, table)), labels);
+  }
+
+  for (name, mapping, labels) in [
+    ("Shift_JIS", JisMapping::Jis, "shift-jis sjis x-sjis ms_kanji csshiftjis"),
+    ("Windows-31J", JisMapping::Windows31J, "cswindows31j cp932 ms932 windows-932"),
+  ] {
+    let encoding =
+      Builtin { name, decoder: move || ShiftJisDecoder::new(mapping), encoder: move || ShiftJisEncoder::new(mapping) };
+    add(Arc::new(encoding), labels);
+  }
+  for (name, mapping, labels) in
+    [("EUC-JP", JisMapping::Jis, "eucjp x-euc-jp cseucpkdfmtjapanese"), ("CP51932", JisMapping::Windows31J, "")]
+  {
+    let encoding =
+      Builtin { name, decoder: move || EucJpDecoder::new(mapping), encoder: move || EucJpEncoder::new(mapping) };
+    add(Arc::new(encoding), labels);
+  }
+  for (name, mapping, labels) in
+    [("ISO-2022-JP", JisMapping::Jis, "csiso2022jp"), ("CP50220", JisMapping::Windows31J, "")]
+  {
+    let encoding = Builtin {
+      name,
+      decoder: move || Iso2022JpDecoder::new(mapping),
+      encoder: move || Iso2022JpEncoder::new(mapping),
+    };
+    add(Arc::new(encoding), labels);
+  }
+  encodings
+}
+
+/// The names, tables and labels of the built-in [SingleByteEncoding]s.
+const SINGLE_BYTE_ENCODINGS: [(&str, &str, &str); 25] = [
+  ("ISO-8859-2", ISO_8859_2, "iso_8859-2:1987 iso-ir-101 iso_8859-2 iso8859-2 iso88592 latin2 l2 csisolatin2"),
+  ("ISO-8859-3", ISO_8859_3, "iso_8859-3:1988 iso-ir-109 iso_8859-3 iso8859-3 iso88593 latin3 l3 csisolatin3"),
+  ("ISO-8859-4", ISO_8859_4, "iso_8859-4:1988 iso-ir-110 iso_8859-4 iso8859-4 iso88594 latin4 l4 csisolatin4"),
+  ("ISO-8859-5", ISO_8859_5, "iso_8859-5:1988 iso-ir-144 iso_8859-5 iso8859-5 iso88595 cyrillic csisolatincyrillic"),
+  (
+    "ISO-8859-6",
+    ISO_8859_6,
+    "iso_8859-6:1987 iso-ir-127 iso_8859-6 iso8859-6 iso88596 ecma-114 asmo-708 arabic \
+    csisolatinarabic iso-8859-6-e iso-8859-6-i csiso88596e csiso88596i",
+  ),
+  (
+    "ISO-8859-7",
+    ISO_8859_7,
+    "iso_8859-7:1987 iso-ir-126 iso_8859-7 iso8859-7 iso88597 elot_928 ecma-118 greek greek8 \
+    csisolatingreek sun_eu_greek",
+  ),
+  (
+    "ISO-8859-8",
+    ISO_8859_8,
+    "iso_8859-8:1988 iso-ir-138 iso_8859-8 iso8859-8 iso88598 hebrew csisolatinhebrew \
+    iso-8859-8-e iso-8859-8-i csiso88598e csiso88598i visual logical",
+  ),
+  ("ISO-8859-9", ISO_8859_9, "iso_8859-9:1989 iso-ir-148 iso_8859-9 iso8859-9 iso88599 latin5 l5 csisolatin5"),
+  ("ISO-8859-10", ISO_8859_10, "iso_8859-10:1992 iso-ir-157 iso8859-10 iso885910 latin6 l6 csisolatin6"),
+  ("ISO-8859-11", ISO_8859_11, "iso8859-11 iso885911"),
+  ("ISO-8859-13", ISO_8859_13, "iso8859-13 iso885913 csiso885913"),
+  (
+    "ISO-8859-14",
+    ISO_8859_14,
+    "iso_8859-14:1998 iso-ir-199 iso_8859-14 iso8859-14 iso885914 latin8 l8 iso-celtic \
+    csiso885914",
+  ),
+  ("ISO-8859-15", ISO_8859_15, "iso_8859-15 iso8859-15 iso885915 latin-9 l9 csiso885915 csisolatin9"),
+  ("ISO-8859-16", ISO_8859_16, "iso_8859-16:2001 iso-ir-226 iso_8859-16 latin10 l10 csiso885916"),
+  ("windows-1250", WINDOWS_1250, "cp1250 x-cp1250 cswindows1250"),
+  ("windows-1251", WINDOWS_1251, "cp1251 x-cp1251 cswindows1251"),
+  ("windows-1252", WINDOWS_1252, "cp1252 x-cp1252 cswindows1252"),
+  ("windows-1253", WINDOWS_1253, "cp1253 x-cp1253 cswindows1253"),
+  ("windows-1254", WINDOWS_1254, "cp1254 x-cp1254 cswindows1254"),
+  ("windows-1255", WINDOWS_1255, "cp1255 x-cp1255 cswindows1255"),
+  ("windows-1256", WINDOWS_1256, "cp1256 x-cp1256 cswindows1256"),
+  ("windows-1257", WINDOWS_1257, "cp1257 x-cp1257 cswindows1257"),
+  ("windows-1258", WINDOWS_1258, "cp1258 x-cp1258 cswindows1258"),
+  ("KOI8-R", KOI8_R, "koi8_r koi8 koi cskoi8r"),
+  ("KOI8-U", KOI8_U, "koi8_u koi8-ru cskoi8u"),
+];
@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use crate::io::{
+  decoder, encoder, lookup, register, CharReader, DecodingReader, Encoding, JisMapping, Malformed, ShiftJisDecoder,
+  ShiftJisEncoder, Unmappable,
+};
+
+#[test]
+fn resolve_labels() {
+  for (label, name) in [
+    ("utf-8", "UTF-8"),
+    (" Unicode-1-1-UTF-8\t", "UTF-8"),
+    ("csUTF16LE", "UTF-16LE"),
+    ("ucs-4", "UTF-32"),
+    ("ANSI_X3.4-1968", "US-ASCII"),
+    ("latin1", "ISO-8859-1"),
+    ("iso_8859-5:1988", "ISO-8859-5"),
+    ("L9", "ISO-8859-15"),
+    ("latin10", "ISO-8859-16"),
+    ("x-cp1251", "windows-1251"),
+    ("koi", "KOI8-R"),
+    ("koi8-ru", "KOI8-U"),
+    ("MS_Kanji", "Shift_JIS"),
+    ("cp932", "Windows-31J"),
+    ("x-euc-jp", "EUC-JP"),
+    ("csISO2022JP", "ISO-2022-JP"),
+  ] {
+    assert_eq!(Some(name), lookup(label).as_ref().map(|encoding| encoding.name()), "{}", label);
+    assert_eq!(name, decoder(label).unwrap().encoding(), "{}", label);
+  }
+  assert!(lookup("iso-8859-12").is_none());
+  assert!(lookup("utf-7").is_none());
+  assert!(encoder("x-unknown").is_none());
+}
+
+#[test]
+fn single_byte_encodings() {
+  for (label, bytes, text) in [
+    ("ISO-8859-2", &b"\xA1\xB5\xE8"[..], "Ąľč"),
+    ("ISO-8859-5", b"\xB0\xD0\xF0", "Аа№"),
+    ("ISO-8859-7", b"\xC1\xE1", "Αα"),
+    ("ISO-8859-15", b"\xA4\xBD", "€œ"),
+    ("windows-1250", b"\x8A\x9A", "Šš"),
+    ("windows-1252", b"\x80\x93\x94", "€“”"),
+    ("windows-1256", b"\xC7\xE1", "\u{627}\u{644}"),
+    ("KOI8-R", b"\xC1\xE1", "аА"),
+    ("KOI8-U", b"\xA4\xB4", "єЄ"),
+  ] {
+    let mut chars = ['\0'; 8];
+    let (read, written) = decoder(label).unwrap().decode(bytes, &mut chars, true).unwrap();
+    assert_eq!((bytes.len(), text), (read, chars[..written].iter().collect::<String>().as_str()), "{}", label);
+    let mut out = Vec::new();
+    encoder(label).unwrap().encode(text, &mut out).unwrap();
+    assert_eq!(bytes, out, "{}", label);
+  }
+
+  // a byte not assigned is malformed, and a character not in the table cannot be encoded
+  let mut chars = ['\0'; 8];
+  assert_eq!(Ok((1, 1)), decoder("windows-1252").unwrap().decode(b"a\x81", &mut chars, true));
+  assert_eq!(Err(Malformed), decoder("windows-1252").unwrap().decode(b"\x81", &mut chars, true));
+  let mut out = Vec::new();
+  assert_eq!(Err(Unmappable('\u{3042}')), encoder("KOI8-R").unwrap().encode("a\u{3042}", &mut out));
+  assert_eq!(b"a", &out[..]);
+
+  // the declaration of a document selects the encoding by any label
+  let mut reader = DecodingReader::new(&b"<?xml version='1.0' encoding='cp1251'?><a>\xC0</a>"[..]);
+  let mut buffer = ['\0'; 64];
+  let length = reader.read(&mut buffer).unwrap();
+  assert!(buffer[..length].iter().collect::<String>().ends_with("<a>\u{410}</a>"));
+  assert_eq!("windows-1251", reader.encoding().unwrap());
+}
+
+#[test]
+fn register_encoding() {
+  struct Custom;
+  impl Encoding for Custom {
+    fn name(&self) -> &str {
+      "x-shift-jis-custom"
+    }
+    fn decoder(&self) -> Box<dyn crate::io::Decoder> {
+      Box::new(ShiftJisDecoder::new(JisMapping::Windows31J))
+    }
+    fn encoder(&self) -> Box<dyn crate::io::Encoder> {
+      Box::new(ShiftJisEncoder::new(JisMapping::Windows31J))
+    }
+  }
+  register(Arc::new(Custom), &["X-SJIS-Custom-Alias"]);
+  assert_eq!("x-shift-jis-custom", lookup("X-Shift-JIS-Custom").unwrap().name());
+  let mut chars = ['\0'; 2];
+  assert_eq!(Ok((2, 1)), decoder("x-sjis-custom-alias").unwrap().decode(b"\x81\x60", &mut chars, true));
+  assert_eq!('\u{FF5E}', chars[0]);
+}
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{decode_each, encode_each, Decoded, Decoder, Encoder, Encoding, Malformed, Unmappable};
+
+/// An [Encoding] whose bytes below `#x80` are ASCII and whose bytes from `#x80` to `#xFF` are the characters of a
+/// table, such as ISO-8859-2 or Windows-1251. It can also be [registered](super::register()) for a code page that is
+/// not built in.
+///
+/// ```
+/// use std::sync::Arc;
+/// use xenolith::io::{decoder, register, SingleByteEncoding};
+///
+/// // a code page that has only `€` at #x80
+/// let mut upper = ['\u{FFFD}'; 128];
+/// upper[0] = '€';
+/// register(Arc::new(SingleByteEncoding::new("x-euro", upper)), &["x-euro-sign"]);
+/// let mut chars = ['\0'; 4];
+/// assert_eq!(Ok((2, 2)), decoder("X-Euro-Sign").unwrap().decode(b"\x80a", &mut chars, true));
+/// assert_eq!(['€', 'a'], chars[..2]);
+/// ```
+///
+pub struct SingleByteEncoding {
+  name: String,
+  upper: Arc<[char; 128]>,
+}
+
+impl SingleByteEncoding {
+  /// Creates an encoding of the `upper` half of the table, from `#x80` to `#xFF`, in which U+FFFD is a byte that is
+  /// not assigned.
+  pub fn new(name: &str, upper: [char; 128]) -> SingleByteEncoding {
+    SingleByteEncoding { name: name.to_string(), upper: Arc::new(upper) }
+  }
+
+  /// Creates an encoding of the table written as a string of 128 characters.
+  pub(super) fn from_table(name: &str, table: &str) -> SingleByteEncoding {
+    let mut upper = ['\u{FFFD}'; 128];
+    for (i, ch) in table.chars().take(128).enumerate() {
+      upper[i] = ch;
+    }
+    Self::new(name, upper)
+  }
+}
+
+impl Encoding for SingleByteEncoding {
+  fn name(&self) -> &str {
+    &self.name
+  }
+
+  fn decoder(&self) -> Box<dyn Decoder> {
+    Box::new(SingleByteDecoder { name: self.name.clone(), upper: self.upper.clone() })
+  }
+
+  fn encoder(&self) -> Box<dyn Encoder> {
+    let bytes = self.upper.iter().enumerate().filter(|(_, ch)| **ch != '\u{FFFD}');
+    let bytes = bytes.rev().map(|(i, ch)| (*ch, 0x80 + i as u8)).collect();
+    Box::new(SingleByteEncoder { name: self.name.clone(), bytes })
+  }
+}
+
+/// The [Decoder] of a [SingleByteEncoding], in which a byte that is not assigned is malformed.
+///
+pub struct SingleByteDecoder {
+  name: String,
+  upper: Arc<[char; 128]>,
+}
+
+impl Decoder for SingleByteDecoder {
+  fn encoding(&self) -> &str {
+    &self.name
+  }
+
+  fn decode(&mut self, bytes: &[u8], chars: &mut [char], last: bool) -> std::result::Result<(usize, usize), Malformed> {
+    decode_each(bytes, chars, last, |bytes| match bytes[0] {
+      b @ 0x00..=0x7F => Ok(Decoded::Char(b as char, 1)),
+      b => match self.upper[(b - 0x80) as usize] {
+        '\u{FFFD}' => Err(Malformed),
+        ch => Ok(Decoded::Char(ch, 1)),
+      },
+    })
+  }
+}
+
+/// The [Encoder] of a [SingleByteEncoding]. A character assigned to several bytes is encoded to the first of them.
+///
+pub struct SingleByteEncoder {
+  name: String,
+  bytes: HashMap<char, u8>,
+}
+
+impl Encoder for SingleByteEncoder {
+  fn encoding(&self) -> &str {
+    &self.name
+  }
+
+  fn can_encode(&self, ch: char) -> bool {
+    ch.is_ascii() || self.bytes.contains_key(&ch)
+  }
+
+  fn encode(&mut self, text: &str, out: &mut Vec<u8>) -> std::result::Result<(), Unmappable> {
+    encode_each(text, out, |ch, out| {
+      let b = if ch.is_ascii() { Some(ch as u8) } else { self.bytes.get(&ch).copied() };
+      out.extend(b);
+      b.is_some()
+    })
+  }
+}
@@ -0,0 +1,279 @@
+//! The upper halves of the single-byte encodings, from `#x80` to `#xFF`, generated from the decoders of Python
+//! codecs, which follow the mapping tables of the Unicode Consortium and RFC 1489 and 2319 for KOI8. U+FFFD is a
+//! byte that is not assigned.
+//!
+
+pub(super) const ISO_8859_2: &str = concat!(
+  "\u{80}\u{81}\u{82}\u{83}\u{84}\u{85}\u{86}\u{87}\u{88}\u{89}\u{8A}\u{8B}\u{8C}\u{8D}\u{8E}\u{8F}",
+  "\u{90}\u{91}\u{92}\u{93}\u{94}\u{95}\u{96}\u{97}\u{98}\u{99}\u{9A}\u{9B}\u{9C}\u{9D}\u{9E}\u{9F}",
+  "\u{A0}Ą˘Ł¤ĽŚ§¨ŠŞŤŹ\u{AD}ŽŻ",
+  "°ą˛ł´ľśˇ¸šşťź˝žż",
+  "ŔÁÂĂÄĹĆÇČÉĘËĚÍÎĎ",
+  "ĐŃŇÓÔŐÖ×ŘŮÚŰÜÝŢß",
+  "ŕáâăäĺćçčéęëěíîď",
+  "đńňóôőö÷řůúűüýţ˙",
+);
+
+pub(super) const ISO_8859_3: &str = concat!(
+  "\u{80}\u{81}\u{82}\u{83}\u{84}\u{85}\u{86}\u{87}\u{88}\u{89}\u{8A}\u{8B}\u{8C}\u{8D}\u{8E}\u{8F}",
+  "\u{90}\u{91}\u{92}\u{93}\u{94}\u{95}\u{96}\u{97}\u{98}\u{99}\u{9A}\u{9B}\u{9C}\u{9D}\u{9E}\u{9F}",
+  "\u{A0}Ħ˘£¤\u{FFFD}Ĥ§¨İŞĞĴ\u{AD}\u{FFFD}Ż",
+  "°ħ²³´µĥ·¸ışğĵ½\u{FFFD}ż",
+  "ÀÁÂ\u{FFFD}ÄĊĈÇÈÉÊËÌÍÎÏ",
+  "\u{FFFD}ÑÒÓÔĠÖ×ĜÙÚÛÜŬŜß",
+  "àáâ\u{FFFD}äċĉçèéêëìíîï",
+  "\u{FFFD}ñòóôġö÷ĝùúûüŭŝ˙",
+);
+
+pub(super) const ISO_8859_4: &str = concat!(
+  "\u{80}\u{81}\u{82}\u{83}\u{84}\u{85}\u{86}\u{87}\u{88}\u{89}\u{8A}\u{8B}\u{8C}\u{8D}\u{8E}\u{8F}",
+  "\u{90}\u{91}\u{92}\u{93}\u{94}\u{95}\u{96}\u{97}\u{98}\u{99}\u{9A}\u{9B}\u{9C}\u{9D}\u{9E}\u{9F}",
+  "\u{A0}ĄĸŖ¤ĨĻ§¨ŠĒĢŦ\u{AD}Ž¯",
+  "°ą˛ŗ´ĩļˇ¸šēģŧŊžŋ",
+  "ĀÁÂÃÄÅÆĮČÉĘËĖÍÎĪ",
+  "ĐŅŌĶÔÕÖ×ØŲÚÛÜŨŪß",
+  "āáâãäåæįčéęëėíîī",
+  "đņōķôõö÷øųúûüũū˙",
+);
+
+pub(super) const ISO_8859_5: &str = concat!(
+  "\u{80}\u{81}\u{82}\u{83}\u{84}\u{85}\u{86}\u{87}\u{88}\u{89}\u{8A}\u{8B}\u{8C}\u{8D}\u{8E}\u{8F}",
+  "\u{90}\u{91}\u{92}\u{93}\u{94}\u{95}\u{96}\u{97}\u{98}\u{99}\u{9A}\u{9B}\u{9C}\u{9D}\u{9E}\u{9F}",
+  "\u{A0}ЁЂЃЄЅІЇЈЉЊЋЌ\u{AD}ЎЏ",
+  "АБВГДЕЖЗИЙКЛМНОП",
+  "РСТУФХЦЧШЩЪЫЬЭЮЯ",
+  "абвгдежзийклмноп",
+  "рстуфхцчшщъыьэюя",
+  "№ёђѓєѕіїјљњћќ§ўџ",
+);
+
+pub(super) const ISO_8859_6: &str = concat!(
+  "\u{80}\u{81}\u{82}\u{83}\u{84}\u{85}\u{86}\u{87}\u{88}\u{89}\u{8A}\u{8B}\u{8C}\u{8D}\u{8E}\u{8F}",
+  "\u{90}\u{91}\u{92}\u{93}\u{94}\u{95}\u{96}\u{97}\u{98}\u{99}\u{9A}\u{9B}\u{9C}\u{9D}\u{9E}\u{9F}",
+  "\u{A0}\u{FFFD}\u{FFFD}\u{FFFD}¤\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}،\u{AD}\u{FFFD}\u{FFFD}",
+  "\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}؛\u{FFFD}\u{FFFD}\u{FFFD}؟",
+  "\u{FFFD}ءآأؤإئابةتثجحخد",
+  "ذرزسشصضطظعغ\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}",
+  "ـفقكلمنهوىي\u{64B}\u{64C}\u{64D}\u{64E}\u{64F}",
+  "\u{650}\u{651}\u{652}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}",
+);
+
+pub(super) const ISO_8859_7: &str = concat!(
+  "\u{80}\u{81}\u{82}\u{83}\u{84}\u{85}\u{86}\u{87}\u{88}\u{89}\u{8A}\u{8B}\u{8C}\u{8D}\u{8E}\u{8F}",
+  "\u{90}\u{91}\u{92}\u{93}\u{94}\u{95}\u{96}\u{97}\u{98}\u{99}\u{9A}\u{9B}\u{9C}\u{9D}\u{9E}\u{9F}",
+  "\u{A0}‘’£€₯¦§¨©ͺ«¬\u{AD}\u{FFFD}―",
+  "°±²³΄΅Ά·ΈΉΊ»Ό½ΎΏ",
+  "ΐΑΒΓΔΕΖΗΘΙΚΛΜΝΞΟ",
+  "ΠΡ\u{FFFD}ΣΤΥΦΧΨΩΪΫάέήί",
+  "ΰαβγδεζηθικλμνξο",
+  "πρςστυφχψωϊϋόύώ\u{FFFD}",
+);
+
+pub(super) const ISO_8859_8: &str = concat!(
+  "\u{80}\u{81}\u{82}\u{83}\u{84}\u{85}\u{86}\u{87}\u{88}\u{89}\u{8A}\u{8B}\u{8C}\u{8D}\u{8E}\u{8F}",
+  "\u{90}\u{91}\u{92}\u{93}\u{94}\u{95}\u{96}\u{97}\u{98}\u{99}\u{9A}\u{9B}\u{9C}\u{9D}\u{9E}\u{9F}",
+  "\u{A0}\u{FFFD}¢£¤¥¦§¨©×«¬\u{AD}®¯",
+  "°±²³´µ¶·¸¹÷»¼½¾\u{FFFD}",
+  "\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}",
+  "\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}‗",
+  "אבגדהוזחטיךכלםמן",
+  "נסעףפץצקרשת\u{FFFD}\u{FFFD}\u{200E}\u{200F}\u{FFFD}",
+);
+
+pub(super) const ISO_8859_9: &str = concat!(
+  "\u{80}\u{81}\u{82}\u{83}\u{84}\u{85}\u{86}\u{87}\u{88}\u{89}\u{8A}\u{8B}\u{8C}\u{8D}\u{8E}\u{8F}",
+  "\u{90}\u{91}\u{92}\u{93}\u{94}\u{95}\u{96}\u{97}\u{98}\u{99}\u{9A}\u{9B}\u{9C}\u{9D}\u{9E}\u{9F}",
+  "\u{A0}¡¢£¤¥¦§¨©ª«¬\u{AD}®¯",
+  "°±²³´µ¶·¸¹º»¼½¾¿",
+  "ÀÁÂÃÄÅÆÇÈÉÊËÌÍÎÏ",
+  "ĞÑÒÓÔÕÖ×ØÙÚÛÜİŞß",
+  "àáâãäåæçèéêëìíîï",
+  "ğñòóôõö÷øùúûüışÿ",
+);
+
+pub(super) const ISO_8859_10: &str = concat!(
+  "\u{80}\u{81}\u{82}\u{83}\u{84}\u{85}\u{86}\u{87}\u{88}\u{89}\u{8A}\u{8B}\u{8C}\u{8D}\u{8E}\u{8F}",
+  "\u{90}\u{91}\u{92}\u{93}\u{94}\u{95}\u{96}\u{97}\u{98}\u{99}\u{9A}\u{9B}\u{9C}\u{9D}\u{9E}\u{9F}",
+  "\u{A0}ĄĒĢĪĨĶ§ĻĐŠŦŽ\u{AD}ŪŊ",
+  "°ąēģīĩķ·ļđšŧž―ūŋ",
+  "ĀÁÂÃÄÅÆĮČÉĘËĖÍÎÏ",
+  "ÐŅŌÓÔÕÖŨØŲÚÛÜÝÞß",
+  "āáâãäåæįčéęëėíîï",
+  "ðņōóôõöũøųúûüýþĸ",
+);
+
+pub(super) const ISO_8859_11: &str = concat!(
+  "\u{80}\u{81}\u{82}\u{83}\u{84}\u{85}\u{86}\u{87}\u{88}\u{89}\u{8A}\u{8B}\u{8C}\u{8D}\u{8E}\u{8F}",
+  "\u{90}\u{91}\u{92}\u{93}\u{94}\u{95}\u{96}\u{97}\u{98}\u{99}\u{9A}\u{9B}\u{9C}\u{9D}\u{9E}\u{9F}",
+  "\u{A0}กขฃคฅฆงจฉชซฌญฎฏ",
+  "ฐฑฒณดตถทธนบปผฝพฟ",
+  "ภมยรฤลฦวศษสหฬอฮฯ",
+  "ะ\u{E31}าำ\u{E34}\u{E35}\u{E36}\u{E37}\u{E38}\u{E39}\u{E3A}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}฿",
+  "เแโใไๅๆ\u{E47}\u{E48}\u{E49}\u{E4A}\u{E4B}\u{E4C}\u{E4D}\u{E4E}๏",
+  "๐๑๒๓๔๕๖๗๘๙๚๛\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}",
+);
+
+pub(super) const ISO_8859_13: &str = concat!(
+  "\u{80}\u{81}\u{82}\u{83}\u{84}\u{85}\u{86}\u{87}\u{88}\u{89}\u{8A}\u{8B}\u{8C}\u{8D}\u{8E}\u{8F}",
+  "\u{90}\u{91}\u{92}\u{93}\u{94}\u{95}\u{96}\u{97}\u{98}\u{99}\u{9A}\u{9B}\u{9C}\u{9D}\u{9E}\u{9F}",
+  "\u{A0}”¢£¤„¦§Ø©Ŗ«¬\u{AD}®Æ",
+  "°±²³“µ¶·ø¹ŗ»¼½¾æ",
+  "ĄĮĀĆÄÅĘĒČÉŹĖĢĶĪĻ",
+  "ŠŃŅÓŌÕÖ×ŲŁŚŪÜŻŽß",
+  "ąįāćäåęēčéźėģķīļ",
+  "šńņóōõö÷ųłśūüżž’",
+);
+
+pub(super) const ISO_8859_14: &str = concat!(
+  "\u{80}\u{81}\u{82}\u{83}\u{84}\u{85}\u{86}\u{87}\u{88}\u{89}\u{8A}\u{8B}\u{8C}\u{8D}\u{8E}\u{8F}",
+  "\u{90}\u{91}\u{92}\u{93}\u{94}\u{95}\u{96}\u{97}\u{98}\u{99}\u{9A}\u{9B}\u{9C}\u{9D}\u{9E}\u{9F}",
+  "\u{A0}Ḃḃ£ĊċḊ§Ẁ©ẂḋỲ\u{AD}®Ÿ",
+  "ḞḟĠġṀṁ¶ṖẁṗẃṠỳẄẅṡ",
+  "ÀÁÂÃÄÅÆÇÈÉÊËÌÍÎÏ",
+  "ŴÑÒÓÔÕÖṪØÙÚÛÜÝŶß",
+  "àáâãäåæçèéêëìíîï",
+  "ŵñòóôõöṫøùúûüýŷÿ",
+);
+
+pub(super) const ISO_8859_15: &str = concat!(
+  "\u{80}\u{81}\u{82}\u{83}\u{84}\u{85}\u{86}\u{87}\u{88}\u{89}\u{8A}\u{8B}\u{8C}\u{8D}\u{8E}\u{8F}",
+  "\u{90}\u{91}\u{92}\u{93}\u{94}\u{95}\u{96}\u{97}\u{98}\u{99}\u{9A}\u{9B}\u{9C}\u{9D}\u{9E}\u{9F}",
+  "\u{A0}¡¢£€¥Š§š©ª«¬\u{AD}®¯",
+  "°±²³Žµ¶·ž¹º»ŒœŸ¿",
+  "ÀÁÂÃÄÅÆÇÈÉÊËÌÍÎÏ",
+  "ÐÑÒÓÔÕÖ×ØÙÚÛÜÝÞß",
+  "àáâãäåæçèéêëìíîï",
+  "ðñòóôõö÷øùúûüýþÿ",
+);
+
+pub(super) const ISO_8859_16: &str = concat!(
+  "\u{80}\u{81}\u{82}\u{83}\u{84}\u{85}\u{86}\u{87}\u{88}\u{89}\u{8A}\u{8B}\u{8C}\u{8D}\u{8E}\u{8F}",
+  "\u{90}\u{91}\u{92}\u{93}\u{94}\u{95}\u{96}\u{97}\u{98}\u{99}\u{9A}\u{9B}\u{9C}\u{9D}\u{9E}\u{9F}",
+  "\u{A0}ĄąŁ€„Š§š©Ș«Ź\u{AD}źŻ",
+  "°±ČłŽ”¶·žčș»ŒœŸż",
+  "ÀÁÂĂÄĆÆÇÈÉÊËÌÍÎÏ",
+  "ĐŃÒÓÔŐÖŚŰÙÚÛÜĘȚß",
+  "àáâăäćæçèéêëìíîï",
+  "đńòóôőöśűùúûüęțÿ",
+);
+
+pub(super) const WINDOWS_1250: &str = concat!(
+  "€\u{FFFD}‚\u{FFFD}„…†‡\u{FFFD}‰Š‹ŚŤŽŹ",
+  "\u{FFFD}‘’“”•–—\u{FFFD}™š›śťžź",
+  "\u{A0}ˇ˘Ł¤Ą¦§¨©Ş«¬\u{AD}®Ż",
+  "°±˛ł´µ¶·¸ąş»Ľ˝ľż",
+  "ŔÁÂĂÄĹĆÇČÉĘËĚÍÎĎ",
+  "ĐŃŇÓÔŐÖ×ŘŮÚŰÜÝŢß",
+  "ŕáâăäĺćçčéęëěíîď",
+  "đńňóôőö÷řůúűüýţ˙",
+);
+
+pub(super) const WINDOWS_1251: &str = concat!(
+  "ЂЃ‚ѓ„…†‡€‰Љ‹ЊЌЋЏ",
+  "ђ‘’“”•–—\u{FFFD}™љ›њќћџ",
+  "\u{A0}ЎўЈ¤Ґ¦§Ё©Є«¬\u{AD}®Ї",
+  "°±Ііґµ¶·ё№є»јЅѕї",
+  "АБВГДЕЖЗИЙКЛМНОП",
+  "РСТУФХЦЧШЩЪЫЬЭЮЯ",
+  "абвгдежзийклмноп",
+  "рстуфхцчшщъыьэюя",
+);
+
+pub(super) const WINDOWS_1252: &str = concat!(
+  "€\u{FFFD}‚ƒ„…†‡ˆ‰Š‹Œ\u{FFFD}Ž\u{FFFD}",
+  "\u{FFFD}‘’“”•–—˜™š›œ\u{FFFD}žŸ",
+  "\u{A0}¡¢£¤¥¦§¨©ª«¬\u{AD}®¯",
+  "°±²³´µ¶·¸¹º»¼½¾¿",
+  "ÀÁÂÃÄÅÆÇÈÉÊËÌÍÎÏ",
+  "ÐÑÒÓÔÕÖ×ØÙÚÛÜÝÞß",
+  "àáâãäåæçèéêëìíîï",
+  "ðñòóôõö÷øùúûüýþÿ",
+);
+
+pub(super) const WINDOWS_1253: &str = concat!(
+  "€\u{FFFD}‚ƒ„…†‡\u{FFFD}‰\u{FFFD}‹\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}",
+  "\u{FFFD}‘’“”•–—\u{FFFD}™\u{FFFD}›\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}",
+  "\u{A0}΅Ά£¤¥¦§¨©\u{FFFD}«¬\u{AD}®―",
+  "°±²³΄µ¶·ΈΉΊ»Ό½ΎΏ",
+  "ΐΑΒΓΔΕΖΗΘΙΚΛΜΝΞΟ",
+  "ΠΡ\u{FFFD}ΣΤΥΦΧΨΩΪΫάέήί",
+  "ΰαβγδεζηθικλμνξο",
+  "πρςστυφχψωϊϋόύώ\u{FFFD}",
+);
+
+pub(super) const WINDOWS_1254: &str = concat!(
+  "€\u{FFFD}‚ƒ„…†‡ˆ‰Š‹Œ\u{FFFD}\u{FFFD}\u{FFFD}",
+  "\u{FFFD}‘’“”•–—˜™š›œ\u{FFFD}\u{FFFD}Ÿ",
+  "\u{A0}¡¢£¤¥¦§¨©ª«¬\u{AD}®¯",
+  "°±²³´µ¶·¸¹º»¼½¾¿",
+  "ÀÁÂÃÄÅÆÇÈÉÊËÌÍÎÏ",
+  "ĞÑÒÓÔÕÖ×ØÙÚÛÜİŞß",
+  "àáâãäåæçèéêëìíîï",
+  "ğñòóôõö÷øùúûüışÿ",
+);
+
+pub(super) const WINDOWS_1255: &str = concat!(
+  "€\u{FFFD}‚ƒ„…†‡ˆ‰\u{FFFD}‹\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}",
+  "\u{FFFD}‘’“”•–—˜™\u{FFFD}›\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}",
+  "\u{A0}¡¢£₪¥¦§¨©×«¬\u{AD}®¯",
+  "°±²³´µ¶·¸¹÷»¼½¾¿",
+  "\u{5B0}\u{5B1}\u{5B2}\u{5B3}\u{5B4}\u{5B5}\u{5B6}\u{5B7}\u{5B8}\u{5B9}\u{FFFD}\u{5BB}\u{5BC}\u{5BD}־\u{5BF}",
+  "׀\u{5C1}\u{5C2}׃װױײ׳״\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}\u{FFFD}",
+  "אבגדהוזחטיךכלםמן",
+  "נסעףפץצקרשת\u{FFFD}\u{FFFD}\u{200E}\u{200F}\u{FFFD}",
+);
+
+pub(super) const WINDOWS_1256: &str = concat!(
+  "€پ‚ƒ„…†‡ˆ‰ٹ‹Œچژڈ",
+  "گ‘’“”•–—ک™ڑ›œ\u{200C}\u{200D}ں",
+  "\u{A0}،¢£¤¥¦§¨©ھ«¬\u{AD}®¯",
+  "°±²³´µ¶·¸¹؛»¼½¾؟",
+  "ہءآأؤإئابةتثجحخد",
+  "ذرزسشصض×طظعغـفقك",
+  "àلâمنهوçèéêëىيîï",
+  "\u{64B}\u{64C}\u{64D}\u{64E}ô\u{64F}\u{650}÷\u{651}ù\u{652}ûü\u{200E}\u{200F}ے",
+);
+
+pub(super) const WINDOWS_1257: &str = concat!(
+  "€\u{FFFD}‚\u{FFFD}„…†‡\u{FFFD}‰\u{FFFD}‹\u{FFFD}¨ˇ¸",
+  "\u{FFFD}‘’“”•–—\u{FFFD}™\u{FFFD}›\u{FFFD}¯˛\u{FFFD}",
+  "\u{A0}\u{FFFD}¢£¤\u{FFFD}¦§Ø©Ŗ«¬\u{AD}®Æ",
+  "°±²³´µ¶·ø¹ŗ»¼½¾æ",
+  "ĄĮĀĆÄÅĘĒČÉŹĖĢĶĪĻ",
+  "ŠŃŅÓŌÕÖ×ŲŁŚŪÜŻŽß",
+  "ąįāćäåęēčéźėģķīļ",
+  "šńņóōõö÷ųłśūüżž˙",
+);
+
+pub(super) const WINDOWS_1258: &str = concat!(
+  "€\u{FFFD}‚ƒ„…†‡ˆ‰\u{FFFD}‹Œ\u{FFFD}\u{FFFD}\u{FFFD}",
+  "\u{FFFD}‘’“”•–—˜™\u{FFFD}›œ\u{FFFD}\u{FFFD}Ÿ",
+  "\u{A0}¡¢£¤¥¦§¨©ª«¬\u{AD}®¯",
+  "°±²³´µ¶·¸¹º»¼½¾¿",
+  "ÀÁÂĂÄÅÆÇÈÉÊË\u{300}ÍÎÏ",
+  "ĐÑ\u{309}ÓÔƠÖ×ØÙÚÛÜƯ\u{303}ß",
+  "àáâăäåæçèéêë\u{301}íîï",
+  "đñ\u{323}óôơö÷øùúûüư₫ÿ",
+);
+
+pub(super) const KOI8_R: &str = concat!(
+  "─│┌┐└┘├┤┬┴┼▀▄█▌▐",
+  "░▒▓⌠■∙√≈≤≥\u{A0}⌡°²·÷",
+  "═║╒ё╓╔╕╖╗╘╙╚╛╜╝╞",
+  "╟╠╡Ё╢╣╤╥╦╧╨╩╪╫╬©",
+  "юабцдефгхийклмно",
+  "пярстужвьызшэщчъ",
+  "ЮАБЦДЕФГХИЙКЛМНО",
+  "ПЯРСТУЖВЬЫЗШЭЩЧЪ",
+);
+
+pub(super) const KOI8_U: &str = concat!(
+  "─│┌┐└┘├┤┬┴┼▀▄█▌▐",
+  "░▒▓⌠■∙√≈≤≥\u{A0}⌡°²·÷",
+  "═║╒ёє╔ії╗╘╙╚╛ґ╝╞",
+  "╟╠╡ЁЄ╣ІЇ╦╧╨╩╪Ґ╬©",
+  "юабцдефгхийклмно",
+  "пярстужвьызшэщчъ",
+  "ЮАБЦДЕФГХИЙКЛМНО",
+  "ПЯРСТУЖВЬЫЗШЭЩЧЪ",
+);
@@ -8,19 +8,32 @@ pub mod xml;
 #[cfg(test)]
 pub mod test;
 
-/// A structure that indicates a position within a paticular file or stream by line and column numbers.
+/// A structure that indicates a position within a paticular file or stream by line and column numbers, and by the
+/// offsets from the beginning of the stream.
 ///
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Location {
-  /// The line number indicates the number of line feeds (CR, LF, or CRLF sequences) that have appeared from the
-  /// beginning of the stream to that position. 0 means the first line.
+  /// The line number indicates the number of line feeds (CR, LF, or CRLF sequences, and also NEL, CR NEL and LS in
+  /// XML 1.1) that have appeared from the beginning of the stream to that position. 0 means the first line.
   ///
   pub line_number: u64,
 
-  /// The column number indicates the number of characters from the beginning of the line to that position. 0 means the
-  /// beginning of the line.
+  /// The column number indicates the number of characters, that is, Unicode scalar values, from the beginning of the
+  /// line to that position. A supplementary character counts as one. 0 means the beginning of the line.
   ///
   pub column_number: u64,
+
+  /// The number of bytes from the beginning of the stream to that position before the line ends are translated. The
+  /// bytes are those in the encoding of a stream decoded by a [DecodingReader](io::DecodingReader), where an escape
+  /// sequence that changes the state of the decoder is counted in the character after it, and those of the characters
+  /// in UTF-8 for a reader that doesn't report them, such as a [StringReader](io::StringReader).
+  ///
+  pub byte_offset: u64,
+
+  /// The number of characters from the beginning of the stream to that position, before their line ends are
+  /// translated, so that CRLF counts as two.
+  ///
+  pub char_offset: u64,
 }
 
 impl Location {
@@ -28,9 +41,14 @@ impl Location {
   /// `Node.get_user_data(Location::USERDATA_NAME)`
   pub const USERDATA_NAME: &'static str = "xenolith.xml.parser.location";
 
-  /// Constructs location for the specified line/column.
+  /// Constructs location for the specified line/column, whose offsets are 0.
   pub fn new(line_number: u64, column_number: u64) -> Location {
-    return Location { line_number, column_number };
+    Location { line_number, column_number, byte_offset: 0, char_offset: 0 }
+  }
+
+  /// Constructs location for the specified line/column and offsets.
+  pub fn with_offsets(line_number: u64, column_number: u64, byte_offset: u64, char_offset: u64) -> Location {
+    Location { line_number, column_number, byte_offset, char_offset }
   }
 }
 
@@ -40,6 +58,39 @@ impl Display for Location {
   }
 }
 
+/// A range of text in a particular file or stream, from the `start` location to the `end` location exclusive, which
+/// a node parsed holds as user data named [Span::USERDATA_NAME]. `system_id` is the system identifier of the entity
+/// that the text is in, if known.
+///
+/// The span of an element covers its start tag, and that of its end tag is held separately as
+/// [Span::END_TAG_USERDATA_NAME]; an empty-element tag is both. The nodes in the replacement text of an internal
+/// entity have empty spans just after the reference, as their locations are.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Span {
+  pub start: Location,
+  pub end: Location,
+  pub system_id: Option<String>,
+}
+
+impl Span {
+  /// A name to refer to the span of a node parsed. `Node.get_user_data(Span::USERDATA_NAME)`
+  pub const USERDATA_NAME: &'static str = "xenolith.xml.parser.span";
+
+  /// A name to refer to the span of the end tag of an element parsed.
+  pub const END_TAG_USERDATA_NAME: &'static str = "xenolith.xml.parser.span.end-tag";
+
+  pub fn new(start: Location, end: Location, system_id: Option<&str>) -> Span {
+    Span { start, end, system_id: system_id.map(|id| id.to_string()) }
+  }
+
+  /// The text of the span in the `text` of the whole entity, taken by the byte offsets. The offsets are those in the
+  /// `text` only if the entity was read from a string or from bytes in UTF-8.
+  pub fn slice<'a>(&self, text: &'a str) -> Option<&'a str> {
+    text.get(self.start.byte_offset as usize..self.end.byte_offset as usize)
+  }
+}
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Error, Debug)]
@@ -48,4 +99,8 @@ pub enum Error {
   W3C(#[from] crate::xml::w3c::dom::DOMException),
   #[error(transparent)]
   IO(#[from] std::io::Error),
+  /// The document is not well-formed. `code` tells which production or constraint failed, and `system_id` is the
+  /// system identifier of the entity being read, if known.
+  #[error("{message} at {}{location} [{code}]", system_id.as_deref().unwrap_or_default())]
+  Parse { code: crate::xml::parser::ErrorCode, location: Location, system_id: Option<String>, message: String },
 }
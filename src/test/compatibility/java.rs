@@ -38,7 +38,7 @@ pub fn run(test_case: &str, code: &str) -> String {
 fn capture_output(cmd: &mut Command) -> String {
   let output = match cmd.output() {
     Ok(output) => output,
-    Err(err) => panic!("[{}] {:?}", cmd.get_program().to_string_lossy().to_string(), err),
+    Err(err) => panic!("[{}] {:?}", cmd.get_program().to_string_lossy(), err),
   };
   if !output.status.success() {
     let msg = String::from_utf8_lossy(&output.stderr).to_string();
@@ -3,7 +3,7 @@
 pub mod compatibility;
 
 use std::env;
-use std::fs::{create_dir_all, remove_dir_all, create_dir};
+use std::fs::{create_dir, create_dir_all, remove_dir_all};
 use std::path::{Path, PathBuf};
 
 pub struct TempDir(PathBuf);
@@ -41,6 +41,20 @@ pub fn temp_dir(test_case: &str) -> TempDir {
   panic!("The temporary directory namespace is full: {:?}", dir)
 }
 
+/// Runs the future on the current thread until it completes. The future is polled repeatedly without waiting for a
+/// wake-up, which is enough for the readers and writers in tests that are pending only momentarily.
+///
+#[cfg(feature = "async")]
+pub fn block_on<F: std::future::Future>(future: F) -> F::Output {
+  let mut future = std::pin::pin!(future);
+  let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+  loop {
+    if let std::task::Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+      return output;
+    }
+  }
+}
+
 // use std::rc::{Rc, Weak};
 
 // trait Node {
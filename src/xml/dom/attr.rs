@@ -0,0 +1,130 @@
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+
+use crate::xml::w3c::dom::{self as W3C, NodeType};
+
+use super::*;
+
+pub struct Attr {
+  pub(super) core: NodeCore,
+  name: String,
+  namespace_uri: Option<String>,
+  prefix: Option<String>,
+  local_name: Option<String>,
+  value: RefCell<String>,
+  specified: Cell<bool>,
+  is_id: Cell<bool>,
+  owner_element: RefCell<Weak<Element>>,
+}
+
+impl Attr {
+  pub(super) fn new(
+    owner_document: Weak<Document>, name: &str, namespace_uri: Option<&str>, prefix: Option<&str>,
+    local_name: Option<&str>,
+  ) -> Rc<Attr> {
+    Rc::new_cyclic(|this| Attr {
+      core: NodeCore::new(WeakNodeRef::Attr(this.clone()), owner_document),
+      name: name.to_string(),
+      namespace_uri: namespace_uri.map(|n| n.to_string()),
+      prefix: prefix.map(|p| p.to_string()),
+      local_name: local_name.map(|l| l.to_string()),
+      value: RefCell::new(String::new()),
+      specified: Cell::new(true),
+      is_id: Cell::new(false),
+      owner_element: RefCell::new(Weak::new()),
+    })
+  }
+
+  /// [Attr.isId](https://www.w3.org/TR/DOM-Level-3-Core/core.html#Attr-isId)
+  pub fn is_id(&self) -> bool {
+    self.is_id.get()
+  }
+
+  pub(crate) fn set_specified(&self, specified: bool) {
+    self.specified.set(specified);
+  }
+
+  pub(crate) fn set_owner_element(&self, owner_element: Weak<Element>) {
+    *self.owner_element.borrow_mut() = owner_element;
+  }
+}
+
+impl W3C::Node<DOMImplementation> for Attr {
+  node_tree_methods!();
+
+  fn attributes(&self) -> Option<&NamedNodeMap> {
+    None
+  }
+  fn base_uri(&self) -> Option<String> {
+    None
+  }
+  fn local_name(&self) -> Option<&str> {
+    self.local_name.as_deref()
+  }
+  fn namespace_uri(&self) -> Option<&str> {
+    self.namespace_uri.as_deref()
+  }
+  fn node_name(&self) -> &str {
+    &self.name
+  }
+  fn node_type(&self) -> NodeType {
+    NodeType::AttributeNode
+  }
+  fn node_value(&self) -> W3C::Result<Option<String>> {
+    Ok(Some(self.value.borrow().clone()))
+  }
+  fn set_node_value(&self, value: &str) -> W3C::Result<()> {
+    W3C::Attr::set_value(self, value)
+  }
+  fn prefix(&self) -> Option<String> {
+    self.prefix.clone()
+  }
+  /// The qualified name is borrowed by `node_name()` and `name()`, so the prefix of an attribute cannot be changed.
+  fn set_prefix(&self, _prefix: Option<String>) -> W3C::Result<()> {
+    Err(W3C::DOMException::NotSupported)
+  }
+  fn text_content(&self) -> Option<String> {
+    Some(self.value.borrow().clone())
+  }
+  fn set_text_content(&self, text_content: &str) -> W3C::Result<()> {
+    W3C::Attr::set_value(self, text_content)
+  }
+  fn has_attributes(&self) -> bool {
+    false
+  }
+}
+
+impl W3C::Attr<DOMImplementation> for Attr {
+  fn name(&self) -> &str {
+    &self.name
+  }
+  fn specified(&self) -> bool {
+    self.specified.get()
+  }
+  fn value(&self) -> String {
+    self.value.borrow().clone()
+  }
+  fn set_value(&self, value: &str) -> W3C::Result<()> {
+    *self.value.borrow_mut() = value.to_string();
+    self.specified.set(true);
+    Ok(())
+  }
+  fn owner_element(&self) -> Option<Rc<Element>> {
+    self.owner_element.borrow().upgrade()
+  }
+
+  fn clone_attr(&self, _deep: bool) -> Rc<Self> {
+    let attr = Attr::new(
+      self.core.owner_document_weak(),
+      &self.name,
+      self.namespace_uri.as_deref(),
+      self.prefix.as_deref(),
+      self.local_name.as_deref(),
+    );
+    *attr.value.borrow_mut() = self.value.borrow().clone();
+    attr.specified.set(true);
+    attr.is_id.set(self.is_id.get());
+    self.core.clone_user_data(&NodeRef::Attr(attr.clone()));
+    attr
+  }
+}
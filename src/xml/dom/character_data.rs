@@ -0,0 +1,192 @@
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+
+use crate::xml::w3c::dom::{self as W3C, NodeType};
+
+use super::*;
+
+/// Expands [W3C::Node] and [W3C::CharacterData] for the node type that has `core: NodeCore` and
+/// `data: RefCell<String>` fields.
+///
+/// `element_content_whitespace` is used only by [Text]; see [Text::is_element_content_whitespace()].
+macro_rules! character_data {
+  ($name:ident, $node_name:expr, $node_type:expr) => {
+    pub struct $name {
+      pub(super) core: NodeCore,
+      data: RefCell<String>,
+      element_content_whitespace: Cell<bool>,
+    }
+
+    impl $name {
+      pub(super) fn new(owner_document: Weak<Document>, data: &str) -> Rc<$name> {
+        Rc::new_cyclic(|this| $name {
+          core: NodeCore::new(WeakNodeRef::$name(this.clone()), owner_document),
+          data: RefCell::new(data.to_string()),
+          element_content_whitespace: Cell::new(false),
+        })
+      }
+
+      fn duplicate(&self) -> Rc<$name> {
+        let node = $name::new(self.core.owner_document_weak(), &self.data.borrow());
+        node.element_content_whitespace.set(self.element_content_whitespace.get());
+        self.core.clone_user_data(&NodeRef::$name(node.clone()));
+        node
+      }
+    }
+
+    impl W3C::Node<DOMImplementation> for $name {
+      node_tree_methods!();
+
+      fn attributes(&self) -> Option<&NamedNodeMap> {
+        None
+      }
+      fn base_uri(&self) -> Option<String> {
+        None
+      }
+      fn local_name(&self) -> Option<&str> {
+        None
+      }
+      fn namespace_uri(&self) -> Option<&str> {
+        None
+      }
+      fn node_name(&self) -> &str {
+        $node_name
+      }
+      fn node_type(&self) -> NodeType {
+        $node_type
+      }
+      fn node_value(&self) -> W3C::Result<Option<String>> {
+        Ok(Some(self.data.borrow().clone()))
+      }
+      fn set_node_value(&self, value: &str) -> W3C::Result<()> {
+        W3C::CharacterData::set_data(self, value)
+      }
+      fn prefix(&self) -> Option<String> {
+        None
+      }
+      fn set_prefix(&self, _prefix: Option<String>) -> W3C::Result<()> {
+        Ok(())
+      }
+      fn text_content(&self) -> Option<String> {
+        Some(self.data.borrow().clone())
+      }
+      fn set_text_content(&self, text_content: &str) -> W3C::Result<()> {
+        W3C::CharacterData::set_data(self, text_content)
+      }
+      fn has_attributes(&self) -> bool {
+        false
+      }
+    }
+
+    impl W3C::CharacterData<DOMImplementation> for $name {
+      fn data(&self) -> String {
+        self.data.borrow().clone()
+      }
+      fn set_data(&self, data: &str) -> W3C::Result<()> {
+        *self.data.borrow_mut() = data.to_string();
+        Ok(())
+      }
+      fn length(&self) -> usize {
+        self.data.borrow().chars().count()
+      }
+      fn substring_data(&self, offset: usize, count: usize) -> W3C::Result<String> {
+        let data = self.data.borrow();
+        let (begin, end) = char_range(&data, offset, count)?;
+        Ok(data[begin..end].to_string())
+      }
+      fn append_data(&self, arg: &str) -> W3C::Result<()> {
+        self.data.borrow_mut().push_str(arg);
+        Ok(())
+      }
+      fn insert_data(&self, offset: usize, arg: &str) -> W3C::Result<()> {
+        self.replace_data(offset, 0, arg)
+      }
+      fn delete_data(&self, offset: usize, count: usize) -> W3C::Result<()> {
+        self.replace_data(offset, count, "")
+      }
+      fn replace_data(&self, offset: usize, count: usize, arg: &str) -> W3C::Result<()> {
+        let mut data = self.data.borrow_mut();
+        let (begin, end) = char_range(&data, offset, count)?;
+        data.replace_range(begin..end, arg);
+        Ok(())
+      }
+    }
+  };
+}
+
+character_data!(Text, "#text", NodeType::TextNode);
+character_data!(Comment, "#comment", NodeType::CommentNode);
+character_data!(CDATASection, "#cdata-section", NodeType::CDATASectionNode);
+
+impl Text {
+  /// [Text.isElementContentWhitespace](https://www.w3.org/TR/DOM-Level-3-Core/core.html#Text3-isElementContentWhitespace),
+  /// which is `true` if the text is whitespace in an element declared to have element content by the DTD.
+  pub fn is_element_content_whitespace(&self) -> bool {
+    self.element_content_whitespace.get()
+  }
+
+  pub(crate) fn set_element_content_whitespace(&self, element_content_whitespace: bool) {
+    self.element_content_whitespace.set(element_content_whitespace);
+  }
+}
+
+impl W3C::Text<DOMImplementation> for Text {
+  fn split_text(&self, offset: usize) -> W3C::Result<Rc<Self>> {
+    let new_text = self.duplicate();
+    split_data(&self.core, &self.data, &new_text.data, NodeRef::Text(new_text.clone()), offset)?;
+    Ok(new_text)
+  }
+
+  fn clone_text(&self, _deep: bool) -> Rc<Self> {
+    self.duplicate()
+  }
+}
+
+impl W3C::Comment<DOMImplementation> for Comment {
+  fn clone_comment(&self, _deep: bool) -> Rc<Self> {
+    self.duplicate()
+  }
+}
+
+/// Note that a `CDATASection` is not a [Text] in Rust, so `split_text()` returns a new `CDATASection`.
+///
+impl W3C::Text<DOMImplementation> for CDATASection {
+  fn split_text(&self, offset: usize) -> W3C::Result<Rc<Self>> {
+    let new_text = self.duplicate();
+    split_data(&self.core, &self.data, &new_text.data, NodeRef::CDATASection(new_text.clone()), offset)?;
+    Ok(new_text)
+  }
+
+  fn clone_text(&self, _deep: bool) -> Rc<Self> {
+    self.duplicate()
+  }
+}
+
+impl W3C::CDATASection<DOMImplementation> for CDATASection {
+  fn clone_cdata_section(&self, _deep: bool) -> Rc<Self> {
+    self.duplicate()
+  }
+}
+
+/// Converts the range in characters to the range in bytes.
+///
+fn char_range(data: &str, offset: usize, count: usize) -> W3C::Result<(usize, usize)> {
+  let mut indices = data.char_indices().map(|(i, _)| i).chain(std::iter::once(data.len()));
+  let begin = indices.nth(offset).ok_or(W3C::DOMException::IndexSize)?;
+  let end = if count == 0 { Some(begin) } else { indices.nth(count - 1) };
+  Ok((begin, end.unwrap_or(data.len())))
+}
+
+/// Moves the data after `offset` to `new_data` and inserts `new_node` as the next sibling of the node.
+///
+fn split_data(
+  core: &NodeCore, data: &RefCell<String>, new_data: &RefCell<String>, new_node: NodeRef, offset: usize,
+) -> W3C::Result<()> {
+  let (begin, _) = char_range(&data.borrow(), offset, 0)?;
+  *new_data.borrow_mut() = data.borrow_mut().split_off(begin);
+  if let Some(parent) = core.parent_node() {
+    let next = core.sibling(1);
+    core_of(&parent).insert_before(new_node, next.as_ref())?;
+  }
+  Ok(())
+}
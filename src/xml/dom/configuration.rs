@@ -0,0 +1,214 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::xml::w3c::dom::{self as W3C, DOMErrorSeverity};
+use crate::{Error, Location};
+
+use super::*;
+
+pub type DOMParameter = W3C::DOMParameter<DOMImplementation>;
+pub type DOMError = W3C::DOMError<DOMImplementation>;
+pub type DOMLocator = W3C::DOMLocator<DOMImplementation>;
+
+/// [DOMConfiguration](W3C::DOMConfiguration) that controls how a document is built by
+/// [DocumentBuilder](crate::xml::parser::DocumentBuilder), normalized by [Document::normalize_document()] and written
+/// by [LSSerializer].
+///
+/// A clone refers to the same parameters, so a configuration changed through a builder also applies to the documents
+/// it has built, and it can be handed to a serializer as it is. The supported parameters are:
+///
+/// | Parameter                    | Default | Values          |
+/// |:-----------------------------|:--------|:----------------|
+/// | `cdata-sections`             | `true`  | `true`, `false` |
+/// | `comments`                   | `true`  | `true`, `false` |
+/// | `datatype-normalization`     | `false` | `false`         |
+/// | `element-content-whitespace` | `true`  | `true`, `false` |
+/// | `entities`                   | `true`  | `true`, `false` |
+/// | `error-handler`              | `None`  | any handler     |
+/// | `infoset`                    | -       | `true`, `false` |
+/// | `namespaces`                 | `true`  | `true`, `false` |
+/// | `split-cdata-sections`       | `true`  | `true`, `false` |
+/// | `validate`                   | `false` | `false`         |
+/// | `well-formed`                | `true`  | `true`, `false` |
+///
+/// `infoset` is `true` while the parameters it implies have the values of the XML Information Set, and setting it to
+/// `true` sets them; setting it to `false` has no effect, as the specification.
+///
+/// ```
+/// use xenolith::xml::dom::{DOMConfiguration, DOMParameter};
+/// use xenolith::xml::w3c::dom::{DOMConfiguration as _, DOMException};
+///
+/// let config = DOMConfiguration::new();
+/// config.set_parameter("Comments", DOMParameter::Boolean(false)).unwrap();
+/// assert!(matches!(config.get_parameter("comments"), Ok(DOMParameter::Boolean(false))));
+/// assert!(!config.can_set_parameter("validate", &DOMParameter::Boolean(true)));
+/// assert!(matches!(config.set_parameter("unknown", DOMParameter::Boolean(true)), Err(DOMException::NotFound)));
+/// ```
+///
+#[derive(Clone)]
+pub struct DOMConfiguration {
+  parameters: Rc<RefCell<Parameters>>,
+}
+
+struct Parameters {
+  /// The values of the boolean parameters in the order of [FLAGS].
+  flags: [bool; FLAGS.len()],
+  error_handler: Option<Rc<dyn W3C::DOMErrorHandler<DOMImplementation>>>,
+}
+
+/// The boolean parameters with their default values, and whether the value other than the default is supported.
+const FLAGS: [(&str, bool, bool); 9] = [
+  ("cdata-sections", true, true),
+  ("comments", true, true),
+  ("datatype-normalization", false, false),
+  ("element-content-whitespace", true, true),
+  ("entities", true, true),
+  ("namespaces", true, true),
+  ("split-cdata-sections", true, true),
+  ("validate", false, false),
+  ("well-formed", true, true),
+];
+
+/// The values that `infoset` implies.
+const INFOSET: [(&str, bool); 7] = [
+  ("cdata-sections", false),
+  ("comments", true),
+  ("datatype-normalization", false),
+  ("element-content-whitespace", true),
+  ("entities", false),
+  ("namespaces", true),
+  ("well-formed", true),
+];
+
+impl DOMConfiguration {
+  pub fn new() -> DOMConfiguration {
+    let parameters = Parameters { flags: FLAGS.map(|(_, default, _)| default), error_handler: None };
+    DOMConfiguration { parameters: Rc::new(RefCell::new(parameters)) }
+  }
+
+  /// Returns the value of the boolean parameter `name`, which must be one of the lowercase names in [FLAGS].
+  pub(crate) fn flag(&self, name: &str) -> bool {
+    let i = flag_index(name).expect("the parameter must be a boolean one");
+    self.parameters.borrow().flags[i]
+  }
+
+  pub(crate) fn set_flag(&self, name: &str, value: bool) {
+    let i = flag_index(name).expect("the parameter must be a boolean one");
+    self.parameters.borrow_mut().flags[i] = value;
+  }
+
+  /// Reports an error about the `node` to the `error-handler`, and returns whether the processing should continue.
+  /// Without a handler, the processing continues unless the error is fatal.
+  pub(crate) fn report(
+    &self, severity: DOMErrorSeverity, error_type: &str, message: &str, node: Option<&NodeRef>,
+  ) -> bool {
+    let location = node.and_then(|node| node.as_node().get_user_data(Location::USERDATA_NAME));
+    let location = location.as_ref().and_then(|location| location.downcast_ref::<Location>());
+    let uri = node.and_then(|node| core_of(node).owner_document()).and_then(|document| document.document_uri());
+    let error = DOMError {
+      severity,
+      message: message.to_string(),
+      error_type: error_type.to_string(),
+      related_data: node.cloned(),
+      location: DOMLocator {
+        line_number: location.map(|location| location.line_number + 1),
+        column_number: location.map(|location| location.column_number + 1),
+        related_node: node.cloned(),
+        uri,
+      },
+    };
+    self.handle(error)
+  }
+
+  /// Reports an error of the parser to the `error-handler`, and returns whether the parsing should continue. The
+  /// type of a well-formedness error is the code of [ErrorCode](crate::xml::parser::ErrorCode), such as `"XML201"`.
+  pub(crate) fn report_parse_error(&self, severity: DOMErrorSeverity, error: &Error) -> bool {
+    let (error_type, location, uri) = match error {
+      Error::Parse { code, location, system_id, .. } => (format!("XML{:03}", code.code()), Some(location), system_id),
+      Error::IO(_) => ("io-error".to_string(), None, &None),
+      Error::W3C(_) => ("dom-exception".to_string(), None, &None),
+    };
+    let error = DOMError {
+      severity,
+      message: error.to_string(),
+      error_type,
+      related_data: None,
+      location: DOMLocator {
+        line_number: location.map(|location| location.line_number + 1),
+        column_number: location.map(|location| location.column_number + 1),
+        related_node: None,
+        uri: uri.clone(),
+      },
+    };
+    self.handle(error)
+  }
+
+  fn handle(&self, error: DOMError) -> bool {
+    // the handler is called without borrowing the parameters, so that it can change them
+    let handler = self.parameters.borrow().error_handler.clone();
+    match handler {
+      Some(handler) => handler.handle_error(&error),
+      None => error.severity != DOMErrorSeverity::FatalError,
+    }
+  }
+
+  fn infoset(&self) -> bool {
+    INFOSET.iter().all(|(name, value)| self.flag(name) == *value)
+  }
+}
+
+impl Default for DOMConfiguration {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl W3C::DOMConfiguration<DOMImplementation> for DOMConfiguration {
+  fn set_parameter(&self, name: &str, value: DOMParameter) -> W3C::Result<()> {
+    let name = name.to_ascii_lowercase();
+    match (name.as_str(), value) {
+      ("infoset", DOMParameter::Boolean(true)) => INFOSET.iter().for_each(|(name, value)| self.set_flag(name, *value)),
+      ("infoset", DOMParameter::Boolean(false)) => (),
+      ("error-handler", DOMParameter::ErrorHandler(handler)) => self.parameters.borrow_mut().error_handler = handler,
+      (name, DOMParameter::Boolean(value)) if flag_index(name).is_some() => {
+        let (_, default, supported) = FLAGS[flag_index(name).unwrap()];
+        if value != default && !supported {
+          return Err(W3C::DOMException::NotSupported);
+        }
+        self.set_flag(name, value);
+      }
+      (name, _) if self.parameter_names().iter().any(|n| n == name) => return Err(W3C::DOMException::TypeMismatch),
+      _ => return Err(W3C::DOMException::NotFound),
+    }
+    Ok(())
+  }
+
+  fn get_parameter(&self, name: &str) -> W3C::Result<DOMParameter> {
+    match name.to_ascii_lowercase().as_str() {
+      "infoset" => Ok(DOMParameter::Boolean(self.infoset())),
+      "error-handler" => Ok(DOMParameter::ErrorHandler(self.parameters.borrow().error_handler.clone())),
+      name if flag_index(name).is_some() => Ok(DOMParameter::Boolean(self.flag(name))),
+      _ => Err(W3C::DOMException::NotFound),
+    }
+  }
+
+  fn can_set_parameter(&self, name: &str, value: &DOMParameter) -> bool {
+    match (name.to_ascii_lowercase().as_str(), value) {
+      ("infoset", DOMParameter::Boolean(_)) | ("error-handler", DOMParameter::ErrorHandler(_)) => true,
+      (name, DOMParameter::Boolean(value)) => match flag_index(name) {
+        Some(i) => *value == FLAGS[i].1 || FLAGS[i].2,
+        None => false,
+      },
+      _ => false,
+    }
+  }
+
+  fn parameter_names(&self) -> Vec<String> {
+    let names = FLAGS.iter().map(|(name, _, _)| *name).chain(["error-handler", "infoset"]);
+    names.map(|name| name.to_string()).collect()
+  }
+}
+
+fn flag_index(name: &str) -> Option<usize> {
+  FLAGS.iter().position(|(n, _, _)| *n == name)
+}
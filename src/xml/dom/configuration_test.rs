@@ -0,0 +1,195 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::io::StringReader;
+use crate::xml::dom::{DOMConfiguration, DOMError, DOMImplementation, DOMParameter, Document, LSSerializer, NodeRef};
+use crate::xml::parser::{DocumentBuilder, InputSource};
+use crate::xml::w3c::dom::{
+  DOMConfiguration as _, DOMErrorHandler, DOMErrorSeverity, DOMException, DOMImplementation as _, Document as _,
+  Element as _, Node as _,
+};
+use crate::Error;
+
+/// Collects the errors reported as `severity type line:column`.
+#[derive(Default)]
+struct Errors(RefCell<Vec<String>>);
+
+impl DOMErrorHandler<DOMImplementation> for Errors {
+  fn handle_error(&self, error: &DOMError) -> bool {
+    let location = format!("{}:{}", error.location.line_number.unwrap_or(0), error.location.column_number.unwrap_or(0));
+    self.0.borrow_mut().push(format!("{:?} {} {}", error.severity, error.error_type, location));
+    error.severity != DOMErrorSeverity::FatalError
+  }
+}
+
+fn set(config: &DOMConfiguration, name: &str, value: bool) {
+  config.set_parameter(name, DOMParameter::Boolean(value)).unwrap();
+}
+
+fn parse(builder: &DocumentBuilder, xml: &str) -> Rc<Document> {
+  builder.parse(InputSource::new(StringReader::new(xml))).unwrap()
+}
+
+fn serialize(document: &Rc<Document>) -> String {
+  let mut serializer = LSSerializer::new();
+  serializer.set_xml_declaration(false);
+  serializer.set_dom_config(document.dom_config());
+  serializer.write_to_string(&NodeRef::Document(document.clone())).unwrap()
+}
+
+#[test]
+fn parameters() {
+  let config = DOMConfiguration::new();
+  assert_eq!(11, config.parameter_names().len());
+  assert!(matches!(config.get_parameter("infoset"), Ok(DOMParameter::Boolean(false))));
+  set(&config, "INFOSET", true);
+  assert!(matches!(config.get_parameter("infoset"), Ok(DOMParameter::Boolean(true))));
+  assert!(matches!(config.get_parameter("entities"), Ok(DOMParameter::Boolean(false))));
+  assert!(matches!(config.get_parameter("cdata-sections"), Ok(DOMParameter::Boolean(false))));
+  set(&config, "infoset", false);
+  assert!(matches!(config.get_parameter("infoset"), Ok(DOMParameter::Boolean(true))));
+  set(&config, "comments", false);
+  assert!(matches!(config.get_parameter("infoset"), Ok(DOMParameter::Boolean(false))));
+
+  let clone = config.clone();
+  set(&clone, "comments", true);
+  assert!(matches!(config.get_parameter("comments"), Ok(DOMParameter::Boolean(true))));
+
+  assert!(config.can_set_parameter("validate", &DOMParameter::Boolean(false)));
+  assert!(!config.can_set_parameter("datatype-normalization", &DOMParameter::Boolean(true)));
+  assert!(!config.can_set_parameter("comments", &DOMParameter::ErrorHandler(None)));
+  let result = config.set_parameter("validate", DOMParameter::Boolean(true));
+  assert!(matches!(result, Err(DOMException::NotSupported)));
+  let result = config.set_parameter("error-handler", DOMParameter::Boolean(true));
+  assert!(matches!(result, Err(DOMException::TypeMismatch)));
+  assert!(matches!(config.get_parameter("schema-type"), Err(DOMException::NotFound)));
+  assert!(matches!(config.get_parameter("error-handler"), Ok(DOMParameter::ErrorHandler(None))));
+}
+
+#[test]
+fn build_with_parameters() {
+  let xml = "<!DOCTYPE a [<!ELEMENT a (b*)><!ELEMENT b (#PCDATA)><!ENTITY e 'x<c/>y'>]>\
+             <a>\n  <b>&e;<![CDATA[<]]><!--c--></b>\n</a>";
+  let mut builder = DocumentBuilder::new();
+  assert_eq!(
+    "<a>\n  <b>x<c/>y<![CDATA[<]]><!--c--></b>\n</a>",
+    serialize(&parse(&builder, xml)).split_once('\n').unwrap().1
+  );
+
+  let config = builder.dom_config().clone();
+  set(&config, "entities", true);
+  set(&config, "cdata-sections", false);
+  set(&config, "comments", false);
+  set(&config, "element-content-whitespace", false);
+  let document = parse(&builder, xml);
+  let b = document.document_element().unwrap().first_child().unwrap();
+  assert!(matches!(b.as_node().first_child(), Some(NodeRef::EntityReference(_))));
+  assert_eq!("<a><b>&e;&lt;</b></a>", serialize(&document).split_once('\n').unwrap().1);
+
+  // errors of the parser are reported to the handler
+  let errors = Rc::new(Errors::default());
+  config.set_parameter("error-handler", DOMParameter::ErrorHandler(Some(errors.clone()))).unwrap();
+  let (_, diagnostics) = builder.parse_recovering(InputSource::new(StringReader::new("<a>R&D</a>\n<b/>")));
+  assert_eq!(2, diagnostics.len());
+  assert_eq!(vec!["Error XML151 1:5", "FatalError XML107 2:1"], *errors.0.borrow());
+
+  builder.set_namespace_aware(false);
+  assert!(matches!(config.get_parameter("namespaces"), Ok(DOMParameter::Boolean(false))));
+  assert!(matches!(builder.parse(InputSource::new(StringReader::new("<a"))), Err(Error::Parse { .. })));
+}
+
+#[test]
+fn normalize_document() {
+  let builder = DocumentBuilder::new();
+  set(builder.dom_config(), "entities", true);
+  let xml = "<!DOCTYPE a [<!ELEMENT a (b)><!ENTITY e 'x<b/>y'>]><a> &e; <!--c--></a>";
+  let document = parse(&builder, xml);
+  let errors = Rc::new(Errors::default());
+  let config = document.dom_config();
+  config.set_parameter("error-handler", DOMParameter::ErrorHandler(Some(errors.clone()))).unwrap();
+  set(&config, "entities", false);
+  set(&config, "comments", false);
+  set(&config, "element-content-whitespace", false);
+  let a = document.document_element().unwrap();
+  let cdata = document.create_cdata_section("]]>").unwrap();
+  a.append_child(NodeRef::CDATASection(cdata)).unwrap();
+  assert!(document.normalize_document());
+  assert_eq!("<a>x<b/>y<![CDATA[]]]]><![CDATA[>]]></a>", serialize(&document).split_once('\n').unwrap().1);
+  assert_eq!(vec!["Warning cdata-sections-splitted 0:0"], *errors.0.borrow());
+
+  // the handler stops normalization at an error
+  set(&config, "split-cdata-sections", false);
+  a.first_child().unwrap().as_node().set_node_value("\u{1}").unwrap();
+  let text = document.create_text_node("]]>");
+  a.append_child(NodeRef::Text(text)).unwrap();
+  errors.0.borrow_mut().clear();
+  config
+    .set_parameter("error-handler", DOMParameter::ErrorHandler(Some(Rc::new(StopAtError(errors.clone())))))
+    .unwrap();
+  assert!(!document.normalize_document());
+  assert_eq!(vec!["Error wf-invalid-character 1:59"], *errors.0.borrow());
+}
+
+struct StopAtError(Rc<Errors>);
+
+impl DOMErrorHandler<DOMImplementation> for StopAtError {
+  fn handle_error(&self, error: &DOMError) -> bool {
+    self.0.handle_error(error);
+    error.severity == DOMErrorSeverity::Warning
+  }
+}
+
+#[test]
+fn fix_namespaces() {
+  let document = DOMImplementation::new().create_document(Some("urn:a"), Some("p:a"), None).unwrap();
+  let a = document.document_element().unwrap();
+  a.set_attribute_ns(Some("urn:x"), "x", "1").unwrap();
+  a.set_attribute_ns(Some("urn:a"), "p:y", "2").unwrap();
+  let b = document.create_element_ns(None, "b").unwrap();
+  b.set_attribute_ns(Some("urn:x"), "q:z", "3").unwrap();
+  a.append_child(NodeRef::Element(b)).unwrap();
+
+  let expected =
+    "<p:a NS1:x=\"1\" p:y=\"2\" xmlns:p=\"urn:a\" xmlns:NS1=\"urn:x\"><b q:z=\"3\" xmlns:q=\"urn:x\"/></p:a>";
+  assert_eq!(expected, serialize(&document));
+
+  assert!(document.normalize_document());
+  let expected =
+    "<p:a p:y=\"2\" xmlns:p=\"urn:a\" xmlns:NS1=\"urn:x\" NS1:x=\"1\"><b q:z=\"3\" xmlns:q=\"urn:x\"/></p:a>";
+  assert_eq!(expected, serialize(&document));
+
+  set(&document.dom_config(), "well-formed", true);
+  a.set_attribute("c", "\u{0}").unwrap();
+  let mut serializer = LSSerializer::new();
+  serializer.set_dom_config(document.dom_config());
+  let result = serializer.write_to_string(&NodeRef::Document(document.clone()));
+  assert!(matches!(result, Err(Error::W3C(DOMException::InvalidCharacter))));
+}
+
+#[test]
+fn stop_serialization_at_warning() {
+  struct StopAtWarning(Rc<Errors>);
+
+  impl DOMErrorHandler<DOMImplementation> for StopAtWarning {
+    fn handle_error(&self, error: &DOMError) -> bool {
+      self.0.handle_error(error);
+      false
+    }
+  }
+
+  let document = parse(&DocumentBuilder::new(), "<a><b>x</b></a>");
+  let b = document.document_element().unwrap().first_child().unwrap();
+  b.as_node().append_child(NodeRef::CDATASection(document.create_cdata_section("]]>").unwrap())).unwrap();
+  let errors = Rc::new(Errors::default());
+  let config = document.dom_config();
+  config
+    .set_parameter("error-handler", DOMParameter::ErrorHandler(Some(Rc::new(StopAtWarning(errors.clone())))))
+    .unwrap();
+  let mut serializer = LSSerializer::new();
+  serializer.set_dom_config(config);
+  let result = serializer.write_to_string(&NodeRef::Document(document.clone()));
+  assert!(matches!(result, Err(Error::IO(_))));
+  let result = serializer.write(&NodeRef::Document(document.clone()), &mut Vec::new());
+  assert!(matches!(result, Err(Error::IO(_))));
+  assert_eq!(vec!["Warning cdata-sections-splitted 0:0"; 2], *errors.0.borrow());
+}
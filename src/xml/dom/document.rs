@@ -1,199 +1,257 @@
-use crate::xml::w3c::dom::{self as W3C, DOMObject, DOMUserData};
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
 
-use super::{
-  Attr, CDATASection, Comment, DOMImplementation as IMPL, DocumentFragment, DocumentType, Element, EntityReference,
-  NamedNodeMap, NodeList, NodeRef, ProcessingInstruction, Text,
-};
+use crate::xml::parser::chars::is_name;
+use crate::xml::w3c::dom::{self as W3C, NodeType};
+
+use super::*;
 
 pub struct Document {
-  base_uri: Option<String>,
-  child_nodes: NodeList,
+  pub(super) core: NodeCore,
+  implementation: DOMImplementation,
+  document_uri: RefCell<Option<String>>,
+  xml_version: RefCell<String>,
+  dom_config: RefCell<DOMConfiguration>,
 }
 
-impl W3C::Node<IMPL> for Document {
-  fn attributes(&self) -> Option<NamedNodeMap> {
-    None
+impl Document {
+  /// Creates an empty document. Use [W3C::DOMImplementation::create_document()] to create a document with a document
+  /// element.
+  ///
+  pub fn new(implementation: DOMImplementation) -> Rc<Document> {
+    Rc::new_cyclic(|this| Document {
+      core: NodeCore::new(WeakNodeRef::Document(this.clone()), Weak::new()),
+      implementation,
+      document_uri: RefCell::new(None),
+      xml_version: RefCell::new("1.0".to_string()),
+      dom_config: RefCell::new(DOMConfiguration::new()),
+    })
   }
-  fn base_uri(&self) -> Option<String> {
-    self.base_uri
+
+  /// [Document.documentURI](https://www.w3.org/TR/DOM-Level-3-Core/core.html#Document3-documentURI)
+  pub fn document_uri(&self) -> Option<String> {
+    self.document_uri.borrow().clone()
+  }
+
+  pub fn set_document_uri(&self, document_uri: Option<&str>) {
+    *self.document_uri.borrow_mut() = document_uri.map(|uri| uri.to_string());
   }
-  fn child_nodes(&self) -> &NodeList {
-    &self.child_nodes
+
+  /// [Document.xmlVersion](https://www.w3.org/TR/DOM-Level-3-Core/core.html#Document3-version), which is `1.0` by
+  /// default or `1.1` for a document parsed as XML 1.1.
+  pub fn xml_version(&self) -> String {
+    self.xml_version.borrow().clone()
   }
-  fn first_child(&self) -> Option<NodeRef> {
-    self.child_nodes.first().map(|n| n.clone())
+
+  /// Sets the version to `1.0` or `1.1`; other versions are not supported.
+  pub fn set_xml_version(&self, xml_version: &str) -> W3C::Result<()> {
+    if !matches!(xml_version, "1.0" | "1.1") {
+      return Err(W3C::DOMException::NotSupported);
+    }
+    *self.xml_version.borrow_mut() = xml_version.to_string();
+    Ok(())
   }
-  fn last_child(&self) -> Option<NodeRef> {
-    self.child_nodes.last().map(|n| n.clone())
+
+  /// [Document.domConfig](https://www.w3.org/TR/DOM-Level-3-Core/core.html#Document3-domConfig), which refers to
+  /// the configuration of the [DocumentBuilder](crate::xml::parser::DocumentBuilder) for a document parsed by it.
+  pub fn dom_config(&self) -> DOMConfiguration {
+    self.dom_config.borrow().clone()
   }
-  fn local_name(&self) -> Option<&str> {
+
+  pub(crate) fn set_dom_config(&self, dom_config: DOMConfiguration) {
+    *self.dom_config.borrow_mut() = dom_config;
+  }
+
+  /// [Document.normalizeDocument()](https://www.w3.org/TR/DOM-Level-3-Core/core.html#Document3-normalizeDocument)
+  ///
+  /// In addition to merging adjacent text nodes as [W3C::Node::normalize()], the nodes are changed as the parameters
+  /// of [Document::dom_config()]: comments, CDATA sections, entity references and whitespace in element content are
+  /// removed or replaced when their parameters are `false`, CDATA sections containing `]]>` are split, and missing
+  /// namespace declarations are added if `namespaces` is `true`. Errors are reported to the `error-handler`, and if it
+  /// returns `false`, the normalization stops there with `false`.
+  ///
+  pub fn normalize_document(&self) -> bool {
+    normalize_document(self)
+  }
+
+  fn this(&self) -> Weak<Document> {
+    self.core.children_owner_document()
+  }
+}
+
+impl W3C::Node<DOMImplementation> for Document {
+  node_tree_methods!();
+
+  fn attributes(&self) -> Option<&NamedNodeMap> {
     None
   }
-  fn namespace_uri(&self) -> Option<&str> {
+  fn base_uri(&self) -> Option<String> {
+    self.document_uri()
+  }
+  fn local_name(&self) -> Option<&str> {
     None
   }
-  fn next_sibling(&self) -> Option<NodeRef> {
+  fn namespace_uri(&self) -> Option<&str> {
     None
   }
   fn node_name(&self) -> &str {
     "#document"
   }
-  fn node_type(&self) -> W3C::NodeType {
-    W3C::NodeType::DocumentNode
+  fn node_type(&self) -> NodeType {
+    NodeType::DocumentNode
   }
-  fn node_value(&self) -> W3C::Result<Option<&str>> {
+  fn node_value(&self) -> W3C::Result<Option<String>> {
     Ok(None)
   }
-  fn set_node_value(&mut self, _value: &str) -> W3C::Result<()> {
+  fn set_node_value(&self, _value: &str) -> W3C::Result<()> {
     Ok(())
   }
-  fn owner_document(&self) -> Option<&Document> {
-    // NOTE: valid value is returned by [NodeRef::owned_document()].
-    None
-  }
-  fn parent_node(&self) -> Option<W3C::NodeRef<IMPL>> {
-    None
-  }
   fn prefix(&self) -> Option<String> {
     None
   }
-  fn set_prefix(&self, prefix: Option<String>) -> W3C::Result<()> {
-    Err(W3C::DOMException::NoModificationAllowed)
-  }
-  fn previous_sibling(&self) -> Option<W3C::NodeRef<IMPL>> {
-    None
+  fn set_prefix(&self, _prefix: Option<String>) -> W3C::Result<()> {
+    Ok(())
   }
   fn text_content(&self) -> Option<String> {
     None
   }
-  fn set_text_content(&self, text_content: &str) -> W3C::Result<()> {
-    // noop
+  fn set_text_content(&self, _text_content: &str) -> W3C::Result<()> {
     Ok(())
   }
-
-  fn append_child(&mut self, new_child: NodeRef) -> W3C::Result<&NodeRef> {
-    self.child_nodes.nodes.push(new_child);
-    Ok(self.child_nodes.nodes.last().unwrap())
-  }
-  fn get_feature(&self, feature: &str, version: Option<&str>) -> Option<DOMObject> {
-    todo!()
-  }
-  fn get_user_data(&self, key: &str) -> Option<DOMUserData> {
-    todo!()
-  }
   fn has_attributes(&self) -> bool {
-    todo!()
-  }
-  fn has_child_nodes(&self) -> bool {
-    todo!()
-  }
-  fn insert_before(&mut self, new_child: NodeRef, ref_child: NodeRef) -> W3C::Result<NodeRef> {
-    todo!()
-  }
-  fn is_default_namespace(&self, namespace_uri: &str) -> bool {
-    todo!()
-  }
-  fn is_equal_node(&self, arg: &NodeRef) -> bool {
-    todo!()
-  }
-  fn is_same_node(&self, other: &NodeRef) -> bool {
-    todo!()
-  }
-  fn is_supported(&self, feature: &str, version: &str) -> bool {
-    todo!()
-  }
-  fn lookup_namespace_uri(&self, prefix: &str) -> Option<&str> {
-    todo!()
-  }
-  fn lookup_prefix(&self, namespace_uri: &str) -> Option<&str> {
-    todo!()
-  }
-  fn normalize(&mut self) {
-    todo!()
-  }
-  fn remove_child(&mut self, old_child: &NodeRef) -> W3C::Result<NodeRef> {
-    todo!()
-  }
-  fn replace_child(&mut self, new_child: NodeRef, old_child: NodeRef) -> W3C::Result<NodeRef> {
-    todo!()
-  }
-  fn set_user_data(
-    &mut self, key: &str, data: W3C::DOMUserData, handler: Box<dyn W3C::UserDataHandler<IMPL>>,
-  ) -> Option<W3C::DOMUserData> {
-    todo!()
+    false
   }
 }
 
-impl W3C::Document<IMPL> for Document {
-  fn doctype(&self) -> &DocumentType {
-    todo!()
+impl W3C::Document<DOMImplementation> for Document {
+  fn doctype(&self) -> Option<Rc<DocumentType>> {
+    self.core.child_nodes().to_vec().into_iter().find_map(|node| match node {
+      NodeRef::DocumentType(doctype) => Some(doctype),
+      _ => None,
+    })
   }
 
-  fn implementation(&self) -> &IMPL {
-    todo!()
+  fn implementation(&self) -> &DOMImplementation {
+    &self.implementation
   }
 
-  fn document_element(&self) -> &Element {
-    todo!()
+  fn document_element(&self) -> Option<Rc<Element>> {
+    self.core.child_nodes().to_vec().into_iter().find_map(|node| match node {
+      NodeRef::Element(element) => Some(element),
+      _ => None,
+    })
   }
 
-  fn create_element(&self, tag_name: &str) -> W3C::Result<Element> {
-    todo!()
+  fn create_element(&self, tag_name: &str) -> W3C::Result<Rc<Element>> {
+    if !is_name(tag_name) {
+      return Err(W3C::DOMException::InvalidCharacter);
+    }
+    Ok(Element::new(self.this(), tag_name, None, None, None))
   }
 
-  fn create_document_fragment(&self) -> DocumentFragment {
-    todo!()
+  fn create_document_fragment(&self) -> Rc<DocumentFragment> {
+    DocumentFragment::new(self.this())
   }
 
-  fn create_text_node(&self, data: &str) -> Text {
-    todo!()
+  fn create_text_node(&self, data: &str) -> Rc<Text> {
+    Text::new(self.this(), data)
   }
 
-  fn create_comment(data: &str) -> Comment {
-    todo!()
+  fn create_comment(&self, data: &str) -> Rc<Comment> {
+    Comment::new(self.this(), data)
   }
 
-  fn create_cdata_section(data: &str) -> CDATASection {
-    todo!()
+  fn create_cdata_section(&self, data: &str) -> W3C::Result<Rc<CDATASection>> {
+    Ok(CDATASection::new(self.this(), data))
   }
 
-  fn create_processing_instruction(&self, target: &str, data: &str) -> ProcessingInstruction {
-    todo!()
+  fn create_processing_instruction(&self, target: &str, data: &str) -> W3C::Result<Rc<ProcessingInstruction>> {
+    if !is_name(target) {
+      return Err(W3C::DOMException::InvalidCharacter);
+    }
+    Ok(ProcessingInstruction::new(self.this(), target, data))
   }
 
-  fn create_attribute(&self, name: &str) -> W3C::Result<Attr> {
-    todo!()
+  fn create_attribute(&self, name: &str) -> W3C::Result<Rc<Attr>> {
+    if !is_name(name) {
+      return Err(W3C::DOMException::InvalidCharacter);
+    }
+    Ok(Attr::new(self.this(), name, None, None, None))
   }
 
-  fn create_entity_reference(&self, name: &str) -> W3C::Result<EntityReference> {
-    todo!()
+  fn create_entity_reference(&self, name: &str) -> W3C::Result<Rc<EntityReference>> {
+    if !is_name(name) {
+      return Err(W3C::DOMException::InvalidCharacter);
+    }
+    let reference = EntityReference::new(self.this(), name);
+    let entity = self.doctype().and_then(|doctype| W3C::NamedNodeMap::get_named_item(&doctype.entities, name));
+    if let Some(entity) = entity {
+      core_of(&entity).clone_children(&NodeRef::EntityReference(reference.clone()));
+    }
+    Ok(reference)
   }
 
   fn get_elements_by_tag_name(&self, tagname: &str) -> NodeList {
-    todo!()
+    use W3C::Element as _;
+    descendant_elements(&self.core.this(), &|e: &Element| tagname == "*" || e.tag_name() == tagname)
   }
 
-  fn import_node(&mut self, imported_node: W3C::NodeRef<IMPL>, deep: bool) -> W3C::NodeRef<IMPL> {
-    todo!()
+  fn import_node(&self, imported_node: NodeRef, deep: bool) -> W3C::Result<NodeRef> {
+    match imported_node {
+      NodeRef::Document(_) | NodeRef::DocumentType(_) => Err(W3C::DOMException::NotSupported),
+      node => {
+        let node = node.clone_node(deep);
+        adopt(&node, self.this());
+        Ok(node)
+      }
+    }
   }
 
-  fn create_element_ns(&self, namespace_uri: &str, qualified_name: &str) -> W3C::Result<Element> {
-    todo!()
+  fn create_element_ns(&self, namespace_uri: Option<&str>, qualified_name: &str) -> W3C::Result<Rc<Element>> {
+    let (prefix, local_name) = split_qualified_name_ns(namespace_uri, qualified_name)?;
+    Ok(Element::new(self.this(), qualified_name, namespace_uri, prefix, Some(local_name)))
   }
 
-  fn create_attribute_ns(&self, namespace_uri: &str, qualified_name: &str) -> W3C::Result<Attr> {
-    todo!()
+  fn create_attribute_ns(&self, namespace_uri: Option<&str>, qualified_name: &str) -> W3C::Result<Rc<Attr>> {
+    let (prefix, local_name) = split_qualified_name_ns(namespace_uri, qualified_name)?;
+    Ok(Attr::new(self.this(), qualified_name, namespace_uri, prefix, Some(local_name)))
   }
 
   fn get_elements_by_tag_name_ns(&self, namespace_uri: &str, local_name: &str) -> NodeList {
-    todo!()
+    use W3C::Node as _;
+    descendant_elements(&self.core.this(), &|e: &Element| {
+      (namespace_uri == "*" || e.namespace_uri() == Some(namespace_uri))
+        && (local_name == "*" || e.local_name() == Some(local_name))
+    })
   }
 
-  fn get_element_by_id(&self, element_id: &str) -> Option<Element> {
-    todo!()
+  fn get_element_by_id(&self, element_id: &str) -> Option<Rc<Element>> {
+    use W3C::Attr as _;
+    let ids = descendant_elements(&self.core.this(), &|e: &Element| {
+      e.attributes.to_vec().iter().any(|attr| match attr {
+        NodeRef::Attr(attr) => attr.is_id() && attr.value() == element_id,
+        _ => false,
+      })
+    });
+    match ids.first() {
+      Some(NodeRef::Element(element)) => Some(element),
+      _ => None,
+    }
   }
 
-  fn clone_document(&self, deep: bool) -> Self {
-    todo!()
+  fn clone_document(&self, deep: bool) -> Rc<Self> {
+    let document = Document::new(self.implementation.clone());
+    document.set_document_uri(self.document_uri().as_deref());
+    *document.xml_version.borrow_mut() = self.xml_version();
+    let node = NodeRef::Document(document.clone());
+    self.core.clone_user_data(&node);
+    if deep {
+      for child in self.core.child_nodes().to_vec() {
+        let child = child.clone_node(true);
+        adopt(&child, Rc::downgrade(&document));
+        core_of(&node).insert(child, None).expect("the cloned children must be acceptable");
+      }
+    }
+    document
   }
 }
@@ -0,0 +1,73 @@
+use std::rc::{Rc, Weak};
+
+use crate::xml::w3c::dom::{self as W3C, NodeType};
+
+use super::*;
+
+pub struct DocumentFragment {
+  pub(super) core: NodeCore,
+}
+
+impl DocumentFragment {
+  pub(super) fn new(owner_document: Weak<Document>) -> Rc<DocumentFragment> {
+    Rc::new_cyclic(|this| DocumentFragment {
+      core: NodeCore::new(WeakNodeRef::DocumentFragment(this.clone()), owner_document),
+    })
+  }
+}
+
+impl W3C::Node<DOMImplementation> for DocumentFragment {
+  node_tree_methods!();
+
+  fn attributes(&self) -> Option<&NamedNodeMap> {
+    None
+  }
+  fn base_uri(&self) -> Option<String> {
+    None
+  }
+  fn local_name(&self) -> Option<&str> {
+    None
+  }
+  fn namespace_uri(&self) -> Option<&str> {
+    None
+  }
+  fn node_name(&self) -> &str {
+    "#document-fragment"
+  }
+  fn node_type(&self) -> NodeType {
+    NodeType::DocumentFragmentNode
+  }
+  fn node_value(&self) -> W3C::Result<Option<String>> {
+    Ok(None)
+  }
+  fn set_node_value(&self, _value: &str) -> W3C::Result<()> {
+    Ok(())
+  }
+  fn prefix(&self) -> Option<String> {
+    None
+  }
+  fn set_prefix(&self, _prefix: Option<String>) -> W3C::Result<()> {
+    Ok(())
+  }
+  fn text_content(&self) -> Option<String> {
+    Some(self.core.children_text_content())
+  }
+  fn set_text_content(&self, text_content: &str) -> W3C::Result<()> {
+    self.core.set_children_text_content(text_content)
+  }
+  fn has_attributes(&self) -> bool {
+    false
+  }
+}
+
+impl W3C::DocumentFragment<DOMImplementation> for DocumentFragment {
+  fn clone_document_fragment(&self, deep: bool) -> Rc<Self> {
+    let fragment = DocumentFragment::new(self.core.owner_document_weak());
+    let node = NodeRef::DocumentFragment(fragment.clone());
+    self.core.clone_user_data(&node);
+    if deep {
+      self.core.clone_children(&node);
+    }
+    fragment
+  }
+}
@@ -1,7 +1,159 @@
+use std::rc::Rc;
+
 use crate::test::compatibility::java;
+use crate::xml::dom::{DOMImplementation, NodeRef};
+use crate::xml::w3c::dom::{
+  self as W3C, Attr as _, CharacterData as _, DOMImplementation as _, Document as _, Element as _, Node as _,
+  NodeList as _, Text as _,
+};
 
 #[test]
 fn owner_document() {
-  let expected = java::run("owner_document", r#""#);
-  
-}
\ No newline at end of file
+  let expected = java::run(
+    "owner_document",
+    r#"
+    Document doc = DocumentBuilderFactory.newInstance().newDocumentBuilder().newDocument();
+    Element root = doc.createElement("root");
+    doc.appendChild(root);
+    Text text = doc.createTextNode("text");
+    root.appendChild(text);
+    System.out.println(doc.getOwnerDocument() == null);
+    System.out.println(root.getOwnerDocument() == doc);
+    System.out.println(text.getOwnerDocument() == doc);
+    System.out.println(text.getParentNode() == root);
+    System.out.print(doc.getDocumentElement() == root);
+  "#,
+  );
+
+  let document = DOMImplementation::new().create_document(None, None, None).unwrap();
+  let root = document.create_element("root").unwrap();
+  document.append_child(NodeRef::Element(root.clone())).unwrap();
+  let text = document.create_text_node("text");
+  root.append_child(NodeRef::Text(text.clone())).unwrap();
+  let actual = [
+    document.owner_document().is_none(),
+    Rc::ptr_eq(&root.owner_document().unwrap(), &document),
+    Rc::ptr_eq(&text.owner_document().unwrap(), &document),
+    text.parent_node().unwrap().ptr_eq(&NodeRef::Element(root.clone())),
+    Rc::ptr_eq(&document.document_element().unwrap(), &root),
+  ];
+  assert_eq!(expected, actual.iter().map(|b| b.to_string()).collect::<Vec<_>>().join("\n"));
+}
+
+#[test]
+fn child_manipulation() {
+  let document = DOMImplementation::new().create_document(None, Some("root"), None).unwrap();
+  let root = document.document_element().unwrap();
+  let a = NodeRef::Element(document.create_element("a").unwrap());
+  let b = NodeRef::Element(document.create_element("b").unwrap());
+  let c = NodeRef::Element(document.create_element("c").unwrap());
+  root.append_child(a.clone()).unwrap();
+  root.append_child(c.clone()).unwrap();
+  root.insert_before(b.clone(), Some(&c)).unwrap();
+  let names = |node: &dyn W3C::Node<DOMImplementation>| {
+    node.child_nodes().to_vec().iter().map(|n| n.as_node().node_name().to_string()).collect::<Vec<_>>().join(",")
+  };
+  assert_eq!("a,b,c", names(root.as_ref()));
+  assert!(b.as_node().previous_sibling().unwrap().ptr_eq(&a));
+  assert!(b.as_node().next_sibling().unwrap().ptr_eq(&c));
+
+  // moving a node removes it from the former parent
+  a.as_node().append_child(c.clone()).unwrap();
+  assert_eq!("a,b", names(root.as_ref()));
+  assert!(c.as_node().parent_node().unwrap().ptr_eq(&a));
+
+  root.replace_child(c.clone(), &b).unwrap();
+  assert_eq!("a,c", names(root.as_ref()));
+  assert!(b.as_node().parent_node().is_none());
+  root.remove_child(&a).unwrap();
+  assert_eq!("c", names(root.as_ref()));
+
+  // hierarchy constraints
+  let root_ref = NodeRef::Element(root.clone());
+  assert!(matches!(c.as_node().append_child(root_ref.clone()), Err(W3C::DOMException::HierarchyRequest)));
+  assert!(matches!(root.remove_child(&a), Err(W3C::DOMException::NotFound)));
+  assert!(matches!(document.append_child(b.clone()), Err(W3C::DOMException::HierarchyRequest)));
+  let other = DOMImplementation::new().create_document(None, None, None).unwrap();
+  assert!(matches!(other.append_child(root_ref), Err(W3C::DOMException::WrongDocument)));
+
+  // the children of a fragment are checked together
+  let fragment = other.create_document_fragment();
+  fragment.append_child(NodeRef::Element(other.create_element("x").unwrap())).unwrap();
+  fragment.append_child(NodeRef::Comment(other.create_comment("c"))).unwrap();
+  fragment.append_child(NodeRef::Element(other.create_element("y").unwrap())).unwrap();
+  let fragment = NodeRef::DocumentFragment(fragment);
+  assert!(matches!(other.append_child(fragment.clone()), Err(W3C::DOMException::HierarchyRequest)));
+  assert_eq!(0, other.child_nodes().length());
+  assert_eq!("x,#comment,y", names(fragment.as_node()));
+  fragment.as_node().remove_child(&fragment.as_node().last_child().unwrap()).unwrap();
+  other.append_child(fragment.clone()).unwrap();
+  assert_eq!("x,#comment", names(other.as_ref()));
+}
+
+#[test]
+fn attributes_and_text() {
+  let document = DOMImplementation::new().create_document(None, Some("root"), None).unwrap();
+  let root = document.document_element().unwrap();
+  root.set_attribute("a", "1").unwrap();
+  root.set_attribute("a", "2").unwrap();
+  assert_eq!(Some("2".to_string()), root.get_attribute("a"));
+  let attr = root.get_attribute_node("a").unwrap();
+  assert!(Rc::ptr_eq(&attr.owner_element().unwrap(), &root));
+  root.remove_attribute("a").unwrap();
+  assert!(!root.has_attributes());
+  assert!(attr.owner_element().is_none());
+
+  root.append_child(NodeRef::Text(document.create_text_node("hello, "))).unwrap();
+  root.append_child(NodeRef::Text(document.create_text_node(""))).unwrap();
+  root.append_child(NodeRef::Text(document.create_text_node("world"))).unwrap();
+  root.normalize();
+  assert_eq!(1, root.child_nodes().length());
+  let text = match root.first_child() {
+    Some(NodeRef::Text(text)) => text,
+    _ => unreachable!(),
+  };
+  assert_eq!("hello, world", text.data());
+  let world = text.split_text(7).unwrap();
+  assert_eq!("hello, ", text.data());
+  assert_eq!("world", world.data());
+  assert!(text.next_sibling().unwrap().ptr_eq(&NodeRef::Text(world)));
+  text.replace_data(0, 5, "こんにちは").unwrap();
+  assert_eq!("こんにちは, ", text.data());
+  assert_eq!("こんにちは, world", root.text_content().unwrap());
+}
+
+#[test]
+fn clone_and_compare() {
+  let document = DOMImplementation::new().create_document(None, Some("root"), None).unwrap();
+  let root = document.document_element().unwrap();
+  root.set_attribute("a", "1").unwrap();
+  root.append_child(NodeRef::Comment(document.create_comment("c"))).unwrap();
+  let root = NodeRef::Element(root);
+  let shallow = root.clone_node(false);
+  let deep = root.clone_node(true);
+  assert!(!shallow.as_node().is_equal_node(&root));
+  assert!(deep.as_node().is_equal_node(&root));
+  assert!(!deep.as_node().is_same_node(&root));
+  assert_eq!(
+    Some("1".to_string()),
+    match &shallow {
+      NodeRef::Element(e) => e.get_attribute("a"),
+      _ => None,
+    }
+  );
+}
+
+#[test]
+fn namespaces() {
+  let ns = "http://example.com/ns";
+  let document = DOMImplementation::new().create_document(Some(ns), Some("x:root"), None).unwrap();
+  let root = document.document_element().unwrap();
+  assert_eq!(Some("x".to_string()), root.prefix());
+  assert_eq!(Some("root"), root.local_name());
+  root.set_attribute_ns(Some(crate::xml::dom::XMLNS_NAMESPACE_URI), "xmlns:x", ns).unwrap();
+  assert_eq!(Some(ns.to_string()), root.lookup_namespace_uri(Some("x")));
+  assert_eq!(Some("x".to_string()), root.lookup_prefix(ns));
+  assert!(matches!(document.create_element_ns(None, "x:a"), Err(W3C::DOMException::Namespace)));
+  assert!(matches!(document.create_element_ns(Some(ns), "xml:a"), Err(W3C::DOMException::Namespace)));
+  assert!(matches!(document.create_element("1a"), Err(W3C::DOMException::InvalidCharacter)));
+}
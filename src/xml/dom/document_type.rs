@@ -1,16 +1,55 @@
-use std::collections::{HashMap, HashSet};
+use std::rc::{Rc, Weak};
+
+use crate::xml::w3c::dom::{self as W3C, NodeType};
 
-use crate::xml::w3c::dom as W3C;
 use super::*;
 
-#[derive(Clone, PartialEq, Eq)]
 pub struct DocumentType {
+  pub(super) core: NodeCore,
   qualified_name: String,
-  public_id: String,
-  system_id: String,
-  entities: NamedNodeMap,
-  notations: NamedNodeMap,
-  internal_subset: Option<String>
+  public_id: Option<String>,
+  system_id: Option<String>,
+  pub(super) entities: NamedNodeMap,
+  pub(super) notations: NamedNodeMap,
+  internal_subset: Option<String>,
+}
+
+impl DocumentType {
+  /// Creates a document type that doesn't belong to any document yet. The owner document is set when it's added to a
+  /// document.
+  ///
+  pub fn new(
+    qualified_name: &str, public_id: Option<&str>, system_id: Option<&str>, internal_subset: Option<&str>,
+  ) -> Rc<DocumentType> {
+    Rc::new_cyclic(|this| DocumentType {
+      core: NodeCore::new(WeakNodeRef::DocumentType(this.clone()), Weak::new()),
+      qualified_name: qualified_name.to_string(),
+      public_id: public_id.map(|p| p.to_string()),
+      system_id: system_id.map(|s| s.to_string()),
+      entities: NamedNodeMap::new(NodeType::EntityNode, Weak::new()),
+      notations: NamedNodeMap::new(NodeType::NotaionNode, Weak::new()),
+      internal_subset: internal_subset.map(|i| i.to_string()),
+    })
+  }
+
+  /// Adds the entity declared in the DTD. An entity with the same name that has already been added is kept, since the
+  /// first declaration is binding.
+  pub(crate) fn add_entity(&self, entity: Rc<Entity>) {
+    let node = NodeRef::Entity(entity);
+    let qname = QName::of(node.as_node());
+    if self.entities.get(&qname).is_none() {
+      self.entities.set(qname, node).expect("the entity must be acceptable");
+    }
+  }
+
+  /// Adds the notation declared in the DTD. A notation with the same name that has already been added is kept.
+  pub(crate) fn add_notation(&self, notation: Rc<Notation>) {
+    let node = NodeRef::Notation(notation);
+    let qname = QName::of(node.as_node());
+    if self.notations.get(&qname).is_none() {
+      self.notations.set(qname, node).expect("the notation must be acceptable");
+    }
+  }
 }
 
 impl W3C::DocumentType<DOMImplementation> for DocumentType {
@@ -23,165 +62,76 @@ impl W3C::DocumentType<DOMImplementation> for DocumentType {
   fn notations(&self) -> &NamedNodeMap {
     &self.notations
   }
-  fn public_id(&self) -> &str {
-    &self.public_id
+  fn public_id(&self) -> Option<&str> {
+    self.public_id.as_deref()
   }
-  fn system_id(&self) -> &str {
-    &self.system_id
+  fn system_id(&self) -> Option<&str> {
+    self.system_id.as_deref()
   }
   fn internal_subset(&self) -> Option<&str> {
-    self.internal_subset
+    self.internal_subset.as_deref()
   }
 
-  fn clone_document_type(&self, deep: bool) -> Self {
-    self.clone()
+  fn clone_document_type(&self, _deep: bool) -> Rc<Self> {
+    let doctype = DocumentType::new(
+      &self.qualified_name,
+      self.public_id.as_deref(),
+      self.system_id.as_deref(),
+      self.internal_subset.as_deref(),
+    );
+    for entity in self.entities.to_vec() {
+      let entity = entity.clone_node(true);
+      doctype.entities.set(QName::of(entity.as_node()), entity).expect("the cloned entity must be acceptable");
+    }
+    for notation in self.notations.to_vec() {
+      let notation = notation.clone_node(true);
+      doctype.notations.set(QName::of(notation.as_node()), notation).expect("the cloned notation must be acceptable");
+    }
+    self.core.clone_user_data(&NodeRef::DocumentType(doctype.clone()));
+    doctype
   }
 }
 
-impl W3C::Node<DOMImplementation> for DocumentType{
-    fn attributes(&self) -> Option<<DOMImplementation as W3C::DOMImplementation>::NamedNodeMap> {
-        todo!()
-    }
-
-    fn base_uri(&self) -> Option<String> {
-        todo!()
-    }
-
-    fn child_nodes(&self) -> &<DOMImplementation as W3C::DOMImplementation>::NodeList {
-        todo!()
-    }
-
-    fn first_child(&self) -> Option<W3C::NodeRef<DOMImplementation>> {
-        todo!()
-    }
-
-    fn last_child(&self) -> Option<W3C::NodeRef<DOMImplementation>> {
-        todo!()
-    }
-
-    fn local_name(&self) -> Option<&str> {
-        todo!()
-    }
-
-    fn namespace_uri(&self) -> Option<&str> {
-        todo!()
-    }
-
-    fn next_sibling(&self) -> Option<W3C::NodeRef<DOMImplementation>> {
-        todo!()
-    }
-
-    fn node_name(&self) -> &str {
-        todo!()
-    }
-
-    fn node_type(&self) -> W3C::NodeType {
-        todo!()
-    }
-
-    fn node_value(&self) -> W3C::Result<Option<&str>> {
-        todo!()
-    }
-
-    fn set_node_value(&mut self, value: &str) -> W3C::Result<()> {
-        todo!()
-    }
-
-    fn owner_document(&self) -> Option<&<DOMImplementation as W3C::DOMImplementation>::Document> {
-        todo!()
-    }
-
-    fn parent_node(&self) -> Option<W3C::NodeRef<DOMImplementation>> {
-        todo!()
-    }
-
-    fn prefix(&self) -> Option<String> {
-        todo!()
-    }
-
-    fn set_prefix(&self, prefix: Option<String>) -> W3C::Result<()> {
-        todo!()
-    }
-
-    fn previous_sibling(&self) -> Option<W3C::NodeRef<DOMImplementation>> {
-        todo!()
-    }
-
-    fn text_content(&self) -> Option<String> {
-      None
-    }
+impl W3C::Node<DOMImplementation> for DocumentType {
+  node_tree_methods!();
 
-    fn set_text_content(&self, text_content: &str) -> W3C::Result<()> {
-        todo!()
-    }
-
-    fn append_child(&mut self, new_child: W3C::NodeRef<DOMImplementation>) -> W3C::Result<W3C::NodeRef<DOMImplementation>> {
-        todo!()
-    }
-
-    fn compare_document_position(&self, other: &W3C::NodeRef<DOMImplementation>) -> W3C::DocumentPosition {
-        todo!()
-    }
-
-    fn get_feature(&self, feature: &str, version: Option<&str>) -> Option<W3C::DOMObject> {
-        todo!()
-    }
-
-    fn get_user_data(&self, key: &str) -> Option<W3C::DOMUserData> {
-        todo!()
-    }
-
-    fn has_attributes(&self) -> bool {
-        todo!()
-    }
-
-    fn has_child_nodes(&self) -> bool {
-        todo!()
-    }
-
-    fn insert_before(&mut self, new_child: W3C::NodeRef<DOMImplementation>, ref_child: W3C::NodeRef<DOMImplementation>) -> W3C::Result<W3C::NodeRef<DOMImplementation>> {
-        todo!()
-    }
-
-    fn is_default_namespace(&self, namespace_uri: &str) -> bool {
-        todo!()
-    }
-
-    fn is_equal_node(&self, arg: &W3C::NodeRef<DOMImplementation>) -> bool {
-        todo!()
-    }
-
-    fn is_same_node(&self, other: &W3C::NodeRef<DOMImplementation>) -> bool {
-        todo!()
-    }
-
-    fn is_supported(&self, feature: &str, version: &str) -> bool {
-        todo!()
-    }
-
-    fn lookup_namespace_uri(&self, prefix: &str) -> Option<&str> {
-        todo!()
-    }
-
-    fn lookup_prefix(&self, namespace_uri: &str) -> Option<&str> {
-        todo!()
-    }
-
-    fn normalize(&mut self) {
-        todo!()
-    }
-
-    fn remove_child(&mut self, old_child: &W3C::NodeRef<DOMImplementation>) -> W3C::Result<W3C::NodeRef<DOMImplementation>> {
-        todo!()
-    }
-
-    fn replace_child(&mut self, new_child: W3C::NodeRef<DOMImplementation>, old_child: W3C::NodeRef<DOMImplementation>) -> W3C::Result<W3C::NodeRef<DOMImplementation>> {
-        todo!()
-    }
-
-    fn set_user_data(
-    &mut self, key: &str, data: W3C::DOMUserData, handler: Box<dyn W3C::UserDataHandler<DOMImplementation>>,
-  ) -> Option<W3C::DOMUserData> {
-        todo!()
-    }
-}
\ No newline at end of file
+  fn attributes(&self) -> Option<&NamedNodeMap> {
+    None
+  }
+  fn base_uri(&self) -> Option<String> {
+    None
+  }
+  fn local_name(&self) -> Option<&str> {
+    None
+  }
+  fn namespace_uri(&self) -> Option<&str> {
+    None
+  }
+  fn node_name(&self) -> &str {
+    &self.qualified_name
+  }
+  fn node_type(&self) -> NodeType {
+    NodeType::DocumentTypeNode
+  }
+  fn node_value(&self) -> W3C::Result<Option<String>> {
+    Ok(None)
+  }
+  fn set_node_value(&self, _value: &str) -> W3C::Result<()> {
+    Ok(())
+  }
+  fn prefix(&self) -> Option<String> {
+    None
+  }
+  fn set_prefix(&self, _prefix: Option<String>) -> W3C::Result<()> {
+    Ok(())
+  }
+  fn text_content(&self) -> Option<String> {
+    None
+  }
+  fn set_text_content(&self, _text_content: &str) -> W3C::Result<()> {
+    Ok(())
+  }
+  fn has_attributes(&self) -> bool {
+    false
+  }
+}
@@ -0,0 +1,181 @@
+use std::rc::{Rc, Weak};
+
+use crate::xml::w3c::dom::{self as W3C, NodeType};
+
+use super::*;
+
+pub struct Element {
+  pub(super) core: NodeCore,
+  tag_name: String,
+  namespace_uri: Option<String>,
+  prefix: Option<String>,
+  local_name: Option<String>,
+  pub(super) attributes: NamedNodeMap,
+}
+
+impl Element {
+  pub(super) fn new(
+    owner_document: Weak<Document>, tag_name: &str, namespace_uri: Option<&str>, prefix: Option<&str>,
+    local_name: Option<&str>,
+  ) -> Rc<Element> {
+    Rc::new_cyclic(|this| Element {
+      core: NodeCore::new(WeakNodeRef::Element(this.clone()), owner_document),
+      tag_name: tag_name.to_string(),
+      namespace_uri: namespace_uri.map(|n| n.to_string()),
+      prefix: prefix.map(|p| p.to_string()),
+      local_name: local_name.map(|l| l.to_string()),
+      attributes: NamedNodeMap::new(NodeType::AttributeNode, this.clone()),
+    })
+  }
+
+  fn attr(node: Option<NodeRef>) -> Option<Rc<Attr>> {
+    match node {
+      Some(NodeRef::Attr(attr)) => Some(attr),
+      _ => None,
+    }
+  }
+
+  fn document(&self) -> W3C::Result<Rc<Document>> {
+    self.core.owner_document().ok_or(W3C::DOMException::WrongDocument)
+  }
+}
+
+impl W3C::Node<DOMImplementation> for Element {
+  node_tree_methods!();
+
+  fn attributes(&self) -> Option<&NamedNodeMap> {
+    Some(&self.attributes)
+  }
+  fn base_uri(&self) -> Option<String> {
+    self.core.owner_document().and_then(|document| document.document_uri())
+  }
+  fn local_name(&self) -> Option<&str> {
+    self.local_name.as_deref()
+  }
+  fn namespace_uri(&self) -> Option<&str> {
+    self.namespace_uri.as_deref()
+  }
+  fn node_name(&self) -> &str {
+    &self.tag_name
+  }
+  fn node_type(&self) -> NodeType {
+    NodeType::ElementNode
+  }
+  fn node_value(&self) -> W3C::Result<Option<String>> {
+    Ok(None)
+  }
+  fn set_node_value(&self, _value: &str) -> W3C::Result<()> {
+    Ok(())
+  }
+  fn prefix(&self) -> Option<String> {
+    self.prefix.clone()
+  }
+  /// The qualified name is borrowed by `node_name()` and `tag_name()`, so the prefix of an element cannot be changed.
+  fn set_prefix(&self, _prefix: Option<String>) -> W3C::Result<()> {
+    Err(W3C::DOMException::NotSupported)
+  }
+  fn text_content(&self) -> Option<String> {
+    Some(self.core.children_text_content())
+  }
+  fn set_text_content(&self, text_content: &str) -> W3C::Result<()> {
+    self.core.set_children_text_content(text_content)
+  }
+  fn has_attributes(&self) -> bool {
+    W3C::NamedNodeMap::length(&self.attributes) > 0
+  }
+}
+
+impl W3C::Element<DOMImplementation> for Element {
+  fn tag_name(&self) -> &str {
+    &self.tag_name
+  }
+  fn get_attribute(&self, name: &str) -> Option<String> {
+    self.get_attribute_node(name).map(|attr| W3C::Attr::value(&*attr))
+  }
+  fn set_attribute(&self, name: &str, value: &str) -> W3C::Result<()> {
+    match self.get_attribute_node(name) {
+      Some(attr) => W3C::Attr::set_value(&*attr, value),
+      None => {
+        let attr = W3C::Document::create_attribute(&*self.document()?, name)?;
+        W3C::Attr::set_value(&*attr, value)?;
+        self.set_attribute_node(attr).map(|_| ())
+      }
+    }
+  }
+  fn remove_attribute(&self, name: &str) -> W3C::Result<()> {
+    match W3C::NamedNodeMap::remove_named_item(&self.attributes, name) {
+      Ok(_) | Err(W3C::DOMException::NotFound) => Ok(()),
+      Err(err) => Err(err),
+    }
+  }
+  fn get_attribute_node(&self, name: &str) -> Option<Rc<Attr>> {
+    Self::attr(W3C::NamedNodeMap::get_named_item(&self.attributes, name))
+  }
+  fn set_attribute_node(&self, new_attr: Rc<Attr>) -> W3C::Result<Option<Rc<Attr>>> {
+    self.set_attribute_node_ns(new_attr)
+  }
+  fn remove_attribute_node(&self, old_attr: &Rc<Attr>) -> W3C::Result<Rc<Attr>> {
+    let node = NodeRef::Attr(old_attr.clone());
+    if !self.attributes.to_vec().iter().any(|attr| attr.ptr_eq(&node)) {
+      return Err(W3C::DOMException::NotFound);
+    }
+    self.attributes.remove(&QName::of(old_attr.as_ref()))?;
+    Ok(old_attr.clone())
+  }
+  fn get_elements_by_tag_name(&self, name: &str) -> NodeList {
+    descendant_elements(&self.core.this(), &|e: &Element| name == "*" || e.tag_name == name)
+  }
+  fn get_attribute_ns(&self, namespace_uri: Option<&str>, local_name: &str) -> Option<String> {
+    self.get_attribute_node_ns(namespace_uri, local_name).map(|attr| W3C::Attr::value(&*attr))
+  }
+  fn set_attribute_ns(&self, namespace_uri: Option<&str>, qualified_name: &str, value: &str) -> W3C::Result<()> {
+    let attr = W3C::Document::create_attribute_ns(&*self.document()?, namespace_uri, qualified_name)?;
+    W3C::Attr::set_value(&*attr, value)?;
+    self.set_attribute_node_ns(attr).map(|_| ())
+  }
+  fn remove_attribute_ns(&self, namespace_uri: Option<&str>, local_name: &str) -> W3C::Result<()> {
+    match W3C::NamedNodeMap::remove_named_item_ns(&self.attributes, namespace_uri, local_name) {
+      Ok(_) | Err(W3C::DOMException::NotFound) => Ok(()),
+      Err(err) => Err(err),
+    }
+  }
+  fn get_attribute_node_ns(&self, namespace_uri: Option<&str>, local_name: &str) -> Option<Rc<Attr>> {
+    Self::attr(W3C::NamedNodeMap::get_named_item_ns(&self.attributes, namespace_uri, local_name).ok().flatten())
+  }
+  fn set_attribute_node_ns(&self, new_attr: Rc<Attr>) -> W3C::Result<Option<Rc<Attr>>> {
+    let old = W3C::NamedNodeMap::set_named_item_ns(&self.attributes, NodeRef::Attr(new_attr))?;
+    Ok(Self::attr(old))
+  }
+  fn get_elements_by_tag_name_ns(&self, namespace_uri: &str, local_name: &str) -> NodeList {
+    descendant_elements(&self.core.this(), &|e: &Element| {
+      (namespace_uri == "*" || e.namespace_uri.as_deref() == Some(namespace_uri))
+        && (local_name == "*" || e.local_name.as_deref() == Some(local_name))
+    })
+  }
+  fn has_attribute(&self, name: &str) -> bool {
+    self.get_attribute_node(name).is_some()
+  }
+  fn has_attribute_ns(&self, namespace_uri: Option<&str>, local_name: &str) -> bool {
+    self.get_attribute_node_ns(namespace_uri, local_name).is_some()
+  }
+
+  fn clone_element(&self, deep: bool) -> Rc<Self> {
+    let element = Element::new(
+      self.core.owner_document_weak(),
+      &self.tag_name,
+      self.namespace_uri.as_deref(),
+      self.prefix.as_deref(),
+      self.local_name.as_deref(),
+    );
+    for attr in self.attributes.to_vec() {
+      let attr = attr.clone_node(true);
+      element.attributes.set(QName::of(attr.as_node()), attr).expect("the cloned attribute must be acceptable");
+    }
+    let node = NodeRef::Element(element.clone());
+    self.core.clone_user_data(&node);
+    if deep {
+      self.core.clone_children(&node);
+    }
+    element
+  }
+}
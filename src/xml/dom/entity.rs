@@ -0,0 +1,263 @@
+use std::rc::{Rc, Weak};
+
+use crate::xml::w3c::dom::{self as W3C, NodeType};
+
+use super::*;
+
+/// An entity declared in the DTD. The children are the nodes of its replacement text for a parsed entity.
+///
+pub struct Entity {
+  pub(super) core: NodeCore,
+  name: String,
+  public_id: Option<String>,
+  system_id: Option<String>,
+  notation_name: Option<String>,
+  replacement_text: Option<String>,
+}
+
+impl Entity {
+  pub(crate) fn new(
+    owner_document: Weak<Document>, name: &str, public_id: Option<&str>, system_id: Option<&str>,
+    notation_name: Option<&str>, replacement_text: Option<&str>,
+  ) -> Rc<Entity> {
+    Rc::new_cyclic(|this| Entity {
+      core: NodeCore::new(WeakNodeRef::Entity(this.clone()), owner_document),
+      name: name.to_string(),
+      public_id: public_id.map(|p| p.to_string()),
+      system_id: system_id.map(|s| s.to_string()),
+      notation_name: notation_name.map(|n| n.to_string()),
+      replacement_text: replacement_text.map(|r| r.to_string()),
+    })
+  }
+
+  /// The replacement text of an internal entity, which is used to expand the references in a fragment parsed in the
+  /// context of the document.
+  pub(crate) fn replacement_text(&self) -> Option<&str> {
+    self.replacement_text.as_deref()
+  }
+}
+
+impl W3C::Node<DOMImplementation> for Entity {
+  node_tree_methods!();
+
+  fn attributes(&self) -> Option<&NamedNodeMap> {
+    None
+  }
+  fn base_uri(&self) -> Option<String> {
+    self.system_id.clone()
+  }
+  fn local_name(&self) -> Option<&str> {
+    None
+  }
+  fn namespace_uri(&self) -> Option<&str> {
+    None
+  }
+  fn node_name(&self) -> &str {
+    &self.name
+  }
+  fn node_type(&self) -> NodeType {
+    NodeType::EntityNode
+  }
+  fn node_value(&self) -> W3C::Result<Option<String>> {
+    Ok(None)
+  }
+  fn set_node_value(&self, _value: &str) -> W3C::Result<()> {
+    Ok(())
+  }
+  fn prefix(&self) -> Option<String> {
+    None
+  }
+  fn set_prefix(&self, _prefix: Option<String>) -> W3C::Result<()> {
+    Ok(())
+  }
+  fn text_content(&self) -> Option<String> {
+    Some(self.core.children_text_content())
+  }
+  fn set_text_content(&self, _text_content: &str) -> W3C::Result<()> {
+    Err(W3C::DOMException::NoModificationAllowed)
+  }
+  fn has_attributes(&self) -> bool {
+    false
+  }
+}
+
+impl W3C::Entity<DOMImplementation> for Entity {
+  fn public_id(&self) -> Option<&str> {
+    self.public_id.as_deref()
+  }
+  fn system_id(&self) -> Option<&str> {
+    self.system_id.as_deref()
+  }
+  fn notation_name(&self) -> Option<&str> {
+    self.notation_name.as_deref()
+  }
+
+  fn clone_entity(&self, deep: bool) -> Rc<Self> {
+    let entity = Entity::new(
+      self.core.owner_document_weak(),
+      &self.name,
+      self.public_id.as_deref(),
+      self.system_id.as_deref(),
+      self.notation_name.as_deref(),
+      self.replacement_text.as_deref(),
+    );
+    let node = NodeRef::Entity(entity.clone());
+    self.core.clone_user_data(&node);
+    if deep {
+      self.core.clone_children(&node);
+    }
+    entity
+  }
+}
+
+/// A reference to an entity. The children are the copies of the children of the referenced [Entity].
+///
+pub struct EntityReference {
+  pub(super) core: NodeCore,
+  name: String,
+}
+
+impl EntityReference {
+  pub(super) fn new(owner_document: Weak<Document>, name: &str) -> Rc<EntityReference> {
+    Rc::new_cyclic(|this| EntityReference {
+      core: NodeCore::new(WeakNodeRef::EntityReference(this.clone()), owner_document),
+      name: name.to_string(),
+    })
+  }
+}
+
+impl W3C::Node<DOMImplementation> for EntityReference {
+  node_tree_methods!();
+
+  fn attributes(&self) -> Option<&NamedNodeMap> {
+    None
+  }
+  fn base_uri(&self) -> Option<String> {
+    self.core.owner_document().and_then(|document| document.document_uri())
+  }
+  fn local_name(&self) -> Option<&str> {
+    None
+  }
+  fn namespace_uri(&self) -> Option<&str> {
+    None
+  }
+  fn node_name(&self) -> &str {
+    &self.name
+  }
+  fn node_type(&self) -> NodeType {
+    NodeType::EntityReferenceNode
+  }
+  fn node_value(&self) -> W3C::Result<Option<String>> {
+    Ok(None)
+  }
+  fn set_node_value(&self, _value: &str) -> W3C::Result<()> {
+    Ok(())
+  }
+  fn prefix(&self) -> Option<String> {
+    None
+  }
+  fn set_prefix(&self, _prefix: Option<String>) -> W3C::Result<()> {
+    Ok(())
+  }
+  fn text_content(&self) -> Option<String> {
+    Some(self.core.children_text_content())
+  }
+  fn set_text_content(&self, _text_content: &str) -> W3C::Result<()> {
+    Err(W3C::DOMException::NoModificationAllowed)
+  }
+  fn has_attributes(&self) -> bool {
+    false
+  }
+}
+
+impl W3C::EntityReference<DOMImplementation> for EntityReference {
+  fn clone_entity_reference(&self, _deep: bool) -> Rc<Self> {
+    let reference = EntityReference::new(self.core.owner_document_weak(), &self.name);
+    let node = NodeRef::EntityReference(reference.clone());
+    self.core.clone_user_data(&node);
+    self.core.clone_children(&node);
+    reference
+  }
+}
+
+/// A notation declared in the DTD.
+///
+pub struct Notation {
+  pub(super) core: NodeCore,
+  name: String,
+  public_id: Option<String>,
+  system_id: Option<String>,
+}
+
+impl Notation {
+  pub(crate) fn new(
+    owner_document: Weak<Document>, name: &str, public_id: Option<&str>, system_id: Option<&str>,
+  ) -> Rc<Notation> {
+    Rc::new_cyclic(|this| Notation {
+      core: NodeCore::new(WeakNodeRef::Notation(this.clone()), owner_document),
+      name: name.to_string(),
+      public_id: public_id.map(|p| p.to_string()),
+      system_id: system_id.map(|s| s.to_string()),
+    })
+  }
+}
+
+impl W3C::Node<DOMImplementation> for Notation {
+  node_tree_methods!();
+
+  fn attributes(&self) -> Option<&NamedNodeMap> {
+    None
+  }
+  fn base_uri(&self) -> Option<String> {
+    None
+  }
+  fn local_name(&self) -> Option<&str> {
+    None
+  }
+  fn namespace_uri(&self) -> Option<&str> {
+    None
+  }
+  fn node_name(&self) -> &str {
+    &self.name
+  }
+  fn node_type(&self) -> NodeType {
+    NodeType::NotaionNode
+  }
+  fn node_value(&self) -> W3C::Result<Option<String>> {
+    Ok(None)
+  }
+  fn set_node_value(&self, _value: &str) -> W3C::Result<()> {
+    Ok(())
+  }
+  fn prefix(&self) -> Option<String> {
+    None
+  }
+  fn set_prefix(&self, _prefix: Option<String>) -> W3C::Result<()> {
+    Ok(())
+  }
+  fn text_content(&self) -> Option<String> {
+    None
+  }
+  fn set_text_content(&self, _text_content: &str) -> W3C::Result<()> {
+    Ok(())
+  }
+  fn has_attributes(&self) -> bool {
+    false
+  }
+}
+
+impl W3C::Notation<DOMImplementation> for Notation {
+  fn public_id(&self) -> Option<&str> {
+    self.public_id.as_deref()
+  }
+  fn system_id(&self) -> Option<&str> {
+    self.system_id.as_deref()
+  }
+
+  fn clone_notation(&self, _deep: bool) -> Rc<Self> {
+    let notation =
+      Notation::new(self.core.owner_document_weak(), &self.name, self.public_id.as_deref(), self.system_id.as_deref());
+    self.core.clone_user_data(&NodeRef::Notation(notation.clone()));
+    notation
+  }
+}
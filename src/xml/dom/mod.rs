@@ -5,28 +5,79 @@
 //! The following exceptions defined in the [W3C Recommendation](https://www.w3.org/TR/DOM-Level-3-Core/core.html#ID-17189187)
 //! are not or rarely raised in Rust implementations.
 //!
-//! - [W3C::DOMException::NoModificationAllowed], because static check is self-explanatory as to whether it is
-//!   read-only or not.
-//! - [W3C::DOMException::WrongDocument], because DOMImplementation must match on static type checking.
-//! - [W3C::DOMException::NotSupported], because this implementation supports namespaces ("XML" feature).
+//! - [W3C::DOMException::NoModificationAllowed] is raised only when the text content of an entity or entity reference
+//!   is set; the other read-only nodes are not guarded.
+//! - [W3C::DOMException::NotSupported] is raised when the prefix of an element or attribute is changed, because the
+//!   qualified name is borrowed by `node_name()`, and when a document or document type is imported.
 //!
+//! ### Tree
+//!
+//! Each node is held by [Rc] and shared with [NodeRef]s. A node refers to its parent and its owner document with weak
+//! references, so the tree is freed when the last reference to the document and its nodes is dropped.
+//!
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
+use std::rc::{Rc, Weak};
 
+use crate::xml::parser::chars::is_name;
 use crate::xml::w3c::dom as W3C;
 
+pub use attr::*;
+pub use character_data::*;
+pub use configuration::*;
 pub use document::*;
+pub use document_fragment::*;
 pub use document_type::*;
+pub use element::*;
+pub use entity::*;
+pub use processing_instruction::*;
+pub use serializer::*;
 
+use node::*;
+use normalization::*;
+
+mod attr;
+mod character_data;
+mod configuration;
 mod document;
+mod document_fragment;
 mod document_type;
+mod element;
+mod entity;
+mod node;
+mod normalization;
+mod processing_instruction;
+mod serializer;
 
+#[cfg(test)]
+mod configuration_test;
 #[cfg(test)]
 mod document_test;
+#[cfg(test)]
+mod serializer_test;
 
+#[derive(Clone)]
 pub struct DOMImplementation {
   features: Features,
 }
 
+impl DOMImplementation {
+  pub fn new() -> Self {
+    let mut features = Features::new();
+    for version in [None, Some("1.0"), Some("2.0"), Some("3.0")] {
+      features.set_feature("Core", version, true);
+      features.set_feature("XML", version, true);
+    }
+    DOMImplementation { features }
+  }
+}
+
+impl Default for DOMImplementation {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
 impl W3C::DOMImplementation for DOMImplementation {
   type Document = Document;
   type DocumentType = DocumentType;
@@ -49,138 +100,200 @@ impl W3C::DOMImplementation for DOMImplementation {
 
   fn create_document_type(
     &self, qualified_name: &str, public_id: &str, system_id: &str,
-  ) -> W3C::Result<Self::DocumentType> {
-    todo!()
+  ) -> W3C::Result<Rc<DocumentType>> {
+    let (_, _) = split_qualified_name(qualified_name)?;
+    Ok(DocumentType::new(qualified_name, Some(public_id), Some(system_id), None))
   }
 
-  fn create_document(&self, namespace_uri: &str, qualified_name: &str, doctype: Self::DocumentType) -> Self::Document {
-    todo!()
+  fn create_document(
+    &self, namespace_uri: Option<&str>, qualified_name: Option<&str>, doctype: Option<Rc<DocumentType>>,
+  ) -> W3C::Result<Rc<Document>> {
+    use W3C::{Document as _, Node as _};
+    let document = Document::new(self.clone());
+    if let Some(doctype) = doctype {
+      if doctype.owner_document().is_some() {
+        return Err(W3C::DOMException::WrongDocument);
+      }
+      document.append_child(NodeRef::DocumentType(doctype))?;
+    }
+    if let Some(qualified_name) = qualified_name {
+      let element = document.create_element_ns(namespace_uri, qualified_name)?;
+      document.append_child(NodeRef::Element(element))?;
+    } else if namespace_uri.is_some() {
+      return Err(W3C::DOMException::Namespace);
+    }
+    Ok(document)
   }
+
   fn get_feature(&self, feature: &str, version: Option<&str>) -> Option<W3C::DOMObject> {
-    todo!()
+    if self.has_feature(feature, version) {
+      Some(())
+    } else {
+      None
+    }
   }
 }
 
+/// A list of nodes. The child list of a node is live; it reflects the changes of the children.
+///
+#[derive(Default)]
 pub struct NodeList {
-  nodes: Vec<NodeRef>,
+  nodes: RefCell<Vec<NodeRef>>,
 }
 
 impl NodeList {
   fn new() -> Self {
-    NodeList { nodes: Vec::new() }
+    NodeList { nodes: RefCell::new(Vec::new()) }
+  }
+  fn first(&self) -> Option<NodeRef> {
+    self.nodes.borrow().first().cloned()
+  }
+  fn last(&self) -> Option<NodeRef> {
+    self.nodes.borrow().last().cloned()
+  }
+  fn index_of(&self, node: &NodeRef) -> Option<usize> {
+    self.nodes.borrow().iter().position(|n| n.ptr_eq(node))
+  }
+  fn insert(&self, index: usize, node: NodeRef) {
+    self.nodes.borrow_mut().insert(index, node);
   }
-  fn first(&self) -> Option<&NodeRef> {
-    self.nodes.first()
+  fn remove(&self, index: usize) -> NodeRef {
+    self.nodes.borrow_mut().remove(index)
   }
-  fn last(&self) -> Option<&NodeRef> {
-    self.nodes.last()
+  fn len(&self) -> usize {
+    self.nodes.borrow().len()
+  }
+
+  /// Returns a snapshot of the nodes in this list.
+  pub fn to_vec(&self) -> Vec<NodeRef> {
+    self.nodes.borrow().clone()
+  }
+}
+
+impl From<Vec<NodeRef>> for NodeList {
+  fn from(nodes: Vec<NodeRef>) -> Self {
+    NodeList { nodes: RefCell::new(nodes) }
   }
 }
 
 impl W3C::NodeList<DOMImplementation> for NodeList {
   fn item(&self, index: usize) -> Option<NodeRef> {
-    if index < self.nodes.len() {
-      Some(self.nodes[index].clone())
-    } else {
-      None
-    }
+    self.nodes.borrow().get(index).cloned()
   }
   fn length(&self) -> usize {
-    self.nodes.len()
+    self.len()
   }
 }
 
+/// A collection of attributes of an element, or entities or notations of a document type.
+///
 pub struct NamedNodeMap {
   node_type: W3C::NodeType,
-  named: HashMap<QName, NodeRef>,
-  indexed: Vec<QName>,
+  owner: Weak<Element>,
+  named: RefCell<HashMap<QName, NodeRef>>,
+  indexed: RefCell<Vec<QName>>,
 }
 
 impl NamedNodeMap {
+  fn new(node_type: W3C::NodeType, owner: Weak<Element>) -> Self {
+    NamedNodeMap { node_type, owner, named: RefCell::new(HashMap::new()), indexed: RefCell::new(Vec::new()) }
+  }
   fn get(&self, qname: &QName) -> Option<NodeRef> {
-    self.named.get(qname).map(|n| n.clone())
+    self.named.borrow().get(qname).cloned()
   }
-  fn remove(&mut self, qname: &QName) -> W3C::Result<NodeRef> {
-    match self.named.remove(&qname) {
+  fn remove(&self, qname: &QName) -> W3C::Result<NodeRef> {
+    match self.named.borrow_mut().remove(qname) {
       None => Err(W3C::DOMException::NotFound),
       Some(node) => {
-        for i in 0..self.indexed.len() {
-          if &self.indexed[i] == qname {
-            self.indexed.remove(i);
-            break;
-          }
+        self.indexed.borrow_mut().retain(|q| q != qname);
+        if let NodeRef::Attr(attr) = &node {
+          attr.set_owner_element(Weak::new());
         }
         Ok(node)
       }
     }
   }
-  fn set(&mut self, qname: QName, node: NodeRef) -> W3C::Result<Option<NodeRef>> {
-    if let W3C::NodeRef::Attr(attr) = &node {
-      if node.as_node().parent_node().is_some() {
-        return Err(W3C::DOMException::InuseAttribute);
-      }
-    } else if self.node_type == W3C::NodeType::ElementNode {
+  fn set(&self, qname: QName, node: NodeRef) -> W3C::Result<Option<NodeRef>> {
+    use W3C::Attr as _;
+    if node.as_node().node_type() != self.node_type {
       return Err(W3C::DOMException::HierarchyRequest);
-    } else {
-      // TODO: other combination for DOMException::HierarchyRequest
+    }
+    if let NodeRef::Attr(attr) = &node {
+      if let Some(owner) = attr.owner_element() {
+        if !Weak::ptr_eq(&Rc::downgrade(&owner), &self.owner) {
+          return Err(W3C::DOMException::InuseAttribute);
+        }
+      }
+      attr.set_owner_element(self.owner.clone());
     }
     let sub = qname.clone();
-    let result = self.named.insert(qname, node);
-    if result.is_none() {
-      self.indexed.push(sub);
+    let result = self.named.borrow_mut().insert(qname, node.clone());
+    match &result {
+      None => self.indexed.borrow_mut().push(sub),
+      Some(old @ NodeRef::Attr(attr)) if !old.ptr_eq(&node) => attr.set_owner_element(Weak::new()),
+      Some(_) => (),
     }
     Ok(result)
   }
+  fn find_by_name(&self, name: &str) -> Option<QName> {
+    let qname = QName::new(None, Some(name.to_string()));
+    if self.named.borrow().contains_key(&qname) {
+      return Some(qname);
+    }
+    let named = self.named.borrow();
+    self
+      .indexed
+      .borrow()
+      .iter()
+      .find(|q| named.get(q).map(|n| n.as_node().node_name() == name).unwrap_or(false))
+      .cloned()
+  }
+
+  /// Returns a snapshot of the nodes in this map in the order they were added.
+  pub fn to_vec(&self) -> Vec<NodeRef> {
+    let named = self.named.borrow();
+    self.indexed.borrow().iter().filter_map(|qname| named.get(qname).cloned()).collect()
+  }
 }
 
 impl W3C::NamedNodeMap<DOMImplementation> for NamedNodeMap {
   fn length(&self) -> usize {
-    self.indexed.len()
+    self.indexed.borrow().len()
   }
   fn get_named_item(&self, name: &str) -> Option<NodeRef> {
-    self.get(&QName::new(None, Some(name.to_string())))
+    self.find_by_name(name).and_then(|qname| self.get(&qname))
   }
-  fn get_named_item_ns(&self, namespace_uri: &str, local_name: &str) -> W3C::Result<Option<NodeRef>> {
-    Ok(self.get(&QName::new(Some(namespace_uri.to_string()), Some(local_name.to_string()))))
+  fn get_named_item_ns(&self, namespace_uri: Option<&str>, local_name: &str) -> W3C::Result<Option<NodeRef>> {
+    Ok(self.get(&QName::new(namespace_uri.map(|n| n.to_string()), Some(local_name.to_string()))))
   }
   fn item(&self, index: usize) -> Option<NodeRef> {
-    self.indexed.get(index).map(|qname| self.get(qname)).flatten()
+    let qname = self.indexed.borrow().get(index).cloned();
+    qname.and_then(|qname| self.get(&qname))
   }
-  fn remove_named_item(&mut self, name: &str) -> W3C::Result<NodeRef> {
-    let qname = QName::new(None, Some(name.to_string()));
-    self.remove(&qname)
+  fn remove_named_item(&self, name: &str) -> W3C::Result<NodeRef> {
+    match self.find_by_name(name) {
+      Some(qname) => self.remove(&qname),
+      None => Err(W3C::DOMException::NotFound),
+    }
   }
-  fn remove_named_item_ns(&mut self, namespace_uri: &str, local_name: &str) -> W3C::Result<NodeRef> {
-    let qname = QName::new(Some(namespace_uri.to_string()), Some(local_name.to_string()));
+  fn remove_named_item_ns(&self, namespace_uri: Option<&str>, local_name: &str) -> W3C::Result<NodeRef> {
+    let qname = QName::new(namespace_uri.map(|n| n.to_string()), Some(local_name.to_string()));
     self.remove(&qname)
   }
-  fn set_named_item(&mut self, arg: NodeRef) -> W3C::Result<Option<NodeRef>> {
+  fn set_named_item(&self, arg: NodeRef) -> W3C::Result<Option<NodeRef>> {
     let name = arg.as_node().node_name();
     let qname = QName::new(None, Some(name.to_string()));
     self.set(qname, arg)
   }
-  fn set_named_item_ns(&mut self, arg: W3C::NodeRef<DOMImplementation>) -> W3C::Result<Option<NodeRef>> {
-    let namespace_uri = arg.as_node().namespace_uri().map(|n| n.to_string());
-    let local_name = arg.as_node().local_name().map(|n| n.to_string());
-    let qname = QName::new(namespace_uri, local_name);
+  fn set_named_item_ns(&self, arg: NodeRef) -> W3C::Result<Option<NodeRef>> {
+    let qname = QName::of(arg.as_node());
     self.set(qname, arg)
   }
 }
 
-pub struct Element {}
-pub struct Attr {}
-pub struct Text {}
-pub struct EntityReference {}
-pub struct ProcessingInstruction {}
-pub struct DocumentFragment {}
-pub struct Comment {}
-pub struct CDATASection {}
-pub struct Notation {}
-pub struct Entity {}
-
 pub type NodeRef = W3C::NodeRef<DOMImplementation>;
 
-#[derive(Hash, Clone, PartialEq, Eq)]
+#[derive(Debug, Hash, Clone, PartialEq, Eq)]
 struct QName {
   namespace_uri: Option<String>,
   local_name: Option<String>,
@@ -190,8 +303,55 @@ impl QName {
   pub fn new(namespace_uri: Option<String>, local_name: Option<String>) -> Self {
     Self { namespace_uri, local_name }
   }
+
+  /// The key of the specified node; the namespace URI and local name, or node name for a node created by DOM Level 1
+  /// methods.
+  pub fn of(node: &dyn W3C::Node<DOMImplementation>) -> Self {
+    match node.local_name() {
+      Some(local_name) => Self::new(node.namespace_uri().map(|n| n.to_string()), Some(local_name.to_string())),
+      None => Self::new(None, Some(node.node_name().to_string())),
+    }
+  }
+}
+
+/// Splits the qualified name into prefix and local part, with checking that each part is a valid name.
+///
+fn split_qualified_name(qualified_name: &str) -> W3C::Result<(Option<&str>, &str)> {
+  if !is_name(qualified_name) {
+    return Err(W3C::DOMException::InvalidCharacter);
+  }
+  match qualified_name.split_once(':') {
+    None => Ok((None, qualified_name)),
+    Some((prefix, local_name)) if !prefix.is_empty() && is_name(local_name) && !local_name.contains(':') => {
+      Ok((Some(prefix), local_name))
+    }
+    Some(_) => Err(W3C::DOMException::Namespace),
+  }
+}
+
+/// The namespace URI bound to the `xml` prefix.
+pub const XML_NAMESPACE_URI: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// The namespace URI bound to the `xmlns` prefix.
+pub const XMLNS_NAMESPACE_URI: &str = "http://www.w3.org/2000/xmlns/";
+
+/// Splits the qualified name of an element or attribute created with namespace, with checking the constraints on the
+/// reserved prefixes.
+///
+fn split_qualified_name_ns<'a>(
+  namespace_uri: Option<&str>, qualified_name: &'a str,
+) -> W3C::Result<(Option<&'a str>, &'a str)> {
+  let (prefix, local_name) = split_qualified_name(qualified_name)?;
+  let is_xmlns = prefix == Some("xmlns") || (prefix.is_none() && local_name == "xmlns");
+  match (prefix, namespace_uri) {
+    (Some(_), None) => Err(W3C::DOMException::Namespace),
+    (Some("xml"), Some(ns)) if ns != XML_NAMESPACE_URI => Err(W3C::DOMException::Namespace),
+    _ if is_xmlns != (namespace_uri == Some(XMLNS_NAMESPACE_URI)) => Err(W3C::DOMException::Namespace),
+    _ => Ok((prefix, local_name)),
+  }
 }
 
+#[derive(Clone)]
 struct Features {
   features: HashSet<(String, Option<String>)>,
 }
@@ -202,12 +362,13 @@ impl Features {
   }
   pub fn has_feature(&self, feature: &str, version: Option<&str>) -> bool {
     let feature = feature.to_lowercase();
-    let version = version.map(|version| if version.is_empty() { None } else { Some(version.to_string()) }).flatten();
-    self.features.contains(&(feature, version)) || (version.is_some() && self.features.contains(&(feature, None)))
+    let version = version.and_then(|version| if version.is_empty() { None } else { Some(version.to_string()) });
+    let has_none = version.is_some() && self.features.contains(&(feature.clone(), None));
+    self.features.contains(&(feature, version)) || has_none
   }
   pub fn set_feature(&mut self, feature: &str, version: Option<&str>, enabled: bool) {
     let feature = feature.to_lowercase();
-    let version = version.map(|version| if version.is_empty() { None } else { Some(version.to_string()) }).flatten();
+    let version = version.and_then(|version| if version.is_empty() { None } else { Some(version.to_string()) });
     if enabled {
       self.features.insert((feature, version));
     } else {
@@ -0,0 +1,620 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+
+use crate::xml::w3c::dom::{self as W3C, DOMUserData, NodeType, UserDataHandler, UserDataHandlerOperationType};
+
+use super::*;
+
+/// A weak counterpart of [NodeRef] that is used to refer to the parent and the node itself.
+///
+#[derive(Clone)]
+pub(crate) enum WeakNodeRef {
+  DocumentFragment(Weak<DocumentFragment>),
+  Document(Weak<Document>),
+  Attr(Weak<Attr>),
+  Element(Weak<Element>),
+  Text(Weak<Text>),
+  Comment(Weak<Comment>),
+  CDATASection(Weak<CDATASection>),
+  DocumentType(Weak<DocumentType>),
+  Notation(Weak<Notation>),
+  Entity(Weak<Entity>),
+  EntityReference(Weak<EntityReference>),
+  ProcessingInstruction(Weak<ProcessingInstruction>),
+}
+
+impl WeakNodeRef {
+  pub fn upgrade(&self) -> Option<NodeRef> {
+    match self {
+      WeakNodeRef::DocumentFragment(n) => n.upgrade().map(NodeRef::DocumentFragment),
+      WeakNodeRef::Document(n) => n.upgrade().map(NodeRef::Document),
+      WeakNodeRef::Attr(n) => n.upgrade().map(NodeRef::Attr),
+      WeakNodeRef::Element(n) => n.upgrade().map(NodeRef::Element),
+      WeakNodeRef::Text(n) => n.upgrade().map(NodeRef::Text),
+      WeakNodeRef::Comment(n) => n.upgrade().map(NodeRef::Comment),
+      WeakNodeRef::CDATASection(n) => n.upgrade().map(NodeRef::CDATASection),
+      WeakNodeRef::DocumentType(n) => n.upgrade().map(NodeRef::DocumentType),
+      WeakNodeRef::Notation(n) => n.upgrade().map(NodeRef::Notation),
+      WeakNodeRef::Entity(n) => n.upgrade().map(NodeRef::Entity),
+      WeakNodeRef::EntityReference(n) => n.upgrade().map(NodeRef::EntityReference),
+      WeakNodeRef::ProcessingInstruction(n) => n.upgrade().map(NodeRef::ProcessingInstruction),
+    }
+  }
+}
+
+/// The state that every node has: the links to the node itself, its owner document, its parent and its children,
+/// and the user data attached to it.
+///
+pub(crate) struct NodeCore {
+  this: WeakNodeRef,
+  owner_document: RefCell<Weak<Document>>,
+  parent: RefCell<Option<WeakNodeRef>>,
+  child_nodes: NodeList,
+  user_data: RefCell<HashMap<String, UserData>>,
+}
+
+struct UserData {
+  data: DOMUserData,
+  handler: Option<Rc<dyn UserDataHandler<DOMImplementation>>>,
+}
+
+impl NodeCore {
+  pub fn new(this: WeakNodeRef, owner_document: Weak<Document>) -> NodeCore {
+    NodeCore {
+      this,
+      owner_document: RefCell::new(owner_document),
+      parent: RefCell::new(None),
+      child_nodes: NodeList::new(),
+      user_data: RefCell::new(HashMap::new()),
+    }
+  }
+
+  pub fn this(&self) -> NodeRef {
+    self.this.upgrade().expect("the node must be held by Rc")
+  }
+
+  pub fn child_nodes(&self) -> &NodeList {
+    &self.child_nodes
+  }
+
+  pub fn owner_document(&self) -> Option<Rc<Document>> {
+    self.owner_document.borrow().upgrade()
+  }
+
+  pub fn owner_document_weak(&self) -> Weak<Document> {
+    self.owner_document.borrow().clone()
+  }
+
+  pub fn set_owner_document(&self, document: Weak<Document>) {
+    *self.owner_document.borrow_mut() = document;
+  }
+
+  pub fn parent_node(&self) -> Option<NodeRef> {
+    self.parent.borrow().as_ref().and_then(|parent| parent.upgrade())
+  }
+
+  fn set_parent(&self, parent: Option<WeakNodeRef>) {
+    *self.parent.borrow_mut() = parent;
+  }
+
+  /// Returns the sibling at the relative position `offset` from this node.
+  pub fn sibling(&self, offset: isize) -> Option<NodeRef> {
+    let parent = self.parent_node()?;
+    let siblings = core_of(&parent).child_nodes();
+    let index = siblings.index_of(&self.this())? as isize + offset;
+    if index < 0 {
+      None
+    } else {
+      W3C::NodeList::item(siblings, index as usize)
+    }
+  }
+
+  /// Inserts `new_child` before the child at `index`, or appends it if `index` is `None`. If `new_child` is a
+  /// document fragment, its children are moved instead.
+  pub fn insert(&self, new_child: NodeRef, index: Option<usize>) -> W3C::Result<NodeRef> {
+    let children = match &new_child {
+      NodeRef::DocumentFragment(fragment) => fragment.core.child_nodes().to_vec(),
+      _ => vec![new_child.clone()],
+    };
+    self.check_insertion(&children, None)?;
+    let mut index = index;
+    for child in children.into_iter() {
+      if let Some(parent) = core_of(&child).parent_node() {
+        let position = core_of(&parent).remove_child(&child)?;
+        if let Some(i) = index {
+          if parent.ptr_eq(&self.this()) && position < i {
+            index = Some(i - 1);
+          }
+        }
+      }
+      core_of(&child).set_parent(Some(self.this.clone()));
+      adopt(&child, self.children_owner_document());
+      match index {
+        Some(i) => {
+          self.child_nodes.insert(i, child);
+          index = Some(i + 1);
+        }
+        None => self.child_nodes.insert(self.child_nodes.len(), child),
+      }
+    }
+    Ok(new_child)
+  }
+
+  /// Removes the specified child and returns its former position.
+  pub fn remove_child(&self, old_child: &NodeRef) -> W3C::Result<usize> {
+    match self.child_nodes.index_of(old_child) {
+      Some(index) => {
+        let child = self.child_nodes.remove(index);
+        core_of(&child).set_parent(None);
+        Ok(index)
+      }
+      None => Err(W3C::DOMException::NotFound),
+    }
+  }
+
+  pub fn replace_child(&self, new_child: NodeRef, old_child: &NodeRef) -> W3C::Result<NodeRef> {
+    if new_child.ptr_eq(old_child) {
+      return Ok(new_child);
+    }
+    let index = self.child_nodes.index_of(old_child).ok_or(W3C::DOMException::NotFound)?;
+    match &new_child {
+      NodeRef::DocumentFragment(fragment) => {
+        self.check_insertion(&fragment.core.child_nodes().to_vec(), Some(old_child))?
+      }
+      _ => self.check_insertion(std::slice::from_ref(&new_child), Some(old_child))?,
+    }
+    self.remove_child(old_child)?;
+    match self.insert(new_child, Some(index)) {
+      Ok(_) => Ok(old_child.clone()),
+      Err(err) => {
+        self.insert(old_child.clone(), Some(index))?;
+        Err(err)
+      }
+    }
+  }
+
+  pub fn insert_before(&self, new_child: NodeRef, ref_child: Option<&NodeRef>) -> W3C::Result<NodeRef> {
+    match ref_child {
+      Some(ref_child) => {
+        let index = self.child_nodes.index_of(ref_child).ok_or(W3C::DOMException::NotFound)?;
+        self.insert(new_child, Some(index))
+      }
+      None => self.insert(new_child, None),
+    }
+  }
+
+  /// Checks whether the `children` inserted together, such as those of a document fragment, can be children of this
+  /// node. `replacing` is the node that will be replaced by the `children`.
+  fn check_insertion(&self, children: &[NodeRef], replacing: Option<&NodeRef>) -> W3C::Result<()> {
+    for child in children {
+      self.check_insertion_of(child, children, replacing)?;
+    }
+    Ok(())
+  }
+
+  /// Checks whether `child`, one of the `inserted` nodes, can be a child of this node.
+  fn check_insertion_of(&self, child: &NodeRef, inserted: &[NodeRef], replacing: Option<&NodeRef>) -> W3C::Result<()> {
+    if let Some(document) = core_of(child).owner_document() {
+      if !Weak::ptr_eq(&Rc::downgrade(&document), &self.children_owner_document()) {
+        return Err(W3C::DOMException::WrongDocument);
+      }
+    }
+    let this = self.this();
+    let mut ancestor = Some(this.clone());
+    while let Some(node) = ancestor {
+      if node.ptr_eq(child) {
+        return Err(W3C::DOMException::HierarchyRequest);
+      }
+      ancestor = core_of(&node).parent_node();
+    }
+    let child_type = child.as_node().node_type();
+    let allowed = match this.as_node().node_type() {
+      NodeType::DocumentNode => {
+        // the other nodes of the type that the document will have besides `child`
+        let count = |node_type: NodeType| {
+          let children = self.child_nodes.to_vec();
+          let existing = children
+            .iter()
+            .filter(|c| c.as_node().node_type() == node_type)
+            .filter(|c| !c.ptr_eq(child) && replacing.map(|r| !c.ptr_eq(r)).unwrap_or(true))
+            .count();
+          existing + inserted.iter().filter(|c| c.as_node().node_type() == node_type && !c.ptr_eq(child)).count()
+        };
+        match child_type {
+          NodeType::ElementNode => count(NodeType::ElementNode) == 0,
+          NodeType::DocumentTypeNode => count(NodeType::DocumentTypeNode) == 0,
+          NodeType::ProcessingInstructionNode | NodeType::CommentNode => true,
+          _ => false,
+        }
+      }
+      NodeType::ElementNode | NodeType::DocumentFragmentNode | NodeType::EntityReferenceNode | NodeType::EntityNode => {
+        matches!(
+          child_type,
+          NodeType::ElementNode
+            | NodeType::TextNode
+            | NodeType::CDATASectionNode
+            | NodeType::EntityReferenceNode
+            | NodeType::ProcessingInstructionNode
+            | NodeType::CommentNode
+        )
+      }
+      _ => false,
+    };
+    if allowed {
+      Ok(())
+    } else {
+      Err(W3C::DOMException::HierarchyRequest)
+    }
+  }
+
+  pub fn children_owner_document(&self) -> Weak<Document> {
+    match &self.this {
+      WeakNodeRef::Document(document) => document.clone(),
+      _ => self.owner_document_weak(),
+    }
+  }
+
+  pub fn get_user_data(&self, key: &str) -> Option<DOMUserData> {
+    self.user_data.borrow().get(key).map(|u| u.data.clone())
+  }
+
+  pub fn set_user_data(
+    &self, key: &str, data: Option<DOMUserData>, handler: Option<Box<dyn UserDataHandler<DOMImplementation>>>,
+  ) -> Option<DOMUserData> {
+    let mut user_data = self.user_data.borrow_mut();
+    let old = match data {
+      Some(data) => user_data.insert(key.to_string(), UserData { data, handler: handler.map(Rc::from) }),
+      None => user_data.remove(key),
+    };
+    old.map(|u| u.data)
+  }
+
+  /// Copies the user data to the cloned node, and notifies the handlers.
+  pub fn clone_user_data(&self, dst: &NodeRef) {
+    let src = self.this();
+    let dst_core = core_of(dst);
+    for (key, user_data) in self.user_data.borrow().iter() {
+      if let Some(handler) = &user_data.handler {
+        handler.handle(UserDataHandlerOperationType::Cloned, key, &user_data.data, &src, Some(dst));
+      }
+      let copy = UserData { data: user_data.data.clone(), handler: user_data.handler.clone() };
+      dst_core.user_data.borrow_mut().insert(key.clone(), copy);
+    }
+  }
+
+  /// Appends the clones of the children of this node to the specified node.
+  pub fn clone_children(&self, dst: &NodeRef) {
+    for child in self.child_nodes.to_vec() {
+      let child = child.clone_node(true);
+      core_of(dst).insert(child, None).expect("the cloned children must be acceptable");
+    }
+  }
+
+  /// Concatenates the text content of the children except comments and processing instructions.
+  pub fn children_text_content(&self) -> String {
+    let mut text = String::new();
+    for child in self.child_nodes.to_vec() {
+      match child.as_node().node_type() {
+        NodeType::CommentNode | NodeType::ProcessingInstructionNode => (),
+        _ => text.push_str(&child.as_node().text_content().unwrap_or_default()),
+      }
+    }
+    text
+  }
+
+  /// Replaces all children with a single text node.
+  pub fn set_children_text_content(&self, text_content: &str) -> W3C::Result<()> {
+    use W3C::Document;
+    for child in self.child_nodes.to_vec() {
+      self.remove_child(&child)?;
+    }
+    if !text_content.is_empty() {
+      let document = self.owner_document().or_else(|| match self.this() {
+        NodeRef::Document(document) => Some(document),
+        _ => None,
+      });
+      if let Some(document) = document {
+        self.insert(NodeRef::Text(document.create_text_node(text_content)), None)?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Merges adjacent text nodes and removes empty ones in the subtree.
+  pub fn normalize(&self) {
+    use W3C::CharacterData;
+    let mut previous: Option<Rc<Text>> = None;
+    for child in self.child_nodes.to_vec() {
+      match &child {
+        NodeRef::Text(text) if text.length() == 0 => {
+          let _ = self.remove_child(&child);
+        }
+        NodeRef::Text(text) => match &previous {
+          Some(prev) => {
+            let _ = prev.append_data(&text.data());
+            let _ = self.remove_child(&child);
+          }
+          None => previous = Some(text.clone()),
+        },
+        _ => {
+          previous = None;
+          child.as_node().normalize();
+        }
+      }
+    }
+  }
+}
+
+/// Returns the core state of the specified node.
+pub(crate) fn core_of(node: &NodeRef) -> &NodeCore {
+  match node {
+    NodeRef::DocumentFragment(n) => &n.core,
+    NodeRef::Document(n) => &n.core,
+    NodeRef::Attr(n) => &n.core,
+    NodeRef::Element(n) => &n.core,
+    NodeRef::Text(n) => &n.core,
+    NodeRef::Comment(n) => &n.core,
+    NodeRef::CDATASection(n) => &n.core,
+    NodeRef::DocumentType(n) => &n.core,
+    NodeRef::Notation(n) => &n.core,
+    NodeRef::Entity(n) => &n.core,
+    NodeRef::EntityReference(n) => &n.core,
+    NodeRef::ProcessingInstruction(n) => &n.core,
+  }
+}
+
+/// Changes the owner document of the node and its descendants.
+pub(crate) fn adopt(node: &NodeRef, document: Weak<Document>) {
+  let core = core_of(node);
+  if Weak::ptr_eq(&core.owner_document_weak(), &document) {
+    return;
+  }
+  core.set_owner_document(document.clone());
+  match node {
+    NodeRef::Element(element) => {
+      for attr in element.attributes.to_vec() {
+        adopt(&attr, document.clone());
+      }
+    }
+    NodeRef::DocumentType(doctype) => {
+      for node in doctype.entities.to_vec().iter().chain(doctype.notations.to_vec().iter()) {
+        adopt(node, document.clone());
+      }
+    }
+    _ => (),
+  }
+  for child in core.child_nodes().to_vec() {
+    adopt(&child, document.clone());
+  }
+}
+
+/// [Node.isEqualNode()](https://www.w3.org/TR/DOM-Level-3-Core/core.html#Node3-isEqualNode)
+pub(crate) fn is_equal_node(a: &NodeRef, b: &NodeRef) -> bool {
+  use W3C::{DocumentType as _, NamedNodeMap as _};
+  let (x, y) = (a.as_node(), b.as_node());
+  if x.node_type() != y.node_type()
+    || x.node_name() != y.node_name()
+    || x.local_name() != y.local_name()
+    || x.namespace_uri() != y.namespace_uri()
+    || x.prefix() != y.prefix()
+    || x.node_value().ok() != y.node_value().ok()
+  {
+    return false;
+  }
+  match (x.attributes(), y.attributes()) {
+    (Some(x), Some(y)) => {
+      if x.length() != y.length() {
+        return false;
+      }
+      for attr in x.to_vec() {
+        let key = QName::of(attr.as_node());
+        match y.get(&key) {
+          Some(other) if is_equal_node(&attr, &other) => (),
+          _ => return false,
+        }
+      }
+    }
+    (None, None) => (),
+    _ => return false,
+  }
+  if let (NodeRef::DocumentType(x), NodeRef::DocumentType(y)) = (a, b) {
+    if x.public_id() != y.public_id()
+      || x.system_id() != y.system_id()
+      || x.internal_subset() != y.internal_subset()
+      || !is_equal_map(x.entities(), y.entities())
+      || !is_equal_map(x.notations(), y.notations())
+    {
+      return false;
+    }
+  }
+  let (xs, ys) = (core_of(a).child_nodes().to_vec(), core_of(b).child_nodes().to_vec());
+  xs.len() == ys.len() && xs.iter().zip(ys.iter()).all(|(x, y)| is_equal_node(x, y))
+}
+
+fn is_equal_map(x: &NamedNodeMap, y: &NamedNodeMap) -> bool {
+  let (xs, ys) = (x.to_vec(), y.to_vec());
+  xs.len() == ys.len() && xs.iter().all(|n| y.get(&QName::of(n.as_node())).map(|m| is_equal_node(n, &m)) == Some(true))
+}
+
+/// Returns the element from which the namespace lookup of the node starts.
+/// See [Namespace Algorithms](https://www.w3.org/TR/DOM-Level-3-Core/namespaces-algorithms.html).
+fn namespace_context(node: &NodeRef) -> Option<Rc<Element>> {
+  use W3C::{Attr as _, Document as _};
+  match node {
+    NodeRef::Element(element) => Some(element.clone()),
+    NodeRef::Document(document) => document.document_element(),
+    NodeRef::Attr(attr) => attr.owner_element(),
+    NodeRef::Entity(_) | NodeRef::Notation(_) | NodeRef::DocumentType(_) | NodeRef::DocumentFragment(_) => None,
+    _ => ancestor_element(node),
+  }
+}
+
+fn ancestor_element(node: &NodeRef) -> Option<Rc<Element>> {
+  let mut parent = core_of(node).parent_node();
+  while let Some(node) = parent {
+    if let NodeRef::Element(element) = node {
+      return Some(element);
+    }
+    parent = core_of(&node).parent_node();
+  }
+  None
+}
+
+/// [Node.lookupNamespaceURI()](https://www.w3.org/TR/DOM-Level-3-Core/core.html#Node3-lookupNamespaceURI)
+pub(crate) fn lookup_namespace_uri(node: &NodeRef, prefix: Option<&str>) -> Option<String> {
+  use W3C::{Attr as _, Node};
+  let mut element = namespace_context(node);
+  while let Some(e) = element {
+    if e.namespace_uri().is_some() && e.prefix().as_deref() == prefix {
+      return e.namespace_uri().map(|n| n.to_string());
+    }
+    for attr in e.attributes.to_vec() {
+      if let NodeRef::Attr(attr) = attr {
+        let declared = match (attr.prefix().as_deref(), attr.local_name(), prefix) {
+          (Some("xmlns"), Some(local_name), Some(prefix)) => local_name == prefix,
+          (None, Some("xmlns"), None) => true,
+          (None, None, None) => attr.name() == "xmlns",
+          _ => false,
+        };
+        if declared {
+          let value = attr.value();
+          return if value.is_empty() { None } else { Some(value) };
+        }
+      }
+    }
+    element = ancestor_element(&NodeRef::Element(e));
+  }
+  None
+}
+
+/// [Node.lookupPrefix()](https://www.w3.org/TR/DOM-Level-3-Core/core.html#Node3-lookupNamespacePrefix)
+pub(crate) fn lookup_prefix(node: &NodeRef, namespace_uri: &str) -> Option<String> {
+  use W3C::{Attr as _, Node};
+  let mut element = namespace_context(node);
+  while let Some(e) = element {
+    if let Some(prefix) = e.prefix() {
+      if e.namespace_uri() == Some(namespace_uri)
+        && lookup_namespace_uri(node, Some(&prefix)).as_deref() == Some(namespace_uri)
+      {
+        return Some(prefix);
+      }
+    }
+    for attr in e.attributes.to_vec() {
+      if let NodeRef::Attr(attr) = attr {
+        if attr.prefix().as_deref() == Some("xmlns") && attr.value() == namespace_uri {
+          let prefix = attr.local_name().map(|p| p.to_string());
+          if lookup_namespace_uri(node, prefix.as_deref()).as_deref() == Some(namespace_uri) {
+            return prefix;
+          }
+        }
+      }
+    }
+    element = ancestor_element(&NodeRef::Element(e));
+  }
+  None
+}
+
+/// Expands the methods of [W3C::Node] that are common to all node types. The type must have a `core: NodeCore` field.
+macro_rules! node_tree_methods {
+  () => {
+    fn child_nodes(&self) -> &NodeList {
+      self.core.child_nodes()
+    }
+    fn first_child(&self) -> Option<NodeRef> {
+      self.core.child_nodes().first()
+    }
+    fn last_child(&self) -> Option<NodeRef> {
+      self.core.child_nodes().last()
+    }
+    fn next_sibling(&self) -> Option<NodeRef> {
+      self.core.sibling(1)
+    }
+    fn previous_sibling(&self) -> Option<NodeRef> {
+      self.core.sibling(-1)
+    }
+    fn parent_node(&self) -> Option<NodeRef> {
+      self.core.parent_node()
+    }
+    fn owner_document(&self) -> Option<Rc<Document>> {
+      self.core.owner_document()
+    }
+    fn append_child(&self, new_child: NodeRef) -> W3C::Result<NodeRef> {
+      self.core.insert(new_child, None)
+    }
+    fn insert_before(&self, new_child: NodeRef, ref_child: Option<&NodeRef>) -> W3C::Result<NodeRef> {
+      self.core.insert_before(new_child, ref_child)
+    }
+    fn remove_child(&self, old_child: &NodeRef) -> W3C::Result<NodeRef> {
+      self.core.remove_child(old_child).map(|_| old_child.clone())
+    }
+    fn replace_child(&self, new_child: NodeRef, old_child: &NodeRef) -> W3C::Result<NodeRef> {
+      self.core.replace_child(new_child, old_child)
+    }
+    fn has_child_nodes(&self) -> bool {
+      W3C::NodeList::length(self.core.child_nodes()) > 0
+    }
+    fn normalize(&self) {
+      self.core.normalize()
+    }
+    fn get_feature(&self, feature: &str, version: Option<&str>) -> Option<W3C::DOMObject> {
+      if W3C::Node::is_supported(self, feature, version.unwrap_or_default()) {
+        Some(())
+      } else {
+        None
+      }
+    }
+    fn is_supported(&self, feature: &str, version: &str) -> bool {
+      let version = if version.is_empty() { None } else { Some(version) };
+      match self.core.owner_document() {
+        Some(document) => {
+          W3C::DOMImplementation::has_feature(W3C::Document::implementation(&*document), feature, version)
+        }
+        None => W3C::DOMImplementation::has_feature(&DOMImplementation::new(), feature, version),
+      }
+    }
+    fn get_user_data(&self, key: &str) -> Option<W3C::DOMUserData> {
+      self.core.get_user_data(key)
+    }
+    fn set_user_data(
+      &self, key: &str, data: Option<W3C::DOMUserData>,
+      handler: Option<Box<dyn W3C::UserDataHandler<DOMImplementation>>>,
+    ) -> Option<W3C::DOMUserData> {
+      self.core.set_user_data(key, data, handler)
+    }
+    fn is_same_node(&self, other: &NodeRef) -> bool {
+      self.core.this().ptr_eq(other)
+    }
+    fn is_equal_node(&self, arg: &NodeRef) -> bool {
+      is_equal_node(&self.core.this(), arg)
+    }
+    fn is_default_namespace(&self, namespace_uri: Option<&str>) -> bool {
+      lookup_namespace_uri(&self.core.this(), None).as_deref() == namespace_uri
+    }
+    fn lookup_namespace_uri(&self, prefix: Option<&str>) -> Option<String> {
+      lookup_namespace_uri(&self.core.this(), prefix)
+    }
+    fn lookup_prefix(&self, namespace_uri: &str) -> Option<String> {
+      lookup_prefix(&self.core.this(), namespace_uri)
+    }
+  };
+}
+
+pub(crate) use node_tree_methods;
+
+/// Collects the descendant elements of the node that satisfy `matches` in document order.
+pub(crate) fn descendant_elements<F: Fn(&Element) -> bool>(node: &NodeRef, matches: &F) -> NodeList {
+  fn collect<F: Fn(&Element) -> bool>(node: &NodeRef, matches: &F, elements: &mut Vec<NodeRef>) {
+    for child in core_of(node).child_nodes().to_vec() {
+      if let NodeRef::Element(element) = &child {
+        if matches(element) {
+          elements.push(child.clone());
+        }
+        collect(&child, matches, elements);
+      }
+    }
+  }
+  let mut elements = Vec::new();
+  collect(node, matches, &mut elements);
+  NodeList::from(elements)
+}
@@ -0,0 +1,233 @@
+use std::rc::Rc;
+
+use crate::xml::parser::chars::{is_char, is_char11};
+use crate::xml::w3c::dom::{
+  Attr as _, CharacterData as _, DOMErrorSeverity, Element as _, Node as _, NodeList as _, ProcessingInstruction as _,
+};
+
+use super::*;
+
+/// Normalizes the children of the `document` as the parameters of its configuration. Returns `false` if the
+/// `error-handler` stopped the normalization.
+///
+pub(super) fn normalize_document(document: &Document) -> bool {
+  let normalizer = Normalizer { config: document.dom_config(), xml11: document.xml_version() == "1.1" };
+  let node = document.core.this();
+  let completed = normalizer.normalize_children(&node);
+  node.as_node().normalize();
+  completed
+}
+
+struct Normalizer {
+  config: DOMConfiguration,
+  xml11: bool,
+}
+
+impl Normalizer {
+  fn normalize_children(&self, parent: &NodeRef) -> bool {
+    let core = core_of(parent);
+    let mut i = 0;
+    while let Some(child) = core.child_nodes().item(i) {
+      match &child {
+        NodeRef::Comment(_) if !self.config.flag("comments") => {
+          let _ = core.remove_child(&child);
+          continue;
+        }
+        NodeRef::Text(text)
+          if text.is_element_content_whitespace() && !self.config.flag("element-content-whitespace") =>
+        {
+          let _ = core.remove_child(&child);
+          continue;
+        }
+        NodeRef::CDATASection(cdata) if !self.config.flag("cdata-sections") => {
+          let text = NodeRef::Text(Text::new(core.children_owner_document(), &cdata.data()));
+          core_of(&child).clone_user_data(&text);
+          let _ = core.replace_child(text, &child);
+          continue;
+        }
+        NodeRef::EntityReference(reference) if !self.config.flag("entities") && reference.has_child_nodes() => {
+          // the expansion takes the place of the reference and is normalized next
+          for (j, expansion) in core_of(&child).child_nodes().to_vec().into_iter().enumerate() {
+            core.insert(expansion, Some(i + j)).expect("the expansion must be acceptable where the reference is");
+          }
+          let _ = core.remove_child(&child);
+          continue;
+        }
+        NodeRef::CDATASection(cdata) => {
+          let data = cdata.data();
+          if !self.check_characters(&child, &data) {
+            return false;
+          }
+          if data.contains("]]>") && !self.split_cdata_section(core, &child, &data) {
+            return false;
+          }
+        }
+        NodeRef::Text(text) if !self.check_characters(&child, &text.data()) => return false,
+        NodeRef::Comment(comment) if !self.check_characters(&child, &comment.data()) => return false,
+        NodeRef::ProcessingInstruction(pi) if !self.check_characters(&child, &pi.data()) => return false,
+        NodeRef::Element(element) => {
+          if self.config.flag("namespaces") && fix_namespaces(element).is_err() {
+            let message = "the namespace declarations could not be added";
+            if !self.config.report(DOMErrorSeverity::Error, "namespace-fixup-failed", message, Some(&child)) {
+              return false;
+            }
+          }
+          for attr in element.attributes.to_vec() {
+            if let NodeRef::Attr(value) = &attr {
+              if !self.check_characters(&attr, &value.value()) {
+                return false;
+              }
+            }
+          }
+          if !self.normalize_children(&child) {
+            return false;
+          }
+        }
+        _ => (),
+      }
+      i += 1;
+    }
+    true
+  }
+
+  /// Reports `wf-invalid-character` if the `data` of the `node` contains a character that is not allowed in the
+  /// version of the document, and the `well-formed` parameter is `true`.
+  fn check_characters(&self, node: &NodeRef, data: &str) -> bool {
+    if !self.config.flag("well-formed") || data.chars().all(|ch| if self.xml11 { is_char11(ch) } else { is_char(ch) }) {
+      return true;
+    }
+    let message = "the node contains a character that is not allowed in XML";
+    self.config.report(DOMErrorSeverity::Error, "wf-invalid-character", message, Some(node))
+  }
+
+  /// Splits the CDATA section that contains `]]>` into multiple ones if `split-cdata-sections` is `true`, or reports
+  /// `invalid-data-in-cdata-section` otherwise.
+  fn split_cdata_section(&self, core: &NodeCore, cdata: &NodeRef, data: &str) -> bool {
+    if !self.config.flag("split-cdata-sections") {
+      let message = "the CDATA section contains the terminator ']]>'";
+      return self.config.report(DOMErrorSeverity::Error, "invalid-data-in-cdata-section", message, Some(cdata));
+    }
+    let mut last = None;
+    for section in split_cdata(data) {
+      let section = NodeRef::CDATASection(CDATASection::new(core.children_owner_document(), section));
+      let _ = core.insert_before(section.clone(), Some(cdata));
+      last = Some(section);
+    }
+    let _ = core.remove_child(cdata);
+    let message = "the CDATA section is split at the terminator ']]>'";
+    self.config.report(DOMErrorSeverity::Warning, "cdata-sections-splitted", message, last.as_ref())
+  }
+}
+
+/// Splits the data of a CDATA section at `]]>`, so that each part can be written in a CDATA section.
+pub(super) fn split_cdata(data: &str) -> impl Iterator<Item = &str> {
+  let mut rest = Some(data);
+  std::iter::from_fn(move || {
+    let data = rest?;
+    match data.find("]]>") {
+      Some(i) => {
+        rest = Some(&data[i + 2..]);
+        Some(&data[..i + 2])
+      }
+      None => rest.take(),
+    }
+  })
+}
+
+/// Adds the namespace declarations that the element and its attributes need but are not in scope, as the
+/// [namespace normalization](https://www.w3.org/TR/DOM-Level-3-Core/namespaces-algorithms.html#normalizeDocumentAlgo).
+/// An attribute whose namespace has no prefix in scope is recreated with a prefix `NS1`, `NS2`, ..., because the
+/// prefix of a node cannot be changed in this implementation.
+fn fix_namespaces(element: &Rc<Element>) -> W3C::Result<()> {
+  let node = NodeRef::Element(element.clone());
+  let declare = |prefix: Option<&str>, namespace_uri: Option<&str>| {
+    let name = prefix.map(|prefix| format!("xmlns:{}", prefix)).unwrap_or_else(|| "xmlns".to_string());
+    element.set_attribute_ns(Some(XMLNS_NAMESPACE_URI), &name, namespace_uri.unwrap_or_default())
+  };
+  if element.local_name().is_some() {
+    let prefix = element.prefix();
+    if declared_namespace(&node, prefix.as_deref()).as_deref() != element.namespace_uri() {
+      declare(prefix.as_deref(), element.namespace_uri())?;
+    }
+  }
+  for attr in element.attributes.to_vec() {
+    let NodeRef::Attr(attr) = attr else { continue };
+    let Some(namespace_uri) = attr.namespace_uri() else { continue };
+    if namespace_uri == XMLNS_NAMESPACE_URI || namespace_uri == XML_NAMESPACE_URI {
+      continue;
+    }
+    let declared = |prefix: &str| declared_namespace(&node, Some(prefix));
+    match attr.prefix() {
+      Some(prefix) if declared(&prefix).as_deref() == Some(namespace_uri) => (),
+      Some(prefix) if declared(&prefix).is_none() => declare(Some(&prefix), Some(namespace_uri))?,
+      _ => {
+        let prefix = match declared_prefix(&node, namespace_uri) {
+          Some(prefix) => prefix,
+          None => {
+            let prefix = generate_prefix(|prefix| declared(prefix).is_some());
+            declare(Some(&prefix), Some(namespace_uri))?;
+            prefix
+          }
+        };
+        let local_name = attr.local_name().unwrap_or_default().to_string();
+        element.remove_attribute_node(&attr)?;
+        element.set_attribute_ns(Some(namespace_uri), &format!("{}:{}", prefix, local_name), &attr.value())?;
+      }
+    }
+  }
+  Ok(())
+}
+
+/// Returns the prefix that the attribute declares if it's a namespace declaration: `Some(None)` for `xmlns` and
+/// `Some(Some(prefix))` for `xmlns:prefix`.
+pub(super) fn namespace_declaration(attr: &Attr) -> Option<Option<&str>> {
+  match attr.name().strip_prefix("xmlns") {
+    Some("") => Some(None),
+    Some(name) => name.strip_prefix(':').map(Some),
+    None => None,
+  }
+}
+
+/// Returns the first of `NS1`, `NS2`, ... that is not `declared`.
+pub(super) fn generate_prefix(declared: impl Fn(&str) -> bool) -> String {
+  (1..).map(|i| format!("NS{}", i)).find(|prefix| !declared(prefix)).expect("a prefix must be available")
+}
+
+/// Returns the namespace URI bound to the `prefix` by the namespace declarations in scope of the element, unlike
+/// [W3C::Node::lookup_namespace_uri()] which also refers to the namespaces of the elements.
+fn declared_namespace(element: &NodeRef, prefix: Option<&str>) -> Option<String> {
+  if prefix == Some("xml") {
+    return Some(XML_NAMESPACE_URI.to_string());
+  }
+  let mut node = Some(element.clone());
+  while let Some(NodeRef::Element(e)) = node {
+    for attr in e.attributes.to_vec() {
+      if let NodeRef::Attr(attr) = attr {
+        if namespace_declaration(&attr) == Some(prefix) {
+          let value = attr.value();
+          return if value.is_empty() { None } else { Some(value) };
+        }
+      }
+    }
+    node = e.core.parent_node();
+  }
+  None
+}
+
+/// Returns a prefix bound to the `namespace_uri` by the namespace declarations in scope of the element.
+fn declared_prefix(element: &NodeRef, namespace_uri: &str) -> Option<String> {
+  let mut node = Some(element.clone());
+  while let Some(NodeRef::Element(e)) = node {
+    for attr in e.attributes.to_vec() {
+      if let NodeRef::Attr(attr) = attr {
+        if let Some(Some(prefix)) = namespace_declaration(&attr) {
+          if declared_namespace(element, Some(prefix)).as_deref() == Some(namespace_uri) {
+            return Some(prefix.to_string());
+          }
+        }
+      }
+    }
+    node = e.core.parent_node();
+  }
+  None
+}
@@ -0,0 +1,85 @@
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+use crate::xml::w3c::dom::{self as W3C, NodeType};
+
+use super::*;
+
+pub struct ProcessingInstruction {
+  pub(super) core: NodeCore,
+  target: String,
+  data: RefCell<String>,
+}
+
+impl ProcessingInstruction {
+  pub(super) fn new(owner_document: Weak<Document>, target: &str, data: &str) -> Rc<ProcessingInstruction> {
+    Rc::new_cyclic(|this| ProcessingInstruction {
+      core: NodeCore::new(WeakNodeRef::ProcessingInstruction(this.clone()), owner_document),
+      target: target.to_string(),
+      data: RefCell::new(data.to_string()),
+    })
+  }
+}
+
+impl W3C::Node<DOMImplementation> for ProcessingInstruction {
+  node_tree_methods!();
+
+  fn attributes(&self) -> Option<&NamedNodeMap> {
+    None
+  }
+  fn base_uri(&self) -> Option<String> {
+    self.core.owner_document().and_then(|document| document.document_uri())
+  }
+  fn local_name(&self) -> Option<&str> {
+    None
+  }
+  fn namespace_uri(&self) -> Option<&str> {
+    None
+  }
+  fn node_name(&self) -> &str {
+    &self.target
+  }
+  fn node_type(&self) -> NodeType {
+    NodeType::ProcessingInstructionNode
+  }
+  fn node_value(&self) -> W3C::Result<Option<String>> {
+    Ok(Some(self.data.borrow().clone()))
+  }
+  fn set_node_value(&self, value: &str) -> W3C::Result<()> {
+    W3C::ProcessingInstruction::set_data(self, value)
+  }
+  fn prefix(&self) -> Option<String> {
+    None
+  }
+  fn set_prefix(&self, _prefix: Option<String>) -> W3C::Result<()> {
+    Ok(())
+  }
+  fn text_content(&self) -> Option<String> {
+    Some(self.data.borrow().clone())
+  }
+  fn set_text_content(&self, text_content: &str) -> W3C::Result<()> {
+    W3C::ProcessingInstruction::set_data(self, text_content)
+  }
+  fn has_attributes(&self) -> bool {
+    false
+  }
+}
+
+impl W3C::ProcessingInstruction<DOMImplementation> for ProcessingInstruction {
+  fn target(&self) -> &str {
+    &self.target
+  }
+  fn data(&self) -> String {
+    self.data.borrow().clone()
+  }
+  fn set_data(&self, data: &str) -> W3C::Result<()> {
+    *self.data.borrow_mut() = data.to_string();
+    Ok(())
+  }
+
+  fn clone_processing_instruction(&self, _deep: bool) -> Rc<Self> {
+    let pi = ProcessingInstruction::new(self.core.owner_document_weak(), &self.target, &self.data.borrow());
+    self.core.clone_user_data(&NodeRef::ProcessingInstruction(pi.clone()));
+    pi
+  }
+}
@@ -0,0 +1,469 @@
+use std::io::ErrorKind;
+use std::rc::Rc;
+
+use crate::io::{encoder, Encoder, Unmappable};
+use crate::xml::parser::chars::{is_char, is_char11};
+use crate::xml::w3c::dom::{
+  self as W3C, Attr as _, CharacterData as _, DOMErrorSeverity, DocumentType as _, NamedNodeMap as _, Node as _,
+  ProcessingInstruction as _,
+};
+use crate::{Error, Result};
+
+use super::{
+  core_of, generate_prefix, namespace_declaration, split_cdata, DOMConfiguration, NodeRef, XMLNS_NAMESPACE_URI,
+  XML_NAMESPACE_URI,
+};
+
+/// [Interface *LSSerializer*](https://www.w3.org/TR/DOM-Level-3-LS/load-save.html#LS-LSSerializer) that writes a node
+/// and its descendants as a XML document or fragment in UTF-8 or the other [encoding](LSSerializer::encoding()).
+///
+/// The markup is generated a piece at a time and written through a buffer, so that a large document is not built up
+/// in memory as a whole. Attributes that were not specified in the document but defaulted from the DTD are omitted, as
+/// the `discard-default-content` parameter of DOM Load and Save.
+///
+/// The output follows the parameters of [LSSerializer::dom_config()]: comments are omitted if `comments` is `false`,
+/// CDATA sections are written as text if `cdata-sections` is `false`, entity references are replaced by their
+/// children if `entities` is `false`, and whitespace in element content is omitted if `element-content-whitespace`
+/// is `false`. If `namespaces` is `true`, the namespace declarations that the elements and attributes need but are
+/// not written are added. If `well-formed` is `true`, a character not allowed in XML fails the serialization with
+/// [W3C::DOMException::InvalidCharacter], as does a CDATA section containing `]]>` if `split-cdata-sections` is
+/// `false`. These errors and the warnings about split CDATA sections are reported to the `error-handler`, and the
+/// serialization also fails with an I/O error if the handler returns `false` for such a warning.
+///
+/// A character of a text or an attribute value that the encoding cannot represent is written as a character
+/// reference. Such a character elsewhere, as in a name or a comment, fails the output of [LSSerializer::write()].
+///
+/// ```
+/// use xenolith::io::StringReader;
+/// use xenolith::xml::dom::{LSSerializer, NodeRef};
+/// use xenolith::xml::parser::{DocumentBuilder, InputSource};
+///
+/// let input = InputSource::new(StringReader::new("<a x='1 &amp; 2'>R&amp;D<![CDATA[<>]]></a>"));
+/// let document = DocumentBuilder::new().parse(input).unwrap();
+/// let xml = LSSerializer::new().write_to_string(&NodeRef::Document(document)).unwrap();
+/// assert_eq!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<a x=\"1 &amp; 2\">R&amp;D<![CDATA[<>]]></a>", xml);
+/// ```
+///
+pub struct LSSerializer {
+  encoding: String,
+  new_line: String,
+  xml_declaration: bool,
+  config: DOMConfiguration,
+}
+
+impl LSSerializer {
+  /// The size of the buffer that the markup is written through.
+  const BUFFER_SIZE: usize = 8 * 1024;
+
+  pub fn new() -> LSSerializer {
+    LSSerializer {
+      encoding: "UTF-8".to_string(),
+      new_line: "\n".to_string(),
+      xml_declaration: true,
+      config: DOMConfiguration::new(),
+    }
+  }
+
+  /// [LSSerializer.domConfig](https://www.w3.org/TR/DOM-Level-3-LS/load-save.html#LS-LSSerializer-config), which
+  /// may be shared with a document or a [DocumentBuilder](crate::xml::parser::DocumentBuilder).
+  pub fn dom_config(&self) -> &DOMConfiguration {
+    &self.config
+  }
+
+  pub fn set_dom_config(&mut self, config: DOMConfiguration) {
+    self.config = config;
+  }
+
+  /// The encoding of the output, which is named in the XML declaration. The default is `UTF-8`. The serialization
+  /// fails with `unsupported-encoding` if it's not in the [registry](crate::io::Encoding).
+  pub fn encoding(&self) -> &str {
+    &self.encoding
+  }
+
+  pub fn set_encoding(&mut self, encoding: &str) {
+    self.encoding = encoding.to_string();
+  }
+
+  /// The end-of-line sequence written between the nodes at the document level. The default is `"\n"`.
+  pub fn new_line(&self) -> &str {
+    &self.new_line
+  }
+
+  pub fn set_new_line(&mut self, new_line: &str) {
+    self.new_line = new_line.to_string();
+  }
+
+  /// Whether the XML declaration is written at the start of a document. The default is `true`.
+  pub fn xml_declaration(&self) -> bool {
+    self.xml_declaration
+  }
+
+  pub fn set_xml_declaration(&mut self, xml_declaration: bool) {
+    self.xml_declaration = xml_declaration;
+  }
+
+  /// Writes the `node` to a string, whose characters that the encoding cannot represent are escaped the same as
+  /// [LSSerializer::write()] but not encoded.
+  pub fn write_to_string(&self, node: &NodeRef) -> Result<String> {
+    let mut markup = Markup::new(node, self.encoder()?);
+    let mut out = String::new();
+    while markup.next(self, &mut out)? {}
+    Ok(out)
+  }
+
+  /// Writes the `node` in the encoding to the `out`.
+  pub fn write<W: std::io::Write>(&self, node: &NodeRef, out: &mut W) -> Result<()> {
+    let mut markup = Markup::new(node, self.encoder()?);
+    let mut buffer = String::with_capacity(Self::BUFFER_SIZE);
+    let mut bytes = Vec::with_capacity(Self::BUFFER_SIZE);
+    while markup.next(self, &mut buffer)? {
+      if buffer.len() >= Self::BUFFER_SIZE {
+        markup.encode(&buffer, &mut bytes, false)?;
+        out.write_all(&bytes)?;
+        buffer.clear();
+        bytes.clear();
+      }
+    }
+    markup.encode(&buffer, &mut bytes, true)?;
+    out.write_all(&bytes)?;
+    out.flush()?;
+    Ok(())
+  }
+
+  /// Writes the `node` in the encoding to the asynchronous `out`.
+  #[cfg(feature = "async")]
+  pub async fn write_async<W: futures_io::AsyncWrite + Unpin>(&self, node: &NodeRef, out: &mut W) -> Result<()> {
+    use std::future::poll_fn;
+    use std::pin::Pin;
+
+    async fn write_all<W: futures_io::AsyncWrite + Unpin>(out: &mut W, mut bytes: &[u8]) -> std::io::Result<()> {
+      while !bytes.is_empty() {
+        match poll_fn(|cx| Pin::new(&mut *out).poll_write(cx, bytes)).await? {
+          0 => return Err(std::io::ErrorKind::WriteZero.into()),
+          len => bytes = &bytes[len..],
+        }
+      }
+      Ok(())
+    }
+
+    let mut markup = Markup::new(node, self.encoder()?);
+    let mut buffer = String::with_capacity(Self::BUFFER_SIZE);
+    let mut bytes = Vec::with_capacity(Self::BUFFER_SIZE);
+    while markup.next(self, &mut buffer)? {
+      if buffer.len() >= Self::BUFFER_SIZE {
+        markup.encode(&buffer, &mut bytes, false)?;
+        write_all(out, &bytes).await?;
+        buffer.clear();
+        bytes.clear();
+      }
+    }
+    markup.encode(&buffer, &mut bytes, true)?;
+    write_all(out, &bytes).await?;
+    poll_fn(|cx| Pin::new(&mut *out).poll_flush(cx)).await?;
+    Ok(())
+  }
+
+  fn encoder(&self) -> Result<Box<dyn Encoder>> {
+    encoder(&self.encoding).ok_or_else(|| {
+      let message = format!("unsupported encoding: {}", self.encoding);
+      self.config.report(DOMErrorSeverity::FatalError, "unsupported-encoding", &message, None);
+      std::io::Error::new(ErrorKind::Unsupported, message).into()
+    })
+  }
+}
+
+impl Default for LSSerializer {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// The nodes to be serialized in document order, which generates the markup a node at a time.
+struct Markup {
+  steps: Vec<Step>,
+  /// The namespace declarations written for the open elements, as pairs of a prefix and a namespace URI.
+  bindings: Vec<(Option<String>, Option<String>)>,
+  xml11: bool,
+  encoder: Box<dyn Encoder>,
+}
+
+enum Step {
+  /// Closes the element and removes the namespace declarations after the index.
+  Close(NodeRef, usize),
+  Open(NodeRef),
+  NewLine,
+}
+
+impl Markup {
+  fn new(node: &NodeRef, encoder: Box<dyn Encoder>) -> Markup {
+    let xml11 = match node {
+      NodeRef::Document(document) => document.xml_version() == "1.1",
+      _ => core_of(node).owner_document().is_some_and(|document| document.xml_version() == "1.1"),
+    };
+    Markup { steps: vec![Step::Open(node.clone())], bindings: Vec::new(), xml11, encoder }
+  }
+
+  /// Encodes the `markup` generated into `bytes`, and ends the stream if `last`. It fails if the markup contains a
+  /// character that the encoding cannot represent.
+  fn encode(&mut self, markup: &str, bytes: &mut Vec<u8>, last: bool) -> Result<()> {
+    if let Err(Unmappable(ch)) = self.encoder.encode(markup, bytes) {
+      let message = format!("the character U+{:04X} cannot be encoded in {}", ch as u32, self.encoder.encoding());
+      return Err(std::io::Error::new(ErrorKind::InvalidData, message).into());
+    }
+    if last {
+      self.encoder.finish(bytes);
+    }
+    Ok(())
+  }
+
+  /// Appends the markup of the next node to `out`. `false` is returned if all nodes have been written.
+  fn next(&mut self, serializer: &LSSerializer, out: &mut String) -> Result<bool> {
+    let config = &serializer.config;
+    let node = match self.steps.pop() {
+      Some(Step::Open(node)) => node,
+      Some(Step::Close(node, bindings)) => {
+        out.push_str("</");
+        out.push_str(node.as_node().node_name());
+        out.push('>');
+        self.bindings.truncate(bindings);
+        return Ok(true);
+      }
+      Some(Step::NewLine) => {
+        out.push_str(&serializer.new_line);
+        return Ok(true);
+      }
+      None => return Ok(false),
+    };
+    match &node {
+      NodeRef::Document(document) => {
+        if serializer.xml_declaration {
+          let declaration =
+            format!("<?xml version=\"{}\" encoding=\"{}\"?>", document.xml_version(), self.encoder.encoding());
+          out.push_str(&declaration);
+          if document.has_child_nodes() {
+            out.push_str(&serializer.new_line);
+          }
+        }
+        self.push_children(&node, true);
+      }
+      NodeRef::DocumentFragment(_) => self.push_children(&node, false),
+      NodeRef::Element(element) => {
+        let bindings = self.bindings.len();
+        let attributes = element.attributes().expect("an element must have attributes");
+        let attributes = (0..attributes.length()).filter_map(|i| match attributes.item(i) {
+          Some(NodeRef::Attr(attr)) if attr.specified() => Some(attr),
+          _ => None,
+        });
+        let attributes = attributes.collect::<Vec<_>>();
+        let mut names = attributes.iter().map(|attr| attr.name().to_string()).collect::<Vec<_>>();
+        let mut declarations = Vec::new();
+        if config.flag("namespaces") {
+          for attr in attributes.iter() {
+            if let Some(prefix) = namespace_declaration(attr) {
+              let value = attr.value();
+              self.bindings.push((prefix.map(|p| p.to_string()), Some(value).filter(|v| !v.is_empty())));
+            }
+          }
+          if element.local_name().is_some() && self.bound(element.prefix().as_deref()) != element.namespace_uri() {
+            declarations.push((element.prefix(), element.namespace_uri().map(|n| n.to_string())));
+            self.bindings.push(declarations.last().unwrap().clone());
+          }
+          for (attr, name) in attributes.iter().zip(names.iter_mut()) {
+            let Some(namespace_uri) = attr.namespace_uri() else { continue };
+            if namespace_uri == XMLNS_NAMESPACE_URI || namespace_uri == XML_NAMESPACE_URI {
+              continue;
+            }
+            let prefix = match attr.prefix() {
+              Some(prefix) if self.bound(Some(&prefix)) == Some(namespace_uri) => continue,
+              Some(prefix) if self.bound(Some(&prefix)).is_none() => prefix,
+              _ => match self.bound_prefix(namespace_uri) {
+                Some(prefix) => {
+                  *name = format!("{}:{}", prefix, attr.local_name().unwrap_or_default());
+                  continue;
+                }
+                None => generate_prefix(|prefix| self.bound(Some(prefix)).is_some()),
+              },
+            };
+            *name = format!("{}:{}", prefix, attr.local_name().unwrap_or_default());
+            declarations.push((Some(prefix), Some(namespace_uri.to_string())));
+            self.bindings.push(declarations.last().unwrap().clone());
+          }
+        }
+        out.push('<');
+        out.push_str(element.node_name());
+        for (attr, name) in attributes.iter().zip(names.iter()) {
+          let value = attr.value();
+          self.check_characters(config, &NodeRef::Attr(attr.clone()), &value)?;
+          out.push(' ');
+          out.push_str(name);
+          out.push_str("=\"");
+          escape_attribute_value(&value, self.encoder.as_ref(), out);
+          out.push('"');
+        }
+        for (prefix, namespace_uri) in declarations {
+          out.push_str(&prefix.map(|prefix| format!(" xmlns:{}=\"", prefix)).unwrap_or(" xmlns=\"".to_string()));
+          escape_attribute_value(namespace_uri.as_deref().unwrap_or_default(), self.encoder.as_ref(), out);
+          out.push('"');
+        }
+        if element.has_child_nodes() {
+          out.push('>');
+          self.steps.push(Step::Close(node.clone(), bindings));
+          self.push_children(&node, false);
+        } else {
+          out.push_str("/>");
+          self.bindings.truncate(bindings);
+        }
+      }
+      NodeRef::Attr(attr) => escape_attribute_value(&attr.value(), self.encoder.as_ref(), out),
+      NodeRef::Text(text) if text.is_element_content_whitespace() && !config.flag("element-content-whitespace") => (),
+      NodeRef::Text(text) => {
+        let data = text.data();
+        self.check_characters(config, &node, &data)?;
+        escape_text(&data, self.encoder.as_ref(), out);
+      }
+      NodeRef::CDATASection(cdata) if !config.flag("cdata-sections") => {
+        let data = cdata.data();
+        self.check_characters(config, &node, &data)?;
+        escape_text(&data, self.encoder.as_ref(), out);
+      }
+      NodeRef::CDATASection(cdata) => {
+        let data = cdata.data();
+        self.check_characters(config, &node, &data)?;
+        if data.contains("]]>") {
+          if !config.flag("split-cdata-sections") {
+            let message = "the CDATA section contains the terminator ']]>'";
+            config.report(DOMErrorSeverity::FatalError, "invalid-data-in-cdata-section", message, Some(&node));
+            return Err(Error::W3C(W3C::DOMException::InvalidCharacter));
+          }
+          let message = "the CDATA section is split at the terminator ']]>'";
+          if !config.report(DOMErrorSeverity::Warning, "cdata-sections-splitted", message, Some(&node)) {
+            let message = "the error handler stopped the serialization at a split CDATA section";
+            return Err(std::io::Error::other(message).into());
+          }
+        }
+        for section in split_cdata(&data) {
+          out.push_str("<![CDATA[");
+          out.push_str(section);
+          out.push_str("]]>");
+        }
+      }
+      NodeRef::Comment(_) if !config.flag("comments") => (),
+      NodeRef::Comment(comment) => {
+        let data = comment.data();
+        self.check_characters(config, &node, &data)?;
+        out.push_str("<!--");
+        out.push_str(&data);
+        out.push_str("-->");
+      }
+      NodeRef::ProcessingInstruction(pi) => {
+        let data = pi.data();
+        self.check_characters(config, &node, &data)?;
+        out.push_str("<?");
+        out.push_str(pi.target());
+        if !data.is_empty() {
+          out.push(' ');
+          out.push_str(&data);
+        }
+        out.push_str("?>");
+      }
+      NodeRef::EntityReference(reference) if !config.flag("entities") && reference.has_child_nodes() => {
+        self.push_children(&node, false)
+      }
+      NodeRef::EntityReference(reference) => {
+        out.push('&');
+        out.push_str(reference.node_name());
+        out.push(';');
+      }
+      NodeRef::DocumentType(doctype) => write_doctype(doctype, out),
+      NodeRef::Entity(_) | NodeRef::Notation(_) => (),
+    }
+    Ok(true)
+  }
+
+  /// Schedules the children of the `node`, separated by new lines if `new_lines`.
+  fn push_children(&mut self, node: &NodeRef, new_lines: bool) {
+    let mut child = node.as_node().last_child();
+    while let Some(node) = child {
+      child = node.as_node().previous_sibling();
+      self.steps.push(Step::Open(node));
+      if new_lines && child.is_some() {
+        self.steps.push(Step::NewLine);
+      }
+    }
+  }
+
+  /// Returns the namespace URI that the `prefix` is bound to by the declarations written.
+  fn bound(&self, prefix: Option<&str>) -> Option<&str> {
+    if prefix == Some("xml") {
+      return Some(XML_NAMESPACE_URI);
+    }
+    let binding = self.bindings.iter().rev().find(|(p, _)| p.as_deref() == prefix);
+    binding.and_then(|(_, namespace_uri)| namespace_uri.as_deref())
+  }
+
+  /// Returns a prefix that is bound to the `namespace_uri` by the declarations written.
+  fn bound_prefix(&self, namespace_uri: &str) -> Option<String> {
+    let mut prefixes = self.bindings.iter().rev().filter_map(|(prefix, _)| prefix.as_deref());
+    prefixes.find(|prefix| self.bound(Some(prefix)) == Some(namespace_uri)).map(|prefix| prefix.to_string())
+  }
+
+  /// Fails with `wf-invalid-character` if `well-formed` is `true` and the `data` of the `node` contains a character
+  /// that is not allowed in the version of the document.
+  fn check_characters(&self, config: &DOMConfiguration, node: &NodeRef, data: &str) -> Result<()> {
+    if !config.flag("well-formed") || data.chars().all(|ch| if self.xml11 { is_char11(ch) } else { is_char(ch) }) {
+      return Ok(());
+    }
+    let message = "the node contains a character that is not allowed in XML";
+    config.report(DOMErrorSeverity::FatalError, "wf-invalid-character", message, Some(node));
+    Err(Error::W3C(W3C::DOMException::InvalidCharacter))
+  }
+}
+
+fn write_doctype(doctype: &Rc<super::DocumentType>, out: &mut String) {
+  out.push_str("<!DOCTYPE ");
+  out.push_str(doctype.name());
+  let quote = |literal: &str| if literal.contains('"') { format!("'{}'", literal) } else { format!("\"{}\"", literal) };
+  match (doctype.public_id(), doctype.system_id()) {
+    (Some(public_id), Some(system_id)) => {
+      out.push_str(&format!(" PUBLIC {} {}", quote(public_id), quote(system_id)));
+    }
+    (None, Some(system_id)) => out.push_str(&format!(" SYSTEM {}", quote(system_id))),
+    _ => (),
+  }
+  if let Some(internal_subset) = doctype.internal_subset() {
+    out.push_str(" [");
+    out.push_str(internal_subset);
+    out.push(']');
+  }
+  out.push('>');
+}
+
+/// Escapes the markup characters of the text, and the characters that the `encoder` cannot represent.
+fn escape_text(text: &str, encoder: &dyn Encoder, out: &mut String) {
+  for ch in text.chars() {
+    match ch {
+      '&' => out.push_str("&amp;"),
+      '<' => out.push_str("&lt;"),
+      '>' => out.push_str("&gt;"),
+      '\r' => out.push_str("&#xD;"),
+      ch if !encoder.can_encode(ch) => out.push_str(&format!("&#x{:X};", ch as u32)),
+      ch => out.push(ch),
+    }
+  }
+}
+
+/// Escapes the attribute value so that it's read back as it is, without the attribute-value normalization.
+fn escape_attribute_value(value: &str, encoder: &dyn Encoder, out: &mut String) {
+  for ch in value.chars() {
+    match ch {
+      '&' => out.push_str("&amp;"),
+      '<' => out.push_str("&lt;"),
+      '"' => out.push_str("&quot;"),
+      '\t' => out.push_str("&#x9;"),
+      '\n' => out.push_str("&#xA;"),
+      '\r' => out.push_str("&#xD;"),
+      ch if !encoder.can_encode(ch) => out.push_str(&format!("&#x{:X};", ch as u32)),
+      ch => out.push(ch),
+    }
+  }
+}
@@ -0,0 +1,116 @@
+use crate::io::{encoder, DecodingReader, StringReader};
+use crate::xml::dom::{Document, LSSerializer, NodeRef};
+use crate::xml::parser::{DocumentBuilder, InputSource};
+use crate::xml::w3c::dom::{Document as _, Element as _, Node as _};
+use crate::Error;
+
+use std::rc::Rc;
+
+fn parse(xml: &str) -> Rc<Document> {
+  DocumentBuilder::new().parse(InputSource::new(StringReader::new(xml))).unwrap()
+}
+
+const XML: &str = r#"<?xml version="1.1"?>
+<!DOCTYPE a PUBLIC "-//X//DTD A//EN" "a.dtd" [<!ATTLIST a d CDATA "default">]>
+<!-- comment --><a x="&lt;&amp;&quot;&#x9;&#xA;&#xD;'&gt;"><b/>x&gt;y &amp; z&#xD;<![CDATA[<]]]]><![CDATA[>]]><?pi?><?pi data?>&undeclared;</a>
+"#;
+
+#[test]
+fn serialize_document() {
+  let document = parse(XML);
+  let expected = r#"<?xml version="1.1" encoding="UTF-8"?>
+<!DOCTYPE a PUBLIC "-//X//DTD A//EN" "a.dtd" [<!ATTLIST a d CDATA "default">]>
+<!-- comment -->
+<a x="&lt;&amp;&quot;&#x9;&#xA;&#xD;'>"><b/>x&gt;y &amp; z&#xD;<![CDATA[<]]]]><![CDATA[>]]><?pi?><?pi data?>&undeclared;</a>"#;
+  let serializer = LSSerializer::new();
+  let xml = serializer.write_to_string(&NodeRef::Document(document.clone())).unwrap();
+  assert_eq!(expected, xml);
+  assert_eq!(expected, serializer.write_to_string(&NodeRef::Document(parse(&xml))).unwrap());
+
+  let mut bytes = Vec::new();
+  serializer.write(&NodeRef::Document(document.clone()), &mut bytes).unwrap();
+  assert_eq!(expected.as_bytes(), bytes);
+
+  let mut serializer = LSSerializer::new();
+  serializer.set_xml_declaration(false);
+  serializer.set_new_line("\r\n");
+  let root = document.document_element().unwrap();
+  assert_eq!("<b/>", serializer.write_to_string(&root.first_child().unwrap()).unwrap());
+  root.set_attribute("d", "specified").unwrap();
+  let xml = serializer.write_to_string(&NodeRef::Document(document)).unwrap();
+  assert!(xml.starts_with("<!DOCTYPE a PUBLIC \"-//X//DTD A//EN\" \"a.dtd\" [<!ATTLIST a d CDATA \"default\">]>\r\n"));
+  assert!(xml.contains(r#"<a x="&lt;"#) && xml.contains(r#" d="specified"><b/>"#), "{}", xml);
+}
+
+#[test]
+fn serialize_in_encoding() {
+  let document = parse("<a x='\u{65E5}\u{1F600}'>\u{65E5}\u{672C}\u{1F600}<!--\u{FF71}--></a>");
+  let mut serializer = LSSerializer::new();
+  serializer.set_encoding("shift_jis");
+  let mut bytes = Vec::new();
+  serializer.write(&NodeRef::Document(document.clone()), &mut bytes).unwrap();
+  let expected = "<?xml version=\"1.0\" encoding=\"Shift_JIS\"?>\n<a x=\"\u{65E5}&#x1F600;\">\u{65E5}\u{672C}&#x1F600;<!--\u{FF71}--></a>";
+  assert_eq!(expected, serializer.write_to_string(&NodeRef::Document(document.clone())).unwrap());
+  let mut encoded = Vec::new();
+  encoder("Shift_JIS").unwrap().encode(expected, &mut encoded).unwrap();
+  assert_eq!(encoded, bytes);
+  let input = InputSource::new(DecodingReader::new(std::io::Cursor::new(bytes)));
+  let parsed = DocumentBuilder::new().parse(input).unwrap();
+  assert_eq!(Some("\u{65E5}\u{672C}\u{1F600}".to_string()), parsed.document_element().unwrap().text_content());
+
+  // a character that cannot be escaped fails the output
+  serializer.set_encoding("ISO-2022-JP");
+  let result = serializer.write(&NodeRef::Document(document.clone()), &mut Vec::new());
+  assert!(matches!(result, Err(Error::IO(err)) if err.kind() == std::io::ErrorKind::InvalidData));
+  serializer.set_encoding("X-UNKNOWN");
+  let result = serializer.write(&NodeRef::Document(document), &mut Vec::new());
+  assert!(matches!(result, Err(Error::IO(err)) if err.kind() == std::io::ErrorKind::Unsupported));
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn serialize_and_parse_asynchronously() {
+  use std::future::poll_fn;
+  use std::pin::Pin;
+  use std::task::{Context, Poll};
+
+  use futures_core::Stream;
+  use futures_io::AsyncRead;
+
+  use crate::test::block_on;
+  use crate::xml::parser::{AsyncPullParser, Event, PullParser};
+
+  /// A reader that returns a byte at a time, and is pending before each byte.
+  struct Trickle(Vec<u8>, usize, bool);
+  impl AsyncRead for Trickle {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<std::io::Result<usize>> {
+      self.2 = !self.2;
+      if self.2 {
+        cx.waker().wake_by_ref();
+        return Poll::Pending;
+      }
+      let Trickle(bytes, position, _) = &mut *self;
+      let len = if *position < bytes.len() && !buf.is_empty() { 1 } else { 0 };
+      buf[..len].copy_from_slice(&bytes[*position..*position + len]);
+      *position += len;
+      Poll::Ready(Ok(len))
+    }
+  }
+
+  let document = parse(XML);
+  let mut bytes = Vec::new();
+  block_on(LSSerializer::new().write_async(&NodeRef::Document(document.clone()), &mut bytes)).unwrap();
+  let xml = LSSerializer::new().write_to_string(&NodeRef::Document(document)).unwrap();
+  assert_eq!(xml.as_bytes(), bytes);
+
+  let expected = PullParser::new(StringReader::new(&xml)).collect::<crate::Result<Vec<Event>>>().unwrap();
+  let mut parser = AsyncPullParser::new(Trickle(bytes, 0, false));
+  let events = block_on(async {
+    let mut events = Vec::new();
+    while let Some(event) = poll_fn(|cx| Pin::new(&mut parser).poll_next(cx)).await {
+      events.push(event.unwrap());
+    }
+    events
+  });
+  assert_eq!(expected, events);
+}
@@ -0,0 +1,101 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use futures_io::AsyncRead;
+
+use crate::Result;
+
+use super::{Event, EventKind, FeedParser, PullParser};
+
+/// A parser that reads a UTF-8 document from [AsyncRead] and returns its events as a [Stream], available with the
+/// `async` feature.
+///
+/// The bytes are fed to [FeedParser] as they are read, so the task yields to the executor while waiting for the
+/// reader instead of blocking the thread. The stream ends after [EventKind::EndDocument] or the first error.
+///
+/// The parser is not [Send], since [PullParser] shares its entity resolver by `Rc` and reads entities from readers
+/// that are not required to be `Send`. It has to be polled on the thread that created it, for example in a task
+/// spawned by `tokio::task::spawn_local()` within a `LocalSet`, or by a single-threaded executor.
+///
+/// ```
+/// use std::future::poll_fn;
+/// use std::pin::Pin;
+/// use futures_core::Stream;
+/// use xenolith::xml::parser::{AsyncPullParser, EventKind};
+///
+/// async fn texts(xml: &[u8]) -> Vec<String> {
+///   let mut parser = AsyncPullParser::new(xml);
+///   let mut texts = Vec::new();
+///   while let Some(event) = poll_fn(|cx| Pin::new(&mut parser).poll_next(cx)).await {
+///     if let EventKind::Text(text) = event.unwrap().kind {
+///       texts.push(text);
+///     }
+///   }
+///   texts
+/// }
+/// # use std::future::Future;
+/// # let mut future = std::pin::pin!(texts(b"<greeting>hello</greeting>"));
+/// # let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+/// # let std::task::Poll::Ready(texts) = future.as_mut().poll(&mut cx) else { panic!() };
+/// assert_eq!(vec!["hello"], texts);
+/// ```
+///
+pub struct AsyncPullParser<R: AsyncRead + Unpin> {
+  reader: R,
+  parser: FeedParser,
+  buffer: Box<[u8]>,
+  finished: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncPullParser<R> {
+  const BUFFER_SIZE: usize = 8 * 1024;
+
+  pub fn new(reader: R) -> AsyncPullParser<R> {
+    Self::with_system_id(reader, None)
+  }
+
+  /// Creates a parser for the document of the `system_id`, which is used as the base URI of relative references and
+  /// reported with errors.
+  pub fn with_system_id(reader: R, system_id: Option<&str>) -> AsyncPullParser<R> {
+    let parser = FeedParser::with_system_id(system_id);
+    AsyncPullParser { reader, parser, buffer: vec![0; Self::BUFFER_SIZE].into_boxed_slice(), finished: false }
+  }
+
+  /// The underlying parser, to be configured before the first event is read. See [FeedParser::pull_parser_mut()].
+  pub fn pull_parser_mut(&mut self) -> &mut PullParser {
+    self.parser.pull_parser_mut()
+  }
+}
+
+impl<R: AsyncRead + Unpin> Stream for AsyncPullParser<R> {
+  type Item = Result<Event>;
+
+  fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    let this = self.get_mut();
+    while !this.finished {
+      match this.parser.next_event() {
+        Ok(Some(event)) => {
+          this.finished = event.kind == EventKind::EndDocument;
+          return Poll::Ready(Some(Ok(event)));
+        }
+        Ok(None) => (),
+        Err(err) => {
+          this.finished = true;
+          return Poll::Ready(Some(Err(err)));
+        }
+      }
+      match Pin::new(&mut this.reader).poll_read(cx, &mut this.buffer) {
+        Poll::Ready(Ok(0)) => this.parser.finish(),
+        Poll::Ready(Ok(len)) => this.parser.feed(&this.buffer[..len]),
+        Poll::Ready(Err(err)) if err.kind() == std::io::ErrorKind::Interrupted => (),
+        Poll::Ready(Err(err)) => {
+          this.finished = true;
+          return Poll::Ready(Some(Err(err.into())));
+        }
+        Poll::Pending => return Poll::Pending,
+      }
+    }
+    Poll::Ready(None)
+  }
+}
@@ -0,0 +1,493 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::rc::Rc;
+
+use crate::io::DecodingReader;
+use crate::Result;
+
+use super::{
+  file_path, file_uri, normalize_uri, percent_decode, resolve_uri, DenyAllResolver, EntityResolver, EventKind,
+  InputSource, PullParser,
+};
+
+/// The namespace of the elements of
+/// [XML Catalogs](https://www.oasis-open.org/committees/download.php/14809/xml-catalogs.html).
+pub const CATALOG_NAMESPACE: &str = "urn:oasis:names:tc:entity:xmlns:xml:catalog";
+
+/// Which of the identifiers [CatalogResolver] uses when an external identifier has both a public and a system
+/// identifier, as the `prefer` attribute of a catalog.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatalogPrefer {
+  /// `public` entries match even if a system identifier is also given.
+  Public,
+  /// `public` entries match only if no system identifier is given.
+  System,
+}
+
+/// An [EntityResolver] that maps the public and system identifiers of external entities to local copies with
+/// [OASIS XML Catalogs 1.1](https://www.oasis-open.org/committees/download.php/14809/xml-catalogs.html).
+///
+/// The catalog files added to the resolver are consulted in order. The entries `public`, `system`, `rewriteSystem`,
+/// `systemSuffix`, `delegatePublic`, `delegateSystem`, `uri`, `rewriteURI`, `uriSuffix`, `delegateURI` and
+/// `nextCatalog` are supported within `catalog` and `group` elements, with the `prefer` and `xml:base` attributes.
+/// The catalogs referred to by `nextCatalog` and `delegate*` entries are loaded when they are needed, and a catalog
+/// that can't be read is treated as empty.
+///
+/// An entity found in the catalogs is read from the file if its URI is a `file:` URI, or with the fallback resolver
+/// otherwise. An entity not found in the catalogs is passed to the fallback resolver as it is, which is
+/// [DenyAllResolver] by default.
+///
+/// ```rust,no_run
+/// use std::rc::Rc;
+/// use xenolith::xml::parser::{CatalogResolver, DocumentBuilder};
+///
+/// let mut resolver = CatalogResolver::new();
+/// resolver.add_catalog("/etc/xml/catalog").unwrap();
+/// let mut builder = DocumentBuilder::new();
+/// builder.set_entity_resolver(Rc::new(resolver));
+/// ```
+///
+pub struct CatalogResolver {
+  catalogs: Vec<Rc<Catalog>>,
+  prefer: CatalogPrefer,
+  fallback: Rc<dyn EntityResolver>,
+  /// The catalogs loaded for `nextCatalog` and `delegate*` entries by their URIs; `None` if they couldn't be read.
+  loaded: RefCell<HashMap<String, Option<Rc<Catalog>>>>,
+}
+
+struct Catalog {
+  uri: String,
+  entries: Vec<Entry>,
+}
+
+/// A catalog entry with the `prefer` in effect, which is `None` if no ancestor specifies it. The URIs of the entries
+/// have been resolved against the base URI.
+///
+struct Entry {
+  prefer: Option<CatalogPrefer>,
+  kind: EntryKind,
+}
+
+enum EntryKind {
+  Public { public_id: String, uri: String },
+  System { system_id: String, uri: String },
+  RewriteSystem { start: String, prefix: String },
+  SystemSuffix { suffix: String, uri: String },
+  DelegatePublic { start: String, catalog: String },
+  DelegateSystem { start: String, catalog: String },
+  Uri { name: String, uri: String },
+  RewriteUri { start: String, prefix: String },
+  UriSuffix { suffix: String, uri: String },
+  DelegateUri { start: String, catalog: String },
+  NextCatalog { catalog: String },
+}
+
+/// What is looked up in the catalogs.
+#[derive(Clone, Copy)]
+enum Key<'a> {
+  ExternalId { public_id: Option<&'a str>, system_id: Option<&'a str> },
+  Uri(&'a str),
+}
+
+enum Outcome {
+  Found(String),
+  /// A delegation failed, which ends the resolution without a match.
+  NotFound,
+  /// The catalog has no matching entry, and the next catalog should be consulted.
+  Continue,
+}
+
+impl CatalogResolver {
+  /// Creates a resolver without catalogs that prefers public identifiers.
+  pub fn new() -> CatalogResolver {
+    CatalogResolver {
+      catalogs: Vec::new(),
+      prefer: CatalogPrefer::Public,
+      fallback: Rc::new(DenyAllResolver),
+      loaded: RefCell::new(HashMap::new()),
+    }
+  }
+
+  /// Reads the catalog file and appends it to the list of catalogs to be consulted.
+  pub fn add_catalog<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+    let path = path.as_ref().canonicalize()?;
+    let mut input = InputSource::new(DecodingReader::new(File::open(&path)?));
+    input.system_id = Some(file_uri(&path));
+    self.add_catalog_source(input)
+  }
+
+  /// Reads the catalog from the input source and appends it to the list of catalogs to be consulted. The system
+  /// identifier of the input is the base URI of the relative URIs in the catalog.
+  pub fn add_catalog_source(&mut self, input: InputSource) -> Result<()> {
+    let catalog = Rc::new(Catalog::read(input)?);
+    self.loaded.borrow_mut().insert(catalog.uri.clone(), Some(catalog.clone()));
+    self.catalogs.push(catalog);
+    Ok(())
+  }
+
+  /// The initial `prefer` of the catalogs that don't specify it. The default is [CatalogPrefer::Public].
+  pub fn prefer(&self) -> CatalogPrefer {
+    self.prefer
+  }
+
+  pub fn set_prefer(&mut self, prefer: CatalogPrefer) {
+    self.prefer = prefer;
+  }
+
+  /// The resolver that reads the entities not found in the catalogs, or found with URIs other than `file:`.
+  pub fn fallback(&self) -> &Rc<dyn EntityResolver> {
+    &self.fallback
+  }
+
+  pub fn set_fallback(&mut self, fallback: Rc<dyn EntityResolver>) {
+    self.fallback = fallback;
+  }
+
+  /// Looks up the URI of the external identifier in the catalogs as defined in §7.1.2 of the specification. A public
+  /// identifier or a system identifier in the `urn:publicid:` namespace is normalized before it's compared, and so are
+  /// the dot segments of an absolute system identifier.
+  pub fn lookup_external_id(&self, public_id: Option<&str>, system_id: Option<&str>) -> Option<String> {
+    let mut public_id = public_id.map(normalize_public_id);
+    let mut system_id = system_id.map(normalize_uri);
+    if let Some(urn) = system_id.as_deref().and_then(unwrap_urn) {
+      public_id.get_or_insert(urn);
+      system_id = None;
+    }
+    let key = Key::ExternalId { public_id: public_id.as_deref(), system_id: system_id.as_deref() };
+    self.found(self.lookup(&self.catalogs, key, &mut Vec::new()))
+  }
+
+  /// Looks up the URI mapped from the `uri` in the catalogs as defined in §7.2.2 of the specification. The dot segments
+  /// of an absolute `uri` are removed before it's compared.
+  pub fn lookup_uri(&self, uri: &str) -> Option<String> {
+    let uri = unwrap_urn(uri).unwrap_or_else(|| normalize_uri(uri));
+    self.found(self.lookup(&self.catalogs, Key::Uri(&uri), &mut Vec::new()))
+  }
+
+  fn found(&self, outcome: Outcome) -> Option<String> {
+    match outcome {
+      Outcome::Found(uri) => Some(uri),
+      _ => None,
+    }
+  }
+
+  /// Consults the catalogs in order, each followed by its next catalogs. `visited` holds the URIs of the catalogs
+  /// being consulted to stop circular references.
+  fn lookup(&self, catalogs: &[Rc<Catalog>], key: Key, visited: &mut Vec<String>) -> Outcome {
+    for catalog in catalogs {
+      if visited.contains(&catalog.uri) {
+        continue;
+      }
+      visited.push(catalog.uri.clone());
+      let outcome = match key {
+        Key::ExternalId { public_id, system_id } => self.lookup_external_id_in(catalog, public_id, system_id, visited),
+        Key::Uri(uri) => self.lookup_uri_in(catalog, uri, visited),
+      };
+      visited.pop();
+      if !matches!(outcome, Outcome::Continue) {
+        return outcome;
+      }
+    }
+    Outcome::Continue
+  }
+
+  fn lookup_external_id_in(
+    &self, catalog: &Catalog, public_id: Option<&str>, system_id: Option<&str>, visited: &mut Vec<String>,
+  ) -> Outcome {
+    if let Some(system_id) = system_id {
+      for entry in &catalog.entries {
+        if let EntryKind::System { system_id: id, uri } = &entry.kind {
+          if id == system_id {
+            return Outcome::Found(uri.clone());
+          }
+        }
+      }
+      let rewrite = longest(catalog.entries.iter().filter_map(|entry| match &entry.kind {
+        EntryKind::RewriteSystem { start, prefix } if system_id.starts_with(start.as_str()) => Some((start, prefix)),
+        _ => None,
+      }));
+      if let Some((start, prefix)) = rewrite {
+        return rewrite_uri(prefix, &system_id[start.len()..]);
+      }
+      let suffix = longest(catalog.entries.iter().filter_map(|entry| match &entry.kind {
+        EntryKind::SystemSuffix { suffix, uri } if system_id.ends_with(suffix.as_str()) => Some((suffix, uri)),
+        _ => None,
+      }));
+      if let Some((_, uri)) = suffix {
+        return Outcome::Found(uri.clone());
+      }
+      let delegates = delegates(catalog.entries.iter().filter_map(|entry| match &entry.kind {
+        EntryKind::DelegateSystem { start, catalog } if system_id.starts_with(start.as_str()) => Some((start, catalog)),
+        _ => None,
+      }));
+      if !delegates.is_empty() {
+        return self.delegate(&delegates, Key::ExternalId { public_id: None, system_id: Some(system_id) }, visited);
+      }
+    }
+    if let Some(public_id) = public_id {
+      let preferred =
+        |entry: &Entry| system_id.is_none() || entry.prefer.unwrap_or(self.prefer) == CatalogPrefer::Public;
+      for entry in catalog.entries.iter().filter(|entry| preferred(entry)) {
+        if let EntryKind::Public { public_id: id, uri } = &entry.kind {
+          if id == public_id {
+            return Outcome::Found(uri.clone());
+          }
+        }
+      }
+      let delegates =
+        delegates(catalog.entries.iter().filter(|entry| preferred(entry)).filter_map(|entry| match &entry.kind {
+          EntryKind::DelegatePublic { start, catalog } if public_id.starts_with(start.as_str()) => {
+            Some((start, catalog))
+          }
+          _ => None,
+        }));
+      if !delegates.is_empty() {
+        return self.delegate(&delegates, Key::ExternalId { public_id: Some(public_id), system_id: None }, visited);
+      }
+    }
+    self.lookup(&self.next_catalogs(catalog), Key::ExternalId { public_id, system_id }, visited)
+  }
+
+  fn lookup_uri_in(&self, catalog: &Catalog, uri: &str, visited: &mut Vec<String>) -> Outcome {
+    for entry in &catalog.entries {
+      if let EntryKind::Uri { name, uri: mapped } = &entry.kind {
+        if name == uri {
+          return Outcome::Found(mapped.clone());
+        }
+      }
+    }
+    let rewrite = longest(catalog.entries.iter().filter_map(|entry| match &entry.kind {
+      EntryKind::RewriteUri { start, prefix } if uri.starts_with(start.as_str()) => Some((start, prefix)),
+      _ => None,
+    }));
+    if let Some((start, prefix)) = rewrite {
+      return rewrite_uri(prefix, &uri[start.len()..]);
+    }
+    let suffix = longest(catalog.entries.iter().filter_map(|entry| match &entry.kind {
+      EntryKind::UriSuffix { suffix, uri: mapped } if uri.ends_with(suffix.as_str()) => Some((suffix, mapped)),
+      _ => None,
+    }));
+    if let Some((_, mapped)) = suffix {
+      return Outcome::Found(mapped.clone());
+    }
+    let delegates = delegates(catalog.entries.iter().filter_map(|entry| match &entry.kind {
+      EntryKind::DelegateUri { start, catalog } if uri.starts_with(start.as_str()) => Some((start, catalog)),
+      _ => None,
+    }));
+    if !delegates.is_empty() {
+      return self.delegate(&delegates, Key::Uri(uri), visited);
+    }
+    self.lookup(&self.next_catalogs(catalog), Key::Uri(uri), visited)
+  }
+
+  /// Looks up the key in the delegated catalogs alone. The resolution ends here even if nothing is found.
+  fn delegate(&self, catalogs: &[&String], key: Key, visited: &mut Vec<String>) -> Outcome {
+    let catalogs = catalogs.iter().filter_map(|uri| self.load(uri)).collect::<Vec<_>>();
+    match self.lookup(&catalogs, key, visited) {
+      Outcome::Found(uri) => Outcome::Found(uri),
+      _ => Outcome::NotFound,
+    }
+  }
+
+  fn next_catalogs(&self, catalog: &Catalog) -> Vec<Rc<Catalog>> {
+    let next = catalog.entries.iter().filter_map(|entry| match &entry.kind {
+      EntryKind::NextCatalog { catalog } => Some(catalog),
+      _ => None,
+    });
+    next.filter_map(|uri| self.load(uri)).collect()
+  }
+
+  /// Reads the catalog of the `file:` URI the first time it's referred to.
+  fn load(&self, uri: &str) -> Option<Rc<Catalog>> {
+    if let Some(catalog) = self.loaded.borrow().get(uri) {
+      return catalog.clone();
+    }
+    let catalog = file_path(uri).and_then(|path| File::open(path).ok()).and_then(|file| {
+      let mut input = InputSource::new(DecodingReader::new(file));
+      input.system_id = Some(uri.to_string());
+      Catalog::read(input).ok().map(Rc::new)
+    });
+    self.loaded.borrow_mut().insert(uri.to_string(), catalog.clone());
+    catalog
+  }
+}
+
+impl Default for CatalogResolver {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl EntityResolver for CatalogResolver {
+  fn resolve_entity(
+    &self, name: &str, public_id: Option<&str>, base_uri: Option<&str>, system_id: &str,
+  ) -> Result<Option<InputSource>> {
+    let uri = self.lookup_external_id(public_id, Some(system_id)).or_else(|| {
+      let absolute = resolve_uri(base_uri, system_id);
+      (absolute != system_id).then(|| self.lookup_external_id(public_id, Some(&absolute))).flatten()
+    });
+    let uri = match uri {
+      Some(uri) => uri,
+      None => return self.fallback.resolve_entity(name, public_id, base_uri, system_id),
+    };
+    match file_path(&uri) {
+      Some(path) => {
+        let mut input = InputSource::new(DecodingReader::new(File::open(path)?));
+        input.public_id = public_id.map(|id| id.to_string());
+        input.system_id = Some(uri);
+        Ok(Some(input))
+      }
+      None => self.fallback.resolve_entity(name, public_id, None, &uri),
+    }
+  }
+}
+
+impl Catalog {
+  fn read(input: InputSource) -> Result<Catalog> {
+    let uri = input.system_id.clone().unwrap_or_default();
+    let mut parser = PullParser::with_input_source(input);
+    parser.set_namespace_aware(true);
+    let mut entries = Vec::new();
+    // the base URI and prefer of each open element, or None for an element outside the catalog namespace, whose
+    // contents are ignored
+    let mut scopes: Vec<Option<(String, Option<CatalogPrefer>)>> = Vec::new();
+    loop {
+      match parser.next_event()?.kind {
+        EventKind::StartElement { namespace_uri, local_name, attributes, .. } => {
+          let (base, prefer) = match scopes.last() {
+            Some(Some(scope)) => scope.clone(),
+            Some(None) => {
+              scopes.push(None);
+              continue;
+            }
+            None => (uri.clone(), None),
+          };
+          if namespace_uri.as_deref() != Some(CATALOG_NAMESPACE) {
+            scopes.push(None);
+            continue;
+          }
+          let attr = |name: &str| attributes.iter().find(|a| a.name == name).map(|a| a.value.as_str());
+          let base = attr("xml:base").map(|b| resolve_uri(Some(&base), b)).unwrap_or(base);
+          let prefer = match attr("prefer") {
+            Some("public") => Some(CatalogPrefer::Public),
+            Some("system") => Some(CatalogPrefer::System),
+            _ => prefer,
+          };
+          let text = |name: &str| attr(name).map(|value| value.to_string());
+          let uri = |name: &str| attr(name).map(|value| resolve_uri(Some(&base), value));
+          let kind = match local_name.as_deref().unwrap_or_default() {
+            "public" => text("publicId").zip(uri("uri")).map(|(public_id, uri)| EntryKind::Public {
+              public_id: unwrap_urn(&public_id).unwrap_or_else(|| normalize_public_id(&public_id)),
+              uri,
+            }),
+            "system" => text("systemId")
+              .zip(uri("uri"))
+              .map(|(system_id, uri)| EntryKind::System { system_id: normalize_uri(&system_id), uri }),
+            "rewriteSystem" => text("systemIdStartString")
+              .zip(uri("rewritePrefix"))
+              .map(|(start, prefix)| EntryKind::RewriteSystem { start, prefix }),
+            "systemSuffix" => {
+              text("systemIdSuffix").zip(uri("uri")).map(|(suffix, uri)| EntryKind::SystemSuffix { suffix, uri })
+            }
+            "delegatePublic" => text("publicIdStartString")
+              .zip(uri("catalog"))
+              .map(|(start, catalog)| EntryKind::DelegatePublic { start: normalize_public_id(&start), catalog }),
+            "delegateSystem" => text("systemIdStartString")
+              .zip(uri("catalog"))
+              .map(|(start, catalog)| EntryKind::DelegateSystem { start, catalog }),
+            "uri" => text("name").zip(uri("uri")).map(|(name, uri)| EntryKind::Uri { name: normalize_uri(&name), uri }),
+            "rewriteURI" => text("uriStartString")
+              .zip(uri("rewritePrefix"))
+              .map(|(start, prefix)| EntryKind::RewriteUri { start, prefix }),
+            "uriSuffix" => text("uriSuffix").zip(uri("uri")).map(|(suffix, uri)| EntryKind::UriSuffix { suffix, uri }),
+            "delegateURI" => text("uriStartString")
+              .zip(uri("catalog"))
+              .map(|(start, catalog)| EntryKind::DelegateUri { start, catalog }),
+            "nextCatalog" => uri("catalog").map(|catalog| EntryKind::NextCatalog { catalog }),
+            _ => None,
+          };
+          entries.extend(kind.map(|kind| Entry { prefer, kind }));
+          scopes.push(Some((base, prefer)));
+        }
+        EventKind::EndElement { .. } => {
+          scopes.pop();
+        }
+        EventKind::EndDocument => break,
+        _ => (),
+      }
+    }
+    Ok(Catalog { uri, entries })
+  }
+}
+
+/// The match with the longest start string or suffix. The first one wins if there are several.
+fn longest<'a, T>(matches: impl Iterator<Item = (&'a String, T)>) -> Option<(&'a String, T)> {
+  matches.fold(None, |longest, (key, value)| match longest {
+    Some((longest_key, _)) if longest_key.len() >= key.len() => longest,
+    _ => Some((key, value)),
+  })
+}
+
+/// The catalogs of the matching `delegate*` entries in order of the length of the start strings, longest first.
+fn delegates<'a>(matches: impl Iterator<Item = (&'a String, &'a String)>) -> Vec<&'a String> {
+  let mut matches = matches.collect::<Vec<_>>();
+  matches.sort_by_key(|(start, _)| std::cmp::Reverse(start.len()));
+  matches.into_iter().map(|(_, catalog)| catalog).collect()
+}
+
+/// The URI rewritten by a `rewriteSystem` or `rewriteURI` entry. The rewriting ends the resolution without a match if
+/// the rest of the identifier climbs out of the `prefix`, as `..%2F` does after the start string `http://x/a`.
+fn rewrite_uri(prefix: &str, rest: &str) -> Outcome {
+  let prefix = normalize_uri(prefix);
+  let uri = normalize_uri(&format!("{}{}", prefix, rest));
+  let climbs = percent_decode(rest).split(['/', '\\']).any(|segment| segment == "..");
+  if uri.starts_with(&prefix) && !climbs {
+    Outcome::Found(uri)
+  } else {
+    Outcome::NotFound
+  }
+}
+
+/// Normalizes the whitespace of the public identifier as defined in
+/// [§4.2.2 of XML](https://www.w3.org/TR/xml/#NT-PubidLiteral).
+fn normalize_public_id(public_id: &str) -> String {
+  public_id.split([' ', '\t', '\r', '\n']).filter(|s| !s.is_empty()).collect::<Vec<_>>().join(" ")
+}
+
+/// Unwraps the `urn:publicid:` URN to the public identifier as defined in
+/// [RFC 3151](https://www.rfc-editor.org/rfc/rfc3151), or `None` if the `id` isn't such a URN.
+fn unwrap_urn(id: &str) -> Option<String> {
+  let urn = id.get(..13).filter(|scheme| scheme.eq_ignore_ascii_case("urn:publicid:")).map(|_| &id[13..])?;
+  let mut public_id = String::with_capacity(urn.len());
+  let mut chars = urn.chars();
+  while let Some(ch) = chars.next() {
+    match ch {
+      '+' => public_id.push(' '),
+      ':' => public_id.push_str("//"),
+      ';' => public_id.push_str("::"),
+      '%' => {
+        let escape = chars.by_ref().take(2).collect::<String>();
+        public_id.push(match escape.to_ascii_uppercase().as_str() {
+          "2B" => '+',
+          "3A" => ':',
+          "2F" => '/',
+          "3B" => ';',
+          "27" => '\'',
+          "3F" => '?',
+          "23" => '#',
+          "25" => '%',
+          _ => {
+            public_id.push('%');
+            public_id.push_str(&escape);
+            continue;
+          }
+        });
+      }
+      ch => public_id.push(ch),
+    }
+  }
+  Some(public_id)
+}
@@ -0,0 +1,140 @@
+use std::fs::write;
+use std::rc::Rc;
+
+use crate::io::StringReader;
+use crate::test::temp_dir;
+use crate::xml::parser::{CatalogPrefer, CatalogResolver, EventKind, PullParser};
+
+#[test]
+fn lookup_external_ids_and_uris() {
+  let dir = temp_dir("lookup_external_ids_and_uris");
+  let dir = dir.as_ref().canonicalize().unwrap();
+  let base = format!("file://{}", dir.display());
+  write(
+    dir.join("catalog.xml"),
+    r#"<?xml version="1.0"?>
+<!DOCTYPE catalog PUBLIC "-//OASIS//DTD XML Catalogs V1.1//EN" "http://www.oasis-open.org/committees/entity/release/1.1/catalog.dtd">
+<catalog xmlns="urn:oasis:names:tc:entity:xmlns:xml:catalog" prefer="system">
+  <public publicId="-//A//DTD  Doc//EN" uri="dtd/a.dtd"/>
+  <group prefer="public" xml:base="http://mirror.example.com/">
+    <public publicId="-//B//DTD Doc//EN" uri="b.dtd"/>
+  </group>
+  <system systemId="http://example.com/a.dtd" uri="dtd/a-system.dtd"/>
+  <rewriteSystem systemIdStartString="http://example.com/" rewritePrefix="www/"/>
+  <rewriteSystem systemIdStartString="http://example.com/dtd/" rewritePrefix="dtd/"/>
+  <systemSuffix systemIdSuffix="/c.dtd" uri="dtd/c.dtd"/>
+  <uri name="http://example.com/schema.xsd" uri="xsd/schema.xsd"/>
+  <delegatePublic publicIdStartString="-//D//" catalog="delegated.xml"/>
+  <ext:entry xmlns:ext="urn:x"><system systemId="ignored" uri="ignored"/></ext:entry>
+  <nextCatalog catalog="next.xml"/>
+</catalog>"#,
+  )
+  .unwrap();
+  write(
+    dir.join("delegated.xml"),
+    r#"<catalog xmlns="urn:oasis:names:tc:entity:xmlns:xml:catalog">
+  <public publicId="-//D//DTD Doc//EN" uri="delegated/d.dtd"/>
+  <nextCatalog catalog="catalog.xml"/>
+</catalog>"#,
+  )
+  .unwrap();
+  write(
+    dir.join("next.xml"),
+    r#"<catalog xmlns="urn:oasis:names:tc:entity:xmlns:xml:catalog">
+  <public publicId="-//D//DTD Other//EN" uri="next/d-other.dtd"/>
+  <system systemId="e.dtd" uri="next/e.dtd"/>
+  <nextCatalog catalog="missing.xml"/>
+  <nextCatalog catalog="catalog.xml"/>
+</catalog>"#,
+  )
+  .unwrap();
+  let mut resolver = CatalogResolver::new();
+  resolver.add_catalog(dir.join("catalog.xml")).unwrap();
+
+  for (public_id, system_id, expected) in [
+    (Some("-//A//DTD Doc//EN"), None, Some("dtd/a.dtd")),
+    (Some("-//A//DTD Doc//EN"), Some("a.dtd"), None),
+    (Some("-//B//DTD\nDoc//EN"), Some("b.dtd"), Some("http://mirror.example.com/b.dtd")),
+    (Some("-//A//DTD Doc//EN"), Some("http://example.com/a.dtd"), Some("dtd/a-system.dtd")),
+    (None, Some("http://example.com/x/y.dtd"), Some("www/x/y.dtd")),
+    (None, Some("http://example.com/dtd/y.dtd"), Some("dtd/y.dtd")),
+    (None, Some("http://other.example.com/c.dtd"), Some("dtd/c.dtd")),
+    (None, Some("urn:publicid:-:A:DTD+Doc:EN"), Some("dtd/a.dtd")),
+    (Some("-//D//DTD Doc//EN"), None, Some("delegated/d.dtd")),
+    (Some("-//D//DTD Other//EN"), None, None),
+    (None, Some("e.dtd"), Some("next/e.dtd")),
+    (None, Some("ignored"), None),
+  ] {
+    let expected =
+      expected.map(|uri| if uri.starts_with("http:") { uri.to_string() } else { format!("{}/{}", base, uri) });
+    assert_eq!(expected, resolver.lookup_external_id(public_id, system_id), "{:?} {:?}", public_id, system_id);
+  }
+  assert_eq!(Some(format!("{}/xsd/schema.xsd", base)), resolver.lookup_uri("http://example.com/schema.xsd"));
+  assert_eq!(None, resolver.lookup_uri("http://example.com/other.xsd"));
+
+  resolver.set_prefer(CatalogPrefer::System);
+  assert_eq!(None, resolver.lookup_external_id(Some("-//A//DTD Doc//EN"), Some("a.dtd")));
+  assert!(resolver.lookup_external_id(Some("-//B//DTD Doc//EN"), Some("b.dtd")).is_some());
+}
+
+#[test]
+fn resolve_public_id_to_local_copy() {
+  let dir = temp_dir("resolve_public_id_to_local_copy");
+  let dir = dir.as_ref();
+  write(
+    dir.join("catalog.xml"),
+    r#"<catalog xmlns="urn:oasis:names:tc:entity:xmlns:xml:catalog">
+  <public publicId="-//X//DTD Doc//EN" uri="doc.dtd"/>
+</catalog>"#,
+  )
+  .unwrap();
+  write(dir.join("doc.dtd"), "<!ENTITY product 'Xenolith'>").unwrap();
+  let mut resolver = CatalogResolver::new();
+  resolver.add_catalog(dir.join("catalog.xml")).unwrap();
+
+  let xml = r#"<!DOCTYPE doc PUBLIC "-//X//DTD Doc//EN" "http://example.com/doc.dtd"><doc>&product;</doc>"#;
+  let mut parser = PullParser::new(StringReader::new(xml));
+  parser.set_entity_resolver(Rc::new(resolver));
+  let texts = parser
+    .map(|event| event.unwrap().kind)
+    .filter_map(|kind| match kind {
+      EventKind::Text(text) => Some(text),
+      _ => None,
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(vec!["Xenolith"], texts);
+}
+
+#[test]
+fn rewrite_without_climbing_out_of_prefix() {
+  let dir = temp_dir("rewrite_without_climbing_out_of_prefix");
+  let dir = dir.as_ref().canonicalize().unwrap();
+  let base = format!("file://{}", dir.display());
+  write(
+    dir.join("catalog.xml"),
+    r#"<catalog xmlns="urn:oasis:names:tc:entity:xmlns:xml:catalog">
+  <rewriteSystem systemIdStartString="http://x/" rewritePrefix="dtds/"/>
+  <rewriteSystem systemIdStartString="http://y/dtd" rewritePrefix="dtds/y"/>
+  <systemSuffix systemIdSuffix="/passwd" uri="dtds/passwd.dtd"/>
+  <rewriteURI uriStartString="http://x/" rewritePrefix="xsd/"/>
+</catalog>"#,
+  )
+  .unwrap();
+  let mut resolver = CatalogResolver::new();
+  resolver.add_catalog(dir.join("catalog.xml")).unwrap();
+
+  for (system_id, expected) in [
+    ("http://x/a/../b.dtd", Some("dtds/b.dtd")),
+    ("http://x/a/%2E%2E/b.dtd", Some("dtds/b.dtd")),
+    ("http://x/../../etc/passwd", Some("dtds/etc/passwd")),
+    ("http://y/dtd/a.dtd", Some("dtds/y/a.dtd")),
+    ("http://y/dtd/../../etc/passwd", Some("dtds/passwd.dtd")),
+    ("http://y/dtd..%2F..%2Fetc/shadow", None),
+    ("http://y/dtd/..%2F..%2Fetc/shadow", None),
+  ] {
+    let expected = expected.map(|uri| format!("{}/{}", base, uri));
+    assert_eq!(expected, resolver.lookup_external_id(None, Some(system_id)), "{}", system_id);
+  }
+  assert_eq!(Some(format!("{}/xsd/etc/passwd", base)), resolver.lookup_uri("http://x/%2e%2e/../etc/passwd"));
+  assert_eq!(None, resolver.lookup_uri("http://x/a/..%2F..%2F..%2Fetc/passwd"));
+}
@@ -0,0 +1,58 @@
+//! Character classes defined in [Extensible Markup Language (XML) 1.0 (Fifth Edition)](https://www.w3.org/TR/xml/).
+//!
+
+/// `[2] Char ::= #x9 | #xA | #xD | [#x20-#xD7FF] | [#xE000-#xFFFD] | [#x10000-#x10FFFF]`
+///
+pub fn is_char(ch: char) -> bool {
+  matches!(ch, '\x09' | '\x0A' | '\x0D' | '\x20'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..='\u{10FFFF}')
+}
+
+/// `[2] Char ::= [#x1-#xD7FF] | [#xE000-#xFFFD] | [#x10000-#x10FFFF]` of
+/// [XML 1.1](https://www.w3.org/TR/xml11/#NT-Char), which may appear as character references.
+///
+pub fn is_char11(ch: char) -> bool {
+  matches!(ch, '\x01'..='\u{D7FF}' | '\u{E000}'..='\u{FFFD}' | '\u{10000}'..='\u{10FFFF}')
+}
+
+/// `[2a] RestrictedChar ::= [#x1-#x8] | [#xB-#xC] | [#xE-#x1F] | [#x7F-#x84] | [#x86-#x9F]` of XML 1.1, which must
+/// not appear directly in a document.
+///
+pub fn is_restricted_char(ch: char) -> bool {
+  matches!(ch, '\x01'..='\x08' | '\x0B'..='\x0C' | '\x0E'..='\x1F' | '\x7F'..='\u{84}' | '\u{86}'..='\u{9F}')
+}
+
+/// `[3] S ::= (#x20 | #x9 | #xD | #xA)+`
+///
+pub fn is_whitespace(ch: char) -> bool {
+  matches!(ch, '\x20' | '\x09' | '\x0D' | '\x0A')
+}
+
+/// `[4] NameStartChar`
+///
+pub fn is_name_start_char(ch: char) -> bool {
+  matches!(ch,
+    ':' | 'A'..='Z' | '_' | 'a'..='z' | '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}' | '\u{F8}'..='\u{2FF}'
+    | '\u{370}'..='\u{37D}' | '\u{37F}'..='\u{1FFF}' | '\u{200C}'..='\u{200D}' | '\u{2070}'..='\u{218F}'
+    | '\u{2C00}'..='\u{2FEF}' | '\u{3001}'..='\u{D7FF}' | '\u{F900}'..='\u{FDCF}' | '\u{FDF0}'..='\u{FFFD}'
+    | '\u{10000}'..='\u{EFFFF}')
+}
+
+/// `[4a] NameChar`
+///
+pub fn is_name_char(ch: char) -> bool {
+  is_name_start_char(ch)
+    || matches!(ch, '-' | '.' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}')
+}
+
+/// `[13] PubidChar`
+///
+pub fn is_pubid_char(ch: char) -> bool {
+  matches!(ch, '\x20' | '\x0D' | '\x0A' | 'a'..='z' | 'A'..='Z' | '0'..='9') || "-'()+,./:=?;!*#@$_%".contains(ch)
+}
+
+/// `[5] Name ::= NameStartChar (NameChar)*`
+///
+pub fn is_name(name: &str) -> bool {
+  let mut chars = name.chars();
+  chars.next().map(is_name_start_char).unwrap_or(false) && chars.all(is_name_char)
+}
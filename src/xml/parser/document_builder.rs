@@ -0,0 +1,458 @@
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use crate::xml::dom::{
+  DOMConfiguration, DOMImplementation, Document, DocumentFragment, DocumentType, Entity, NodeRef, Notation,
+  XMLNS_NAMESPACE_URI,
+};
+use crate::xml::w3c::dom::{
+  Attr as _, CharacterData as _, DOMErrorSeverity, DOMException, DOMUserData, Document as _, DocumentType as _,
+  Element as _, Entity as _, Node as _,
+};
+use crate::{Error, Location, Result, Span};
+
+use super::chars::is_whitespace;
+use super::{
+  ContentSpec, DenyAllResolver, EntityDecl, EntityLimits, EntityResolver, EventKind, InputSource, MarkupDecl,
+  PullParser,
+};
+
+/// A parser that reads a document with [PullParser] and builds its DOM tree, like Java's `DocumentBuilder`.
+///
+/// Each node created from the document holds the [Location] where it appears as user data named
+/// [Location::USERDATA_NAME], and the [Span] of its markup as [Span::USERDATA_NAME]; an element also holds the span of
+/// its end tag as [Span::END_TAG_USERDATA_NAME]. Adjacent character data is merged into a single text node, and the
+/// location of the merged node is where the first one starts and its span extends to where the last one ends.
+///
+/// The document is built as the parameters of [DocumentBuilder::dom_config()], which the built documents share as
+/// their [Document::dom_config()]. The parameters are the defaults of [DOMConfiguration] except that `entities` is
+/// `false`, so the replacement text of an entity reference appears in place of the reference, as Java's
+/// `DocumentBuilder` does by default. The errors of the parser are reported to the `error-handler` as well.
+///
+/// ```
+/// use std::rc::Rc;
+/// use xenolith::{Location, Span};
+/// use xenolith::io::StringReader;
+/// use xenolith::xml::parser::{DocumentBuilder, InputSource};
+/// use xenolith::xml::w3c::dom::{Document, Node};
+///
+/// let xml = "<greeting>\n  <hello/>\n</greeting>";
+/// let document = DocumentBuilder::new().parse(InputSource::new(StringReader::new(xml))).unwrap();
+/// let hello = document.get_elements_by_tag_name("hello").to_vec().remove(0);
+/// let location = hello.as_node().get_user_data(Location::USERDATA_NAME).unwrap();
+/// assert_eq!(Some(&Location::with_offsets(1, 2, 13, 13)), location.downcast_ref::<Location>());
+/// let span = hello.as_node().get_user_data(Span::USERDATA_NAME).unwrap();
+/// assert_eq!(Some("<hello/>"), span.downcast_ref::<Span>().unwrap().slice(xml));
+/// ```
+///
+pub struct DocumentBuilder {
+  implementation: DOMImplementation,
+  config: DOMConfiguration,
+  entity_limits: EntityLimits,
+  resolver: Rc<dyn EntityResolver>,
+}
+
+impl DocumentBuilder {
+  pub fn new() -> DocumentBuilder {
+    let config = DOMConfiguration::new();
+    config.set_flag("entities", false);
+    DocumentBuilder {
+      implementation: DOMImplementation::new(),
+      config,
+      entity_limits: EntityLimits::default(),
+      resolver: Rc::new(DenyAllResolver),
+    }
+  }
+
+  /// The DOM implementation that creates the documents.
+  pub fn dom_implementation(&self) -> &DOMImplementation {
+    &self.implementation
+  }
+
+  /// The configuration that controls how the documents are built. See [DocumentBuilder] for the parameters.
+  pub fn dom_config(&self) -> &DOMConfiguration {
+    &self.config
+  }
+
+  pub fn set_dom_config(&mut self, config: DOMConfiguration) {
+    self.config = config;
+  }
+
+  /// Whether the builder performs namespace processing, which is the `namespaces` parameter of
+  /// [DocumentBuilder::dom_config()]. If `true`, which is the default, elements and attributes are created with their
+  /// namespace URIs resolved, as if by `create_element_ns()` and `create_attribute_ns()`.
+  pub fn namespace_aware(&self) -> bool {
+    self.config.flag("namespaces")
+  }
+
+  pub fn set_namespace_aware(&mut self, namespace_aware: bool) {
+    self.config.set_flag("namespaces", namespace_aware);
+  }
+
+  /// The limits on the expansion of internal entities. See [EntityLimits] for the defaults.
+  pub fn entity_limits(&self) -> &EntityLimits {
+    &self.entity_limits
+  }
+
+  pub fn set_entity_limits(&mut self, entity_limits: EntityLimits) {
+    self.entity_limits = entity_limits;
+  }
+
+  /// The resolver that reads external entities. The default is [DenyAllResolver] that refuses all external access.
+  pub fn entity_resolver(&self) -> &Rc<dyn EntityResolver> {
+    &self.resolver
+  }
+
+  pub fn set_entity_resolver(&mut self, resolver: Rc<dyn EntityResolver>) {
+    self.resolver = resolver;
+  }
+
+  pub fn parse(&self, input: InputSource) -> Result<Rc<Document>> {
+    let mut parser = self.parser(input);
+    let document = self.document(&parser);
+    self.build(&mut parser, &document, NodeRef::Document(document.clone()))?;
+    Ok(document)
+  }
+
+  /// Parses the document in the recovery mode of [PullParser], which repairs common errors instead of failing, and
+  /// returns the document built so far with the errors in the order they occurred. If the document has an error
+  /// that cannot be repaired, it's the last one and the document contains the nodes that precede it.
+  ///
+  /// ```
+  /// use xenolith::Error;
+  /// use xenolith::io::StringReader;
+  /// use xenolith::xml::parser::{DocumentBuilder, ErrorCode, InputSource};
+  /// use xenolith::xml::w3c::dom::{Document, Node};
+  ///
+  /// let input = InputSource::new(StringReader::new("<a><b>R&D</a>"));
+  /// let (document, errors) = DocumentBuilder::new().parse_recovering(input);
+  /// assert_eq!("R&D", document.document_element().unwrap().text_content().unwrap());
+  /// let codes = errors.iter().map(|err| match err {
+  ///   Error::Parse { code, .. } => *code,
+  ///   _ => unreachable!(),
+  /// });
+  /// assert_eq!(vec![ErrorCode::MalformedEntityRef, ErrorCode::ElementTypeMatch], codes.collect::<Vec<_>>());
+  /// ```
+  ///
+  pub fn parse_recovering(&self, input: InputSource) -> (Rc<Document>, Vec<Error>) {
+    let mut parser = self.parser(input);
+    parser.set_recovery(true);
+    let document = self.document(&parser);
+    let result = self.build(&mut parser, &document, NodeRef::Document(document.clone()));
+    let mut errors = parser.take_diagnostics();
+    if let Err(err) = result {
+      errors.push(err);
+    }
+    (document, errors)
+  }
+
+  /// Parses a well-balanced fragment, `[43] content`, as the content of the `context` node, and returns it as a
+  /// [DocumentFragment] of the document that the `context` belongs to. The fragment can refer to the namespace
+  /// prefixes in scope of the `context` and the general entities declared in the DTD of the document. The context
+  /// itself is not changed; see [DocumentBuilder::parse_with_context()] to insert the fragment.
+  ///
+  /// The locations and spans of the nodes are the positions in the fragment.
+  ///
+  pub fn parse_fragment(&self, input: InputSource, context: &NodeRef) -> Result<Rc<DocumentFragment>> {
+    let document = match context {
+      NodeRef::Document(document) => document.clone(),
+      _ => context.as_node().owner_document().ok_or(Error::W3C(DOMException::NotSupported))?,
+    };
+    let entities = document.doctype().map(|doctype| doctype.entities().to_vec()).unwrap_or_default();
+    let entities = entities.into_iter().filter_map(|entity| match entity {
+      NodeRef::Entity(entity) => Some(EntityDecl {
+        name: entity.node_name().to_string(),
+        parameter: false,
+        value: entity.replacement_text().map(|text| text.to_string()),
+        public_id: entity.public_id().map(|id| id.to_string()),
+        system_id: entity.system_id().map(|id| id.to_string()),
+        notation_name: entity.notation_name().map(|name| name.to_string()),
+        base_uri: document.document_uri(),
+      }),
+      _ => None,
+    });
+    let mut parser = self.parser(input);
+    let namespaces = if self.namespace_aware() { in_scope_namespaces(context) } else { Vec::new() };
+    parser.set_fragment_context(document.xml_version() == "1.1", &namespaces, entities.collect());
+    let fragment = document.create_document_fragment();
+    self.build(&mut parser, &document, NodeRef::DocumentFragment(fragment.clone()))?;
+    Ok(fragment)
+  }
+
+  /// Parses a well-balanced fragment in the context of the `context` node and inserts it as the `action`, like
+  /// `LSParser.parseWithContext()`. The node that the fragment is parsed in the context of, that is, the `context`
+  /// itself or its parent depending on the `action`, must be an element or a document fragment. The first node of the
+  /// inserted fragment is returned.
+  ///
+  /// ```
+  /// use xenolith::io::StringReader;
+  /// use xenolith::xml::dom::{LSSerializer, NodeRef};
+  /// use xenolith::xml::parser::{DocumentBuilder, InputSource, ParseAction};
+  /// use xenolith::xml::w3c::dom::{Document, Node};
+  ///
+  /// let builder = DocumentBuilder::new();
+  /// let xml = "<!DOCTYPE a [<!ENTITY e 'entity'>]><a xmlns:p='urn:p'><b/></a>";
+  /// let document = builder.parse(InputSource::new(StringReader::new(xml))).unwrap();
+  /// let b = document.document_element().unwrap().first_child().unwrap();
+  /// let input = InputSource::new(StringReader::new("<p:c>&e;</p:c>text"));
+  /// let c = builder.parse_with_context(input, &b, ParseAction::InsertBefore).unwrap().unwrap();
+  /// assert_eq!(Some("urn:p"), c.as_node().namespace_uri());
+  ///
+  /// let mut serializer = LSSerializer::new();
+  /// serializer.set_xml_declaration(false);
+  /// let root = NodeRef::Element(document.document_element().unwrap());
+  /// assert_eq!("<a xmlns:p=\"urn:p\"><p:c>entity</p:c>text<b/></a>", serializer.write_to_string(&root).unwrap());
+  /// ```
+  ///
+  pub fn parse_with_context(
+    &self, input: InputSource, context: &NodeRef, action: ParseAction,
+  ) -> Result<Option<NodeRef>> {
+    let parent = match action {
+      ParseAction::AppendAsChildren | ParseAction::ReplaceChildren => Some(context.clone()),
+      ParseAction::InsertBefore | ParseAction::InsertAfter | ParseAction::Replace => context.as_node().parent_node(),
+    };
+    let parent = match parent {
+      Some(parent @ (NodeRef::Element(_) | NodeRef::DocumentFragment(_))) => parent,
+      _ => return Err(Error::W3C(DOMException::NotSupported)),
+    };
+    let fragment = self.parse_fragment(input, &parent)?;
+    let first = fragment.first_child();
+    let fragment = NodeRef::DocumentFragment(fragment);
+    let parent = parent.as_node();
+    match action {
+      ParseAction::AppendAsChildren => parent.append_child(fragment),
+      ParseAction::ReplaceChildren => {
+        while let Some(child) = parent.first_child() {
+          parent.remove_child(&child)?;
+        }
+        parent.append_child(fragment)
+      }
+      ParseAction::InsertBefore => parent.insert_before(fragment, Some(context)),
+      ParseAction::InsertAfter => parent.insert_before(fragment, context.as_node().next_sibling().as_ref()),
+      ParseAction::Replace => parent.replace_child(fragment, context),
+    }?;
+    Ok(first)
+  }
+
+  /// Creates the document that the nodes read from the `parser` are appended to.
+  fn document(&self, parser: &PullParser) -> Rc<Document> {
+    let document = Document::new(self.implementation.clone());
+    document.set_document_uri(parser.system_id());
+    document.set_dom_config(self.config.clone());
+    document
+  }
+
+  fn parser(&self, input: InputSource) -> PullParser {
+    let mut parser = PullParser::with_input_source(input);
+    parser.set_namespace_aware(self.namespace_aware());
+    parser.set_entity_limits(self.entity_limits.clone());
+    parser.set_entity_resolver(self.resolver.clone());
+    parser
+  }
+
+  /// Appends the nodes of the events read from `parser` to the `root`, which is the `document` or a fragment of it.
+  /// The errors are reported to the `error-handler` as they occur, and the building stops without an error if the
+  /// handler returns `false` for an error the parser recovered from.
+  fn build(&self, parser: &mut PullParser, document: &Rc<Document>, root: NodeRef) -> Result<()> {
+    let mut parents = vec![root];
+    let mut element_content = HashSet::new();
+    let mut reported = 0;
+    loop {
+      let event = parser.next_event();
+      for error in &parser.diagnostics()[reported..] {
+        reported += 1;
+        if !self.config.report_parse_error(DOMErrorSeverity::Error, error) {
+          return Ok(());
+        }
+      }
+      let event = event.inspect_err(|err| {
+        self.config.report_parse_error(DOMErrorSeverity::FatalError, err);
+      })?;
+      let parent = parents.last().expect("the root must remain at the bottom").clone();
+      let node = match event.kind {
+        EventKind::XMLDecl { .. } => {
+          document.set_xml_version(parser.xml_version())?;
+          continue;
+        }
+        EventKind::DocType { name, public_id, system_id, internal_subset, declarations } => {
+          let doctype =
+            DocumentType::new(&name, public_id.as_deref(), system_id.as_deref(), internal_subset.as_deref());
+          let owner = Rc::downgrade(document);
+          for decl in declarations {
+            match decl {
+              MarkupDecl::Entity(EntityDecl {
+                parameter: false,
+                name,
+                value,
+                public_id,
+                system_id,
+                notation_name,
+                ..
+              }) => {
+                let (public_id, system_id) = (public_id.as_deref(), system_id.as_deref());
+                let (notation_name, value) = (notation_name.as_deref(), value.as_deref());
+                doctype.add_entity(Entity::new(owner.clone(), &name, public_id, system_id, notation_name, value));
+              }
+              MarkupDecl::Notation { name, public_id, system_id } => {
+                doctype.add_notation(Notation::new(owner.clone(), &name, public_id.as_deref(), system_id.as_deref()));
+              }
+              MarkupDecl::Element { name, content_spec: ContentSpec::Children(_) } => {
+                element_content.insert(name);
+              }
+              _ => (),
+            }
+          }
+          NodeRef::DocumentType(doctype)
+        }
+        EventKind::StartElement { name, namespace_uri, local_name, attributes } => {
+          let element = match local_name {
+            Some(_) => document.create_element_ns(namespace_uri.as_deref(), &name)?,
+            None => document.create_element(&name)?,
+          };
+          for attribute in attributes {
+            let attr = match attribute.local_name {
+              Some(_) => document.create_attribute_ns(attribute.namespace_uri.as_deref(), &attribute.name)?,
+              None => document.create_attribute(&attribute.name)?,
+            };
+            attr.set_value(&attribute.value)?;
+            attr.set_specified(attribute.specified);
+            let span = Span::new(attribute.location, attribute.end, parser.system_id());
+            set_location(&NodeRef::Attr(attr.clone()), span);
+            element.set_attribute_node_ns(attr)?;
+          }
+          let element = NodeRef::Element(element);
+          parents.push(element.clone());
+          element
+        }
+        EventKind::EndElement { .. } => {
+          let span: DOMUserData = Rc::new(Span::new(event.location, event.end, parser.system_id()));
+          parent.as_node().set_user_data(Span::END_TAG_USERDATA_NAME, Some(span), None);
+          parents.pop();
+          continue;
+        }
+        EventKind::Text(text) => {
+          let whitespace = matches!(&parent, NodeRef::Element(e) if element_content.contains(e.tag_name()))
+            && text.chars().all(is_whitespace);
+          if whitespace && !self.config.flag("element-content-whitespace") {
+            continue;
+          }
+          match parent.as_node().last_child() {
+            Some(NodeRef::Text(last)) => {
+              last.append_data(&text)?;
+              last.set_element_content_whitespace(last.is_element_content_whitespace() && whitespace);
+              extend_span(&NodeRef::Text(last), event.end);
+              continue;
+            }
+            _ => {
+              let node = document.create_text_node(&text);
+              node.set_element_content_whitespace(whitespace);
+              NodeRef::Text(node)
+            }
+          }
+        }
+        EventKind::StartEntity(name) if self.config.flag("entities") => {
+          let reference = NodeRef::EntityReference(document.create_entity_reference(&name)?);
+          set_location(&reference, Span::new(event.location, event.end, parser.system_id()));
+          parent.as_node().append_child(reference.clone())?;
+          parents.push(reference);
+          continue;
+        }
+        EventKind::EndEntity(_) if self.config.flag("entities") => {
+          parents.pop();
+          continue;
+        }
+        EventKind::StartEntity(_) | EventKind::EndEntity(_) => continue,
+        EventKind::SkippedEntity(name) => NodeRef::EntityReference(document.create_entity_reference(&name)?),
+        EventKind::CDATA(text) if !self.config.flag("cdata-sections") => match parent.as_node().last_child() {
+          Some(NodeRef::Text(last)) => {
+            last.append_data(&text)?;
+            extend_span(&NodeRef::Text(last), event.end);
+            continue;
+          }
+          _ => NodeRef::Text(document.create_text_node(&text)),
+        },
+        EventKind::CDATA(text) => NodeRef::CDATASection(document.create_cdata_section(&text)?),
+        EventKind::Comment(_) if !self.config.flag("comments") => continue,
+        EventKind::Comment(text) => NodeRef::Comment(document.create_comment(&text)),
+        EventKind::ProcessingInstruction { target, data } => NodeRef::ProcessingInstruction(
+          document.create_processing_instruction(&target, data.as_deref().unwrap_or_default())?,
+        ),
+        EventKind::EndDocument => return Ok(()),
+      };
+      set_location(&node, Span::new(event.location, event.end, parser.system_id()));
+      parent.as_node().append_child(node)?;
+    }
+  }
+}
+
+/// How [DocumentBuilder::parse_with_context()] inserts the parsed fragment, as the `ACTION_*` constants of
+/// `LSParser`.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseAction {
+  /// Appends the fragment to the children of the context node.
+  AppendAsChildren,
+  /// Replaces all the children of the context node with the fragment.
+  ReplaceChildren,
+  /// Inserts the fragment as the preceding siblings of the context node.
+  InsertBefore,
+  /// Inserts the fragment as the following siblings of the context node.
+  InsertAfter,
+  /// Replaces the context node with the fragment.
+  Replace,
+}
+
+impl Default for DocumentBuilder {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+fn set_location(node: &NodeRef, span: Span) {
+  let location: DOMUserData = Rc::new(span.start.clone());
+  node.as_node().set_user_data(Location::USERDATA_NAME, Some(location), None);
+  node.as_node().set_user_data(Span::USERDATA_NAME, Some(Rc::new(span)), None);
+}
+
+/// Extends the span of the text node to the `end` of the character data merged into it.
+fn extend_span(node: &NodeRef, end: Location) {
+  let span = node.as_node().get_user_data(Span::USERDATA_NAME);
+  if let Some(span) = span.as_ref().and_then(|span| span.downcast_ref::<Span>()) {
+    let span = Span { end: end.max(span.end.clone()), ..span.clone() };
+    node.as_node().set_user_data(Span::USERDATA_NAME, Some(Rc::new(span)), None);
+  }
+}
+
+/// The namespace bindings in scope of the `node` from the outermost, as `(prefix, namespace_uri)` with the empty
+/// prefix for the default namespace. The namespaces of the elements are bound as well as the declarations, since an
+/// element created by `create_element_ns()` may have no declaration for its namespace.
+fn in_scope_namespaces(node: &NodeRef) -> Vec<(String, Option<String>)> {
+  let mut elements = Vec::new();
+  let mut node = Some(node.clone());
+  while let Some(n) = node {
+    node = n.as_node().parent_node();
+    if let NodeRef::Element(element) = n {
+      elements.push(element);
+    }
+  }
+  let mut namespaces = Vec::new();
+  for element in elements.iter().rev().filter(|element| element.local_name().is_some()) {
+    let mut bind = |prefix: Option<String>, namespace_uri: Option<&str>| {
+      namespaces.push((prefix.unwrap_or_default(), namespace_uri.map(|uri| uri.to_string())));
+    };
+    bind(element.prefix(), element.namespace_uri());
+    for attr in element.attributes().map(|attributes| attributes.to_vec()).unwrap_or_default() {
+      let NodeRef::Attr(attr) = attr else { continue };
+      match attr.namespace_uri() {
+        Some(XMLNS_NAMESPACE_URI) => {
+          let prefix = attr.prefix().map(|_| attr.local_name().unwrap_or_default().to_string());
+          let value = attr.value();
+          bind(prefix, Some(value.as_str()).filter(|uri| !uri.is_empty()));
+        }
+        Some(namespace_uri) if attr.prefix().is_some() => bind(attr.prefix(), Some(namespace_uri)),
+        _ => (),
+      }
+    }
+  }
+  namespaces
+}
@@ -0,0 +1,340 @@
+use std::rc::Rc;
+
+use crate::io::StringReader;
+use crate::test::compatibility::java;
+use crate::xml::dom::{Document, LSSerializer, NodeRef};
+use crate::xml::parser::{DocumentBuilder, ErrorCode, InputSource, ParseAction};
+use crate::xml::w3c::dom::{
+  Attr as _, Document as _, DocumentType as _, Element as _, Entity as _, NamedNodeMap as _, Node as _, NodeList as _,
+  Notation as _,
+};
+use crate::{Error, Location, Result, Span};
+
+fn parse(xml: &str) -> Result<Rc<Document>> {
+  DocumentBuilder::new().parse(InputSource::new(StringReader::new(xml)))
+}
+
+/// Prints the tree in the same format as `dump()` in Java code of [java_dump()].
+fn dump(node: &NodeRef, depth: usize, out: &mut Vec<String>) {
+  let n = node.as_node();
+  let value = n.node_value().unwrap().map(|v| format!("={:?}", v)).unwrap_or_default();
+  let namespace = n.namespace_uri().map(|uri| format!("{{{}}}{}", uri, n.local_name().unwrap())).unwrap_or_default();
+  out.push(format!("{}{:?}:{}{}{}", "  ".repeat(depth), n.node_type() as u16, n.node_name(), namespace, value));
+  if let Some(attributes) = n.attributes() {
+    let mut attrs = (0..attributes.length()).map(|i| attributes.item(i).unwrap()).collect::<Vec<_>>();
+    attrs.sort_by(|a, b| a.as_node().node_name().cmp(b.as_node().node_name()));
+    for attr in attrs.iter() {
+      dump(attr, depth + 1, out);
+    }
+  }
+  for i in 0..n.child_nodes().length() {
+    dump(&n.child_nodes().item(i).unwrap(), depth + 1, out);
+  }
+}
+
+/// Java code that parses `xml` with the JDK's `DocumentBuilder` and prints the tree one node per line.
+fn java_dump(xml: &str) -> String {
+  format!(
+    r#"
+    String xml = "{}";
+    class Dumper {{
+      String q(String s) {{
+        return "\"" + s.replace("\\", "\\\\").replace("\"", "\\\"").replace("\n", "\\n").replace("\t", "\\t") + "\"";
+      }}
+      void dump(Node n, int depth) {{
+        String value = n.getNodeValue() == null ? "" : "=" + q(n.getNodeValue());
+        String ns = n.getNamespaceURI() == null ? "" : "{{" + n.getNamespaceURI() + "}}" + n.getLocalName();
+        System.out.println("  ".repeat(depth) + n.getNodeType() + ":" + n.getNodeName() + ns + value);
+        NamedNodeMap attrs = n.getAttributes();
+        for(int i = 0; attrs != null && i < attrs.getLength(); i++) dump(attrs.item(i), depth + 1);
+        if(n.getNodeType() == Node.ATTRIBUTE_NODE) return;
+        for(Node c = n.getFirstChild(); c != null; c = c.getNextSibling()) dump(c, depth + 1);
+      }}
+    }}
+    DocumentBuilderFactory factory = DocumentBuilderFactory.newInstance();
+    factory.setNamespaceAware(true);
+    factory.setFeature("http://apache.org/xml/features/nonvalidating/load-external-dtd", false);
+    Document doc = factory.newDocumentBuilder().parse(new InputSource(new StringReader(xml)));
+    new Dumper().dump(doc, 0);
+    "#,
+    xml.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+  )
+}
+
+#[test]
+fn tree_compatible_with_java() {
+  let xml = r#"<?xml version="1.0"?>
+<!DOCTYPE root SYSTEM "root.dtd">
+<!-- prolog -->
+<root b="2" a='1 &amp; &#x32;'>
+  <x>text &lt;&gt; more</x><![CDATA[<cdata>]]><?pi data?>
+  <y/><?empty?>
+</root>
+<!-- epilog -->"#;
+  let document = parse(xml).unwrap();
+  let mut actual = Vec::new();
+  dump(&NodeRef::Document(document), 0, &mut actual);
+  let expected = java::run("tree_compatible_with_java", &java_dump(xml));
+  assert_eq!(expected.lines().collect::<Vec<_>>(), actual);
+}
+
+#[test]
+fn namespaces_compatible_with_java() {
+  let xml = r#"<p:root xmlns:p="urn:p" xmlns="urn:d" a="1" p:b="2"><x xml:lang="en"/><y xmlns=""><p:z/></y></p:root>"#;
+  let document = parse(xml).unwrap();
+  let mut actual = Vec::new();
+  dump(&NodeRef::Document(document.clone()), 0, &mut actual);
+  let expected = java::run("namespaces_compatible_with_java", &java_dump(xml));
+  assert_eq!(expected.lines().collect::<Vec<_>>(), actual);
+
+  let root = document.document_element().unwrap();
+  assert_eq!(Some("p".to_string()), root.prefix());
+  assert_eq!(Some("urn:p"), root.namespace_uri());
+  assert_eq!(Some("root"), root.local_name());
+  assert_eq!(Some("urn:d".to_string()), root.lookup_namespace_uri(None));
+  assert_eq!(Some("2".to_string()), root.get_attribute_ns(Some("urn:p"), "b"));
+}
+
+#[test]
+fn default_attributes_compatible_with_java() {
+  let xml = r#"<!DOCTYPE root [
+  <!ATTLIST root id ID #IMPLIED refs IDREFS #IMPLIED kind (x|y) "y" version CDATA #FIXED " 1.0 " note CDATA #IMPLIED>
+  <!ATTLIST item xmlns:p CDATA #FIXED "urn:p" p:type NMTOKEN "  default ">
+]><root id=" r1 " refs="  a&#x20;  b&#10;c " note=" a&#9;b "><item/><item p:type="given"/></root>"#;
+  let document = parse(xml).unwrap();
+  let mut actual = Vec::new();
+  dump(&NodeRef::Document(document.clone()), 0, &mut actual);
+  let expected = java::run("default_attributes_compatible_with_java", &java_dump(xml));
+  assert_eq!(expected.lines().collect::<Vec<_>>(), actual);
+
+  let root = document.document_element().unwrap();
+  assert!(root.get_attribute_node("id").unwrap().specified());
+  assert!(!root.get_attribute_node("kind").unwrap().specified());
+  assert!(!root.get_attribute_node("version").unwrap().specified());
+  let item = match root.first_child() {
+    Some(NodeRef::Element(item)) => item,
+    unexpected => panic!("{:?}", unexpected.map(|node| node.as_node().node_name().to_string())),
+  };
+  assert_eq!(Some("default".to_string()), item.get_attribute_ns(Some("urn:p"), "type"));
+}
+
+#[test]
+fn document_structure() {
+  let mut input = InputSource::new(StringReader::new(r#"<!DOCTYPE a PUBLIC "-//P//EN" "a.dtd"><a><b/>x</a>"#));
+  input.system_id = Some("file:///a.xml".to_string());
+  let document = DocumentBuilder::new().parse(input).unwrap();
+  assert_eq!(Some("file:///a.xml".to_string()), document.document_uri());
+
+  let doctype = document.doctype().unwrap();
+  assert_eq!("a", doctype.name());
+  assert_eq!(Some("-//P//EN"), doctype.public_id());
+  assert_eq!(Some("a.dtd"), doctype.system_id());
+  assert!(Rc::ptr_eq(&document, &doctype.owner_document().unwrap()));
+
+  assert_eq!("1.0", document.xml_version());
+  assert_eq!("1.1", parse("<?xml version='1.1'?><a/>").unwrap().xml_version());
+
+  let root = document.document_element().unwrap();
+  assert_eq!("a", root.node_name());
+  assert_eq!(Some("x".to_string()), root.text_content());
+  let b = root.first_child().unwrap();
+  assert!(b.as_node().parent_node().unwrap().ptr_eq(&NodeRef::Element(root.clone())));
+  assert_eq!("#text", b.as_node().next_sibling().unwrap().as_node().node_name());
+}
+
+#[test]
+fn entities_and_notations() {
+  let xml = r#"<!DOCTYPE a SYSTEM "a.dtd" [
+  <!ENTITY e "<b>x</b>y">
+  <!ENTITY % p "ignored">
+  <!ENTITY img SYSTEM "a.png" NDATA png>
+  <!NOTATION png PUBLIC "image/png">
+]><a>&e;&ext;</a>"#;
+  let document = parse(xml).unwrap();
+  let doctype = document.doctype().unwrap();
+  assert_eq!(2, doctype.entities().length());
+  match doctype.entities().get_named_item("img") {
+    Some(NodeRef::Entity(img)) => {
+      assert_eq!(Some("a.png"), img.system_id());
+      assert_eq!(Some("png"), img.notation_name());
+    }
+    unexpected => panic!("{:?}", unexpected.map(|node| node.as_node().node_name().to_string())),
+  }
+  assert!(doctype.entities().get_named_item("p").is_none());
+  match doctype.notations().get_named_item("png") {
+    Some(NodeRef::Notation(png)) => assert_eq!(Some("image/png"), png.public_id()),
+    unexpected => panic!("{:?}", unexpected.map(|node| node.as_node().node_name().to_string())),
+  }
+
+  // the replacement text is expanded in place, and an undeclared entity is kept as a reference
+  let root = document.document_element().unwrap();
+  let children = root.child_nodes().to_vec();
+  let names = children.iter().map(|node| node.as_node().node_name().to_string()).collect::<Vec<_>>();
+  assert_eq!(vec!["b", "#text", "ext"], names);
+  assert!(matches!(children[2], NodeRef::EntityReference(_)));
+}
+
+#[test]
+fn node_locations() {
+  let xml = "<?xml version=\"1.0\"?>\n<!DOCTYPE r>\n<r a=\"1\">\n  text<!--c--><![CDATA[d]]><?p?><e\n b='2'/></r>";
+  let document = parse(xml).unwrap();
+  let location = |node: &NodeRef| {
+    let data = node.as_node().get_user_data(Location::USERDATA_NAME).unwrap();
+    data.downcast_ref::<Location>().unwrap().to_string()
+  };
+  let root = document.document_element().unwrap();
+  let mut actual = vec![location(&NodeRef::DocumentType(document.doctype().unwrap()))];
+  actual.push(location(&NodeRef::Element(root.clone())));
+  actual.push(location(&root.attributes().unwrap().item(0).unwrap()));
+  for child in root.child_nodes().to_vec() {
+    actual.push(location(&child));
+  }
+  let e = root.last_child().unwrap();
+  actual.push(location(&e.as_node().attributes().unwrap().item(0).unwrap()));
+  assert_eq!(vec!["(2,1)", "(3,1)", "(3,4)", "(3,10)", "(4,7)", "(4,15)", "(4,28)", "(4,33)", "(5,2)"], actual);
+  assert!(document.get_user_data(Location::USERDATA_NAME).is_none());
+}
+
+#[test]
+fn node_spans() {
+  let xml = "<r a='日本'>\r\n  téxt<!--c--><e\r\n b=\"2\"/>&amp;</r >";
+  let document = parse(xml).unwrap();
+  let span = |node: &NodeRef, name: &str| {
+    let data = node.as_node().get_user_data(name).unwrap();
+    data.downcast_ref::<Span>().unwrap().clone()
+  };
+  let slice = |node: &NodeRef| span(node, Span::USERDATA_NAME).slice(xml).unwrap().to_string();
+  let root = NodeRef::Element(document.document_element().unwrap());
+  let mut actual = vec![slice(&root), slice(&root.as_node().attributes().unwrap().item(0).unwrap())];
+  for child in root.as_node().child_nodes().to_vec() {
+    actual.push(slice(&child));
+  }
+  let e = root.as_node().child_nodes().item(2).unwrap();
+  actual.push(slice(&e.as_node().attributes().unwrap().item(0).unwrap()));
+  actual.push(span(&e, Span::END_TAG_USERDATA_NAME).slice(xml).unwrap().to_string());
+  actual.push(span(&root, Span::END_TAG_USERDATA_NAME).slice(xml).unwrap().to_string());
+  let expected = [
+    "<r a='日本'>",
+    "a='日本'",
+    "\r\n  téxt",
+    "<!--c-->",
+    "<e\r\n b=\"2\"/>",
+    "&amp;",
+    "b=\"2\"",
+    "<e\r\n b=\"2\"/>",
+    "</r >",
+  ];
+  assert_eq!(expected.to_vec(), actual);
+
+  let text = span(&root.as_node().first_child().unwrap(), Span::USERDATA_NAME);
+  assert_eq!(Location::with_offsets(0, 10, 14, 10), text.start);
+  assert_eq!(Location::with_offsets(1, 6, 23, 18), text.end);
+  assert_eq!(None, text.system_id);
+}
+
+#[test]
+fn parse_with_context() {
+  let builder = DocumentBuilder::new();
+  let xml = "<!DOCTYPE r [<!ENTITY e '<i>e</i>'>]><r xmlns='urn:d' xmlns:p='urn:p'><a/><b/></r>";
+  let document = builder.parse(InputSource::new(StringReader::new(xml))).unwrap();
+  let root = NodeRef::Element(document.document_element().unwrap());
+  let a = root.as_node().first_child().unwrap();
+  let b = a.as_node().next_sibling().unwrap();
+  let input = |xml: &str| InputSource::new(StringReader::new(xml));
+  let mut serializer = LSSerializer::new();
+  serializer.set_xml_declaration(false);
+
+  let fragment = builder.parse_fragment(input("<p:x y='1'/>&e;t"), &root).unwrap();
+  assert_eq!(3, fragment.child_nodes().length());
+  let x = fragment.first_child().unwrap();
+  assert_eq!((Some("urn:p"), Some("x")), (x.as_node().namespace_uri(), x.as_node().local_name()));
+  let location = x.as_node().get_user_data(Location::USERDATA_NAME).unwrap();
+  assert_eq!(Some(&Location::with_offsets(0, 0, 0, 0)), location.downcast_ref::<Location>());
+  let i = x.as_node().next_sibling().unwrap();
+  assert_eq!(Some("urn:d"), i.as_node().namespace_uri());
+  assert_eq!(2, root.as_node().child_nodes().length());
+
+  let actions = [
+    (&b, ParseAction::AppendAsChildren, "<a/><b>1</b>"),
+    (&b, ParseAction::ReplaceChildren, "<a/><b>2</b>"),
+    (&b, ParseAction::InsertBefore, "<a/>3<b>2</b>"),
+    (&a, ParseAction::InsertAfter, "<a/>43<b>2</b>"),
+    (&b, ParseAction::Replace, "<a/>435"),
+  ];
+  for (i, (context, action, expected)) in actions.iter().enumerate() {
+    let first = builder.parse_with_context(input(&(i + 1).to_string()), context, *action).unwrap();
+    assert_eq!(Some((i + 1).to_string()), first.and_then(|first| first.as_node().text_content()));
+    let expected = format!("<r xmlns=\"urn:d\" xmlns:p=\"urn:p\">{}</r>", expected);
+    assert_eq!(expected, serializer.write_to_string(&root).unwrap());
+  }
+
+  // the fragment must be well-balanced, and the document is not changed if it's not
+  for xml in ["<c>", "</r>", "<q:c/>", "&undeclared;", "<?xml version='1.0'?>", "<!DOCTYPE c>"] {
+    assert!(builder.parse_with_context(input(xml), &root, ParseAction::ReplaceChildren).is_err(), "{}", xml);
+  }
+  assert_eq!(4, root.as_node().child_nodes().length());
+  let result = builder.parse_with_context(input("<c/>"), &root, ParseAction::InsertBefore);
+  assert!(matches!(result, Err(Error::W3C(_))));
+  let result = builder.parse_fragment(input("<c/><c/>"), &root);
+  assert_eq!(2, result.unwrap().child_nodes().length());
+  let result = builder.parse_fragment(input("<c>"), &root);
+  assert!(matches!(result, Err(Error::Parse { code: ErrorCode::UnclosedElement, .. })));
+}
+
+#[test]
+fn malformed_document() {
+  assert!(parse("<a><b></a>").is_err());
+  assert!(parse("").is_err());
+}
+
+#[test]
+fn recover_from_common_errors() {
+  let xml = "<!DOCTYPE a [<!ENTITY e 'E'>]>\n<a x=1 y y='2' z=\"3>\n  <b>R&D & &e; &undeclared; 1 < 2 &#0;</c>\n  <c d='x'></d></c>\n  <e></b>";
+  let (document, errors) = DocumentBuilder::new().parse_recovering(InputSource::new(StringReader::new(xml)));
+  let mut out = Vec::new();
+  dump(&NodeRef::Element(document.document_element().unwrap()), 0, &mut out);
+  assert_eq!(
+    vec![
+      "1:a",
+      "  2:x=\"1\"",
+      "  2:y=\"\"",
+      "  2:z=\"3\"",
+      "  3:#text=\"\\n  \"",
+      "  1:b",
+      "    3:#text=\"R&D & E \"",
+      "    5:undeclared",
+      "    3:#text=\" 1 < 2 \\n  \"",
+      "    1:c",
+      "      2:d=\"x\"",
+      "    3:#text=\"\\n  \"",
+      "    1:e",
+    ],
+    out
+  );
+  let errors = errors
+    .iter()
+    .map(|err| match err {
+      Error::Parse { code, location, .. } => format!("{}{:?}", location, code),
+      _ => format!("{:?}", err),
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(
+    vec![
+      "(2,6)MalformedAttValue",
+      "(2,8)MalformedAttribute",
+      "(2,10)UniqueAttSpec",
+      "(2,18)MalformedAttValue",
+      "(3,7)MalformedEntityRef",
+      "(3,10)MalformedEntityRef",
+      "(3,16)EntityDeclared",
+      "(3,31)InvalidName",
+      "(3,35)LegalCharacter",
+      "(3,39)ElementTypeMatch",
+      "(4,12)ElementTypeMatch",
+      "(5,6)ElementTypeMatch",
+      "(5,10)UnclosedElement",
+    ],
+    errors
+  );
+  assert!(parse(xml).is_err());
+}
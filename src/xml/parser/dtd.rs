@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// `[29] markupdecl` that appears in the internal subset of the DTD, followed by the external subset if the resolver
+/// returned it, in the order of appearance.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkupDecl {
+  /// `<!ELEMENT name contentspec>`
+  Element { name: String, content_spec: ContentSpec },
+  /// `<!ATTLIST element name type default ...>`
+  Attlist { element: String, definitions: Vec<AttDef> },
+  /// `<!ENTITY name "value">`, `<!ENTITY % name "value">` or their external variants.
+  Entity(EntityDecl),
+  /// `<!NOTATION name PUBLIC "public-id" "system-id">`
+  Notation { name: String, public_id: Option<String>, system_id: Option<String> },
+  /// `<?target data?>`
+  ProcessingInstruction { target: String, data: Option<String> },
+  /// `<!--...-->`
+  Comment(String),
+}
+
+/// `[46] contentspec ::= 'EMPTY' | 'ANY' | Mixed | children`
+///
+/// The [Display] format is the normalized content model without whitespace, such as `(#PCDATA|a)*` or `(a,b?)+`.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContentSpec {
+  Empty,
+  Any,
+  /// `[51] Mixed` with the names of the element types that can appear in the character data.
+  Mixed(Vec<String>),
+  /// `[47] children`
+  Children(ContentParticle),
+}
+
+impl Display for ContentSpec {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ContentSpec::Empty => write!(f, "EMPTY"),
+      ContentSpec::Any => write!(f, "ANY"),
+      ContentSpec::Mixed(names) if names.is_empty() => write!(f, "(#PCDATA)"),
+      ContentSpec::Mixed(names) => write!(f, "(#PCDATA|{})*", names.join("|")),
+      ContentSpec::Children(cp) => cp.fmt(f),
+    }
+  }
+}
+
+/// `[48] cp ::= (Name | choice | seq) ('?' | '*' | '+')?`
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentParticle {
+  pub particle: Particle,
+  pub occurrence: Occurrence,
+}
+
+impl Display for ContentParticle {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let join = |f: &mut Formatter<'_>, cps: &[ContentParticle], separator: &str| {
+      let cps = cps.iter().map(|cp| cp.to_string()).collect::<Vec<_>>();
+      write!(f, "({})", cps.join(separator))
+    };
+    match &self.particle {
+      Particle::Name(name) => write!(f, "{}", name)?,
+      Particle::Choice(cps) => join(f, cps, "|")?,
+      Particle::Seq(cps) => join(f, cps, ",")?,
+    }
+    let occurrence = match self.occurrence {
+      Occurrence::Once => "",
+      Occurrence::Optional => "?",
+      Occurrence::ZeroOrMore => "*",
+      Occurrence::OneOrMore => "+",
+    };
+    write!(f, "{}", occurrence)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Particle {
+  Name(String),
+  /// `[49] choice ::= '(' S? cp ( S? '|' S? cp )+ S? ')'`
+  Choice(Vec<ContentParticle>),
+  /// `[50] seq ::= '(' S? cp ( S? ',' S? cp )* S? ')'`
+  Seq(Vec<ContentParticle>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Occurrence {
+  Once,
+  /// `?`
+  Optional,
+  /// `*`
+  ZeroOrMore,
+  /// `+`
+  OneOrMore,
+}
+
+/// `[53] AttDef ::= S Name S AttType S DefaultDecl`
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttDef {
+  pub name: String,
+  pub att_type: AttType,
+  pub default: DefaultDecl,
+}
+
+/// `[54] AttType ::= StringType | TokenizedType | EnumeratedType`
+///
+/// The [Display] format is the keyword of the type, or the enumeration such as `NOTATION (a|b)` or `(x|y)`.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AttType {
+  CData,
+  Id,
+  IdRef,
+  IdRefs,
+  Entity,
+  Entities,
+  NmToken,
+  NmTokens,
+  /// `[58] NotationType` with the notation names.
+  Notation(Vec<String>),
+  /// `[59] Enumeration` with the allowed tokens.
+  Enumeration(Vec<String>),
+}
+
+impl Display for AttType {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      AttType::CData => write!(f, "CDATA"),
+      AttType::Id => write!(f, "ID"),
+      AttType::IdRef => write!(f, "IDREF"),
+      AttType::IdRefs => write!(f, "IDREFS"),
+      AttType::Entity => write!(f, "ENTITY"),
+      AttType::Entities => write!(f, "ENTITIES"),
+      AttType::NmToken => write!(f, "NMTOKEN"),
+      AttType::NmTokens => write!(f, "NMTOKENS"),
+      AttType::Notation(names) => write!(f, "NOTATION ({})", names.join("|")),
+      AttType::Enumeration(tokens) => write!(f, "({})", tokens.join("|")),
+    }
+  }
+}
+
+/// `[60] DefaultDecl ::= '#REQUIRED' | '#IMPLIED' | (('#FIXED' S)? AttValue)`
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DefaultDecl {
+  Required,
+  Implied,
+  /// `#FIXED` with the normalized default value.
+  Fixed(String),
+  /// The normalized default value.
+  Value(String),
+}
+
+impl DefaultDecl {
+  /// The keyword of the declaration, that is, `#REQUIRED`, `#IMPLIED` or `#FIXED`.
+  pub fn mode(&self) -> Option<&'static str> {
+    match self {
+      DefaultDecl::Required => Some("#REQUIRED"),
+      DefaultDecl::Implied => Some("#IMPLIED"),
+      DefaultDecl::Fixed(_) => Some("#FIXED"),
+      DefaultDecl::Value(_) => None,
+    }
+  }
+
+  /// The default value, if any.
+  pub fn value(&self) -> Option<&str> {
+    match self {
+      DefaultDecl::Fixed(value) | DefaultDecl::Value(value) => Some(value),
+      DefaultDecl::Required | DefaultDecl::Implied => None,
+    }
+  }
+}
+
+/// `[70] EntityDecl`. An internal entity has the replacement text as its `value`, and an external entity has the
+/// identifiers instead. An external entity that has a `notation_name` is an unparsed entity.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityDecl {
+  pub name: String,
+  /// `true` for a parameter entity declared as `<!ENTITY % name ...>`.
+  pub parameter: bool,
+  /// The replacement text of an internal entity, in which character references and parameter entity references have
+  /// been replaced.
+  pub value: Option<String>,
+  pub public_id: Option<String>,
+  pub system_id: Option<String>,
+  pub notation_name: Option<String>,
+  /// The system identifier of the entity in which this entity was declared, against which the relative `system_id`
+  /// is resolved.
+  pub base_uri: Option<String>,
+}
+
+impl EntityDecl {
+  pub fn is_external(&self) -> bool {
+    self.value.is_none()
+  }
+
+  pub fn is_unparsed(&self) -> bool {
+    self.notation_name.is_some()
+  }
+}
+
+/// The declarations that the parser has read from the DTD, which are referred to while reading the document.
+///
+#[derive(Clone, Default)]
+pub(crate) struct Declarations {
+  pub entities: HashMap<String, EntityDecl>,
+  pub parameter_entities: HashMap<String, EntityDecl>,
+  pub attlists: HashMap<String, Vec<AttDef>>,
+  /// Whether the DTD has the external subset or parameter entity references that the parser hasn't read. In that
+  /// case, declarations may be missing and references to undeclared entities are not errors.
+  pub partial: bool,
+  /// Whether the declarations of entities and attributes after an unread parameter entity reference are ignored, as
+  /// they might be overridden by the unread declarations.
+  pub ignore_declarations: bool,
+}
+
+impl Declarations {
+  /// Binds the entity declaration unless another one with the same name has been declared; the first declaration is
+  /// binding. `false` is returned if the declaration is ignored.
+  pub fn declare_entity(&mut self, decl: EntityDecl) -> bool {
+    let entities = if decl.parameter { &mut self.parameter_entities } else { &mut self.entities };
+    if self.ignore_declarations || entities.contains_key(&decl.name) {
+      return false;
+    }
+    entities.insert(decl.name.clone(), decl);
+    true
+  }
+
+  /// Binds the attribute definitions of the `element` that haven't been declared yet, and returns them.
+  pub fn declare_attributes(&mut self, element: &str, definitions: Vec<AttDef>) -> Vec<AttDef> {
+    if self.ignore_declarations {
+      return Vec::new();
+    }
+    let declared = self.attlists.entry(element.to_string()).or_default();
+    let mut binding = Vec::new();
+    for definition in definitions {
+      if !declared.iter().chain(binding.iter()).any(|def: &AttDef| def.name == definition.name) {
+        binding.push(definition);
+      }
+    }
+    declared.extend(binding.iter().cloned());
+    binding
+  }
+}
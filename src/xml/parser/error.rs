@@ -0,0 +1,274 @@
+/// The kind of a parse error, identified by the production or the well-formedness constraint of
+/// [XML 1.0 (Fifth Edition)](https://www.w3.org/TR/xml/) that the document failed to match.
+///
+/// The number returned by [ErrorCode::code()] is stable across releases; once assigned, a code is never reused for
+/// another error. Codes in `1xx` are syntax errors of productions, codes in `2xx` are violations of
+/// well-formedness constraints, and codes in `3xx` are violations of
+/// [Namespaces in XML 1.0](https://www.w3.org/TR/xml-names/) that correspond to `DOMException::Namespace`. Codes in
+/// `4xx` are not violations of the specification but the [EntityLimits](super::EntityLimits) that the document
+/// exceeded. Codes in `5xx` are not errors but the warnings in [PullParser::warnings()](super::PullParser::warnings)
+/// about what the parser left unread.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCode {
+  /// `[1] document`: the document has no document element.
+  NoDocumentElement,
+  /// `[22] prolog`: something other than markup appears before the document element.
+  ContentInProlog,
+  /// `[27] Misc`: something other than comments, PIs and whitespace appears after the document element.
+  ContentAfterDocumentElement,
+  /// `[39] element`: the document ends before the element is closed.
+  UnclosedElement,
+  /// `[2] Char`: the document contains a character that is not allowed in XML.
+  InvalidChar,
+  /// `[14] CharData`: the character data contains `]]>`.
+  CDataSectionEndInContent,
+  /// `[1] document`: another element appears after the document element.
+  MultipleDocumentElements,
+  /// `[43] content`: the replacement text of an entity ends an element that started outside, or doesn't end an
+  /// element that started in it.
+  UnbalancedEntity,
+  /// `[23] XMLDecl`: the XML declaration is malformed.
+  MalformedXMLDecl,
+  /// `[26] VersionNum`: the version number isn't `1.` followed by digits.
+  InvalidVersionNum,
+  /// `[81] EncName`: the encoding name contains illegal characters.
+  InvalidEncName,
+  /// `[32] SDDecl`: the standalone declaration is neither `yes` nor `no`.
+  InvalidSDDecl,
+  /// `[77] TextDecl`: the text declaration at the beginning of an external entity is malformed.
+  MalformedTextDecl,
+  /// `[40] STag`: the start tag is malformed.
+  MalformedStartTag,
+  /// `[41] Attribute`: the attribute is malformed.
+  MalformedAttribute,
+  /// `[10] AttValue`: the attribute value is not quoted or not terminated.
+  MalformedAttValue,
+  /// `[42] ETag`: the end tag is malformed.
+  MalformedEndTag,
+  /// `[15] Comment`: the comment is malformed or not terminated.
+  MalformedComment,
+  /// `[16] PI`: the processing instruction is malformed or not terminated.
+  MalformedPI,
+  /// `[17] PITarget`: the target of the processing instruction is a reserved name such as `xml`.
+  ReservedPITarget,
+  /// `[15] Comment`: the comment contains `--` or ends with `-`.
+  DoubleHyphenInComment,
+  /// `[18] CDSect`: the CDATA section is not terminated.
+  MalformedCDSect,
+  /// `[28] doctypedecl`: the document type declaration is malformed.
+  MalformedDoctypeDecl,
+  /// `[22] prolog`: the document type declaration appears twice or after the document element.
+  MisplacedDoctypeDecl,
+  /// `[45] elementdecl`: the element type declaration is malformed.
+  MalformedElementDecl,
+  /// `[52] AttlistDecl`: the attribute-list declaration is malformed.
+  MalformedAttlistDecl,
+  /// `[70] EntityDecl`: the entity declaration is malformed.
+  MalformedEntityDecl,
+  /// `[82] NotationDecl`: the notation declaration is malformed.
+  MalformedNotationDecl,
+  /// `[61] conditionalSect`: the conditional section is malformed, not terminated or in the internal subset.
+  MalformedConditionalSect,
+  /// `[28b] intSubset`: the internal subset is not terminated.
+  MalformedIntSubset,
+  /// `[30] extSubset`: the external subset contains something other than markup declarations.
+  MalformedExtSubset,
+  /// `[11] SystemLiteral`: the system literal is not quoted or not terminated.
+  MalformedSystemLiteral,
+  /// `[12] PubidLiteral`: the public identifier is not quoted, not terminated or contains illegal characters.
+  MalformedPubidLiteral,
+  /// `[66] CharRef`: the character reference is malformed.
+  MalformedCharRef,
+  /// `[68] EntityRef`: the entity reference is not terminated by `;`.
+  MalformedEntityRef,
+  /// `[69] PEReference`: the parameter entity reference is not terminated by `;`.
+  MalformedPEReference,
+  /// `[5] Name`: a name is expected.
+  InvalidName,
+
+  /// `WFC: Element Type Match`: the end tag doesn't match the start tag.
+  ElementTypeMatch,
+  /// `WFC: Legal Character`: the character reference refers to an illegal character.
+  LegalCharacter,
+  /// `WFC: Entity Declared`: the entity is not declared.
+  EntityDeclared,
+  /// `WFC: Unique Att Spec`: the same attribute name appears more than once in a start tag.
+  UniqueAttSpec,
+  /// `WFC: No < in Attribute Values`: the attribute value contains `<`.
+  NoLtInAttributeValues,
+  /// `WFC: No External Entity References`: the attribute value refers to an external entity.
+  NoExternalEntityReferences,
+  /// `WFC: Parsed Entity`: the entity reference refers to an unparsed entity.
+  ParsedEntity,
+  /// `WFC: No Recursion`: the entity refers to itself directly or indirectly.
+  NoRecursion,
+  /// `WFC: PEs in Internal Subset`: the parameter entity reference appears within a markup declaration in the internal
+  /// subset.
+  PEsInInternalSubset,
+
+  /// `[7] QName`: the name has an empty prefix or local part, or more than one colon.
+  MalformedQName,
+  /// `NSC: Prefix Declared`: the prefix is not bound to any namespace.
+  PrefixDeclared,
+  /// `NSC: Reserved Prefixes and Namespace Names`: the `xml` or `xmlns` prefix or namespace name is bound illegally.
+  ReservedPrefix,
+  /// `NSC: No Prefix Undeclaring`: the prefix is bound to an empty namespace name.
+  NoPrefixUndeclaring,
+  /// `NSC: Attributes Unique`: two attributes have the same namespace URI and local name.
+  AttributesUnique,
+
+  /// `Limit: Entity Depth`: entities are nested deeper than [EntityLimits::max_depth](super::EntityLimits).
+  EntityDepthLimit,
+  /// `Limit: Entity Expansion`: the replacement text expanded exceeds
+  /// [EntityLimits::max_expanded_chars](super::EntityLimits).
+  EntityExpansionLimit,
+  /// `Limit: Entity Expansion Ratio`: the replacement text expanded is too large for the size of the document.
+  EntityExpansionRatio,
+
+  /// `Warning: Version`: the version `1.x` other than `1.0` and `1.1` is read as XML 1.0.
+  UnsupportedVersion,
+  /// `Warning: External Subset`: the resolver refused the external DTD subset.
+  ExternalSubsetNotRead,
+  /// `Warning: Entity`: the resolver refused the external entity, or the entity might be declared in the unread part
+  /// of the DTD.
+  EntityNotRead,
+}
+
+impl ErrorCode {
+  /// The stable number that identifies this error.
+  ///
+  pub fn code(&self) -> u16 {
+    match self {
+      ErrorCode::NoDocumentElement => 101,
+      ErrorCode::ContentInProlog => 102,
+      ErrorCode::ContentAfterDocumentElement => 103,
+      ErrorCode::UnclosedElement => 104,
+      ErrorCode::InvalidChar => 105,
+      ErrorCode::CDataSectionEndInContent => 106,
+      ErrorCode::MultipleDocumentElements => 107,
+      ErrorCode::UnbalancedEntity => 108,
+      ErrorCode::MalformedXMLDecl => 110,
+      ErrorCode::InvalidVersionNum => 111,
+      ErrorCode::InvalidEncName => 112,
+      ErrorCode::InvalidSDDecl => 113,
+      ErrorCode::MalformedTextDecl => 114,
+      ErrorCode::MalformedStartTag => 120,
+      ErrorCode::MalformedAttribute => 121,
+      ErrorCode::MalformedAttValue => 122,
+      ErrorCode::MalformedEndTag => 123,
+      ErrorCode::MalformedComment => 130,
+      ErrorCode::MalformedPI => 131,
+      ErrorCode::MalformedCDSect => 132,
+      ErrorCode::ReservedPITarget => 133,
+      ErrorCode::DoubleHyphenInComment => 134,
+      ErrorCode::MalformedDoctypeDecl => 140,
+      ErrorCode::MalformedIntSubset => 141,
+      ErrorCode::MalformedSystemLiteral => 142,
+      ErrorCode::MalformedPubidLiteral => 143,
+      ErrorCode::MisplacedDoctypeDecl => 144,
+      ErrorCode::MalformedElementDecl => 145,
+      ErrorCode::MalformedAttlistDecl => 146,
+      ErrorCode::MalformedEntityDecl => 147,
+      ErrorCode::MalformedNotationDecl => 148,
+      ErrorCode::MalformedConditionalSect => 149,
+      ErrorCode::MalformedExtSubset => 153,
+      ErrorCode::MalformedCharRef => 150,
+      ErrorCode::MalformedEntityRef => 151,
+      ErrorCode::MalformedPEReference => 152,
+      ErrorCode::InvalidName => 160,
+      ErrorCode::ElementTypeMatch => 201,
+      ErrorCode::LegalCharacter => 202,
+      ErrorCode::EntityDeclared => 203,
+      ErrorCode::UniqueAttSpec => 204,
+      ErrorCode::NoLtInAttributeValues => 205,
+      ErrorCode::NoExternalEntityReferences => 206,
+      ErrorCode::ParsedEntity => 207,
+      ErrorCode::NoRecursion => 208,
+      ErrorCode::PEsInInternalSubset => 209,
+      ErrorCode::MalformedQName => 301,
+      ErrorCode::PrefixDeclared => 302,
+      ErrorCode::ReservedPrefix => 303,
+      ErrorCode::NoPrefixUndeclaring => 304,
+      ErrorCode::AttributesUnique => 305,
+      ErrorCode::EntityDepthLimit => 401,
+      ErrorCode::EntityExpansionLimit => 402,
+      ErrorCode::EntityExpansionRatio => 403,
+      ErrorCode::UnsupportedVersion => 501,
+      ErrorCode::ExternalSubsetNotRead => 502,
+      ErrorCode::EntityNotRead => 503,
+    }
+  }
+
+  /// The production or the well-formedness constraint of the specification that failed, such as `[40] STag` or
+  /// `WFC: Element Type Match`, or the limit that was exceeded.
+  ///
+  pub fn constraint(&self) -> &'static str {
+    match self {
+      ErrorCode::NoDocumentElement => "[1] document",
+      ErrorCode::ContentInProlog => "[22] prolog",
+      ErrorCode::ContentAfterDocumentElement => "[27] Misc",
+      ErrorCode::UnclosedElement => "[39] element",
+      ErrorCode::InvalidChar => "[2] Char",
+      ErrorCode::CDataSectionEndInContent => "[14] CharData",
+      ErrorCode::MultipleDocumentElements => "[1] document",
+      ErrorCode::UnbalancedEntity => "[43] content",
+      ErrorCode::MalformedXMLDecl => "[23] XMLDecl",
+      ErrorCode::InvalidVersionNum => "[26] VersionNum",
+      ErrorCode::InvalidEncName => "[81] EncName",
+      ErrorCode::InvalidSDDecl => "[32] SDDecl",
+      ErrorCode::MalformedTextDecl => "[77] TextDecl",
+      ErrorCode::MalformedStartTag => "[40] STag",
+      ErrorCode::MalformedAttribute => "[41] Attribute",
+      ErrorCode::MalformedAttValue => "[10] AttValue",
+      ErrorCode::MalformedEndTag => "[42] ETag",
+      ErrorCode::MalformedComment => "[15] Comment",
+      ErrorCode::MalformedPI => "[16] PI",
+      ErrorCode::MalformedCDSect => "[18] CDSect",
+      ErrorCode::ReservedPITarget => "[17] PITarget",
+      ErrorCode::DoubleHyphenInComment => "[15] Comment",
+      ErrorCode::MalformedDoctypeDecl => "[28] doctypedecl",
+      ErrorCode::MalformedIntSubset => "[28b] intSubset",
+      ErrorCode::MalformedSystemLiteral => "[11] SystemLiteral",
+      ErrorCode::MalformedPubidLiteral => "[12] PubidLiteral",
+      ErrorCode::MisplacedDoctypeDecl => "[22] prolog",
+      ErrorCode::MalformedElementDecl => "[45] elementdecl",
+      ErrorCode::MalformedAttlistDecl => "[52] AttlistDecl",
+      ErrorCode::MalformedEntityDecl => "[70] EntityDecl",
+      ErrorCode::MalformedNotationDecl => "[82] NotationDecl",
+      ErrorCode::MalformedConditionalSect => "[61] conditionalSect",
+      ErrorCode::MalformedExtSubset => "[30] extSubset",
+      ErrorCode::MalformedCharRef => "[66] CharRef",
+      ErrorCode::MalformedEntityRef => "[68] EntityRef",
+      ErrorCode::MalformedPEReference => "[69] PEReference",
+      ErrorCode::InvalidName => "[5] Name",
+      ErrorCode::ElementTypeMatch => "WFC: Element Type Match",
+      ErrorCode::LegalCharacter => "WFC: Legal Character",
+      ErrorCode::EntityDeclared => "WFC: Entity Declared",
+      ErrorCode::UniqueAttSpec => "WFC: Unique Att Spec",
+      ErrorCode::NoLtInAttributeValues => "WFC: No < in Attribute Values",
+      ErrorCode::NoExternalEntityReferences => "WFC: No External Entity References",
+      ErrorCode::ParsedEntity => "WFC: Parsed Entity",
+      ErrorCode::NoRecursion => "WFC: No Recursion",
+      ErrorCode::PEsInInternalSubset => "WFC: PEs in Internal Subset",
+      ErrorCode::MalformedQName => "[7] QName",
+      ErrorCode::PrefixDeclared => "NSC: Prefix Declared",
+      ErrorCode::ReservedPrefix => "NSC: Reserved Prefixes and Namespace Names",
+      ErrorCode::NoPrefixUndeclaring => "NSC: No Prefix Undeclaring",
+      ErrorCode::AttributesUnique => "NSC: Attributes Unique",
+      ErrorCode::EntityDepthLimit => "Limit: Entity Depth",
+      ErrorCode::EntityExpansionLimit => "Limit: Entity Expansion",
+      ErrorCode::EntityExpansionRatio => "Limit: Entity Expansion Ratio",
+      ErrorCode::UnsupportedVersion => "Warning: Version",
+      ErrorCode::ExternalSubsetNotRead => "Warning: External Subset",
+      ErrorCode::EntityNotRead => "Warning: Entity",
+    }
+  }
+}
+
+impl std::fmt::Display for ErrorCode {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "XML{:03} {}", self.code(), self.constraint())
+  }
+}
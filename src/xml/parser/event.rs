@@ -0,0 +1,274 @@
+use std::borrow::Cow;
+
+use crate::Location;
+
+use super::MarkupDecl;
+
+/// An event that [super::PullParser] reports, together with the location where the event starts and the location
+/// just after the markup or text of the event. For [EventKind::StartElement] and [EventKind::EndElement], they are
+/// the start and end of the tag; the `EndElement` of an empty-element tag has the same range as its `StartElement`.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+  pub location: Location,
+  pub end: Location,
+  pub kind: EventKind,
+}
+
+impl Event {
+  /// Creates an event that ends where it starts. The parser sets the `end` when it returns the event.
+  pub fn new(location: Location, kind: EventKind) -> Event {
+    Event { end: location.clone(), location, kind }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventKind {
+  /// `<?xml version="1.0" encoding="UTF-8" standalone="yes"?>`
+  XMLDecl {
+    version: String,
+    encoding: Option<String>,
+    standalone: Option<bool>,
+  },
+  /// `<!DOCTYPE name PUBLIC "public-id" "system-id" [internal-subset]>`. `internal_subset` is the raw text of the
+  /// internal subset, and `declarations` are the markup declarations read from it and from the external subset.
+  DocType {
+    name: String,
+    public_id: Option<String>,
+    system_id: Option<String>,
+    internal_subset: Option<String>,
+    declarations: Vec<MarkupDecl>,
+  },
+  /// `<name attr="value">`. An empty-element tag `<name/>` is reported as a `StartElement` immediately followed by
+  /// an `EndElement`.
+  ///
+  /// `namespace_uri` and `local_name` are resolved only if the parser performs namespace processing; otherwise both
+  /// are `None`.
+  StartElement {
+    name: String,
+    namespace_uri: Option<String>,
+    local_name: Option<String>,
+    attributes: Vec<Attribute>,
+  },
+  /// `</name>`
+  EndElement {
+    name: String,
+    namespace_uri: Option<String>,
+    local_name: Option<String>,
+  },
+  /// Character data whose character references and predefined entity references have been replaced.
+  Text(String),
+  /// The start of the replacement text of the general entity referred to in content. The events of the replacement
+  /// text follow until the corresponding `EndEntity`.
+  StartEntity(String),
+  EndEntity(String),
+  /// A reference to the entity that the parser doesn't read, such as an external entity or an entity that might be
+  /// declared in the unread part of the DTD.
+  SkippedEntity(String),
+  /// `<![CDATA[...]]>`
+  CDATA(String),
+  /// `<!--...-->`
+  Comment(String),
+  /// `<?target data?>`
+  ProcessingInstruction {
+    target: String,
+    data: Option<String>,
+  },
+  EndDocument,
+}
+
+/// An attribute of an element, either specified in the start tag or defaulted by the attribute-list declaration.
+/// Namespace declarations such as `xmlns:p="..."` are reported as attributes in the `http://www.w3.org/2000/xmlns/`
+/// namespace.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute {
+  pub location: Location,
+  /// The location just after the closing quote of the value. A defaulted attribute ends where it starts.
+  pub end: Location,
+  pub name: String,
+  /// The namespace URI that the prefix of the name is bound to. An attribute without prefix has no namespace.
+  pub namespace_uri: Option<String>,
+  /// The local part of the name, if the parser performs namespace processing.
+  pub local_name: Option<String>,
+  /// The normalized attribute value. Values of attributes declared with types other than CDATA have their spaces
+  /// collapsed.
+  pub value: String,
+  /// Whether the attribute is specified in the start tag. `false` if the value is the default of the declaration.
+  pub specified: bool,
+}
+
+/// An event that [super::SliceParser] reports. Its strings borrow from the document, except those that differ from the
+/// text of the document, such as text with references replaced or line ends normalized.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SliceEvent<'a> {
+  pub location: Location,
+  pub end: Location,
+  pub kind: SliceEventKind<'a>,
+}
+
+impl<'a> SliceEvent<'a> {
+  /// Creates an event that ends where it starts. The parser sets the `end` when it returns the event.
+  pub fn new(location: Location, kind: SliceEventKind<'a>) -> SliceEvent<'a> {
+    SliceEvent { end: location.clone(), location, kind }
+  }
+
+  /// Copies the borrowed strings into an [Event] that doesn't borrow from the document.
+  pub fn into_owned(self) -> Event {
+    let kind = match self.kind {
+      SliceEventKind::XMLDecl { version, encoding, standalone } => {
+        EventKind::XMLDecl { version: version.into_owned(), encoding: encoding.map(Cow::into_owned), standalone }
+      }
+      SliceEventKind::DocType { name, public_id, system_id, internal_subset, declarations } => EventKind::DocType {
+        name: name.into_owned(),
+        public_id: public_id.map(Cow::into_owned),
+        system_id: system_id.map(Cow::into_owned),
+        internal_subset: internal_subset.map(Cow::into_owned),
+        declarations,
+      },
+      SliceEventKind::StartElement { name, namespace_uri, local_name, attributes } => EventKind::StartElement {
+        name: name.into_owned(),
+        namespace_uri: namespace_uri.map(Cow::into_owned),
+        local_name: local_name.map(Cow::into_owned),
+        attributes: attributes.into_iter().map(SliceAttribute::into_owned).collect(),
+      },
+      SliceEventKind::EndElement { name, namespace_uri, local_name } => EventKind::EndElement {
+        name: name.into_owned(),
+        namespace_uri: namespace_uri.map(Cow::into_owned),
+        local_name: local_name.map(Cow::into_owned),
+      },
+      SliceEventKind::Text(text) => EventKind::Text(text.into_owned()),
+      SliceEventKind::StartEntity(name) => EventKind::StartEntity(name.into_owned()),
+      SliceEventKind::EndEntity(name) => EventKind::EndEntity(name.into_owned()),
+      SliceEventKind::SkippedEntity(name) => EventKind::SkippedEntity(name.into_owned()),
+      SliceEventKind::CDATA(text) => EventKind::CDATA(text.into_owned()),
+      SliceEventKind::Comment(text) => EventKind::Comment(text.into_owned()),
+      SliceEventKind::ProcessingInstruction { target, data } => {
+        EventKind::ProcessingInstruction { target: target.into_owned(), data: data.map(Cow::into_owned) }
+      }
+      SliceEventKind::EndDocument => EventKind::EndDocument,
+    };
+    Event { location: self.location, end: self.end, kind }
+  }
+}
+
+impl From<Event> for SliceEvent<'_> {
+  fn from(event: Event) -> Self {
+    let kind = match event.kind {
+      EventKind::XMLDecl { version, encoding, standalone } => {
+        SliceEventKind::XMLDecl { version: version.into(), encoding: encoding.map(Cow::Owned), standalone }
+      }
+      EventKind::DocType { name, public_id, system_id, internal_subset, declarations } => SliceEventKind::DocType {
+        name: name.into(),
+        public_id: public_id.map(Cow::Owned),
+        system_id: system_id.map(Cow::Owned),
+        internal_subset: internal_subset.map(Cow::Owned),
+        declarations,
+      },
+      EventKind::StartElement { name, namespace_uri, local_name, attributes } => SliceEventKind::StartElement {
+        name: name.into(),
+        namespace_uri: namespace_uri.map(Cow::Owned),
+        local_name: local_name.map(Cow::Owned),
+        attributes: attributes.into_iter().map(SliceAttribute::from).collect(),
+      },
+      EventKind::EndElement { name, namespace_uri, local_name } => SliceEventKind::EndElement {
+        name: name.into(),
+        namespace_uri: namespace_uri.map(Cow::Owned),
+        local_name: local_name.map(Cow::Owned),
+      },
+      EventKind::Text(text) => SliceEventKind::Text(text.into()),
+      EventKind::StartEntity(name) => SliceEventKind::StartEntity(name.into()),
+      EventKind::EndEntity(name) => SliceEventKind::EndEntity(name.into()),
+      EventKind::SkippedEntity(name) => SliceEventKind::SkippedEntity(name.into()),
+      EventKind::CDATA(text) => SliceEventKind::CDATA(text.into()),
+      EventKind::Comment(text) => SliceEventKind::Comment(text.into()),
+      EventKind::ProcessingInstruction { target, data } => {
+        SliceEventKind::ProcessingInstruction { target: target.into(), data: data.map(Cow::Owned) }
+      }
+      EventKind::EndDocument => SliceEventKind::EndDocument,
+    };
+    SliceEvent { location: event.location, end: event.end, kind }
+  }
+}
+
+/// The kinds of [SliceEvent], the same as [EventKind].
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SliceEventKind<'a> {
+  XMLDecl {
+    version: Cow<'a, str>,
+    encoding: Option<Cow<'a, str>>,
+    standalone: Option<bool>,
+  },
+  DocType {
+    name: Cow<'a, str>,
+    public_id: Option<Cow<'a, str>>,
+    system_id: Option<Cow<'a, str>>,
+    internal_subset: Option<Cow<'a, str>>,
+    declarations: Vec<MarkupDecl>,
+  },
+  StartElement {
+    name: Cow<'a, str>,
+    namespace_uri: Option<Cow<'a, str>>,
+    local_name: Option<Cow<'a, str>>,
+    attributes: Vec<SliceAttribute<'a>>,
+  },
+  EndElement {
+    name: Cow<'a, str>,
+    namespace_uri: Option<Cow<'a, str>>,
+    local_name: Option<Cow<'a, str>>,
+  },
+  Text(Cow<'a, str>),
+  StartEntity(Cow<'a, str>),
+  EndEntity(Cow<'a, str>),
+  SkippedEntity(Cow<'a, str>),
+  CDATA(Cow<'a, str>),
+  Comment(Cow<'a, str>),
+  ProcessingInstruction {
+    target: Cow<'a, str>,
+    data: Option<Cow<'a, str>>,
+  },
+  EndDocument,
+}
+
+/// An attribute of [SliceEventKind::StartElement], the same as [Attribute].
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SliceAttribute<'a> {
+  pub location: Location,
+  pub end: Location,
+  pub name: Cow<'a, str>,
+  pub namespace_uri: Option<Cow<'a, str>>,
+  pub local_name: Option<Cow<'a, str>>,
+  pub value: Cow<'a, str>,
+  pub specified: bool,
+}
+
+impl SliceAttribute<'_> {
+  pub fn into_owned(self) -> Attribute {
+    Attribute {
+      location: self.location,
+      end: self.end,
+      name: self.name.into_owned(),
+      namespace_uri: self.namespace_uri.map(Cow::into_owned),
+      local_name: self.local_name.map(Cow::into_owned),
+      value: self.value.into_owned(),
+      specified: self.specified,
+    }
+  }
+}
+
+impl From<Attribute> for SliceAttribute<'_> {
+  fn from(attr: Attribute) -> Self {
+    SliceAttribute {
+      location: attr.location,
+      end: attr.end,
+      name: attr.name.into(),
+      namespace_uri: attr.namespace_uri.map(Cow::Owned),
+      local_name: attr.local_name.map(Cow::Owned),
+      value: attr.value.into(),
+      specified: attr.specified,
+    }
+  }
+}
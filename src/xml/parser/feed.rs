@@ -0,0 +1,141 @@
+use std::cell::RefCell;
+use std::io::ErrorKind;
+use std::rc::Rc;
+
+use crate::io::CharReader;
+use crate::Result;
+
+use super::{Event, InputSource, PullParser};
+
+/// A non-blocking parser that is fed a UTF-8 document in byte chunks as they arrive, for example from a socket, and
+/// returns the events that have become complete.
+///
+/// [FeedParser::next_event()] returns `None` when the parser needs more data to complete the next event; feed the
+/// next chunk with [FeedParser::feed()] and call it again, or call [FeedParser::finish()] when the document has
+/// ended. An event that is incomplete at the end of the data is read again from its start when more data arrives,
+/// except that a text, a CDATA section or a comment continues from where the data ran out, so the events and errors
+/// are the same as [PullParser] reading the whole document at once, wherever the chunk boundaries fall, even within a
+/// multi-byte character, a tag or a reference.
+///
+/// ```
+/// use xenolith::xml::parser::{EventKind, FeedParser};
+///
+/// let mut parser = FeedParser::new();
+/// let mut texts = Vec::new();
+/// for chunk in ["<greeting>hel".as_bytes(), b"lo &am", b"p; caf\xC3", b"\xA9</gree", b"ting>"] {
+///   parser.feed(chunk);
+///   while let Some(event) = parser.next_event().unwrap() {
+///     if let EventKind::Text(text) = event.kind {
+///       texts.push(text);
+///     }
+///   }
+/// }
+/// parser.finish();
+/// assert!(matches!(parser.next_event().unwrap().unwrap().kind, EventKind::EndDocument));
+/// assert_eq!(vec!["hello & café"], texts);
+/// ```
+///
+pub struct FeedParser {
+  parser: PullParser,
+  feed: Rc<RefCell<Feed>>,
+}
+
+impl FeedParser {
+  pub fn new() -> FeedParser {
+    Self::with_system_id(None)
+  }
+
+  /// Creates a parser for the document of the `system_id`, which is used as the base URI of relative references and
+  /// reported with errors.
+  pub fn with_system_id(system_id: Option<&str>) -> FeedParser {
+    let feed = Rc::new(RefCell::new(Feed::default()));
+    let mut input = InputSource::new(FeedReader(feed.clone()));
+    input.system_id = system_id.map(|system_id| system_id.to_string());
+    FeedParser { parser: PullParser::with_input_source(input), feed }
+  }
+
+  /// The underlying parser, to be configured before the first event is read. Reading events from it directly fails
+  /// with [ErrorKind::WouldBlock] when the data runs out.
+  pub fn pull_parser_mut(&mut self) -> &mut PullParser {
+    &mut self.parser
+  }
+
+  /// Appends the next chunk of the document.
+  pub fn feed(&mut self, bytes: &[u8]) {
+    let mut feed = self.feed.borrow_mut();
+    debug_assert!(!feed.finished, "data is fed after the end of the document");
+    let start = feed.start;
+    feed.bytes.drain(..start);
+    feed.start = 0;
+    feed.bytes.extend_from_slice(bytes);
+  }
+
+  /// Tells that the document has ended and no more chunks follow.
+  pub fn finish(&mut self) {
+    self.feed.borrow_mut().finished = true;
+  }
+
+  /// Whether [FeedParser::finish()] has been called.
+  pub fn is_finished(&self) -> bool {
+    self.feed.borrow().finished
+  }
+
+  /// Returns the next event, or `None` if the parser needs more data to complete it.
+  pub fn next_event(&mut self) -> Result<Option<Event>> {
+    self.parser.try_next_event()
+  }
+}
+
+impl Default for FeedParser {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// The bytes fed to the parser, of which those from `start` haven't been decoded yet.
+#[derive(Default)]
+struct Feed {
+  bytes: Vec<u8>,
+  start: usize,
+  /// The byte offset of `start` in the document.
+  offset: u64,
+  finished: bool,
+}
+
+/// A [CharReader] that decodes the fed bytes, and fails with [ErrorKind::WouldBlock] when they run out before the
+/// document has ended.
+struct FeedReader(Rc<RefCell<Feed>>);
+
+impl CharReader for FeedReader {
+  fn read(&mut self, buffer: &mut [char]) -> Result<usize> {
+    let mut feed = self.0.borrow_mut();
+    // only the bytes that can fill the buffer are decoded
+    let end = feed.bytes.len().min(feed.start + buffer.len() * 4);
+    let bytes = &feed.bytes[feed.start..end];
+    let valid = match std::str::from_utf8(bytes) {
+      Ok(text) => text,
+      // the last character is split by the chunk boundary or by the end of the bytes decoded
+      Err(err) if err.error_len().is_none() && (!feed.finished || end < feed.bytes.len()) => {
+        std::str::from_utf8(&bytes[..err.valid_up_to()]).unwrap()
+      }
+      Err(err) if err.valid_up_to() == 0 => {
+        let message = format!("malformed UTF-8 sequence at byte offset {}", feed.offset);
+        return Err(std::io::Error::new(ErrorKind::InvalidData, message).into());
+      }
+      Err(err) => std::str::from_utf8(&bytes[..err.valid_up_to()]).unwrap(),
+    };
+    if valid.is_empty() && !feed.finished {
+      return Err(std::io::Error::new(ErrorKind::WouldBlock, "waiting for more data").into());
+    }
+    let mut length = 0;
+    let mut consumed = 0;
+    for ch in valid.chars().take(buffer.len()) {
+      buffer[length] = ch;
+      length += 1;
+      consumed += ch.len_utf8();
+    }
+    feed.start += consumed;
+    feed.offset += consumed as u64;
+    Ok(length)
+  }
+}
@@ -0,0 +1,117 @@
+use crate::io::StringReader;
+use crate::xml::parser::{Event, FeedParser, PullParser};
+use crate::{Error, Result};
+
+const XML: &str = "\u{FEFF}<?xml version=\"1.0\"?>\r\n<!DOCTYPE a [\r\n  <!ENTITY e '<b>&#x263A;</b>'>\r\n  \
+  <!ATTLIST a lang CDATA 'ja'>\r\n]>\r\n<a x=\"&lt;&#x263A;\">日本語&amp;テキスト\r<![CDATA[ <>& ]]><!-- ☕ -->&e;<?pi \
+  ∀x?>\r\n  <c/></a>\r\n";
+
+fn parse_at_once(xml: &str) -> Vec<Result<Event>> {
+  let mut events = PullParser::new(StringReader::new(xml)).collect::<Vec<_>>();
+  events.truncate(events.iter().position(|e| e.is_err()).map(|i| i + 1).unwrap_or(events.len()));
+  events
+}
+
+fn parse_in_chunks(bytes: &[u8], splits: &[usize]) -> Vec<Result<Event>> {
+  let mut parser = FeedParser::new();
+  let mut events = Vec::new();
+  let mut start = 0;
+  for end in splits.iter().copied().chain([bytes.len()]) {
+    parser.feed(&bytes[start..end]);
+    start = end;
+    loop {
+      match parser.next_event() {
+        Ok(Some(event)) => events.push(Ok(event)),
+        Ok(None) => break,
+        Err(err) => {
+          events.push(Err(err));
+          return events;
+        }
+      }
+    }
+  }
+  parser.finish();
+  loop {
+    match parser.next_event() {
+      Ok(Some(event)) => {
+        let end = event.kind == crate::xml::parser::EventKind::EndDocument;
+        events.push(Ok(event));
+        if end {
+          return events;
+        }
+      }
+      Ok(None) => panic!("more data is required after the end of the document"),
+      Err(err) => {
+        events.push(Err(err));
+        return events;
+      }
+    }
+  }
+}
+
+fn summary(events: &[Result<Event>]) -> Vec<String> {
+  events
+    .iter()
+    .map(|event| match event {
+      Ok(event) => format!("{:?}", event),
+      Err(Error::Parse { code, location, .. }) => format!("{:?} at {}", code, location),
+      Err(err) => err.to_string(),
+    })
+    .collect()
+}
+
+#[test]
+fn same_events_wherever_chunks_split() {
+  let expected = summary(&parse_at_once(XML));
+  assert!(expected.len() > 10, "{:?}", expected);
+  let bytes = XML.as_bytes();
+  for i in 0..=bytes.len() {
+    assert_eq!(expected, summary(&parse_in_chunks(bytes, &[i])), "split at {}", i);
+  }
+  let every_byte = (1..bytes.len()).collect::<Vec<_>>();
+  assert_eq!(expected, summary(&parse_in_chunks(bytes, &every_byte)));
+
+  // errors are also the same
+  for xml in ["<a><b></a>", "<a x='1' x='2'/>", "<a>&undeclared;</a>", "<a>\u{1}</a>", "<a>", "<a/><b/>"] {
+    let expected = summary(&parse_at_once(xml));
+    assert!(matches!(expected.last(), Some(last) if !last.starts_with("Event")), "{}", xml);
+    for i in 0..=xml.len() {
+      assert_eq!(expected, summary(&parse_in_chunks(xml.as_bytes(), &[i])), "{} split at {}", xml, i);
+    }
+  }
+}
+
+#[test]
+fn long_character_data_in_chunks() {
+  // a text, a CDATA section and a comment much longer than the chunks are continued from where the data ran out
+  let long = "日本語のtext &amp; ]] - more\r\n".repeat(20_000);
+  let xml = format!("<a>{0}<![CDATA[{0}]]><!--{0}--></a>", long);
+  let expected = summary(&parse_at_once(&xml));
+  assert_eq!(6, expected.len());
+  let splits = (1..xml.len() / 8192).map(|i| i * 8192).collect::<Vec<_>>();
+  assert_eq!(expected, summary(&parse_in_chunks(xml.as_bytes(), &splits)));
+}
+
+#[test]
+fn need_more_data() {
+  let mut parser = FeedParser::new();
+  assert!(parser.next_event().unwrap().is_none());
+  parser.feed(b"<a>text");
+  assert!(matches!(parser.next_event(), Ok(Some(_))));
+  assert!(parser.next_event().unwrap().is_none());
+  parser.feed(b"</a");
+  assert!(matches!(parser.next_event(), Ok(Some(_))));
+  assert!(parser.next_event().unwrap().is_none());
+  parser.feed(b">");
+  assert!(matches!(parser.next_event(), Ok(Some(_))));
+  assert!(parser.next_event().unwrap().is_none());
+  assert!(!parser.is_finished());
+  parser.finish();
+  assert!(matches!(parser.next_event(), Ok(Some(_))));
+
+  let mut parser = FeedParser::new();
+  parser.feed(b"<a>\xE6\x97");
+  parser.finish();
+  parser.next_event().unwrap();
+  assert!(matches!(parser.next_event(), Err(Error::IO(_))));
+}
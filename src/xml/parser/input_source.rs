@@ -0,0 +1,18 @@
+use crate::io::CharReader;
+
+/// A single input source for a XML entity, that is, a character stream and its public and system identifiers.
+///
+pub struct InputSource {
+  pub reader: Box<dyn CharReader>,
+  /// The public identifier of the entity, if any.
+  pub public_id: Option<String>,
+  /// The system identifier (URI) of the entity. This is used as the base URI to resolve relative references, and
+  /// reported with locations.
+  pub system_id: Option<String>,
+}
+
+impl InputSource {
+  pub fn new<R: CharReader + 'static>(reader: R) -> InputSource {
+    InputSource { reader: Box::new(reader), public_id: None, system_id: None }
+  }
+}
@@ -0,0 +1,55 @@
+/// Caps on the expansion of internal entities, which protect the parser from documents crafted to exhaust memory or
+/// CPU such as the [billion laughs](https://en.wikipedia.org/wiki/Billion_laughs_attack) and the quadratic blowup
+/// attacks. The parser stops with [ErrorCode::EntityDepthLimit](super::ErrorCode::EntityDepthLimit),
+/// [ErrorCode::EntityExpansionLimit](super::ErrorCode::EntityExpansionLimit) or
+/// [ErrorCode::EntityExpansionRatio](super::ErrorCode::EntityExpansionRatio) when a reference would exceed them.
+///
+/// The [Default] limits are small enough to parse documents from untrusted sources, and large enough for ordinary
+/// documents that use entities.
+///
+/// ```rust
+/// use xenolith::io::StringReader;
+/// use xenolith::xml::parser::{EntityLimits, ErrorCode, PullParser};
+/// use xenolith::Error;
+///
+/// let xml = r#"<!DOCTYPE a [<!ENTITY e "0123456789">]><a>&e;&e;</a>"#;
+/// let mut parser = PullParser::new(StringReader::new(xml));
+/// parser.set_entity_limits(EntityLimits { max_expanded_chars: 15, ..EntityLimits::default() });
+/// let error = loop {
+///   if let Err(error) = parser.next_event() {
+///     break error;
+///   }
+/// };
+/// assert!(matches!(error, Error::Parse { code: ErrorCode::EntityExpansionLimit, .. }));
+/// ```
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntityLimits {
+  /// The maximum number of entities that are expanded in one another.
+  pub max_depth: usize,
+  /// The maximum number of characters of replacement text that are expanded in the whole document.
+  pub max_expanded_chars: usize,
+  /// The maximum ratio of the characters of replacement text expanded to the characters read from the document.
+  pub max_expansion_ratio: usize,
+  /// The number of characters of replacement text that can be expanded regardless of the `max_expansion_ratio`, so
+  /// that small documents can use entities freely.
+  pub ratio_threshold: usize,
+}
+
+impl EntityLimits {
+  /// No limits. This should be used only for trusted documents.
+  pub fn unlimited() -> EntityLimits {
+    EntityLimits {
+      max_depth: usize::MAX,
+      max_expanded_chars: usize::MAX,
+      max_expansion_ratio: usize::MAX,
+      ratio_threshold: usize::MAX,
+    }
+  }
+}
+
+impl Default for EntityLimits {
+  fn default() -> Self {
+    EntityLimits { max_depth: 32, max_expanded_chars: 10_000_000, max_expansion_ratio: 10, ratio_threshold: 1_000_000 }
+  }
+}
@@ -1 +1,60 @@
+//! This module provides XML parsers that read documents from [crate::io::CharReader].
+//!
+//! [PullParser] is the core reader of this module. It tokenizes a document as defined in
+//! [Extensible Markup Language (XML) 1.0 (Fifth Edition)](https://www.w3.org/TR/xml/) and returns [Event]s one by one
+//! on demand. [SAXParser] is built on it, and pushes the contents to handlers with the same callback interfaces as
+//! SAX2. [DocumentBuilder] builds a DOM tree of [crate::xml::dom] from a document. [FeedParser] is a non-blocking
+//! variant of [PullParser] that is fed a document in chunks, and `AsyncPullParser` reads a document from
+//! `futures_io::AsyncRead` as a stream of events with the `async` feature. [SliceParser] reads a document held in
+//! memory and returns events that borrow from it.
+//!
+#[cfg(feature = "async")]
+pub use async_pull_parser::*;
+pub use catalog::*;
+pub use document_builder::*;
+pub use dtd::*;
+pub use error::*;
+pub use event::*;
+pub use feed::*;
+pub use input_source::*;
+pub use limits::*;
+pub use pull_parser::*;
+pub use resolver::*;
+pub use sax::*;
+pub use slice_parser::*;
 
+#[cfg(feature = "async")]
+mod async_pull_parser;
+mod catalog;
+pub(crate) mod chars;
+mod document_builder;
+mod dtd;
+mod error;
+mod event;
+mod feed;
+mod input_source;
+mod limits;
+mod namespace;
+mod pull_parser;
+mod resolver;
+mod sax;
+mod scan;
+mod slice_parser;
+mod source;
+
+#[cfg(test)]
+mod catalog_test;
+#[cfg(test)]
+mod document_builder_test;
+#[cfg(test)]
+mod feed_test;
+#[cfg(test)]
+mod pull_parser_test;
+#[cfg(test)]
+mod resolver_test;
+#[cfg(test)]
+mod sax_test;
+#[cfg(test)]
+mod scan_test;
+#[cfg(test)]
+mod slice_parser_test;
@@ -0,0 +1,40 @@
+use crate::xml::dom::XML_NAMESPACE_URI;
+
+/// A stack of namespace scopes defined in [Namespaces in XML 1.0](https://www.w3.org/TR/xml-names/), like Java's
+/// `NamespaceSupport`. Each element opens a new scope, and the prefixes declared on the element are bound in it until
+/// the element ends.
+///
+/// The empty prefix stands for the default namespace, and a binding to `None` undeclares it.
+///
+pub(crate) struct NamespaceScopes {
+  bindings: Vec<(String, Option<String>)>,
+  scopes: Vec<usize>,
+}
+
+impl NamespaceScopes {
+  pub fn new() -> NamespaceScopes {
+    NamespaceScopes { bindings: vec![("xml".to_string(), Some(XML_NAMESPACE_URI.to_string()))], scopes: Vec::new() }
+  }
+
+  /// Opens a new scope for an element.
+  pub fn push_scope(&mut self) {
+    self.scopes.push(self.bindings.len());
+  }
+
+  /// Closes the innermost scope and discards the prefixes declared in it.
+  pub fn pop_scope(&mut self) {
+    if let Some(len) = self.scopes.pop() {
+      self.bindings.truncate(len);
+    }
+  }
+
+  /// Binds the `prefix` to the `namespace_uri` in the innermost scope.
+  pub fn declare(&mut self, prefix: &str, namespace_uri: Option<&str>) {
+    self.bindings.push((prefix.to_string(), namespace_uri.map(|uri| uri.to_string())));
+  }
+
+  /// The namespace URI that the `prefix` is bound to. `None` is returned if the prefix is not bound.
+  pub fn resolve(&self, prefix: &str) -> Option<&str> {
+    self.bindings.iter().rev().find(|(p, _)| p == prefix).and_then(|(_, uri)| uri.as_deref())
+  }
+}
@@ -0,0 +1,1877 @@
+use std::io::ErrorKind;
+use std::rc::Rc;
+
+use crate::io::CharReader;
+use crate::xml::dom::{XMLNS_NAMESPACE_URI, XML_NAMESPACE_URI};
+use crate::{Error, Location, Result};
+
+use super::chars::{is_char, is_char11, is_name, is_name_char, is_name_start_char, is_pubid_char, is_whitespace};
+use super::dtd::Declarations;
+use super::namespace::NamespaceScopes;
+use super::source::{self, Checkpoint, Source};
+use super::{
+  AttDef, AttType, Attribute, ContentParticle, ContentSpec, DefaultDecl, DenyAllResolver, EntityDecl, EntityLimits,
+  EntityResolver, ErrorCode, Event, EventKind, InputSource, MarkupDecl, Occurrence, Particle,
+};
+
+/// The references to the predefined entities and the characters they stand for.
+pub(crate) const PREDEFINED_ENTITIES: [(&str, char); 5] =
+  [("&lt;", '<'), ("&gt;", '>'), ("&amp;", '&'), ("&apos;", '\''), ("&quot;", '"')];
+
+/// A pull parser that reads a XML document from [CharReader] and returns its contents as a sequence of [Event]s.
+///
+/// ```
+/// use xenolith::io::StringReader;
+/// use xenolith::xml::parser::{EventKind, PullParser};
+///
+/// let mut parser = PullParser::new(StringReader::new("<greeting>hello</greeting>"));
+/// let mut texts = Vec::new();
+/// loop {
+///   let event = parser.next_event().unwrap();
+///   match event.kind {
+///     EventKind::Text(text) => texts.push(text),
+///     EventKind::EndDocument => break,
+///     _ => (),
+///   }
+/// }
+/// assert_eq!(vec!["hello"], texts);
+/// ```
+///
+/// The parser performs [namespace processing](https://www.w3.org/TR/xml-names/) by default: the prefixes of elements
+/// and attributes are resolved to namespace URIs with the namespace declarations in scope, and documents that violate
+/// the namespace constraints are rejected.
+///
+/// A document that declares `version="1.1"` is parsed as [XML 1.1](https://www.w3.org/TR/xml11/): the characters in
+/// `[2a] RestrictedChar` are allowed only as character references, NEL and LS are line ends, and a prefix can be
+/// undeclared with `xmlns:p=""` as defined in [Namespaces in XML 1.1](https://www.w3.org/TR/xml-names11/). The name
+/// characters are the same in both versions since the fifth edition of XML 1.0. Other versions `1.x` are parsed as
+/// XML 1.0.
+///
+/// What the parser leaves unread without an error, such as an external entity that the resolver refused or an
+/// unsupported version `1.x`, is kept as a warning in [PullParser::warnings()].
+///
+/// In recovery mode, which is off by default, the parser repairs common errors instead of failing, so that tools
+/// such as editors can get the contents of a broken document: a mismatched end tag closes the elements up to the
+/// matching start tag or is ignored, unclosed elements are closed at the end of the document, and unescaped `&` and
+/// `<`, unterminated or unquoted attribute values, attributes without values, duplicated attributes and references
+/// to undeclared entities are taken as the parser's best guess. The repaired errors are kept in
+/// [PullParser::diagnostics()], and the other errors are returned as usual.
+///
+pub struct PullParser {
+  source: Source,
+  state: State,
+  elements: Vec<String>,
+  pending: Option<Event>,
+  namespace_aware: bool,
+  namespaces: NamespaceScopes,
+  standalone: Option<bool>,
+  dtd: Declarations,
+  /// The number of open elements when each entity being expanded in content started.
+  entity_elements: Vec<usize>,
+  entity_limits: EntityLimits,
+  /// The number of characters of replacement text expanded so far.
+  expanded_chars: usize,
+  resolver: Rc<dyn EntityResolver>,
+  recovery: bool,
+  /// The errors repaired in recovery mode.
+  diagnostics: Vec<Error>,
+  /// What the parser left unread or unsupported without an error.
+  warnings: Vec<Error>,
+  /// The name and location of the end tag whose unclosed descendants are being closed in recovery mode.
+  closing: Option<(String, Location)>,
+  /// Whether the parser reads a well-balanced fragment instead of a document.
+  fragment: bool,
+  /// The event of character data that the reader ran out of characters in, to be continued from the cursor.
+  partial: Option<Partial>,
+}
+
+/// The kinds of events whose character data can be cut off where the reader runs out of characters.
+#[derive(Clone, Copy)]
+enum CharData {
+  Text,
+  CData,
+  Comment,
+}
+
+/// An event of character data cut off in [PullParser::try_next_event()], with its location and the characters read
+/// so far. It's continued from the cursor instead of being read again from its start, so that a long text fed in
+/// many chunks is read in linear time.
+struct Partial {
+  kind: CharData,
+  location: Location,
+  text: String,
+}
+
+/// The state of the parser saved before an event that may be read again in [PullParser::try_next_event()]. The open
+/// elements and namespace scopes are not saved since they change only after the whole markup has been read.
+struct Saved {
+  source: Checkpoint,
+  state: State,
+  standalone: Option<bool>,
+  dtd: Option<Declarations>,
+  entity_elements: Vec<usize>,
+  expanded_chars: usize,
+  diagnostics: usize,
+  warnings: usize,
+  closing: Option<(String, Location)>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+  Start,
+  Prolog,
+  DocType,
+  Content,
+  Epilog,
+  Finished,
+}
+
+impl PullParser {
+  /// The number of bytes that recovery mode looks ahead to guess how to repair an error.
+  const RECOVERY_LOOKAHEAD: usize = 256;
+
+  pub fn new<R: CharReader + 'static>(reader: R) -> PullParser {
+    Self::with_input_source(InputSource::new(reader))
+  }
+
+  pub fn with_input_source(input: InputSource) -> PullParser {
+    PullParser {
+      source: Source::new(input),
+      state: State::Start,
+      elements: Vec::new(),
+      pending: None,
+      namespace_aware: true,
+      namespaces: NamespaceScopes::new(),
+      standalone: None,
+      dtd: Declarations::default(),
+      entity_elements: Vec::new(),
+      entity_limits: EntityLimits::default(),
+      expanded_chars: 0,
+      resolver: Rc::new(DenyAllResolver),
+      recovery: false,
+      diagnostics: Vec::new(),
+      warnings: Vec::new(),
+      closing: None,
+      fragment: false,
+      partial: None,
+    }
+  }
+
+  /// Makes the parser read a well-balanced fragment, `[43] content`, instead of a document, as if it appeared in an
+  /// element of a document of the version `xml11`, where the `namespaces` are in scope in the order of declaration
+  /// and the general `entities` are declared. The fragment has neither the XML declaration nor the DOCTYPE, and its
+  /// contents are followed by [EventKind::EndDocument]. This must be called before the first event is read.
+  pub(crate) fn set_fragment_context(
+    &mut self, xml11: bool, namespaces: &[(String, Option<String>)], entities: Vec<EntityDecl>,
+  ) {
+    self.fragment = true;
+    self.source.set_xml11(xml11);
+    self.namespaces.push_scope();
+    for (prefix, namespace_uri) in namespaces {
+      self.namespaces.declare(prefix, namespace_uri.as_deref());
+    }
+    for decl in entities {
+      self.dtd.declare_entity(decl);
+    }
+  }
+
+  /// Whether the parser performs namespace processing.
+  pub fn namespace_aware(&self) -> bool {
+    self.namespace_aware
+  }
+
+  /// Enables or disables namespace processing. This must be set before the first event is read.
+  pub fn set_namespace_aware(&mut self, namespace_aware: bool) {
+    self.namespace_aware = namespace_aware;
+  }
+
+  /// The limits on the expansion of internal entities.
+  pub fn entity_limits(&self) -> &EntityLimits {
+    &self.entity_limits
+  }
+
+  /// Replaces the limits on the expansion of internal entities. This must be set before the first event is read.
+  pub fn set_entity_limits(&mut self, entity_limits: EntityLimits) {
+    self.entity_limits = entity_limits;
+  }
+
+  /// The resolver that reads external entities. The default is [DenyAllResolver].
+  pub fn entity_resolver(&self) -> &Rc<dyn EntityResolver> {
+    &self.resolver
+  }
+
+  /// Replaces the resolver that reads external entities. This must be set before the first event is read.
+  pub fn set_entity_resolver(&mut self, resolver: Rc<dyn EntityResolver>) {
+    self.resolver = resolver;
+  }
+
+  /// Whether the parser repairs common errors and goes on.
+  pub fn recovery(&self) -> bool {
+    self.recovery
+  }
+
+  /// Enables or disables recovery mode. This must be set before the first event is read.
+  pub fn set_recovery(&mut self, recovery: bool) {
+    self.recovery = recovery;
+  }
+
+  /// The errors that have been repaired in recovery mode, in the order they occurred.
+  pub fn diagnostics(&self) -> &[Error] {
+    &self.diagnostics
+  }
+
+  /// Takes the errors that have been repaired so far out of the parser.
+  pub fn take_diagnostics(&mut self) -> Vec<Error> {
+    std::mem::take(&mut self.diagnostics)
+  }
+
+  /// The warnings about what the parser has left unread or unsupported, in the order they occurred: the external
+  /// subset, external entities and parameter entities that the resolver refused, references to entities that might
+  /// be declared in the unread part of the DTD, and versions `1.x` read as XML 1.0.
+  pub fn warnings(&self) -> &[Error] {
+    &self.warnings
+  }
+
+  /// The version of XML that the document is parsed as, `1.0` or `1.1`. This is known after the XML declaration has
+  /// been read.
+  pub fn xml_version(&self) -> &'static str {
+    if self.source.xml11() {
+      "1.1"
+    } else {
+      "1.0"
+    }
+  }
+
+  /// The current location of the parser.
+  pub fn location(&self) -> Location {
+    self.source.location()
+  }
+
+  /// The public identifier of the entity being read.
+  pub fn public_id(&self) -> Option<&str> {
+    self.source.public_id()
+  }
+
+  /// The system identifier of the entity being read, that is, the document entity or the external entity being
+  /// expanded.
+  pub fn system_id(&self) -> Option<&str> {
+    self.source.system_id()
+  }
+
+  /// Reads the next event. After the [EventKind::EndDocument] has been reported, this will keep returning
+  /// `EndDocument`.
+  pub fn next_event(&mut self) -> Result<Event> {
+    let mut event = match self.pending.take() {
+      Some(event) => event,
+      None => {
+        self.source.compact();
+        self.event()?
+      }
+    };
+    event.end = self.location();
+    Ok(event)
+  }
+
+  /// Reads the next event from the source.
+  fn event(&mut self) -> Result<Event> {
+    if let Some(partial) = self.partial.take() {
+      return self.continue_character_data(partial);
+    }
+    match self.state {
+      State::Start if self.fragment => {
+        self.source.skip_if("\u{FEFF}")?;
+        self.state = State::Content;
+        self.event()
+      }
+      State::Start => {
+        self.source.skip_if("\u{FEFF}")?;
+        self.state = State::Prolog;
+        if self.source.starts_with("<?xml")? && self.source.peek_nth(5)?.map(is_whitespace).unwrap_or(false) {
+          self.xml_decl()
+        } else {
+          self.source.set_xml11(false);
+          self.event()
+        }
+      }
+      State::Prolog | State::DocType | State::Epilog => self.misc(),
+      State::Content if self.closing.is_some() => self.implied_end_tag(),
+      State::Content => self.content(),
+      State::Finished => Ok(Event::new(self.location(), EventKind::EndDocument)),
+    }
+  }
+
+  /// Returns the next event like [PullParser::next_event()], or `None` if the reader has no more characters available
+  /// yet, which it tells by failing with [ErrorKind::WouldBlock]. In that case the parser goes back to where the event
+  /// starts so that it can be read again once more characters arrive, except that a text, a CDATA section or a
+  /// comment keeps the characters read so far and continues from there.
+  pub(crate) fn try_next_event(&mut self) -> Result<Option<Event>> {
+    if self.pending.is_some() {
+      return self.next_event().map(Some);
+    }
+    self.source.compact();
+    let saved = Saved {
+      source: self.source.checkpoint(),
+      state: self.state,
+      standalone: self.standalone,
+      // the declarations are changed only while the DOCTYPE in the prolog is being read
+      dtd: (self.state == State::Prolog).then(|| self.dtd.clone()),
+      entity_elements: self.entity_elements.clone(),
+      expanded_chars: self.expanded_chars,
+      diagnostics: self.diagnostics.len(),
+      warnings: self.warnings.len(),
+      closing: self.closing.clone(),
+    };
+    match self.event() {
+      Err(Error::IO(err)) if err.kind() == ErrorKind::WouldBlock && self.partial.is_some() => Ok(None),
+      Err(Error::IO(err)) if err.kind() == ErrorKind::WouldBlock => {
+        self.source.rewind(saved.source);
+        self.state = saved.state;
+        self.standalone = saved.standalone;
+        if let Some(dtd) = saved.dtd {
+          self.dtd = dtd;
+        }
+        self.entity_elements = saved.entity_elements;
+        self.expanded_chars = saved.expanded_chars;
+        self.diagnostics.truncate(saved.diagnostics);
+        self.warnings.truncate(saved.warnings);
+        self.closing = saved.closing;
+        Ok(None)
+      }
+      result => result.map(|mut event| {
+        event.end = self.location();
+        Some(event)
+      }),
+    }
+  }
+
+  /// `[23] XMLDecl ::= '<?xml' VersionInfo EncodingDecl? SDDecl? S? '?>'`
+  fn xml_decl(&mut self) -> Result<Event> {
+    let location = self.location();
+    self.source.skip_if("<?xml")?;
+    let (version, encoding, standalone) = self.pseudo_attributes(ErrorCode::MalformedXMLDecl)?;
+    self.source.set_xml11(version.as_deref() == Some("1.1"));
+    self.standalone = standalone;
+    if let Some(version) = version.as_deref().filter(|version| !matches!(*version, "1.0" | "1.1")) {
+      let message = format!("version {} is not supported and read as 1.0", version);
+      self.warning(location.clone(), ErrorCode::UnsupportedVersion, &message);
+    }
+    match version {
+      Some(version) => Ok(Event::new(location, EventKind::XMLDecl { version, encoding, standalone })),
+      None => self.error(location, ErrorCode::MalformedXMLDecl, "version is required in XML declaration"),
+    }
+  }
+
+  /// `[77] TextDecl ::= '<?xml' VersionInfo? EncodingDecl S? '?>'` if the external entity starts with it, after the
+  /// byte order mark if any. The encoding is not used here since the reader of the entity, such as
+  /// [DecodingReader](crate::io::DecodingReader), has decoded it.
+  fn text_decl(&mut self) -> Result<()> {
+    self.source.skip_if("\u{FEFF}")?;
+    if !self.source.starts_with("<?xml")? || !self.source.peek_nth(5)?.map(is_whitespace).unwrap_or(false) {
+      return Ok(());
+    }
+    let location = self.location();
+    self.source.skip_if("<?xml")?;
+    if self.pseudo_attributes(ErrorCode::MalformedTextDecl)?.1.is_none() {
+      return self.error(location, ErrorCode::MalformedTextDecl, "encoding is required in text declaration");
+    }
+    Ok(())
+  }
+
+  /// The pseudo-attributes `version`, `encoding` and `standalone` of the XML declaration, or of the text declaration
+  /// that doesn't allow `standalone` if `code` is [ErrorCode::MalformedTextDecl], up to `?>`.
+  fn pseudo_attributes(&mut self, code: ErrorCode) -> Result<(Option<String>, Option<String>, Option<bool>)> {
+    let mut version = None;
+    let mut encoding = None;
+    let mut standalone = None;
+    let text_decl = code == ErrorCode::MalformedTextDecl;
+    loop {
+      let space = self.skip_whitespace()?;
+      if self.source.skip_if("?>")? {
+        break;
+      }
+      let attr_location = self.location();
+      if !space {
+        return self.error(attr_location, code, "whitespace is required between pseudo-attributes");
+      }
+      let name = self.name()?;
+      self.skip_whitespace()?;
+      self.expect("=", code)?;
+      self.skip_whitespace()?;
+      let value = self.quoted_literal(code)?;
+      match name.as_str() {
+        "version" if version.is_none() && encoding.is_none() && standalone.is_none() => {
+          let mut chars = value.chars();
+          let valid = chars.next() == Some('1') && chars.next() == Some('.') && {
+            let digits = chars.as_str();
+            !digits.is_empty() && digits.chars().all(|ch| ch.is_ascii_digit())
+          };
+          if !valid {
+            return self.error(
+              attr_location,
+              ErrorCode::InvalidVersionNum,
+              &format!("invalid version number: {:?}", value),
+            );
+          }
+          version = Some(value);
+        }
+        "encoding" if (version.is_some() || text_decl) && encoding.is_none() && standalone.is_none() => {
+          let mut chars = value.chars();
+          let valid = chars.next().map(|ch| ch.is_ascii_alphabetic()).unwrap_or(false)
+            && chars.all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-'));
+          if !valid {
+            return self.error(
+              attr_location,
+              ErrorCode::InvalidEncName,
+              &format!("invalid encoding name: {:?}", value),
+            );
+          }
+          encoding = Some(value);
+        }
+        "standalone" if !text_decl && version.is_some() && standalone.is_none() => {
+          standalone = Some(match value.as_str() {
+            "yes" => true,
+            "no" => false,
+            _ => {
+              let message = format!("standalone must be 'yes' or 'no': {:?}", value);
+              return self.error(attr_location, ErrorCode::InvalidSDDecl, &message);
+            }
+          });
+        }
+        _ => {
+          let message = format!("unexpected pseudo-attribute: {}", name);
+          return self.error(attr_location, code, &message);
+        }
+      }
+    }
+    Ok((version, encoding, standalone))
+  }
+
+  /// `[27] Misc ::= Comment | PI | S` that can appear before and after the document element.
+  fn misc(&mut self) -> Result<Event> {
+    self.skip_whitespace()?;
+    let location = self.location();
+    if self.source.is_eof()? {
+      if self.state == State::Epilog {
+        self.state = State::Finished;
+        return Ok(Event::new(location, EventKind::EndDocument));
+      }
+      return self.error(location, ErrorCode::NoDocumentElement, "no document element");
+    }
+    if self.source.starts_with("<!--")? {
+      self.character_data(CharData::Comment, location)
+    } else if self.source.starts_with("<?")? {
+      self.pi()
+    } else if self.source.starts_with("<!DOCTYPE")? {
+      if self.state != State::Prolog {
+        return self.misplaced_doctype(location);
+      }
+      self.state = State::DocType;
+      self.doctype()
+    } else if self.state != State::Epilog && self.source.starts_with("<")? {
+      self.state = State::Content;
+      self.start_tag()
+    } else if self.state == State::Epilog
+      && self.source.starts_with("<")?
+      && self.source.peek_nth(1)?.map(is_name_start_char).unwrap_or(false)
+    {
+      self.error(location, ErrorCode::MultipleDocumentElements, "only one document element is allowed")
+    } else if self.state == State::Epilog {
+      let message = "only comments, processing instructions and whitespace are allowed after the document element";
+      self.error(location, ErrorCode::ContentAfterDocumentElement, message)
+    } else {
+      self.error(location, ErrorCode::ContentInProlog, "content is not allowed in prolog")
+    }
+  }
+
+  /// `[43] content ::= CharData? ((element | Reference | CDSect | PI | Comment) CharData?)*`
+  fn content(&mut self) -> Result<Event> {
+    let location = self.location();
+    if self.source.is_eof()? {
+      if self.source.entity_depth() > 0 {
+        return self.end_entity();
+      }
+      if self.fragment && self.elements.is_empty() {
+        self.state = State::Finished;
+        return Ok(Event::new(location, EventKind::EndDocument));
+      }
+      let name = self.elements.last().map(|name| name.as_str()).unwrap_or_default();
+      let message = format!("unexpected end of document: element <{}> is not closed", name);
+      self.recoverable(location.clone(), ErrorCode::UnclosedElement, &message)?;
+      return self.close_element(location);
+    }
+    if self.recovery && self.source.starts_with("<")? && !self.at_markup()? {
+      self.source.next_char()?;
+      self.recoverable(location.clone(), ErrorCode::InvalidName, "unescaped '<' is taken as a character")?;
+      return Ok(Event::new(location, EventKind::Text("<".to_string())));
+    }
+    let mut ampersand = String::new();
+    if self.unescaped_ampersand(&mut ampersand)? {
+      return Ok(Event::new(location, EventKind::Text(ampersand)));
+    }
+    if self.source.starts_with("</")? {
+      self.end_tag()
+    } else if self.source.starts_with("<!--")? {
+      self.character_data(CharData::Comment, location)
+    } else if self.source.starts_with("<![CDATA[")? {
+      self.character_data(CharData::CData, location)
+    } else if self.source.starts_with("<!DOCTYPE")? {
+      self.misplaced_doctype(location)
+    } else if self.source.starts_with("<?")? {
+      self.pi()
+    } else if self.source.starts_with("<")? {
+      self.start_tag()
+    } else if self.at_entity_reference()? {
+      self.entity_reference()
+    } else {
+      self.character_data(CharData::Text, location)
+    }
+  }
+
+  /// `[40] STag ::= '<' Name (S Attribute)* S? '>'` or `[44] EmptyElemTag ::= '<' Name (S Attribute)* S? '/>'`
+  fn start_tag(&mut self) -> Result<Event> {
+    let location = self.location();
+    self.expect("<", ErrorCode::MalformedStartTag)?;
+    let name = self.name()?;
+    let mut attributes = Vec::new();
+    // whether the whitespace after an attribute without value has been read in recovery mode
+    let mut separated = false;
+    loop {
+      let space = self.skip_whitespace()? || std::mem::take(&mut separated);
+      if self.source.skip_if(">")? {
+        return self.start_element(location, name, attributes);
+      }
+      if self.source.skip_if("/>")? {
+        self.default_attributes(&location, &name, &mut attributes);
+        let (namespace_uri, local_name) = self.start_scope(&location, &name, &mut attributes)?;
+        self.namespaces.pop_scope();
+        let end = EventKind::EndElement {
+          name: name.clone(),
+          namespace_uri: namespace_uri.clone(),
+          local_name: local_name.clone(),
+        };
+        self.pending = Some(Event::new(location.clone(), end));
+        if self.elements.is_empty() && !self.fragment {
+          self.state = State::Epilog;
+        }
+        let start = EventKind::StartElement { name, namespace_uri, local_name, attributes };
+        return Ok(Event::new(location, start));
+      }
+      let attr_location = self.location();
+      if self.source.is_eof()? {
+        let message = format!("unexpected end of document in start tag <{}>", name);
+        self.recoverable(attr_location, ErrorCode::MalformedStartTag, &message)?;
+        return self.start_element(location, name, attributes);
+      }
+      if self.recovery && self.source.starts_with("<")? {
+        let message = format!("start tag <{}> is not closed", name);
+        self.recoverable(attr_location, ErrorCode::MalformedStartTag, &message)?;
+        return self.start_element(location, name, attributes);
+      }
+      if !space {
+        let message = "whitespace is required between attributes";
+        self.recoverable(attr_location.clone(), ErrorCode::MalformedStartTag, message)?;
+      }
+      if self.recovery && !self.source.peek()?.map(is_name_start_char).unwrap_or(false) {
+        let ch = self.source.next_char()?.unwrap_or_default();
+        let message = format!("unexpected character {:?} in start tag <{}> is ignored", ch, name);
+        self.recoverable(attr_location, ErrorCode::MalformedStartTag, &message)?;
+        continue;
+      }
+      let attr_name = self.name()?;
+      let duplicated = attributes.iter().any(|attr: &Attribute| attr.name == attr_name);
+      if duplicated {
+        let message = format!("attribute {} appears more than once in start tag <{}>", attr_name, name);
+        self.recoverable(attr_location.clone(), ErrorCode::UniqueAttSpec, &message)?;
+      }
+      let space = self.skip_whitespace()?;
+      let value = if self.recovery && !self.source.starts_with("=")? {
+        let message = format!("attribute {} has no value", attr_name);
+        self.recoverable(attr_location.clone(), ErrorCode::MalformedAttribute, &message)?;
+        separated = space;
+        String::new()
+      } else {
+        self.expect("=", ErrorCode::MalformedAttribute)?;
+        self.skip_whitespace()?;
+        self.attribute_value()?
+      };
+      if duplicated {
+        continue;
+      }
+      attributes.push(Attribute {
+        location: attr_location,
+        end: self.location(),
+        name: attr_name,
+        namespace_uri: None,
+        local_name: None,
+        value,
+        specified: true,
+      });
+    }
+  }
+
+  /// Normalizes the values of the attributes declared with types other than CDATA, and adds the attributes that the
+  /// start tag of `element` omits but are declared with default values.
+  fn default_attributes(&self, location: &Location, element: &str, attributes: &mut Vec<Attribute>) {
+    let definitions = match self.dtd.attlists.get(element) {
+      Some(definitions) => definitions,
+      None => return,
+    };
+    for attr in attributes.iter_mut() {
+      if let Some(def) = definitions.iter().find(|def| def.name == attr.name) {
+        if def.att_type != AttType::CData {
+          attr.value = collapse_spaces(&attr.value);
+        }
+      }
+    }
+    for def in definitions {
+      if let Some(value) = def.default.value() {
+        if !attributes.iter().any(|attr| attr.name == def.name) {
+          attributes.push(Attribute {
+            location: location.clone(),
+            end: location.clone(),
+            name: def.name.clone(),
+            namespace_uri: None,
+            local_name: None,
+            value: value.to_string(),
+            specified: false,
+          });
+        }
+      }
+    }
+  }
+
+  /// Opens the element of the start tag that has been read up to `>`.
+  fn start_element(&mut self, location: Location, name: String, mut attributes: Vec<Attribute>) -> Result<Event> {
+    self.default_attributes(&location, &name, &mut attributes);
+    let (namespace_uri, local_name) = self.start_scope(&location, &name, &mut attributes)?;
+    self.elements.push(name.clone());
+    let start = EventKind::StartElement { name, namespace_uri, local_name, attributes };
+    Ok(Event::new(location, start))
+  }
+
+  /// `[42] ETag ::= '</' Name S? '>'`
+  fn end_tag(&mut self) -> Result<Event> {
+    let location = self.location();
+    self.expect("</", ErrorCode::MalformedEndTag)?;
+    let name = self.name()?;
+    self.skip_whitespace()?;
+    if self.recovery && !self.source.starts_with(">")? {
+      let message = format!("end tag </{}> is not closed", name);
+      self.recoverable(self.location(), ErrorCode::MalformedEndTag, &message)?;
+    } else {
+      self.expect(">", ErrorCode::MalformedEndTag)?;
+    }
+    if self.entity_elements.last() == Some(&self.elements.len()) {
+      let message = format!("end tag </{}> in entity closes the element that starts outside of it", name);
+      return self.error(location, ErrorCode::UnbalancedEntity, &message);
+    }
+    match self.elements.last() {
+      Some(start) if *start == name => (),
+      Some(start) => {
+        let message = format!("end tag </{}> does not match start tag <{}>", name, start);
+        let open = &self.elements[self.entity_elements.last().copied().unwrap_or(0)..];
+        let closing = open.contains(&name);
+        self.recoverable(location.clone(), ErrorCode::ElementTypeMatch, &message)?;
+        if !closing {
+          return self.event();
+        }
+        self.closing = Some((name, location));
+        return self.implied_end_tag();
+      }
+      None => return self.error(location, ErrorCode::ElementTypeMatch, &format!("unexpected end tag </{}>", name)),
+    }
+    self.close_element(location)
+  }
+
+  /// Closes the innermost element whose end tag is missing in recovery mode, until the element of the end tag in
+  /// `closing` is closed.
+  fn implied_end_tag(&mut self) -> Result<Event> {
+    let (name, location) = self.closing.take().expect("the end tag being closed must be set");
+    if self.elements.last() != Some(&name) {
+      self.closing = Some((name, location.clone()));
+    }
+    self.close_element(location)
+  }
+
+  /// Closes the innermost element.
+  fn close_element(&mut self, location: Location) -> Result<Event> {
+    let name = self.elements.pop().unwrap_or_default();
+    let (namespace_uri, local_name) = self.element_name(&location, &name)?;
+    self.namespaces.pop_scope();
+    if self.elements.is_empty() && !self.fragment {
+      self.state = State::Epilog;
+    }
+    Ok(Event::new(location, EventKind::EndElement { name, namespace_uri, local_name }))
+  }
+
+  /// Opens the namespace scope of a start tag with the namespace declarations in `attributes`, and resolves the names
+  /// of the element and attributes. The namespace URI and local name of the element are returned.
+  fn start_scope(
+    &mut self, location: &Location, name: &str, attributes: &mut [Attribute],
+  ) -> Result<(Option<String>, Option<String>)> {
+    if !self.namespace_aware {
+      return Ok((None, None));
+    }
+    self.namespaces.push_scope();
+    let mut names = Vec::with_capacity(attributes.len());
+    for attr in attributes.iter_mut() {
+      let (prefix, local_name) = self.split_qname(&attr.location, &attr.name)?;
+      let declared = match prefix {
+        None if local_name == "xmlns" => Some(""),
+        Some("xmlns") => Some(local_name),
+        _ => None,
+      };
+      if let Some(declared) = declared {
+        self.check_declaration(&attr.location, declared, &attr.value)?;
+        let namespace_uri = Some(attr.value.as_str()).filter(|uri| !uri.is_empty());
+        self.namespaces.declare(declared, namespace_uri);
+        attr.namespace_uri = Some(XMLNS_NAMESPACE_URI.to_string());
+      }
+      attr.local_name = Some(local_name.to_string());
+      names.push(prefix.map(|prefix| prefix.to_string()));
+    }
+    for (attr, prefix) in attributes.iter_mut().zip(names) {
+      match prefix.as_deref() {
+        None | Some("xmlns") => (),
+        Some(prefix) => match self.namespaces.resolve(prefix) {
+          Some(namespace_uri) => attr.namespace_uri = Some(namespace_uri.to_string()),
+          None => {
+            let message = format!("namespace prefix {} of attribute {} is not declared", prefix, attr.name);
+            return self.error(attr.location.clone(), ErrorCode::PrefixDeclared, &message);
+          }
+        },
+      }
+    }
+    for (i, attr) in attributes.iter().enumerate() {
+      let duplicated = attributes[..i].iter().any(|other| {
+        attr.namespace_uri.is_some()
+          && (&other.namespace_uri, &other.local_name) == (&attr.namespace_uri, &attr.local_name)
+      });
+      if duplicated {
+        let message = format!("attribute {} has the same expanded name as another attribute", attr.name);
+        return self.error(attr.location.clone(), ErrorCode::AttributesUnique, &message);
+      }
+    }
+    self.element_name(location, name)
+  }
+
+  /// Resolves the name of an element with the namespace declarations in scope.
+  fn element_name(&self, location: &Location, name: &str) -> Result<(Option<String>, Option<String>)> {
+    if !self.namespace_aware {
+      return Ok((None, None));
+    }
+    let (prefix, local_name) = self.split_qname(location, name)?;
+    if prefix == Some("xmlns") {
+      let message = format!("element name {} must not have the prefix xmlns", name);
+      return self.error(location.clone(), ErrorCode::ReservedPrefix, &message);
+    }
+    let namespace_uri = self.namespaces.resolve(prefix.unwrap_or_default());
+    if let (Some(prefix), None) = (prefix, namespace_uri) {
+      let message = format!("namespace prefix {} of element {} is not declared", prefix, name);
+      return self.error(location.clone(), ErrorCode::PrefixDeclared, &message);
+    }
+    Ok((namespace_uri.map(|uri| uri.to_string()), Some(local_name.to_string())))
+  }
+
+  /// Checks the constraints on the reserved prefixes and namespace names for the declaration that binds the `prefix`
+  /// (or the default namespace if empty) to the `namespace_uri`. An empty `namespace_uri` undeclares the prefix,
+  /// which is allowed only in XML 1.1.
+  fn check_declaration(&self, location: &Location, prefix: &str, namespace_uri: &str) -> Result<()> {
+    let (code, message) = match prefix {
+      "xmlns" => (ErrorCode::ReservedPrefix, "the prefix xmlns must not be declared".to_string()),
+      "xml" if namespace_uri == XML_NAMESPACE_URI => return Ok(()),
+      "xml" => (ErrorCode::ReservedPrefix, format!("the prefix xml must not be bound to {}", namespace_uri)),
+      _ if namespace_uri == XML_NAMESPACE_URI || namespace_uri == XMLNS_NAMESPACE_URI => {
+        (ErrorCode::ReservedPrefix, format!("the namespace name {} must not be bound to other prefixes", namespace_uri))
+      }
+      _ if !prefix.is_empty() && namespace_uri.is_empty() && !self.source.xml11() => {
+        (ErrorCode::NoPrefixUndeclaring, format!("the prefix {} must not be undeclared", prefix))
+      }
+      _ => return Ok(()),
+    };
+    self.error(location.clone(), code, &message)
+  }
+
+  /// Splits `[7] QName ::= PrefixedName | UnprefixedName` into the prefix and the local part.
+  fn split_qname<'a>(&self, location: &Location, name: &'a str) -> Result<(Option<&'a str>, &'a str)> {
+    match name.split_once(':') {
+      None => Ok((None, name)),
+      Some((prefix, local_name))
+        if !prefix.is_empty()
+          && local_name.chars().next().map(is_name_start_char).unwrap_or(false)
+          && !local_name.contains(':') =>
+      {
+        Ok((Some(prefix), local_name))
+      }
+      Some(_) => self.error(location.clone(), ErrorCode::MalformedQName, &format!("illegal qualified name: {}", name)),
+    }
+  }
+
+  /// Reads the text, the CDATA section or the comment at the cursor as the event of `location`.
+  fn character_data(&mut self, kind: CharData, location: Location) -> Result<Event> {
+    match kind {
+      CharData::Text => (),
+      CharData::CData => self.expect("<![CDATA[", ErrorCode::MalformedCDSect)?,
+      CharData::Comment => self.expect("<!--", ErrorCode::MalformedComment)?,
+    }
+    self.continue_character_data(Partial { kind, location, text: String::new() })
+  }
+
+  /// Reads the rest of the `partial` event. If the reader runs out of characters, the event is kept with the
+  /// characters read so far.
+  fn continue_character_data(&mut self, partial: Partial) -> Result<Event> {
+    let Partial { kind, location, mut text } = partial;
+    let result = match kind {
+      CharData::Text => self.read_text(&mut text),
+      CharData::CData => self.read_cdata(&location, &mut text),
+      CharData::Comment => self.read_comment(&location, &mut text),
+    };
+    match result {
+      Ok(()) => {
+        let kind = match kind {
+          CharData::Text => EventKind::Text(text),
+          CharData::CData => EventKind::CDATA(text),
+          CharData::Comment => EventKind::Comment(text),
+        };
+        Ok(Event::new(location, kind))
+      }
+      Err(Error::IO(err)) if err.kind() == ErrorKind::WouldBlock => {
+        self.partial = Some(Partial { kind, location, text });
+        Err(Error::IO(err))
+      }
+      Err(err) => Err(err),
+    }
+  }
+
+  /// `[14] CharData ::= [^<&]* - ([^<&]* ']]>' [^<&]*)` with references replaced, appended to `text`. The cursor
+  /// doesn't stop in the middle of a reference, so that the text can be continued when the reader runs out of
+  /// characters.
+  fn read_text(&mut self, text: &mut String) -> Result<()> {
+    loop {
+      self.source.read_until_any(b"<&]", text)?;
+      if self.source.starts_with("]]>")? {
+        return self.error(
+          self.location(),
+          ErrorCode::CDataSectionEndInContent,
+          "']]>' is not allowed in character data",
+        );
+      }
+      if self.source.skip_if("]")? {
+        text.push(']');
+        continue;
+      }
+      if self.unescaped_ampersand(text)? {
+        continue;
+      }
+      if !self.source.starts_with("&")? || self.at_entity_reference()? {
+        return Ok(());
+      }
+      if !self.source.starts_with("&#")? {
+        self.reference(text)?;
+        continue;
+      }
+      let (checkpoint, len, diagnostics) = (self.source.checkpoint(), text.len(), self.diagnostics.len());
+      if let Err(err) = self.reference(text) {
+        if matches!(&err, Error::IO(err) if err.kind() == ErrorKind::WouldBlock) {
+          self.source.rewind(checkpoint);
+          text.truncate(len);
+          self.diagnostics.truncate(diagnostics);
+        }
+        return Err(err);
+      }
+    }
+  }
+
+  /// `[15] Comment ::= '<!--' ((Char - '-') | ('-' (Char - '-')))* '-->'`
+  fn comment(&mut self) -> Result<Event> {
+    let location = self.location();
+    self.expect("<!--", ErrorCode::MalformedComment)?;
+    let mut text = String::new();
+    self.read_comment(&location, &mut text)?;
+    Ok(Event::new(location, EventKind::Comment(text)))
+  }
+
+  /// Reads the comment of `location` after `<!--` up to `-->`, appending its text to `text`.
+  fn read_comment(&mut self, location: &Location, text: &mut String) -> Result<()> {
+    loop {
+      self.source.read_until_any(b"-", text)?;
+      if self.source.skip_if("-->")? {
+        return Ok(());
+      }
+      if self.source.starts_with("--")? {
+        let mut hyphens = location.clone();
+        source::forward(&mut hyphens, "<!--");
+        source::forward(&mut hyphens, text);
+        return self.error(hyphens, ErrorCode::DoubleHyphenInComment, "'--' is not allowed in comment");
+      }
+      if !self.source.skip_if("-")? {
+        return self.error(location.clone(), ErrorCode::MalformedComment, "comment is not terminated");
+      }
+      text.push('-');
+    }
+  }
+
+  /// `[16] PI ::= '<?' PITarget (S (Char* - (Char* '?>' Char*)))? '?>'`
+  fn pi(&mut self) -> Result<Event> {
+    let location = self.location();
+    self.expect("<?", ErrorCode::MalformedPI)?;
+    let target_location = self.location();
+    let target = self.name()?;
+    if target.eq_ignore_ascii_case("xml") {
+      let message = format!("processing instruction target {:?} is reserved", target);
+      return self.error(target_location, ErrorCode::ReservedPITarget, &message);
+    }
+    if self.source.skip_if("?>")? {
+      return Ok(Event::new(location, EventKind::ProcessingInstruction { target, data: None }));
+    }
+    if !self.skip_whitespace()? {
+      let message = "whitespace is required after processing instruction target";
+      return self.error(self.location(), ErrorCode::MalformedPI, message);
+    }
+    let mut data = String::new();
+    if !self.source.read_until("?>", &mut data)? {
+      return self.error(location, ErrorCode::MalformedPI, "processing instruction is not terminated");
+    }
+    Ok(Event::new(location, EventKind::ProcessingInstruction { target, data: Some(data) }))
+  }
+
+  /// `[18] CDSect ::= CDStart CData CDEnd` after `CDStart`, appending the `CData` to `text`.
+  fn read_cdata(&mut self, location: &Location, text: &mut String) -> Result<()> {
+    loop {
+      self.source.read_until_any(b"]", text)?;
+      if self.source.skip_if("]]>")? {
+        return Ok(());
+      }
+      if !self.source.skip_if("]")? {
+        return self.error(location.clone(), ErrorCode::MalformedCDSect, "CDATA section is not terminated");
+      }
+      text.push(']');
+    }
+  }
+
+  /// `[28] doctypedecl ::= '<!DOCTYPE' S Name (S ExternalID)? S? ('[' intSubset ']' S?)? '>'`
+  fn doctype(&mut self) -> Result<Event> {
+    let location = self.location();
+    self.expect("<!DOCTYPE", ErrorCode::MalformedDoctypeDecl)?;
+    self.expect_whitespace(ErrorCode::MalformedDoctypeDecl)?;
+    let name = self.name()?;
+    let space = self.skip_whitespace()?;
+    let (mut public_id, mut system_id) = (None, None);
+    if space {
+      if let Some((public, system)) = self.external_id(ErrorCode::MalformedDoctypeDecl, false)? {
+        (public_id, system_id) = (public, system);
+        self.skip_whitespace()?;
+      }
+    }
+    let mut internal_subset = None;
+    let mut declarations = Vec::new();
+    if self.source.skip_if("[")? {
+      internal_subset = Some(self.internal_subset(&mut declarations)?);
+      self.skip_whitespace()?;
+    }
+    self.expect(">", ErrorCode::MalformedDoctypeDecl)?;
+    let base_uri = self.source.system_id();
+    let external_subset = match &system_id {
+      Some(system_id) => self.resolver.resolve_entity("[dtd]", public_id.as_deref(), base_uri, system_id)?,
+      None => self.resolver.get_external_subset(&name, base_uri)?,
+    };
+    match external_subset {
+      Some(input) => self.external_subset(&location, input, &mut declarations)?,
+      None => {
+        if let Some(system_id) = &system_id {
+          let message = format!("external subset {:?} is not read", system_id);
+          self.warning(location.clone(), ErrorCode::ExternalSubsetNotRead, &message);
+          self.dtd.partial = true;
+        }
+      }
+    }
+    let doctype = EventKind::DocType { name, public_id, system_id, internal_subset, declarations };
+    Ok(Event::new(location, doctype))
+  }
+
+  /// `[28b] intSubset ::= (markupdecl | DeclSep)*` up to the closing `]`. The markup declarations are appended to
+  /// `declarations`, and the raw text of the internal subset is returned.
+  fn internal_subset(&mut self, declarations: &mut Vec<MarkupDecl>) -> Result<String> {
+    let mark = self.source.mark();
+    self.dtd_declarations(declarations, "]")?;
+    let subset = self.source.text_since(mark);
+    Ok(subset[..subset.len() - 1].to_string())
+  }
+
+  /// `[30] extSubset ::= TextDecl? extSubsetDecl` read from the `input` that the resolver returned. The markup
+  /// declarations are appended to `declarations`.
+  fn external_subset(
+    &mut self, location: &Location, input: InputSource, declarations: &mut Vec<MarkupDecl>,
+  ) -> Result<()> {
+    self.push_external(location, "[dtd]", input, false)?;
+    self.dtd_declarations(declarations, "")?;
+    self.source.pop_entity();
+    Ok(())
+  }
+
+  /// `[28b] intSubset`, `[31] extSubsetDecl` or the content of `[62] includeSect` up to the `end` delimiter in the
+  /// current entity. The `end` is empty for the external subset that continues to the end of the entity.
+  fn dtd_declarations(&mut self, declarations: &mut Vec<MarkupDecl>, end: &str) -> Result<()> {
+    let location = self.location();
+    let depth = self.source.entity_depth();
+    let (code, production) = match end {
+      "]" => (ErrorCode::MalformedIntSubset, "internal subset"),
+      "" => (ErrorCode::MalformedExtSubset, "external subset"),
+      _ => (ErrorCode::MalformedConditionalSect, "conditional section"),
+    };
+    loop {
+      self.skip_whitespace()?;
+      if self.source.is_eof()? {
+        if self.source.entity_depth() > depth {
+          self.source.pop_entity();
+          continue;
+        } else if end.is_empty() {
+          return Ok(());
+        }
+        return self.error(location, code, &format!("{} is not terminated", production));
+      }
+      if !end.is_empty() && self.source.entity_depth() == depth && self.source.skip_if(end)? {
+        return Ok(());
+      }
+      let decl = if self.source.starts_with("%")? {
+        self.parameter_entity_reference(false)?;
+        continue;
+      } else if self.source.starts_with("<![")? {
+        if !self.source.in_external_entity() {
+          let message = "conditional section is not allowed in the internal subset";
+          return self.error(self.location(), code, message);
+        }
+        self.conditional_section(declarations)?;
+        continue;
+      } else if self.source.starts_with("<!--")? {
+        match self.comment()?.kind {
+          EventKind::Comment(text) => MarkupDecl::Comment(text),
+          _ => unreachable!(),
+        }
+      } else if self.source.starts_with("<?")? {
+        match self.pi()?.kind {
+          EventKind::ProcessingInstruction { target, data } => MarkupDecl::ProcessingInstruction { target, data },
+          _ => unreachable!(),
+        }
+      } else if self.source.starts_with("<!ELEMENT")? {
+        self.element_decl()?
+      } else if self.source.starts_with("<!ATTLIST")? {
+        match self.attlist_decl()? {
+          Some(decl) => decl,
+          None => continue,
+        }
+      } else if self.source.starts_with("<!ENTITY")? {
+        match self.entity_decl()? {
+          Some(decl) => decl,
+          None => continue,
+        }
+      } else if self.source.starts_with("<!NOTATION")? {
+        self.notation_decl()?
+      } else {
+        let message = format!("markup declaration or parameter entity reference expected in {}", production);
+        return self.error(self.location(), code, &message);
+      };
+      declarations.push(decl);
+    }
+  }
+
+  /// `[61] conditionalSect ::= includeSect | ignoreSect`. The declarations in `[62] includeSect` are appended to
+  /// `declarations`, and `[63] ignoreSect` is skipped with the nested sections in it.
+  fn conditional_section(&mut self, declarations: &mut Vec<MarkupDecl>) -> Result<()> {
+    let code = ErrorCode::MalformedConditionalSect;
+    let location = self.location();
+    self.expect("<![", code)?;
+    self.skip_decl_whitespace()?;
+    if self.source.skip_if("INCLUDE")? {
+      self.skip_decl_whitespace()?;
+      self.expect("[", code)?;
+      return self.dtd_declarations(declarations, "]]>");
+    } else if !self.source.skip_if("IGNORE")? {
+      return self.error(self.location(), code, "INCLUDE or IGNORE expected");
+    }
+    self.skip_decl_whitespace()?;
+    self.expect("[", code)?;
+    let mut nest = 1;
+    while nest > 0 {
+      if self.source.skip_if("<![")? {
+        nest += 1;
+      } else if self.source.skip_if("]]>")? {
+        nest -= 1;
+      } else if self.source.next_char()?.is_none() {
+        return self.error(location, code, "conditional section is not terminated");
+      }
+    }
+    Ok(())
+  }
+
+  /// `[69] PEReference ::= '%' Name ';'` in the DTD. The replacement text of the parameter entity is read as a part
+  /// of the DTD; if the reference appears `in_decl`, that is, within a markup declaration, the text is enlarged by a
+  /// space on each side and read through to the rest of the declaration. An undeclared parameter entity or an
+  /// external one that the resolver refused is not read, and the declarations after it are ignored unless the
+  /// document is standalone.
+  fn parameter_entity_reference(&mut self, in_decl: bool) -> Result<()> {
+    let location = self.location();
+    self.expect("%", ErrorCode::MalformedPEReference)?;
+    let name = self.name()?;
+    if !self.source.skip_if(";")? {
+      let message = format!("parameter entity reference %{} is not terminated by ';'", name);
+      return self.error(location, ErrorCode::MalformedPEReference, &message);
+    }
+    self.dtd.partial = true;
+    let reference = format!("%{}", name);
+    let input = match self.dtd.parameter_entities.get(&name).cloned() {
+      Some(EntityDecl { value: Some(value), .. }) if in_decl => {
+        return self.push_entity(&location, &reference, &format!(" {} ", value), true);
+      }
+      Some(EntityDecl { value: Some(value), .. }) => return self.push_entity(&location, &reference, &value, false),
+      Some(decl) => self.resolve_external(&reference, &decl)?,
+      None if self.standalone == Some(true) => {
+        return self.error(location, ErrorCode::EntityDeclared, &format!("undeclared parameter entity: %{};", name))
+      }
+      None => None,
+    };
+    match input {
+      Some(input) => self.push_external(&location, &reference, input, in_decl),
+      None => {
+        let message = format!("parameter entity %{}; is not read", name);
+        self.warning(location, ErrorCode::EntityNotRead, &message);
+        self.dtd.ignore_declarations = self.standalone != Some(true);
+        if in_decl {
+          self.source.push_entity(&reference, " ", true);
+        }
+        Ok(())
+      }
+    }
+  }
+
+  /// `[45] elementdecl ::= '<!ELEMENT' S Name S contentspec S? '>'`
+  fn element_decl(&mut self) -> Result<MarkupDecl> {
+    let code = ErrorCode::MalformedElementDecl;
+    self.expect("<!ELEMENT", code)?;
+    self.decl_whitespace(code)?;
+    let name = self.name()?;
+    self.decl_whitespace(code)?;
+    let content_spec = if self.source.skip_if("EMPTY")? {
+      ContentSpec::Empty
+    } else if self.source.skip_if("ANY")? {
+      ContentSpec::Any
+    } else {
+      self.expect("(", code)?;
+      self.skip_decl_whitespace()?;
+      if self.source.skip_if("#PCDATA")? {
+        self.mixed()?
+      } else {
+        let particle = self.group()?;
+        ContentSpec::Children(ContentParticle { particle, occurrence: self.occurrence()? })
+      }
+    };
+    self.skip_decl_whitespace()?;
+    self.expect(">", code)?;
+    Ok(MarkupDecl::Element { name, content_spec })
+  }
+
+  /// `[51] Mixed ::= '(' S? '#PCDATA' (S? '|' S? Name)* S? ')*' | '(' S? '#PCDATA' S? ')'` after `#PCDATA`.
+  fn mixed(&mut self) -> Result<ContentSpec> {
+    let code = ErrorCode::MalformedElementDecl;
+    let mut names = Vec::new();
+    loop {
+      self.skip_decl_whitespace()?;
+      if self.source.skip_if(")")? {
+        if names.is_empty() {
+          self.source.skip_if("*")?;
+        } else {
+          self.expect("*", code)?;
+        }
+        return Ok(ContentSpec::Mixed(names));
+      }
+      self.expect("|", code)?;
+      self.skip_decl_whitespace()?;
+      names.push(self.name()?);
+    }
+  }
+
+  /// `[49] choice` or `[50] seq` after the opening `(`.
+  fn group(&mut self) -> Result<Particle> {
+    let code = ErrorCode::MalformedElementDecl;
+    let mut cps = vec![self.content_particle()?];
+    let mut separator = None;
+    loop {
+      self.skip_decl_whitespace()?;
+      if self.source.skip_if(")")? {
+        break;
+      }
+      let location = self.location();
+      match (self.source.next_char()?, separator) {
+        (Some(ch @ ('|' | ',')), None) => separator = Some(ch),
+        (Some(ch), Some(sep)) if ch == sep => (),
+        _ => return self.error(location, code, "'|', ',' or ')' expected in content model"),
+      }
+      self.skip_decl_whitespace()?;
+      cps.push(self.content_particle()?);
+    }
+    Ok(if separator == Some('|') { Particle::Choice(cps) } else { Particle::Seq(cps) })
+  }
+
+  /// `[48] cp ::= (Name | choice | seq) ('?' | '*' | '+')?`
+  fn content_particle(&mut self) -> Result<ContentParticle> {
+    let particle = if self.source.skip_if("(")? {
+      self.skip_decl_whitespace()?;
+      self.group()?
+    } else {
+      Particle::Name(self.name()?)
+    };
+    Ok(ContentParticle { particle, occurrence: self.occurrence()? })
+  }
+
+  fn occurrence(&mut self) -> Result<Occurrence> {
+    Ok(if self.source.skip_if("?")? {
+      Occurrence::Optional
+    } else if self.source.skip_if("*")? {
+      Occurrence::ZeroOrMore
+    } else if self.source.skip_if("+")? {
+      Occurrence::OneOrMore
+    } else {
+      Occurrence::Once
+    })
+  }
+
+  /// `[52] AttlistDecl ::= '<!ATTLIST' S Name AttDef* S? '>'`. `None` is returned if the declaration is ignored.
+  fn attlist_decl(&mut self) -> Result<Option<MarkupDecl>> {
+    let code = ErrorCode::MalformedAttlistDecl;
+    self.expect("<!ATTLIST", code)?;
+    self.decl_whitespace(code)?;
+    let element = self.name()?;
+    let mut definitions = Vec::new();
+    loop {
+      let space = self.skip_decl_whitespace()?;
+      if self.source.skip_if(">")? {
+        break;
+      }
+      if !space {
+        return self.error(self.location(), code, "whitespace expected");
+      }
+      let name = self.name()?;
+      self.decl_whitespace(code)?;
+      let att_type = self.att_type()?;
+      self.decl_whitespace(code)?;
+      let default = if self.source.skip_if("#REQUIRED")? {
+        DefaultDecl::Required
+      } else if self.source.skip_if("#IMPLIED")? {
+        DefaultDecl::Implied
+      } else {
+        let fixed = self.source.skip_if("#FIXED")?;
+        if fixed {
+          self.decl_whitespace(code)?;
+        }
+        let mut value = self.attribute_value()?;
+        if att_type != AttType::CData {
+          value = collapse_spaces(&value);
+        }
+        if fixed {
+          DefaultDecl::Fixed(value)
+        } else {
+          DefaultDecl::Value(value)
+        }
+      };
+      definitions.push(AttDef { name, att_type, default });
+    }
+    let definitions = self.dtd.declare_attributes(&element, definitions);
+    Ok(if definitions.is_empty() { None } else { Some(MarkupDecl::Attlist { element, definitions }) })
+  }
+
+  /// `[54] AttType ::= StringType | TokenizedType | EnumeratedType`
+  fn att_type(&mut self) -> Result<AttType> {
+    let code = ErrorCode::MalformedAttlistDecl;
+    for (keyword, att_type) in [
+      ("CDATA", AttType::CData),
+      ("IDREFS", AttType::IdRefs),
+      ("IDREF", AttType::IdRef),
+      ("ID", AttType::Id),
+      ("ENTITY", AttType::Entity),
+      ("ENTITIES", AttType::Entities),
+      ("NMTOKENS", AttType::NmTokens),
+      ("NMTOKEN", AttType::NmToken),
+    ] {
+      if self.source.starts_with(keyword)? && !self.source.peek_nth(keyword.len())?.map(is_name_char).unwrap_or(false) {
+        self.source.skip_if(keyword)?;
+        return Ok(att_type);
+      }
+    }
+    let notation = self.source.skip_if("NOTATION")?;
+    if notation {
+      self.decl_whitespace(code)?;
+    }
+    self.expect("(", code)?;
+    let mut tokens = Vec::new();
+    loop {
+      self.skip_decl_whitespace()?;
+      let location = self.location();
+      let mut token = String::new();
+      if notation {
+        token = self.name()?;
+      } else if self.source.read_while(is_name_char, &mut token)? == 0 {
+        return self.error(location, code, "name token expected");
+      }
+      tokens.push(token);
+      self.skip_decl_whitespace()?;
+      if self.source.skip_if(")")? {
+        break;
+      }
+      self.expect("|", code)?;
+    }
+    Ok(if notation { AttType::Notation(tokens) } else { AttType::Enumeration(tokens) })
+  }
+
+  /// `[70] EntityDecl ::= GEDecl | PEDecl`. `None` is returned if the declaration is ignored.
+  fn entity_decl(&mut self) -> Result<Option<MarkupDecl>> {
+    let code = ErrorCode::MalformedEntityDecl;
+    self.expect("<!ENTITY", code)?;
+    self.decl_whitespace(code)?;
+    let parameter = self.source.skip_if("%")?;
+    if parameter {
+      self.decl_whitespace(code)?;
+    }
+    let name = self.name()?;
+    self.decl_whitespace(code)?;
+    let base_uri = self.source.system_id().map(|id| id.to_string());
+    let mut decl =
+      EntityDecl { name, parameter, value: None, public_id: None, system_id: None, notation_name: None, base_uri };
+    if matches!(self.source.peek()?, Some('"' | '\'')) {
+      decl.value = Some(self.entity_value()?);
+    } else {
+      match self.external_id(code, false)? {
+        Some((public_id, system_id)) => (decl.public_id, decl.system_id) = (public_id, system_id),
+        None => return self.error(self.location(), code, "entity value or external identifier expected"),
+      }
+      let space = self.skip_decl_whitespace()?;
+      if !parameter && space && self.source.skip_if("NDATA")? {
+        self.decl_whitespace(code)?;
+        decl.notation_name = Some(self.name()?);
+      }
+    }
+    self.skip_decl_whitespace()?;
+    self.expect(">", code)?;
+    let predefined =
+      !parameter && PREDEFINED_ENTITIES.iter().any(|(reference, _)| reference[1..reference.len() - 1] == decl.name);
+    Ok(if !predefined && self.dtd.declare_entity(decl.clone()) { Some(MarkupDecl::Entity(decl)) } else { None })
+  }
+
+  /// `[9] EntityValue` with character references and parameter entity references replaced. General entity
+  /// references are left as they are.
+  fn entity_value(&mut self) -> Result<String> {
+    let code = ErrorCode::MalformedEntityDecl;
+    let location = self.location();
+    let quote = self.source.next_char()?;
+    let mut value = String::new();
+    let depth = self.source.entity_depth();
+    loop {
+      match self.source.peek()? {
+        None if self.source.entity_depth() > depth => {
+          self.source.pop_entity();
+        }
+        None => return self.error(location, code, "entity value is not terminated"),
+        ch if ch == quote && self.source.entity_depth() == depth => {
+          self.source.next_char()?;
+          return Ok(value);
+        }
+        Some('%') if !self.source.in_external_entity() => {
+          let message = "parameter entity reference is not allowed in markup declarations in the internal subset";
+          return self.error(self.location(), ErrorCode::PEsInInternalSubset, message);
+        }
+        Some('%') => self.parameter_entity_reference(false)?,
+        Some('&') if self.source.starts_with("&#")? => self.reference(&mut value)?,
+        Some('&') => {
+          let reference_location = self.location();
+          self.source.next_char()?;
+          let name = self.name()?;
+          if !self.source.skip_if(";")? {
+            let message = format!("entity reference &{} is not terminated by ';'", name);
+            return self.error(reference_location, ErrorCode::MalformedEntityRef, &message);
+          }
+          value.push('&');
+          value.push_str(&name);
+          value.push(';');
+        }
+        Some(ch) => {
+          self.source.next_char()?;
+          value.push(ch);
+        }
+      }
+    }
+  }
+
+  /// `[82] NotationDecl ::= '<!NOTATION' S Name S (ExternalID | PublicID) S? '>'`
+  fn notation_decl(&mut self) -> Result<MarkupDecl> {
+    let code = ErrorCode::MalformedNotationDecl;
+    self.expect("<!NOTATION", code)?;
+    self.decl_whitespace(code)?;
+    let name = self.name()?;
+    self.decl_whitespace(code)?;
+    let (public_id, system_id) = match self.external_id(code, true)? {
+      Some(ids) => ids,
+      None => return self.error(self.location(), code, "external or public identifier expected"),
+    };
+    self.skip_decl_whitespace()?;
+    self.expect(">", code)?;
+    Ok(MarkupDecl::Notation { name, public_id, system_id })
+  }
+
+  /// `[75] ExternalID ::= 'SYSTEM' S SystemLiteral | 'PUBLIC' S PubidLiteral S SystemLiteral`, or also
+  /// `[83] PublicID ::= 'PUBLIC' S PubidLiteral` if `public_only` is `true`. `None` is returned if neither keyword
+  /// appears.
+  fn external_id(&mut self, code: ErrorCode, public_only: bool) -> Result<Option<(Option<String>, Option<String>)>> {
+    if self.source.skip_if("SYSTEM")? {
+      self.expect_whitespace(code)?;
+      let system_id = self.quoted_literal(ErrorCode::MalformedSystemLiteral)?;
+      Ok(Some((None, Some(system_id))))
+    } else if self.source.skip_if("PUBLIC")? {
+      self.expect_whitespace(code)?;
+      let pubid_location = self.location();
+      let public_id = self.quoted_literal(ErrorCode::MalformedPubidLiteral)?;
+      if let Some(ch) = public_id.chars().find(|ch| !is_pubid_char(*ch)) {
+        let message = format!("illegal character in public identifier: {:?}", ch);
+        return self.error(pubid_location, ErrorCode::MalformedPubidLiteral, &message);
+      }
+      let space = self.skip_whitespace()?;
+      if public_only && !matches!(self.source.peek()?, Some('"' | '\'')) {
+        return Ok(Some((Some(public_id), None)));
+      }
+      if !space {
+        return self.error(self.location(), code, "whitespace expected");
+      }
+      let system_id = self.quoted_literal(ErrorCode::MalformedSystemLiteral)?;
+      Ok(Some((Some(public_id), Some(system_id))))
+    } else {
+      Ok(None)
+    }
+  }
+
+  /// Skips `[3] S` in a markup declaration and returns whether any whitespace was skipped. A parameter entity
+  /// reference that follows is expanded in an external entity, and rejected in the internal subset.
+  fn skip_decl_whitespace(&mut self) -> Result<bool> {
+    let mut space = self.skip_whitespace()?;
+    while self.source.starts_with("%")? && self.source.peek_nth(1)?.map(is_name_start_char).unwrap_or(false) {
+      if !self.source.in_external_entity() {
+        let message = "parameter entity reference is not allowed in markup declarations in the internal subset";
+        return self.error(self.location(), ErrorCode::PEsInInternalSubset, message);
+      }
+      self.parameter_entity_reference(true)?;
+      space |= self.skip_whitespace()?;
+    }
+    Ok(space)
+  }
+
+  /// Skips `[3] S` that the markup declaration identified by `code` requires.
+  fn decl_whitespace(&mut self, code: ErrorCode) -> Result<()> {
+    if !self.skip_decl_whitespace()? {
+      return self.error(self.location(), code, "whitespace expected");
+    }
+    Ok(())
+  }
+
+  /// `[67] Reference ::= EntityRef | CharRef` that is a character reference or a reference to a predefined entity,
+  /// whose character is appended to `out`.
+  fn reference(&mut self, out: &mut String) -> Result<()> {
+    let location = self.location();
+    if let Some((reference, ch)) = self.predefined_entity()? {
+      self.source.skip_if(reference)?;
+      out.push(ch);
+      return Ok(());
+    }
+    self.expect("&#", ErrorCode::MalformedCharRef)?;
+    let hex = self.source.skip_if("x")?;
+    let mut digits = String::new();
+    if hex {
+      self.source.read_while(|ch| ch.is_ascii_hexdigit(), &mut digits)?;
+    } else {
+      self.source.read_while(|ch| ch.is_ascii_digit(), &mut digits)?;
+    }
+    if digits.is_empty() || !self.source.skip_if(";")? {
+      return self.error(location, ErrorCode::MalformedCharRef, "malformed character reference");
+    }
+    let code = u32::from_str_radix(&digits, if hex { 16 } else { 10 }).ok();
+    let legal = if self.source.xml11() { is_char11 } else { is_char };
+    match code.and_then(char::from_u32).filter(|ch| legal(*ch)) {
+      Some(ch) => out.push(ch),
+      None => {
+        let message = format!("illegal character reference: &#{}{};", if hex { "x" } else { "" }, digits);
+        self.recoverable(location, ErrorCode::LegalCharacter, &message)?;
+      }
+    }
+    Ok(())
+  }
+
+  /// Returns the reference to the predefined entity and its character if the cursor is positioned at it.
+  fn predefined_entity(&mut self) -> Result<Option<(&'static str, char)>> {
+    for (reference, ch) in PREDEFINED_ENTITIES {
+      if self.source.starts_with(reference)? {
+        return Ok(Some((reference, ch)));
+      }
+    }
+    Ok(None)
+  }
+
+  /// Whether the cursor is positioned at `[68] EntityRef` other than the predefined entities.
+  fn at_entity_reference(&mut self) -> Result<bool> {
+    Ok(self.source.starts_with("&")? && !self.source.starts_with("&#")? && self.predefined_entity()?.is_none())
+  }
+
+  /// `[68] EntityRef ::= '&' Name ';'` and returns the name.
+  fn entity_reference_name(&mut self) -> Result<String> {
+    let location = self.location();
+    self.expect("&", ErrorCode::MalformedEntityRef)?;
+    let name = self.name()?;
+    if !self.source.skip_if(";")? {
+      let message = format!("entity reference &{} is not terminated by ';'", name);
+      return self.error(location, ErrorCode::MalformedEntityRef, &message);
+    }
+    Ok(name)
+  }
+
+  /// Whether references to undeclared entities violate `WFC: Entity Declared`, that is, all declarations have been
+  /// read or the document is standalone.
+  fn entity_declaration_required(&self) -> bool {
+    !self.dtd.partial || self.standalone == Some(true)
+  }
+
+  /// `[68] EntityRef` in content. The replacement text of an internal entity, or an external entity that the
+  /// resolver returned, is read as content after the returned [EventKind::StartEntity]; an external entity that the
+  /// resolver refused or an entity that might be declared in the unread part of the DTD is reported as
+  /// [EventKind::SkippedEntity].
+  fn entity_reference(&mut self) -> Result<Event> {
+    let location = self.location();
+    let name = self.entity_reference_name()?;
+    match self.dtd.entities.get(&name).cloned() {
+      Some(decl) if decl.is_unparsed() => {
+        return self.error(location, ErrorCode::ParsedEntity, &format!("reference to unparsed entity: &{};", name))
+      }
+      Some(EntityDecl { value: Some(value), .. }) => self.push_entity(&location, &name, &value, false)?,
+      Some(decl) => match self.resolve_external(&name, &decl)? {
+        Some(input) => self.push_external(&location, &name, input, false)?,
+        None => {
+          let message = format!("external entity &{}; is not read", name);
+          self.warning(location.clone(), ErrorCode::EntityNotRead, &message);
+          return Ok(Event::new(location, EventKind::SkippedEntity(name)));
+        }
+      },
+      None if self.entity_declaration_required() => {
+        let message = format!("undeclared entity: &{};", name);
+        self.recoverable(location.clone(), ErrorCode::EntityDeclared, &message)?;
+        return Ok(Event::new(location, EventKind::SkippedEntity(name)));
+      }
+      None => {
+        let message = format!("entity &{}; may be declared in the unread part of the DTD and is skipped", name);
+        self.warning(location.clone(), ErrorCode::EntityNotRead, &message);
+        return Ok(Event::new(location, EventKind::SkippedEntity(name)));
+      }
+    }
+    self.entity_elements.push(self.elements.len());
+    Ok(Event::new(location, EventKind::StartEntity(name)))
+  }
+
+  /// Finishes the replacement text of the entity referred to in content.
+  fn end_entity(&mut self) -> Result<Event> {
+    let location = self.location();
+    if self.entity_elements.pop() != Some(self.elements.len()) {
+      let name = self.elements.last().map(|name| name.as_str()).unwrap_or_default();
+      let message = format!("element <{}> that starts in entity is not closed in it", name);
+      return self.error(location, ErrorCode::UnbalancedEntity, &message);
+    }
+    let name = self.source.pop_entity().unwrap_or_default();
+    Ok(Event::new(location, EventKind::EndEntity(name)))
+  }
+
+  /// `[68] EntityRef` in an attribute value. The replacement text of the entity is read as a part of the value.
+  fn attribute_entity_reference(&mut self) -> Result<()> {
+    let location = self.location();
+    let name = self.entity_reference_name()?;
+    let (code, message) = match self.dtd.entities.get(&name) {
+      Some(decl) if decl.is_unparsed() => {
+        (ErrorCode::ParsedEntity, format!("reference to unparsed entity: &{};", name))
+      }
+      Some(EntityDecl { value: Some(value), .. }) => {
+        let value = value.clone();
+        return self.push_entity(&location, &name, &value, false);
+      }
+      Some(_) => {
+        (ErrorCode::NoExternalEntityReferences, format!("reference to external entity in attribute value: &{};", name))
+      }
+      None if self.entity_declaration_required() => {
+        return self.recoverable(location, ErrorCode::EntityDeclared, &format!("undeclared entity: &{};", name));
+      }
+      None => return Ok(()),
+    };
+    self.error(location, code, &message)
+  }
+
+  /// Starts reading the replacement text of the internal entity `name`, which is prefixed with `%` for a parameter
+  /// entity, with checking recursion and the [EntityLimits].
+  fn push_entity(&mut self, location: &Location, name: &str, value: &str, transparent: bool) -> Result<()> {
+    self.check_expansion(location, name, value.chars().count())?;
+    self.source.push_entity(name, value, transparent);
+    Ok(())
+  }
+
+  /// Starts reading the external entity `name` from the `input` that the resolver returned, with skipping the text
+  /// declaration.
+  fn push_external(&mut self, location: &Location, name: &str, input: InputSource, transparent: bool) -> Result<()> {
+    self.check_expansion(location, name, 0)?;
+    self.source.push_external(name, input, transparent);
+    self.text_decl()
+  }
+
+  /// Asks the resolver for the external entity `name` declared by `decl`.
+  fn resolve_external(&self, name: &str, decl: &EntityDecl) -> Result<Option<InputSource>> {
+    match &decl.system_id {
+      Some(system_id) => {
+        self.resolver.resolve_entity(name, decl.public_id.as_deref(), decl.base_uri.as_deref(), system_id)
+      }
+      None => Ok(None),
+    }
+  }
+
+  /// Checks that expanding `chars` characters of the entity `name` is neither recursive nor beyond the
+  /// [EntityLimits].
+  fn check_expansion(&mut self, location: &Location, name: &str, chars: usize) -> Result<()> {
+    let reference = if name.starts_with('%') { format!("{};", name) } else { format!("&{};", name) };
+    if self.source.is_expanding(name) {
+      return self.error(location.clone(), ErrorCode::NoRecursion, &format!("entity {} refers to itself", reference));
+    }
+    let limits = &self.entity_limits;
+    if self.source.entity_depth() >= limits.max_depth {
+      let message = format!("entity {} is nested deeper than the limit {}", reference, limits.max_depth);
+      return self.error(location.clone(), ErrorCode::EntityDepthLimit, &message);
+    }
+    self.expanded_chars = self.expanded_chars.saturating_add(chars);
+    if self.expanded_chars > limits.max_expanded_chars {
+      let message =
+        format!("entity {} expands replacement text beyond the limit {} chars", reference, limits.max_expanded_chars);
+      return self.error(location.clone(), ErrorCode::EntityExpansionLimit, &message);
+    }
+    let document_chars = self.source.document_chars();
+    if self.expanded_chars > limits.ratio_threshold
+      && self.expanded_chars > document_chars.saturating_mul(limits.max_expansion_ratio)
+    {
+      let message = format!(
+        "entity {} expands {} chars from a document of {} chars beyond the ratio limit {}",
+        reference, self.expanded_chars, document_chars, limits.max_expansion_ratio
+      );
+      return self.error(location.clone(), ErrorCode::EntityExpansionRatio, &message);
+    }
+    Ok(())
+  }
+
+  /// Reports a document type declaration that appears other than once before the document element.
+  fn misplaced_doctype<T>(&self, location: Location) -> Result<T> {
+    let message = "document type declaration must appear once before the document element";
+    self.error(location, ErrorCode::MisplacedDoctypeDecl, message)
+  }
+
+  /// `[10] AttValue ::= '"' ([^<&"] | Reference)* '"' | "'" ([^<&'] | Reference)* "'"` with whitespace characters
+  /// normalized to spaces.
+  fn attribute_value(&mut self) -> Result<String> {
+    let location = self.location();
+    let quote = match self.source.peek()? {
+      Some(ch) if ch == '"' || ch == '\'' => ch,
+      _ if self.recovery => return self.unquoted_attribute_value(),
+      _ => return self.error(location, ErrorCode::MalformedAttValue, "attribute value must be quoted"),
+    };
+    self.source.next_char()?;
+    let mut value = String::new();
+    let depth = self.source.entity_depth();
+    loop {
+      match self.source.peek()? {
+        None if self.source.entity_depth() > depth => {
+          self.source.pop_entity();
+        }
+        None => {
+          self.recoverable(location, ErrorCode::MalformedAttValue, "attribute value is not terminated")?;
+          return Ok(value);
+        }
+        Some(ch) if ch == quote && self.source.entity_depth() == depth => {
+          self.source.next_char()?;
+          return Ok(value);
+        }
+        Some('<') if self.recovery && !self.quote_comes_first(quote, '>')? => {
+          self.recoverable(location, ErrorCode::MalformedAttValue, "attribute value is not terminated")?;
+          return Ok(value);
+        }
+        Some('<') => {
+          let message = "'<' is not allowed in attribute value";
+          self.recoverable(self.location(), ErrorCode::NoLtInAttributeValues, message)?;
+          self.source.next_char()?;
+          value.push('<');
+        }
+        Some('>') if self.recovery && self.source.entity_depth() == depth && !self.quote_comes_first(quote, '<')? => {
+          self.recoverable(location, ErrorCode::MalformedAttValue, "attribute value is not terminated")?;
+          return Ok(value);
+        }
+        Some('&') if self.unescaped_ampersand(&mut value)? => (),
+        Some('&') if self.at_entity_reference()? => self.attribute_entity_reference()?,
+        Some('&') => self.reference(&mut value)?,
+        Some(ch) if is_whitespace(ch) => {
+          self.source.next_char()?;
+          value.push(' ');
+        }
+        Some(ch) => {
+          self.source.next_char()?;
+          value.push(ch);
+          self.source.read_until_any(&[quote as u8, b'<', b'>', b'&', b'\t', b'\n', b'\r'], &mut value)?;
+        }
+      }
+    }
+  }
+
+  /// An attribute value without quotes in recovery mode, which ends at whitespace or the end of the start tag.
+  fn unquoted_attribute_value(&mut self) -> Result<String> {
+    self.recoverable(self.location(), ErrorCode::MalformedAttValue, "attribute value must be quoted")?;
+    let mut value = String::new();
+    while let Some(ch) = self.source.peek()? {
+      if is_whitespace(ch) || ch == '<' || ch == '>' || self.source.starts_with("/>")? {
+        break;
+      }
+      self.source.next_char()?;
+      value.push(ch);
+    }
+    Ok(value)
+  }
+
+  /// Whether the `quote` that closes the attribute value appears after the character under the cursor before the
+  /// `stop` character, which tells in recovery mode whether the value is unterminated. A value longer than the
+  /// look-ahead is taken as continuing.
+  fn quote_comes_first(&mut self, quote: char, stop: char) -> Result<bool> {
+    let ahead = self.source.lookahead(Self::RECOVERY_LOOKAHEAD)?;
+    Ok(ahead.chars().skip(1).find(|ch| *ch == quote || *ch == stop).map(|ch| ch == quote).unwrap_or(true))
+  }
+
+  /// Takes `&` under the cursor as a character in recovery mode if it doesn't start a well-formed reference, and
+  /// appends it to `out`.
+  fn unescaped_ampersand(&mut self, out: &mut String) -> Result<bool> {
+    if !self.recovery || !self.source.starts_with("&")? {
+      return Ok(false);
+    }
+    let ahead = self.source.lookahead(Self::RECOVERY_LOOKAHEAD)?;
+    let reference = ahead[1..].split_once(';').map(|(reference, _)| reference).unwrap_or_default();
+    let well_formed = match reference.strip_prefix('#') {
+      Some(code) => match code.strip_prefix('x') {
+        Some(hex) => !hex.is_empty() && hex.chars().all(|ch| ch.is_ascii_hexdigit()),
+        None => !code.is_empty() && code.chars().all(|ch| ch.is_ascii_digit()),
+      },
+      None => is_name(reference),
+    };
+    if well_formed {
+      return Ok(false);
+    }
+    self.recoverable(self.location(), ErrorCode::MalformedEntityRef, "unescaped '&' is taken as a character")?;
+    self.source.next_char()?;
+    out.push('&');
+    Ok(true)
+  }
+
+  /// Whether `<` under the cursor starts a tag, a comment, a CDATA section or a processing instruction.
+  fn at_markup(&mut self) -> Result<bool> {
+    Ok(match self.source.peek_nth(1)? {
+      Some('/' | '!' | '?') => true,
+      Some(ch) => is_name_start_char(ch),
+      None => false,
+    })
+  }
+
+  /// `[11] SystemLiteral`, `[12] PubidLiteral` or a value of pseudo-attribute. `code` is reported if the literal is
+  /// malformed.
+  fn quoted_literal(&mut self, code: ErrorCode) -> Result<String> {
+    let location = self.location();
+    let quote = match self.source.next_char()? {
+      Some(ch) if ch == '"' || ch == '\'' => ch,
+      _ => return self.error(location, code, "literal must be quoted"),
+    };
+    let mut literal = String::new();
+    if !self.source.read_until(if quote == '"' { "\"" } else { "'" }, &mut literal)? {
+      return self.error(location, code, "literal is not terminated");
+    }
+    Ok(literal)
+  }
+
+  /// `[5] Name ::= NameStartChar (NameChar)*`
+  fn name(&mut self) -> Result<String> {
+    let location = self.location();
+    let mut name = String::new();
+    match self.source.peek()? {
+      Some(ch) if is_name_start_char(ch) => {
+        self.source.read_while(is_name_char, &mut name)?;
+        Ok(name)
+      }
+      Some(ch) => self.error(location, ErrorCode::InvalidName, &format!("name expected, but {:?} appeared", ch)),
+      None => self.error(location, ErrorCode::InvalidName, "name expected, but reached the end of document"),
+    }
+  }
+
+  /// Skips `[3] S` and returns whether any whitespace was skipped.
+  fn skip_whitespace(&mut self) -> Result<bool> {
+    let mut skipped = String::new();
+    Ok(self.source.read_while(is_whitespace, &mut skipped)? > 0)
+  }
+
+  /// Skips `[3] S` that the production identified by `code` requires.
+  fn expect_whitespace(&mut self, code: ErrorCode) -> Result<()> {
+    if !self.skip_whitespace()? {
+      return self.error(self.location(), code, "whitespace expected");
+    }
+    Ok(())
+  }
+
+  /// Skips the `token` that the production identified by `code` requires.
+  fn expect(&mut self, token: &str, code: ErrorCode) -> Result<()> {
+    if !self.source.skip_if(token)? {
+      let message = match self.source.peek()? {
+        Some(ch) => format!("{:?} expected, but {:?} appeared", token, ch),
+        None => format!("{:?} expected, but reached the end of document", token),
+      };
+      return self.error(self.location(), code, &message);
+    }
+    Ok(())
+  }
+
+  /// Reports the error that recovery mode repairs. The error is kept as a diagnostic in recovery mode so that the
+  /// caller goes on with the repair, and returned otherwise.
+  fn recoverable(&mut self, location: Location, code: ErrorCode, message: &str) -> Result<()> {
+    let error = self.error::<()>(location, code, message).unwrap_err();
+    if !self.recovery {
+      return Err(error);
+    }
+    self.diagnostics.push(error);
+    Ok(())
+  }
+
+  fn warning(&mut self, location: Location, code: ErrorCode, message: &str) {
+    let warning = self.error::<()>(location, code, message).unwrap_err();
+    self.warnings.push(warning);
+  }
+
+  fn error<T>(&self, location: Location, code: ErrorCode, message: &str) -> Result<T> {
+    let system_id = self.source.system_id().map(|id| id.to_string());
+    Err(Error::Parse { code, location, system_id, message: message.to_string() })
+  }
+}
+
+impl Iterator for PullParser {
+  type Item = Result<Event>;
+
+  /// Returns events up to and including [EventKind::EndDocument], or the first error.
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.state == State::Finished {
+      return None;
+    }
+    let event = self.next_event();
+    if event.is_err() {
+      self.state = State::Finished;
+    }
+    Some(event)
+  }
+}
+
+/// Discards the leading and trailing spaces of the attribute value and replaces sequences of spaces by a single space,
+/// as the normalization of attributes whose declared types are not CDATA.
+fn collapse_spaces(value: &str) -> String {
+  value.split(' ').filter(|token| !token.is_empty()).collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,590 @@
+use crate::io::{DecodingReader, StringReader, Utf8Reader};
+use crate::test::compatibility::java;
+use crate::xml::parser::{
+  Attribute, ContentSpec, EntityDecl, EntityLimits, ErrorCode, EventKind, InputSource, MarkupDecl, PullParser,
+};
+use crate::{Error, Location, Result};
+
+fn events(xml: &str) -> Result<Vec<EventKind>> {
+  PullParser::new(StringReader::new(xml)).map(|event| event.map(|e| e.kind)).collect()
+}
+
+fn text(text: &str) -> EventKind {
+  EventKind::Text(text.to_string())
+}
+
+fn start(name: &str) -> EventKind {
+  EventKind::StartElement {
+    name: name.to_string(),
+    namespace_uri: None,
+    local_name: Some(name.to_string()),
+    attributes: vec![],
+  }
+}
+
+fn end(name: &str) -> EventKind {
+  EventKind::EndElement { name: name.to_string(), namespace_uri: None, local_name: Some(name.to_string()) }
+}
+
+#[test]
+fn element_and_text() {
+  let events = events("<a>hello<b/>world</a>").unwrap();
+  assert_eq!(
+    vec![start("a"), text("hello"), start("b"), end("b"), text("world"), end("a"), EventKind::EndDocument],
+    events
+  );
+}
+
+#[test]
+fn prolog_and_epilog() {
+  let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<!-- prolog --><?pi data?>
+<!DOCTYPE root SYSTEM "root.dtd">
+<root/>
+<!-- epilog -->
+"#;
+  let events = events(xml).unwrap();
+  let expected = vec![
+    EventKind::XMLDecl { version: "1.0".to_string(), encoding: Some("UTF-8".to_string()), standalone: Some(false) },
+    EventKind::Comment(" prolog ".to_string()),
+    EventKind::ProcessingInstruction { target: "pi".to_string(), data: Some("data".to_string()) },
+    EventKind::DocType {
+      name: "root".to_string(),
+      public_id: None,
+      system_id: Some("root.dtd".to_string()),
+      internal_subset: None,
+      declarations: vec![],
+    },
+    start("root"),
+    end("root"),
+    EventKind::Comment(" epilog ".to_string()),
+    EventKind::EndDocument,
+  ];
+  assert_eq!(expected, events);
+}
+
+#[test]
+fn doctype_internal_subset() {
+  let subset = r#"<!ELEMENT doc (#PCDATA)><!-- ] --><!ENTITY e "]">"#;
+  let xml = format!(r#"<!DOCTYPE doc PUBLIC "-//X//DTD Y//EN" "doc.dtd" [{}]><doc/>"#, subset);
+  let entity = EntityDecl {
+    name: "e".to_string(),
+    parameter: false,
+    value: Some("]".to_string()),
+    public_id: None,
+    system_id: None,
+    notation_name: None,
+    base_uri: None,
+  };
+  let expected = EventKind::DocType {
+    name: "doc".to_string(),
+    public_id: Some("-//X//DTD Y//EN".to_string()),
+    system_id: Some("doc.dtd".to_string()),
+    internal_subset: Some(subset.to_string()),
+    declarations: vec![
+      MarkupDecl::Element { name: "doc".to_string(), content_spec: ContentSpec::Mixed(vec![]) },
+      MarkupDecl::Comment(" ] ".to_string()),
+      MarkupDecl::Entity(entity),
+    ],
+  };
+  assert_eq!(expected, events(&xml).unwrap()[0]);
+}
+
+#[test]
+fn markup_declarations() {
+  let xml = r#"<!DOCTYPE a [
+  <!ELEMENT a (b, (c | d)*, e?)+>
+  <!ELEMENT b EMPTY>
+  <!ELEMENT c ANY>
+  <!ELEMENT d (#PCDATA | b | c)*>
+  <!ATTLIST a id ID #REQUIRED kind (x|y) "x" n NOTATION (png) #IMPLIED v CDATA #FIXED " 1  2 ">
+  <!ATTLIST a id CDATA #IMPLIED w NMTOKENS #IMPLIED>
+  <!ENTITY % p "<!ENTITY e2 'e2'>">
+  %p;
+  <!ENTITY e1 "&#x41;&e2;&#37;">
+  <!ENTITY e1 "ignored">
+  <!ENTITY lt "&#38;#60;">
+  <!ENTITY amp "&#38;#38;">
+  <!ENTITY ext PUBLIC "-//E//EN" "ext.xml">
+  <!ENTITY img SYSTEM "a.png" NDATA png>
+  <!NOTATION png PUBLIC "image/png">
+  <?pi data?>
+]><a/>"#;
+  let declarations = match events(xml).unwrap().remove(0) {
+    EventKind::DocType { declarations, .. } => declarations,
+    unexpected => panic!("{:?}", unexpected),
+  };
+  let actual = declarations
+    .iter()
+    .map(|decl| match decl {
+      MarkupDecl::Element { name, content_spec } => format!("ELEMENT {} {}", name, content_spec),
+      MarkupDecl::Attlist { element, definitions } => {
+        let defs = definitions
+          .iter()
+          .map(|def| format!("{} {} {:?} {:?}", def.name, def.att_type, def.default.mode(), def.default.value()));
+        format!("ATTLIST {} {}", element, defs.collect::<Vec<_>>().join(", "))
+      }
+      MarkupDecl::Entity(decl) => format!(
+        "ENTITY {}{} {:?} {:?} {:?} {:?}",
+        if decl.parameter { "%" } else { "" },
+        decl.name,
+        decl.value,
+        decl.public_id,
+        decl.system_id,
+        decl.notation_name
+      ),
+      MarkupDecl::Notation { name, public_id, system_id } => {
+        format!("NOTATION {} {:?} {:?}", name, public_id, system_id)
+      }
+      MarkupDecl::ProcessingInstruction { target, data } => format!("PI {} {:?}", target, data),
+      MarkupDecl::Comment(text) => format!("COMMENT {}", text),
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(
+    vec![
+      "ELEMENT a (b,(c|d)*,e?)+",
+      "ELEMENT b EMPTY",
+      "ELEMENT c ANY",
+      "ELEMENT d (#PCDATA|b|c)*",
+      r##"ATTLIST a id ID Some("#REQUIRED") None, kind (x|y) None Some("x"), n NOTATION (png) Some("#IMPLIED") None, v CDATA Some("#FIXED") Some(" 1  2 ")"##,
+      r##"ATTLIST a w NMTOKENS Some("#IMPLIED") None"##,
+      r#"ENTITY %p Some("<!ENTITY e2 'e2'>") None None None"#,
+      r#"ENTITY e2 Some("e2") None None None"#,
+      r#"ENTITY e1 Some("A&e2;%") None None None"#,
+      r#"ENTITY ext None Some("-//E//EN") Some("ext.xml") None"#,
+      r#"ENTITY img None None Some("a.png") Some("png")"#,
+      r#"NOTATION png Some("image/png") None"#,
+      r#"PI pi Some("data")"#,
+    ],
+    actual
+  );
+}
+
+#[test]
+fn entity_expansion() {
+  let xml = r#"<!DOCTYPE a [
+  <!ENTITY b "<b x='&c;'>&c;</b>">
+  <!ENTITY c "c&#38;#38;">
+  <!ENTITY ext SYSTEM "ext.xml">
+]><a>&b;&ext;</a>"#;
+  let events = events(xml).unwrap();
+  let b = EventKind::StartElement {
+    name: "b".to_string(),
+    namespace_uri: None,
+    local_name: Some("b".to_string()),
+    attributes: vec![Attribute {
+      location: Location::with_offsets(4, 8, 117, 117),
+      end: Location::with_offsets(4, 8, 117, 117),
+      name: "x".to_string(),
+      namespace_uri: None,
+      local_name: Some("x".to_string()),
+      value: "c&".to_string(),
+      specified: true,
+    }],
+  };
+  let expected = vec![
+    start("a"),
+    EventKind::StartEntity("b".to_string()),
+    b,
+    EventKind::StartEntity("c".to_string()),
+    text("c&"),
+    EventKind::EndEntity("c".to_string()),
+    end("b"),
+    EventKind::EndEntity("b".to_string()),
+    EventKind::SkippedEntity("ext".to_string()),
+    end("a"),
+    EventKind::EndDocument,
+  ];
+  assert_eq!(expected, events[1..]);
+
+  // declarations might be missing in the external subset that is not read
+  let events = self::events("<!DOCTYPE a SYSTEM 'a.dtd'><a>&x;</a>").unwrap();
+  assert_eq!(EventKind::SkippedEntity("x".to_string()), events[2]);
+}
+
+#[test]
+fn attributes_and_references() {
+  let mut parser = PullParser::new(StringReader::new("<a x='1&amp;2' y=\"&#x41;\n&#66;&lt;\"/>"));
+  parser.set_namespace_aware(false);
+  let event = parser.next_event().unwrap();
+  let attribute = |column: u64, end: Location, name: &str, value: &str| Attribute {
+    location: Location::with_offsets(0, column, column, column),
+    end,
+    name: name.to_string(),
+    namespace_uri: None,
+    local_name: None,
+    value: value.to_string(),
+    specified: true,
+  };
+  let attributes = vec![
+    attribute(3, Location::with_offsets(0, 14, 14, 14), "x", "1&2"),
+    attribute(15, Location::with_offsets(1, 10, 35, 35), "y", "A B<"),
+  ];
+  let expected = EventKind::StartElement { name: "a".to_string(), namespace_uri: None, local_name: None, attributes };
+  assert_eq!(expected, event.kind);
+}
+
+#[test]
+fn cdata_comment_and_pi_in_content() {
+  let events = events("<a><![CDATA[<&>]]><!--c--><?t?>&quot;&apos;&gt;</a>").unwrap();
+  let expected = vec![
+    start("a"),
+    EventKind::CDATA("<&>".to_string()),
+    EventKind::Comment("c".to_string()),
+    EventKind::ProcessingInstruction { target: "t".to_string(), data: None },
+    text("\"'>"),
+    end("a"),
+    EventKind::EndDocument,
+  ];
+  assert_eq!(expected, events);
+}
+
+#[test]
+fn event_locations() {
+  let mut parser = PullParser::new(StringReader::new("<a>\n  <b>text</b>\n</a>"));
+  let mut locations = Vec::new();
+  loop {
+    let event = parser.next_event().unwrap();
+    locations.push((event.location.line_number, event.location.column_number));
+    if event.kind == EventKind::EndDocument {
+      break;
+    }
+  }
+  assert_eq!(vec![(0, 0), (0, 3), (1, 2), (1, 5), (1, 9), (1, 13), (2, 0), (2, 4)], locations);
+}
+
+#[test]
+fn line_ends_and_locations() {
+  let texts = |xml: &str| {
+    let events = events(xml).unwrap();
+    events.into_iter().filter(|e| matches!(e, EventKind::Text(_))).collect::<Vec<_>>()
+  };
+  assert_eq!(vec![text("\nx\ny\n\n\r\n")], texts("<a>\r\nx\ry\r\r\n&#13;&#10;</a>"));
+  assert_eq!(vec![text("x\u{85}y\u{2028}")], texts("<a>x\u{85}y\u{2028}</a>"));
+  assert_eq!(vec![text("x\ny\nz\n\n")], texts("<?xml version='1.1'?><a>x\u{85}y\r\u{85}z\u{2028}\r</a>"));
+  match events("<?xml version='1.0'?>\r\n<a x='1\r\n2'>\r\r\n \u{1F600}\u{85}<b></a>") {
+    Err(Error::Parse { location, .. }) => assert_eq!(Location::with_offsets(4, 6, 48, 44), location),
+    unexpected => panic!("{:?}", unexpected),
+  }
+
+  // CR LF split by the buffer boundary
+  let xml = format!("<a>{}\r\n<b/>\r\r\n</a>", "x".repeat(4092));
+  let mut parser = PullParser::new(StringReader::new(&xml));
+  let locations = parser.by_ref().take(3).map(|e| e.unwrap().location).collect::<Vec<_>>();
+  let expected =
+    vec![Location::new(0, 0), Location::with_offsets(0, 3, 3, 3), Location::with_offsets(1, 0, 4097, 4097)];
+  assert_eq!(expected, locations);
+  assert_eq!(text("\n\n"), parser.nth(1).unwrap().unwrap().kind);
+  assert_eq!(Location::with_offsets(3, 0, 4104, 4104), parser.next().unwrap().unwrap().location);
+}
+
+#[test]
+fn xml11_documents() {
+  let code = |xml: &str| match events(xml) {
+    Err(Error::Parse { code, .. }) => Some(code),
+    Err(err) => panic!("{:?}", err),
+    Ok(_) => None,
+  };
+  let texts =
+    |xml: &str| events(xml).unwrap().into_iter().filter(|e| matches!(e, EventKind::Text(_))).collect::<Vec<_>>();
+  assert_eq!(vec![text("\u{1}\u{7F}")], texts("<?xml version='1.1'?><a>&#1;&#x7F;</a>"));
+  assert_eq!(Some(ErrorCode::LegalCharacter), code("<?xml version='1.0'?><a>&#1;</a>"));
+  assert_eq!(Some(ErrorCode::LegalCharacter), code("<?xml version='1.1'?><a>&#0;</a>"));
+  assert_eq!(vec![text("\u{80}")], texts("<a>\u{80}</a>"));
+  assert_eq!(Some(ErrorCode::InvalidChar), code("<?xml version='1.1'?><a>\u{80}</a>"));
+  let long = format!("<?xml version='1.1'?><a>{}\u{9F}</a>", "x".repeat(5000));
+  assert_eq!(Some(ErrorCode::InvalidChar), code(&long));
+
+  let xml = r#"<p:a xmlns:p="urn:p"><b xmlns:p=""><c/></b><p:d/></p:a>"#;
+  assert_eq!(Some(ErrorCode::NoPrefixUndeclaring), code(xml));
+  assert_eq!(None, code(&format!("<?xml version='1.1'?>{}", xml)));
+  let xml = r#"<?xml version="1.1"?><p:a xmlns:p="urn:p"><b xmlns:p=""><p:c/></b></p:a>"#;
+  assert_eq!(Some(ErrorCode::PrefixDeclared), code(xml));
+
+  let mut parser = PullParser::new(StringReader::new("<?xml version='1.1'?><a/>"));
+  parser.next_event().unwrap();
+  assert_eq!("1.1", parser.xml_version());
+  let mut parser = PullParser::new(StringReader::new("<?xml version='1.9'?><a/>"));
+  parser.next_event().unwrap();
+  assert_eq!("1.0", parser.xml_version());
+}
+
+#[test]
+fn long_document_across_buffer_boundaries() {
+  let text = "0123456789".repeat(2000);
+  let xml = format!("<a>{}<!--{}--></a>", text, text);
+  let events = events(&xml).unwrap();
+  assert_eq!(
+    vec![start("a"), EventKind::Text(text.clone()), EventKind::Comment(text), end("a"), EventKind::EndDocument],
+    events
+  );
+}
+
+#[test]
+fn entity_expansion_limits() {
+  let parse = |xml: &str, limits: EntityLimits| {
+    let mut parser = PullParser::new(StringReader::new(xml));
+    parser.set_entity_limits(limits);
+    parser.map(|event| event.map(|e| e.kind)).collect::<Result<Vec<_>>>()
+  };
+  let assert_error =
+    |result: Result<Vec<EventKind>>, expected_code: ErrorCode, expected_location: Location| match result {
+      Err(Error::Parse { code, location, .. }) => {
+        assert_eq!(expected_code, code);
+        assert_eq!(expected_location, location);
+      }
+      unexpected => panic!("{:?}", unexpected),
+    };
+
+  // billion laughs
+  let mut xml = "<!DOCTYPE lolz [\n<!ENTITY lol0 \"lol\">\n".to_string();
+  for i in 1..10 {
+    xml.push_str(&format!("<!ENTITY lol{} \"{}\">\n", i, format!("&lol{};", i - 1).repeat(10)));
+  }
+  xml.push_str("]>\n<lolz>&lol9;</lolz>");
+  assert_error(parse(&xml, EntityLimits::default()), ErrorCode::EntityExpansionRatio, location_of(&xml, 12, 12));
+  let limits = EntityLimits { max_expanded_chars: 100_000, max_expansion_ratio: usize::MAX, ..EntityLimits::default() };
+  assert_error(parse(&xml, limits), ErrorCode::EntityExpansionLimit, location_of(&xml, 12, 12));
+
+  // quadratic blowup
+  let xml = format!("<!DOCTYPE a [<!ENTITY e \"{}\">]><a>{}</a>", "x".repeat(1000), "&e;".repeat(100));
+  assert!(parse(&xml, EntityLimits::default()).is_ok());
+  let limits = EntityLimits { ratio_threshold: 10_000, ..EntityLimits::default() };
+  assert_error(parse(&xml, limits), ErrorCode::EntityExpansionRatio, location_of(&xml, 0, 1071));
+
+  // nesting depth
+  let xml = r#"<!DOCTYPE a [<!ENTITY e1 "x"><!ENTITY e2 "&e1;"><!ENTITY e3 "&e2;">]><a>&e3;</a>"#;
+  assert!(parse(xml, EntityLimits::default()).is_ok());
+  let limits = EntityLimits { max_depth: 2, ..EntityLimits::default() };
+  assert_error(parse(xml, limits), ErrorCode::EntityDepthLimit, location_of(xml, 0, 76));
+  let limits = EntityLimits { max_expanded_chars: 8, ..EntityLimits::default() };
+  assert_error(parse(xml, limits), ErrorCode::EntityExpansionLimit, location_of(xml, 0, 76));
+  assert!(parse(xml, EntityLimits::unlimited()).is_ok());
+}
+
+#[test]
+fn malformed_documents() {
+  for (xml, line, column, expected) in [
+    ("", 0, 0, ErrorCode::NoDocumentElement),
+    ("<a>", 0, 3, ErrorCode::UnclosedElement),
+    ("<a></b>", 0, 3, ErrorCode::ElementTypeMatch),
+    ("<a x=1/>", 0, 5, ErrorCode::MalformedAttValue),
+    ("<a>\n&unknown;</a>", 1, 0, ErrorCode::EntityDeclared),
+    ("<a/>text", 0, 4, ErrorCode::ContentAfterDocumentElement),
+    ("<a/><b/>", 0, 4, ErrorCode::MultipleDocumentElements),
+    ("text<a/>", 0, 0, ErrorCode::ContentInProlog),
+    ("<a><!-- </a>", 0, 3, ErrorCode::MalformedComment),
+    ("<?xml version='2.0'?><a/>", 0, 6, ErrorCode::InvalidVersionNum),
+    ("<!DOCTYPE a PUBLIC '{}' 'a.dtd'><a/>", 0, 19, ErrorCode::MalformedPubidLiteral),
+    ("<a>&#xD800;</a>", 0, 3, ErrorCode::LegalCharacter),
+    ("<a>&#0;</a>", 0, 3, ErrorCode::LegalCharacter),
+    ("<a>\n x\u{1}</a>", 1, 2, ErrorCode::InvalidChar),
+    ("<a>x]]>y</a>", 0, 4, ErrorCode::CDataSectionEndInContent),
+    ("<a><!-- a -- b --></a>", 0, 10, ErrorCode::DoubleHyphenInComment),
+    ("<a><!-- a ---></a>", 0, 10, ErrorCode::DoubleHyphenInComment),
+    ("<a/><?XmL a?>", 0, 6, ErrorCode::ReservedPITarget),
+    (" <?xml version='1.0'?><a/>", 0, 3, ErrorCode::ReservedPITarget),
+    ("<!DOCTYPE a><!DOCTYPE a><a/>", 0, 12, ErrorCode::MisplacedDoctypeDecl),
+    ("<a/><!DOCTYPE a>", 0, 4, ErrorCode::MisplacedDoctypeDecl),
+    ("<a x='1' y='2' x='3'/>", 0, 15, ErrorCode::UniqueAttSpec),
+    ("<!DOCTYPE a [<!ENTITY e '<b>'>]><a>&e;</b></a>", 0, 38, ErrorCode::UnbalancedEntity),
+    ("<!DOCTYPE a [<!ENTITY e '</a>'>]><a>&e;</a>", 0, 39, ErrorCode::UnbalancedEntity),
+    ("<!DOCTYPE a [<!ELEMENT a (b|c,d)>]><a/>", 0, 29, ErrorCode::MalformedElementDecl),
+    ("<!DOCTYPE a [<!ATTLIST a x FOO #IMPLIED>]><a/>", 0, 27, ErrorCode::MalformedAttlistDecl),
+    ("<!DOCTYPE a [<!ENTITY e>]><a/>", 0, 23, ErrorCode::MalformedEntityDecl),
+    ("<!DOCTYPE a [<!NOTATION n>]><a/>", 0, 25, ErrorCode::MalformedNotationDecl),
+    ("<!DOCTYPE a [%p]><a/>", 0, 13, ErrorCode::MalformedPEReference),
+    ("<!DOCTYPE a [<!ENTITY e SYSTEM 'e.xml'>]><a x='&e;'/>", 0, 47, ErrorCode::NoExternalEntityReferences),
+    ("<!DOCTYPE a [<!ENTITY e SYSTEM 'e' NDATA n>]><a>&e;</a>", 0, 48, ErrorCode::ParsedEntity),
+    ("<!DOCTYPE a [<!ENTITY e '&f;'><!ENTITY f '&e;'>]><a>&e;</a>", 0, 55, ErrorCode::NoRecursion),
+    ("<!DOCTYPE a [<!ENTITY % p 'x'><!ENTITY e %p;>]><a/>", 0, 41, ErrorCode::PEsInInternalSubset),
+    ("<!DOCTYPE a [<!ENTITY e '%p;'>]><a/>", 0, 25, ErrorCode::PEsInInternalSubset),
+    ("<?xml version='1.0' standalone='yes'?><!DOCTYPE a SYSTEM 'a.dtd'><a>&e;</a>", 0, 68, ErrorCode::EntityDeclared),
+    ("<a x='<'/>", 0, 6, ErrorCode::NoLtInAttributeValues),
+    ("<a:b:c/>", 0, 0, ErrorCode::MalformedQName),
+    ("<a x:='1'/>", 0, 3, ErrorCode::MalformedQName),
+    ("<p:a/>", 0, 0, ErrorCode::PrefixDeclared),
+    ("<a xmlns:p='urn:p'/><p:b/>", 0, 20, ErrorCode::MultipleDocumentElements),
+    ("<a><b xmlns:p='urn:p'/><c p:x='1'/></a>", 0, 26, ErrorCode::PrefixDeclared),
+    ("<a xmlns:xml='urn:x'/>", 0, 3, ErrorCode::ReservedPrefix),
+    ("<a xmlns:xmlns='urn:x'/>", 0, 3, ErrorCode::ReservedPrefix),
+    ("<a xmlns:p='http://www.w3.org/2000/xmlns/'/>", 0, 3, ErrorCode::ReservedPrefix),
+    ("<xmlns:a/>", 0, 0, ErrorCode::ReservedPrefix),
+    ("<a xmlns:p=''/>", 0, 3, ErrorCode::NoPrefixUndeclaring),
+    ("<a xmlns:p='urn:x' xmlns:q='urn:x' p:x='1' q:x='2'/>", 0, 43, ErrorCode::AttributesUnique),
+  ] {
+    match events(xml) {
+      Err(Error::Parse { code, location, .. }) => {
+        assert_eq!(location_of(xml, line, column), location, "{}", xml);
+        assert_eq!(expected, code, "{}", xml);
+      }
+      unexpected => panic!("{}: {:?}", xml, unexpected),
+    }
+  }
+}
+
+#[test]
+fn namespaces() {
+  let xml = "<p:a xmlns:p='urn:p' xmlns='urn:d' x='1' p:y='2'><b xmlns=''/><p:c xml:lang='en'/></p:a>";
+  let names = events(xml)
+    .unwrap()
+    .into_iter()
+    .filter_map(|event| match event {
+      EventKind::StartElement { name, namespace_uri, local_name, attributes } => {
+        let mut names = vec![format!("{}={:?}:{:?}", name, namespace_uri, local_name)];
+        for attr in attributes {
+          names.push(format!("@{}={:?}:{:?}", attr.name, attr.namespace_uri, attr.local_name));
+        }
+        Some(names)
+      }
+      EventKind::EndElement { name, namespace_uri, .. } => Some(vec![format!("/{}={:?}", name, namespace_uri)]),
+      _ => None,
+    })
+    .flatten()
+    .collect::<Vec<_>>();
+  let expected = vec![
+    r#"p:a=Some("urn:p"):Some("a")"#,
+    r#"@xmlns:p=Some("http://www.w3.org/2000/xmlns/"):Some("p")"#,
+    r#"@xmlns=Some("http://www.w3.org/2000/xmlns/"):Some("xmlns")"#,
+    r#"@x=None:Some("x")"#,
+    r#"@p:y=Some("urn:p"):Some("y")"#,
+    r#"b=None:Some("b")"#,
+    r#"@xmlns=Some("http://www.w3.org/2000/xmlns/"):Some("xmlns")"#,
+    r#"/b=None"#,
+    r#"p:c=Some("urn:p"):Some("c")"#,
+    r#"@xml:lang=Some("http://www.w3.org/XML/1998/namespace"):Some("lang")"#,
+    r#"/p:c=Some("urn:p")"#,
+    r#"/p:a=Some("urn:p")"#,
+  ];
+  assert_eq!(expected, names);
+
+  // without namespace processing, prefixes are just a part of names
+  let mut parser = PullParser::new(StringReader::new("<p:a q:x='1'/>"));
+  parser.set_namespace_aware(false);
+  assert!(parser.all(|event| event.is_ok()));
+}
+
+#[test]
+fn well_formedness_compatible_with_java() {
+  let documents = [
+    "<a/>",
+    "<a><!-- - --></a>",
+    "<a><!----></a>",
+    "<a><!-- -- --></a>",
+    "<a><!-- ---></a>",
+    "<a>]]></a>",
+    "<a>]]&gt;</a>",
+    "<a>]]</a>",
+    "<a><![CDATA[]]]]><![CDATA[>]]></a>",
+    "<a x='1' x='1'/>",
+    "<a x='1' y='1'/>",
+    "<a x='&lt;'/>",
+    "<a x='<'/>",
+    "<a x='>'/>",
+    "<a>&#x9;&#xA;&#xD;&#x20;</a>",
+    "<a>&#x8;</a>",
+    "<a>&#xFFFE;</a>",
+    "<a>&#x10FFFF;</a>",
+    "<a>\u{7}</a>",
+    "<a>\u{FFFF}</a>",
+    "<a><?xml-stylesheet href='a'?></a>",
+    "<a><?xml version='1.0'?></a>",
+    "<a><?XML?></a>",
+    "<a/><b/>",
+    "<a/>x",
+    "<!DOCTYPE a><!DOCTYPE a><a/>",
+    "<a/><!DOCTYPE a>",
+    "<a><!DOCTYPE a></a>",
+    "<a>&undefined;</a>",
+  ];
+  // characters out of ASCII are written as Unicode escapes in the Java source
+  let literal = |xml: &str| {
+    let escaped = xml.chars().map(|ch| match ch {
+      '"' | '\\' => format!("\\{}", ch),
+      ' '..='~' => ch.to_string(),
+      _ => format!("\\u{:04X}", ch as u32),
+    });
+    format!("\"{}\"", escaped.collect::<String>())
+  };
+  let literals = documents.iter().map(|xml| literal(xml)).collect::<Vec<_>>().join(",");
+  let code = format!(
+    r#"
+    String[] documents = {{ {} }};
+    DocumentBuilderFactory factory = DocumentBuilderFactory.newInstance();
+    DocumentBuilder builder = factory.newDocumentBuilder();
+    builder.setErrorHandler(null);
+    for(String xml : documents) {{
+      try {{
+        builder.parse(new InputSource(new StringReader(xml)));
+        System.out.println("ok");
+      }} catch(SAXException ex) {{
+        System.out.println("ng");
+      }}
+    }}
+    "#,
+    literals
+  );
+  let expected = java::run("well_formedness_compatible_with_java", &code);
+  let actual = documents.iter().map(|xml| if events(xml).is_ok() { "ok" } else { "ng" }).collect::<Vec<_>>();
+  assert_eq!(expected.lines().collect::<Vec<_>>(), actual);
+}
+
+#[test]
+fn error_details() {
+  let mut input = InputSource::new(StringReader::new("<a>\n</b>"));
+  input.system_id = Some("file:///a.xml".to_string());
+  let error = PullParser::with_input_source(input).find_map(|event| event.err()).unwrap();
+  match &error {
+    Error::Parse { code, system_id, .. } => {
+      assert_eq!(201, code.code());
+      assert_eq!("WFC: Element Type Match", code.constraint());
+      assert_eq!(Some("file:///a.xml"), system_id.as_deref());
+    }
+    unexpected => panic!("{:?}", unexpected),
+  }
+  assert_eq!(
+    "end tag </b> does not match start tag <a> at file:///a.xml(2,1) [XML201 WFC: Element Type Match]",
+    error.to_string()
+  );
+}
+
+#[test]
+fn utf8_byte_stream() {
+  let xml = "<名前 属性='値'>テキスト😀</名前>".as_bytes();
+  let events: Vec<EventKind> = PullParser::new(Utf8Reader::new(xml)).map(|e| e.unwrap().kind).collect();
+  assert_eq!(text("テキスト😀"), events[1]);
+  let mut parser = PullParser::new(Utf8Reader::new(&b"<a>\xFF</a>"[..]));
+  assert!(parser.any(|event| matches!(event, Err(Error::IO(_)))));
+}
+
+#[test]
+fn byte_offsets_in_stream_encoding() {
+  // the offsets of `<b/>` and `</a>` in the bytes, where an escape sequence is counted in the character after it
+  for (xml, b, a) in [
+    (&b"<?xml version='1.0' encoding='Shift_JIS'?><a>\x93\xFA\x96\x7B<b/></a>"[..], (49, 47), (53, 51)),
+    (b"<?xml version='1.0' encoding='ISO-2022-JP'?><a>\x1B$B\x46\x7C\x4B\x5C\x1B(B<b/></a>", (54, 49), (61, 53)),
+    (b"\xFF\xFE<\0a\0>\0\r\0\n\0\xE5\x65<\0b\0/\0>\0<\0/\0a\0>\0", (14, 7), (22, 11)),
+  ] {
+    let parser = PullParser::new(DecodingReader::new(xml));
+    let events = parser.map(|e| e.unwrap()).collect::<Vec<_>>();
+    let offsets = |kind: &EventKind| {
+      let location = &events.iter().find(|e| &e.kind == kind).unwrap().location;
+      (location.byte_offset, location.char_offset)
+    };
+    assert_eq!((b, a), (offsets(&start("b")), offsets(&end("a"))));
+  }
+
+  // over the buffer boundary
+  let mut xml = b"<?xml version='1.0' encoding='ISO-8859-1'?><a>".to_vec();
+  xml.extend([0xE9; 5000]);
+  xml.extend(b"<b/></a>");
+  let mut parser = PullParser::new(DecodingReader::new(std::io::Cursor::new(xml)));
+  let location = parser.find(|e| e.as_ref().unwrap().kind == start("b")).unwrap().unwrap().location;
+  assert_eq!((5046, 5046), (location.byte_offset, location.char_offset));
+}
+
+/// The location of the `line` and `column` in the ASCII `xml` without CR, whose offsets are counted from the text.
+fn location_of(xml: &str, line: u64, column: u64) -> Location {
+  let offset = xml.split_inclusive('\n').take(line as usize).map(|line| line.len() as u64).sum::<u64>() + column;
+  Location::with_offsets(line, column, offset, offset)
+}
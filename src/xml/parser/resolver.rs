@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+
+use crate::io::{DecodingReader, StringReader};
+use crate::Result;
+
+use super::InputSource;
+
+/// The resolver that the parser asks for the external entities it needs, modelled on SAX
+/// [*EntityResolver2*](https://www.saxproject.org/apidoc/org/xml/sax/ext/EntityResolver2.html) and DOM
+/// [*LSResourceResolver*](https://www.w3.org/TR/DOM-Level-3-LS/load-save.html#LS-LSResourceResolver).
+///
+/// The parser calls [EntityResolver::resolve_entity()] when it needs the external subset of the DTD, an external
+/// parameter entity or an external parsed entity. If the resolver returns `None`, the entity isn't read: a reference
+/// to an external parsed entity is reported as a skipped entity, and the declarations that follow an unread parameter
+/// entity are ignored as if the DTD had been read partially.
+///
+/// The default resolver of the parsers is [DenyAllResolver] that refuses all external access, so that a document
+/// from an untrusted source can't make the parser read local files or remote resources (XXE).
+///
+pub trait EntityResolver {
+  /// Returns the input source of the external entity, or `None` to refuse reading it.
+  ///
+  /// `name` is the name of the entity: `[dtd]` for the external subset, `%` followed by the name for a parameter
+  /// entity, or the name of a general entity. `base_uri` is the system identifier of the entity in which the entity
+  /// was declared, against which the relative `system_id` should be resolved with [resolve_uri()].
+  ///
+  fn resolve_entity(
+    &self, name: &str, public_id: Option<&str>, base_uri: Option<&str>, system_id: &str,
+  ) -> Result<Option<InputSource>>;
+
+  /// Returns the external subset for a document whose document type declaration has no external identifier, or
+  /// `None`, which is the default, to use the internal subset alone.
+  ///
+  fn get_external_subset(&self, _name: &str, _base_uri: Option<&str>) -> Result<Option<InputSource>> {
+    Ok(None)
+  }
+}
+
+/// An [EntityResolver] that refuses to read any external entity. This is the default of the parsers.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DenyAllResolver;
+
+impl EntityResolver for DenyAllResolver {
+  fn resolve_entity(&self, _: &str, _: Option<&str>, _: Option<&str>, _: &str) -> Result<Option<InputSource>> {
+    Ok(None)
+  }
+}
+
+/// An [EntityResolver] that reads the external entities from files under the root directory. The system identifiers
+/// are `file:` URIs or paths relative to the root directory. The encoding of a file is detected by [DecodingReader].
+///
+/// A system identifier that refers to a file outside the root directory, for example with `..` or a symbolic link,
+/// is rejected with an error of [std::io::ErrorKind::PermissionDenied]. Identifiers with other schemes such as
+/// `http:` are refused and the entities are not read.
+///
+pub struct FileSystemResolver {
+  root: PathBuf,
+}
+
+impl FileSystemResolver {
+  /// Creates a resolver confined to the `root` directory, which must exist.
+  pub fn new<P: AsRef<Path>>(root: P) -> Result<FileSystemResolver> {
+    Ok(FileSystemResolver { root: root.as_ref().canonicalize()? })
+  }
+
+  /// The absolute path of the root directory.
+  pub fn root(&self) -> &Path {
+    &self.root
+  }
+}
+
+impl EntityResolver for FileSystemResolver {
+  fn resolve_entity(
+    &self, _name: &str, public_id: Option<&str>, base_uri: Option<&str>, system_id: &str,
+  ) -> Result<Option<InputSource>> {
+    let target = match file_path(&resolve_uri(base_uri, system_id)) {
+      Some(target) => target,
+      None => return Ok(None),
+    };
+    // the path is checked before it's canonicalized so as not to tell whether a file outside the root exists
+    let mut path = PathBuf::new();
+    for component in self.root.join(target).components() {
+      match component {
+        Component::ParentDir => {
+          path.pop();
+        }
+        Component::CurDir => (),
+        component => path.push(component),
+      }
+    }
+    let path = if path.starts_with(&self.root) { path.canonicalize()? } else { path };
+    if !path.starts_with(&self.root) {
+      let message = format!("{} is outside of the root directory {}", system_id, self.root.display());
+      return Err(std::io::Error::new(ErrorKind::PermissionDenied, message).into());
+    }
+    let mut input = InputSource::new(DecodingReader::new(File::open(&path)?));
+    input.public_id = public_id.map(|id| id.to_string());
+    input.system_id = Some(file_uri(&path));
+    Ok(Some(input))
+  }
+}
+
+/// An [EntityResolver] that returns the texts registered in memory by their system identifiers, which is useful for
+/// tests. The identifiers are looked up as they are written and also as resolved against the base URI.
+///
+#[derive(Debug, Clone, Default)]
+pub struct MapResolver {
+  entities: HashMap<String, String>,
+}
+
+impl MapResolver {
+  pub fn new() -> MapResolver {
+    Self::default()
+  }
+
+  /// Registers the `text` of the entity identified by the `system_id`.
+  pub fn insert(&mut self, system_id: &str, text: &str) {
+    self.entities.insert(system_id.to_string(), text.to_string());
+  }
+}
+
+impl EntityResolver for MapResolver {
+  fn resolve_entity(
+    &self, _name: &str, public_id: Option<&str>, base_uri: Option<&str>, system_id: &str,
+  ) -> Result<Option<InputSource>> {
+    let uri = resolve_uri(base_uri, system_id);
+    let text = match self.entities.get(&uri).or_else(|| self.entities.get(system_id)) {
+      Some(text) => text,
+      None => return Ok(None),
+    };
+    let mut input = InputSource::new(StringReader::new(text));
+    input.public_id = public_id.map(|id| id.to_string());
+    input.system_id = Some(uri);
+    Ok(Some(input))
+  }
+}
+
+/// Resolves the `reference` URI against the `base_uri` as defined in
+/// [RFC 3986 §5.2](https://www.rfc-editor.org/rfc/rfc3986#section-5.2). The `reference` is returned as it is if it's
+/// absolute or if there is no base URI.
+///
+/// ```rust
+/// use xenolith::xml::parser::resolve_uri;
+///
+/// assert_eq!("http://a/b/d.dtd", resolve_uri(Some("http://a/b/c.xml"), "d.dtd"));
+/// assert_eq!("file:///x/e.ent", resolve_uri(Some("file:///x/y/c.dtd"), "../e.ent"));
+/// assert_eq!("urn:x", resolve_uri(Some("http://a/b/c.xml"), "urn:x"));
+/// ```
+///
+pub fn resolve_uri(base_uri: Option<&str>, reference: &str) -> String {
+  let base = match base_uri {
+    Some(base) if !has_scheme(reference) => base,
+    _ => return reference.to_string(),
+  };
+  let base = &base[..base.find(['?', '#']).unwrap_or(base.len())];
+  let (scheme, rest) = match base.find(':') {
+    Some(i) if has_scheme(base) => base.split_at(i + 1),
+    _ => ("", base),
+  };
+  if reference.starts_with("//") {
+    return format!("{}{}", scheme, reference);
+  }
+  let (authority, path) = match rest.strip_prefix("//") {
+    Some(rest) => rest.find('/').map(|i| rest.split_at(i)).unwrap_or((rest, "")),
+    None => ("", rest),
+  };
+  let authority = if rest.starts_with("//") { format!("//{}", authority) } else { String::new() };
+  let path = if reference.is_empty() {
+    path.to_string()
+  } else if reference.starts_with('/') {
+    remove_dot_segments(reference)
+  } else {
+    let directory = &path[..path.rfind('/').map(|i| i + 1).unwrap_or(0)];
+    let directory = if directory.is_empty() && !authority.is_empty() { "/" } else { directory };
+    remove_dot_segments(&format!("{}{}", directory, reference))
+  };
+  format!("{}{}{}", scheme, authority, path)
+}
+
+/// Whether the `uri` starts with `scheme ":"`. A single letter is taken as a drive letter rather than a scheme.
+fn has_scheme(uri: &str) -> bool {
+  match uri.find(':') {
+    Some(i) if i > 1 => {
+      let scheme = &uri[..i];
+      scheme.starts_with(|ch: char| ch.is_ascii_alphabetic())
+        && scheme.chars().all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '.'))
+    }
+    _ => false,
+  }
+}
+
+/// `remove_dot_segments` of RFC 3986 §5.2.4.
+fn remove_dot_segments(path: &str) -> String {
+  let mut segments: Vec<&str> = Vec::new();
+  let parts = path.split('/').collect::<Vec<_>>();
+  for (i, segment) in parts.iter().enumerate() {
+    let last = i + 1 == parts.len();
+    match *segment {
+      "." if last => segments.push(""),
+      "." => (),
+      ".." => {
+        if segments.len() > 1 || segments.first().map(|s| !s.is_empty()).unwrap_or(false) {
+          segments.pop();
+        }
+        if last {
+          segments.push("");
+        }
+      }
+      segment => segments.push(segment),
+    }
+  }
+  segments.join("/")
+}
+
+/// The absolute `uri` with the dot segments removed from its path, including the escaped ones such as `%2E%2E`. A
+/// relative URI is returned as it is, since its dot segments can't be removed until it's resolved.
+pub(crate) fn normalize_uri(uri: &str) -> String {
+  if !has_scheme(uri) && !uri.starts_with('/') {
+    return uri.to_string();
+  }
+  let (uri, query) = uri.split_at(uri.find(['?', '#']).unwrap_or(uri.len()));
+  let mut start = if has_scheme(uri) { uri.find(':').unwrap() + 1 } else { 0 };
+  if let Some(rest) = uri[start..].strip_prefix("//") {
+    start += 2 + rest.find('/').unwrap_or(rest.len());
+  }
+  let path = uri[start..].replace("%2E", ".").replace("%2e", ".");
+  format!("{}{}{}", &uri[..start], remove_dot_segments(&path), query)
+}
+
+/// The path that the `file:` URI refers to, or `None` if the `uri` has another scheme. A URI without scheme is taken
+/// as a path.
+pub(crate) fn file_path(uri: &str) -> Option<PathBuf> {
+  let path = if let Some(path) = uri.strip_prefix("file://") {
+    path.strip_prefix("localhost").unwrap_or(path)
+  } else if let Some(path) = uri.strip_prefix("file:") {
+    path
+  } else if has_scheme(uri) {
+    return None;
+  } else {
+    uri
+  };
+  Some(PathBuf::from(percent_decode(path)))
+}
+
+/// The `file:` URI of the absolute `path`. `%`, space, `#` and `?` are escaped so that [file_path()] gives the `path`
+/// back.
+pub(crate) fn file_uri(path: &Path) -> String {
+  let mut uri = String::from("file://");
+  for ch in path.display().to_string().chars() {
+    match ch {
+      '%' | ' ' | '#' | '?' => uri.push_str(&format!("%{:02X}", ch as u8)),
+      ch => uri.push(ch),
+    }
+  }
+  uri
+}
+
+/// Decodes `%XX` escapes in the path of a `file:` URI.
+pub(crate) fn percent_decode(path: &str) -> String {
+  let bytes = path.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut i = 0;
+  while i < bytes.len() {
+    let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+    match hex.filter(|_| bytes[i] == b'%').and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+      Some(byte) => {
+        decoded.push(byte);
+        i += 3;
+      }
+      None => {
+        decoded.push(bytes[i]);
+        i += 1;
+      }
+    }
+  }
+  String::from_utf8_lossy(&decoded).into_owned()
+}
@@ -0,0 +1,219 @@
+use std::fs::{create_dir, write};
+use std::io::ErrorKind;
+use std::rc::Rc;
+
+use crate::io::StringReader;
+use crate::test::temp_dir;
+use crate::xml::parser::{
+  file_path, file_uri, resolve_uri, EntityResolver, ErrorCode, EventKind, FileSystemResolver, InputSource, MapResolver,
+  PullParser,
+};
+use crate::{Error, Location, Result};
+
+fn events(xml: &str, system_id: &str, resolver: Rc<dyn EntityResolver>) -> Result<Vec<EventKind>> {
+  let mut input = InputSource::new(StringReader::new(xml));
+  input.system_id = Some(system_id.to_string());
+  let mut parser = PullParser::with_input_source(input);
+  parser.set_entity_resolver(resolver);
+  parser.map(|event| event.map(|e| e.kind)).collect()
+}
+
+/// Summarizes the events except for the DOCTYPE in the form such as `<a>`, `text`, `&e;` and `</a>`.
+fn summary(events: &[EventKind]) -> Vec<String> {
+  let mut summary = Vec::new();
+  for event in events {
+    summary.push(match event {
+      EventKind::StartElement { name, attributes, .. } => {
+        let attrs = attributes.iter().map(|a| format!(" {}={:?}", a.name, a.value)).collect::<String>();
+        format!("<{}{}>", name, attrs)
+      }
+      EventKind::EndElement { name, .. } => format!("</{}>", name),
+      EventKind::Text(text) => text.to_string(),
+      EventKind::StartEntity(name) => format!("&{};", name),
+      EventKind::EndEntity(name) => format!("&/{};", name),
+      EventKind::SkippedEntity(name) => format!("&?{};", name),
+      _ => continue,
+    });
+  }
+  summary
+}
+
+#[test]
+fn resolve_relative_uris() {
+  let base = Some("http://a/b/c/d;p?q");
+  for (reference, expected) in [
+    ("g", "http://a/b/c/g"),
+    ("./g", "http://a/b/c/g"),
+    ("g/", "http://a/b/c/g/"),
+    ("/g", "http://a/g"),
+    ("//g", "http://g"),
+    ("", "http://a/b/c/d;p"),
+    (".", "http://a/b/c/"),
+    ("..", "http://a/b/"),
+    ("../g", "http://a/b/g"),
+    ("../../g", "http://a/g"),
+    ("../../../g", "http://a/g"),
+    ("/./g", "http://a/g"),
+    ("g/../h", "http://a/b/c/h"),
+    ("urn:isbn:0451450523", "urn:isbn:0451450523"),
+  ] {
+    assert_eq!(expected, resolve_uri(base, reference), "{}", reference);
+  }
+  assert_eq!("file:///x/y.dtd", resolve_uri(Some("file:///x/doc.xml"), "y.dtd"));
+  assert_eq!("dir/y.dtd", resolve_uri(Some("dir/doc.xml"), "y.dtd"));
+  assert_eq!("y.dtd", resolve_uri(None, "y.dtd"));
+}
+
+#[test]
+fn external_access_is_denied_by_default() {
+  let xml = r#"<!DOCTYPE a SYSTEM "a.dtd" [<!ENTITY xxe SYSTEM "file:///etc/passwd">]><a>&xxe;&undeclared;</a>"#;
+  let mut resolver = MapResolver::new();
+  resolver.insert("file:///etc/passwd", "root");
+  let refused = PullParser::new(StringReader::new(xml)).map(|e| e.map(|e| e.kind)).collect::<Result<Vec<_>>>();
+  assert_eq!(vec!["<a>", "&?xxe;", "&?undeclared;", "</a>"], summary(&refused.unwrap()));
+  let events = events(xml, "file:///doc.xml", Rc::new(resolver)).unwrap();
+  assert_eq!(vec!["<a>", "&xxe;", "root", "&/xxe;", "&?undeclared;", "</a>"], summary(&events));
+}
+
+#[test]
+fn external_subset_and_entities() {
+  let mut resolver = MapResolver::new();
+  resolver.insert(
+    "http://example.com/doc/a.dtd",
+    r#"<?xml encoding="UTF-8"?>
+<!ENTITY % draft "INCLUDE">
+<!ENTITY % final "IGNORE">
+<!ENTITY % attrs "id ID #IMPLIED">
+<!ENTITY % mod SYSTEM "mod/mod.ent">
+%mod;
+<![%draft;[
+  <!ATTLIST a %attrs; status CDATA "draft">
+  <![IGNORE[ <!ENTITY ignored "x"> <![INCLUDE[ ]]> ]]>
+]]>
+<![ %final; [ <!ATTLIST a status CDATA "final"> ]]>
+<!ENTITY chapter SYSTEM "chapter.xml">
+<!ENTITY title "%title;">"#,
+  );
+  resolver.insert("http://example.com/doc/mod/mod.ent", r#"<!ENTITY % title "Title"><!ENTITY mod "in module">"#);
+  resolver.insert("http://example.com/doc/chapter.xml", "<?xml version='1.0' encoding='UTF-8'?><c>&title;</c>");
+  let xml = r#"<!DOCTYPE a SYSTEM "a.dtd" [<!ENTITY mod "in internal subset">]><a>&chapter;&mod;</a>"#;
+  let events = events(xml, "http://example.com/doc/a.xml", Rc::new(resolver)).unwrap();
+  let declarations = match &events[0] {
+    EventKind::DocType { declarations, .. } => declarations.iter().map(|decl| format!("{:?}", decl)).collect(),
+    _ => Vec::new(),
+  };
+  let attlist = r#"Attlist { element: "a", definitions: [AttDef { name: "id", att_type: Id, default: Implied }, AttDef { name: "status", att_type: CData, default: Value("draft") }] }"#;
+  assert_eq!(9, declarations.len());
+  assert_eq!(attlist, declarations[6]);
+  assert!(declarations[5].contains(r#"base_uri: Some("http://example.com/doc/mod/mod.ent")"#));
+  assert_eq!(
+    vec![
+      r#"<a status="draft">"#,
+      "&chapter;",
+      "<c>",
+      "&title;",
+      "Title",
+      "&/title;",
+      "</c>",
+      "&/chapter;",
+      "&mod;",
+      "in internal subset"
+    ],
+    summary(&events)[..10]
+  );
+}
+
+#[test]
+fn errors_in_external_entity() {
+  let mut resolver = MapResolver::new();
+  resolver.insert("http://example.com/e.xml", "<e>\n <f></e>");
+  let xml = r#"<!DOCTYPE a [<!ENTITY e SYSTEM "http://example.com/e.xml">]><a>&e;</a>"#;
+  match events(xml, "http://example.com/a.xml", Rc::new(resolver)) {
+    Err(Error::Parse { code, location, system_id, .. }) => {
+      assert_eq!(ErrorCode::ElementTypeMatch, code);
+      assert_eq!(Location::with_offsets(1, 4, 8, 8), location);
+      assert_eq!(Some("http://example.com/e.xml".to_string()), system_id);
+    }
+    unexpected => panic!("{:?}", unexpected),
+  }
+
+  let mut resolver = MapResolver::new();
+  resolver.insert("a.dtd", "<!ELEMENT a ANY>\n<!ATTLIST a x CDATA #IMPLIED> <a/>");
+  match events("<!DOCTYPE a SYSTEM 'a.dtd'><a/>", "a.xml", Rc::new(resolver)) {
+    Err(Error::Parse { code, location, system_id, .. }) => {
+      assert_eq!(ErrorCode::MalformedExtSubset, code);
+      assert_eq!(Location::with_offsets(1, 30, 47, 47), location);
+      assert_eq!(Some("a.dtd".to_string()), system_id);
+    }
+    unexpected => panic!("{:?}", unexpected),
+  }
+
+  let mut resolver = MapResolver::new();
+  resolver.insert("e.xml", "<e/>&e;");
+  let xml = r#"<!DOCTYPE a [<!ENTITY e SYSTEM "e.xml">]><a>&e;</a>"#;
+  assert!(matches!(events(xml, "a.xml", Rc::new(resolver)), Err(Error::Parse { code: ErrorCode::NoRecursion, .. })));
+
+  let xml = "<!DOCTYPE a [<![INCLUDE[<!ELEMENT a ANY>]]>]><a/>";
+  assert!(matches!(
+    events(xml, "a.xml", Rc::new(MapResolver::new())),
+    Err(Error::Parse { code: ErrorCode::MalformedIntSubset, .. })
+  ));
+}
+
+#[test]
+fn file_system_resolver_is_confined_to_root() {
+  let dir = temp_dir("file_system_resolver_is_confined_to_root");
+  let root = dir.as_ref().join("root");
+  create_dir(&root).unwrap();
+  create_dir(root.join("sub")).unwrap();
+  write(root.join("a.dtd"), "<!ENTITY e SYSTEM 'sub/e.xml'>").unwrap();
+  write(root.join("sub").join("e.xml"), "<e>in file</e>").unwrap();
+  write(root.join("l.xml"), b"<?xml encoding='ISO-8859-1'?><l>caf\xE9</l>").unwrap();
+  write(dir.as_ref().join("secret.txt"), "secret").unwrap();
+  let resolver: Rc<dyn EntityResolver> = Rc::new(FileSystemResolver::new(&root).unwrap());
+
+  let xml = "<!DOCTYPE a SYSTEM 'a.dtd'><a>&e;</a>";
+  let events = events(xml, "doc.xml", resolver.clone()).unwrap();
+  assert_eq!(vec!["<a>", "&e;", "<e>", "in file", "</e>", "&/e;", "</a>"], summary(&events));
+  let xml = "<!DOCTYPE a [<!ENTITY l SYSTEM 'l.xml'>]><a>&l;</a>";
+  let events = self::events(xml, "doc.xml", resolver.clone()).unwrap();
+  assert_eq!(vec!["<a>", "&l;", "<l>", "caf\u{E9}", "</l>", "&/l;", "</a>"], summary(&events));
+
+  let secret = dir.as_ref().join("secret.txt").canonicalize().unwrap();
+  let secret = format!("file://{}", secret.display());
+  let mut system_ids = vec![secret.as_str(), "/../secret.txt"];
+  #[cfg(unix)]
+  {
+    std::os::unix::fs::symlink(dir.as_ref().join("secret.txt"), root.join("link.txt")).unwrap();
+    system_ids.push("link.txt");
+  }
+  for system_id in system_ids {
+    let xml = format!("<!DOCTYPE a [<!ENTITY e SYSTEM '{}'>]><a>&e;</a>", system_id);
+    match self::events(&xml, "doc.xml", resolver.clone()) {
+      Err(Error::IO(err)) => assert_eq!(ErrorKind::PermissionDenied, err.kind()),
+      unexpected => panic!("{}: {:?}", system_id, unexpected),
+    }
+  }
+  let xml = "<!DOCTYPE a [<!ENTITY e SYSTEM 'http://example.com/e.xml'>]><a>&e;</a>";
+  assert_eq!(vec!["<a>", "&?e;", "</a>"], summary(&self::events(xml, "doc.xml", resolver).unwrap()));
+}
+
+#[test]
+fn file_uri_round_trip() {
+  let path = std::env::temp_dir().join("a %41 #1?.xml");
+  let uri = file_uri(&path);
+  assert!(uri.ends_with("/a%20%2541%20%231%3F.xml"), "{}", uri);
+  assert_eq!(Some(path), file_path(&uri));
+
+  // an entity relative to an external subset in a directory whose name looks escaped
+  let dir = temp_dir("file_uri_round_trip");
+  let root = dir.as_ref().join("root");
+  create_dir(&root).unwrap();
+  create_dir(root.join("%41 #")).unwrap();
+  write(root.join("%41 #").join("a.dtd"), "<!ENTITY e SYSTEM 'e.xml'>").unwrap();
+  write(root.join("%41 #").join("e.xml"), "<e/>").unwrap();
+  let resolver: Rc<dyn EntityResolver> = Rc::new(FileSystemResolver::new(&root).unwrap());
+  let xml = "<!DOCTYPE a SYSTEM '%2541%20%23/a.dtd'><a>&e;</a>";
+  let events = events(xml, "doc.xml", resolver).unwrap();
+  assert_eq!(vec!["<a>", "&e;", "<e>", "</e>", "&/e;", "</a>"], summary(&events));
+}
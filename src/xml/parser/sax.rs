@@ -0,0 +1,420 @@
+//! Push-style parser interfaces modelled on [SAX2](https://www.saxproject.org/apidoc/overview-summary.html).
+//!
+//! All handler methods have empty default implementations, so a handler only needs to override the callbacks it is
+//! interested in. Returning `Err` from a callback aborts the parsing with that error.
+//!
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use crate::xml::dom::XMLNS_NAMESPACE_URI;
+use crate::{Error, Location, Result};
+
+use super::chars::is_whitespace;
+use super::{
+  Attribute, ContentSpec, DenyAllResolver, EntityLimits, EntityResolver, EventKind, InputSource, MarkupDecl, PullParser,
+};
+
+/// [Interface *Locator*](https://www.saxproject.org/apidoc/org/xml/sax/Locator.html) that tells the handlers where
+/// the current callback occurs in the document.
+///
+pub trait Locator {
+  /// The location where the markup or text that caused the current callback starts.
+  fn location(&self) -> Location;
+  fn public_id(&self) -> Option<String>;
+  fn system_id(&self) -> Option<String>;
+}
+
+/// [Interface *ContentHandler*](https://www.saxproject.org/apidoc/org/xml/sax/ContentHandler.html) that receives the
+/// logical content of a document.
+///
+/// `namespace_uri` and `local_name` of elements are `None` unless the parser performs namespace processing. With
+/// namespace processing, namespace declarations are reported by `start_prefix_mapping()` and `end_prefix_mapping()`
+/// instead of as attributes, and the empty prefix stands for the default namespace.
+///
+pub trait ContentHandler {
+  /// Receives an object that can be asked for the location of subsequent callbacks. This is called before any other
+  /// callbacks.
+  fn set_document_locator(&mut self, _locator: Rc<dyn Locator>) {}
+  fn start_document(&mut self) -> Result<()> {
+    Ok(())
+  }
+  fn end_document(&mut self) -> Result<()> {
+    Ok(())
+  }
+  fn start_prefix_mapping(&mut self, _prefix: &str, _uri: &str) -> Result<()> {
+    Ok(())
+  }
+  fn end_prefix_mapping(&mut self, _prefix: &str) -> Result<()> {
+    Ok(())
+  }
+  fn start_element(
+    &mut self, _namespace_uri: Option<&str>, _local_name: Option<&str>, _qname: &str, _attributes: &[Attribute],
+  ) -> Result<()> {
+    Ok(())
+  }
+  fn end_element(&mut self, _namespace_uri: Option<&str>, _local_name: Option<&str>, _qname: &str) -> Result<()> {
+    Ok(())
+  }
+  fn characters(&mut self, _text: &str) -> Result<()> {
+    Ok(())
+  }
+  /// Receives whitespace in element content, that is, whitespace in an element whose declared content model doesn't
+  /// allow character data. The whitespace is reported by `characters()` instead if the element type is not declared.
+  fn ignorable_whitespace(&mut self, _text: &str) -> Result<()> {
+    Ok(())
+  }
+  fn processing_instruction(&mut self, _target: &str, _data: &str) -> Result<()> {
+    Ok(())
+  }
+  fn skipped_entity(&mut self, _name: &str) -> Result<()> {
+    Ok(())
+  }
+}
+
+/// [Interface *LexicalHandler*](https://www.saxproject.org/apidoc/org/xml/sax/ext/LexicalHandler.html) that receives
+/// lexical information that doesn't affect the logical content, such as comments and CDATA section boundaries.
+///
+pub trait LexicalHandler {
+  fn start_dtd(&mut self, _name: &str, _public_id: Option<&str>, _system_id: Option<&str>) -> Result<()> {
+    Ok(())
+  }
+  fn end_dtd(&mut self) -> Result<()> {
+    Ok(())
+  }
+  /// Reports the beginning of a general entity referred to in the content. Parameter entities and the external DTD
+  /// subset are not reported since the DTD is read as a whole before `start_dtd()`.
+  fn start_entity(&mut self, _name: &str) -> Result<()> {
+    Ok(())
+  }
+  fn end_entity(&mut self, _name: &str) -> Result<()> {
+    Ok(())
+  }
+  fn start_cdata(&mut self) -> Result<()> {
+    Ok(())
+  }
+  fn end_cdata(&mut self) -> Result<()> {
+    Ok(())
+  }
+  fn comment(&mut self, _text: &str) -> Result<()> {
+    Ok(())
+  }
+}
+
+/// [Interface *DeclHandler*](https://www.saxproject.org/apidoc/org/xml/sax/ext/DeclHandler.html) that receives
+/// element, attribute and entity declarations in the DTD.
+///
+pub trait DeclHandler {
+  /// Reports an element type declaration. The content `model` is normalized without whitespace, such as `EMPTY`,
+  /// `ANY`, `(#PCDATA|a)*` or `(a,b?)+`.
+  fn element_decl(&mut self, _name: &str, _model: &str) -> Result<()> {
+    Ok(())
+  }
+  /// Reports an attribute declaration. `mode` is one of `#IMPLIED`, `#REQUIRED` or `#FIXED`, or `None`.
+  fn attribute_decl(
+    &mut self, _element_name: &str, _attribute_name: &str, _attribute_type: &str, _mode: Option<&str>,
+    _value: Option<&str>,
+  ) -> Result<()> {
+    Ok(())
+  }
+  fn internal_entity_decl(&mut self, _name: &str, _value: &str) -> Result<()> {
+    Ok(())
+  }
+  fn external_entity_decl(&mut self, _name: &str, _public_id: Option<&str>, _system_id: &str) -> Result<()> {
+    Ok(())
+  }
+}
+
+/// [Interface *DTDHandler*](https://www.saxproject.org/apidoc/org/xml/sax/DTDHandler.html) that receives notation
+/// and unparsed entity declarations.
+///
+pub trait DTDHandler {
+  fn notation_decl(&mut self, _name: &str, _public_id: Option<&str>, _system_id: Option<&str>) -> Result<()> {
+    Ok(())
+  }
+  fn unparsed_entity_decl(
+    &mut self, _name: &str, _public_id: Option<&str>, _system_id: &str, _notation_name: &str,
+  ) -> Result<()> {
+    Ok(())
+  }
+}
+
+/// [Interface *ErrorHandler*](https://www.saxproject.org/apidoc/org/xml/sax/ErrorHandler.html) that receives
+/// warnings and errors of the parser. Returning `Err` from `warning()` or `error()` aborts the parsing with that
+/// error.
+///
+pub trait ErrorHandler {
+  /// Receives a warning about what the parser left unread, as [PullParser::warnings()], before the callback of the
+  /// markup it's about.
+  fn warning(&mut self, _error: &Error) -> Result<()> {
+    Ok(())
+  }
+  /// Receives an error that the parser repaired in recovery mode, as [PullParser::diagnostics()].
+  fn error(&mut self, _error: &Error) -> Result<()> {
+    Ok(())
+  }
+  /// Receives the error of the parser that stops the parsing. Errors returned by the handler's own callbacks are not
+  /// reported here.
+  fn fatal_error(&mut self, _error: &Error) {}
+}
+
+/// A handler that implements all handler interfaces with no-op callbacks, like Java's `DefaultHandler2`.
+///
+#[derive(Default)]
+pub struct DefaultHandler;
+
+impl ContentHandler for DefaultHandler {}
+impl LexicalHandler for DefaultHandler {}
+impl DeclHandler for DefaultHandler {}
+impl DTDHandler for DefaultHandler {}
+impl ErrorHandler for DefaultHandler {}
+
+/// A push parser that reads a document with [PullParser] and reports its contents to a handler.
+///
+/// ```
+/// use xenolith::io::StringReader;
+/// use xenolith::xml::parser::{Attribute, ContentHandler, DTDHandler, DeclHandler, ErrorHandler, InputSource};
+/// use xenolith::xml::parser::{LexicalHandler, SAXParser};
+///
+/// struct ElementCounter(usize);
+/// impl ContentHandler for ElementCounter {
+///   fn start_element(
+///     &mut self, _: Option<&str>, _: Option<&str>, _: &str, _: &[Attribute],
+///   ) -> xenolith::Result<()> {
+///     self.0 += 1;
+///     Ok(())
+///   }
+/// }
+/// impl LexicalHandler for ElementCounter {}
+/// impl DeclHandler for ElementCounter {}
+/// impl DTDHandler for ElementCounter {}
+/// impl ErrorHandler for ElementCounter {}
+///
+/// let mut counter = ElementCounter(0);
+/// let input = InputSource::new(StringReader::new("<a><b/><c/></a>"));
+/// SAXParser::new().parse(input, &mut counter).unwrap();
+/// assert_eq!(3, counter.0);
+/// ```
+///
+pub struct SAXParser {
+  namespace_aware: bool,
+  recovery: bool,
+  entity_limits: EntityLimits,
+  resolver: Rc<dyn EntityResolver>,
+}
+
+impl SAXParser {
+  pub fn new() -> SAXParser {
+    SAXParser {
+      namespace_aware: true,
+      recovery: false,
+      entity_limits: EntityLimits::default(),
+      resolver: Rc::new(DenyAllResolver),
+    }
+  }
+
+  /// Whether the parser performs namespace processing. This is `true` by default.
+  pub fn namespace_aware(&self) -> bool {
+    self.namespace_aware
+  }
+
+  pub fn set_namespace_aware(&mut self, namespace_aware: bool) {
+    self.namespace_aware = namespace_aware;
+  }
+
+  /// Whether the parser repairs common errors and goes on as the recovery mode of [PullParser]. The repaired errors
+  /// are reported to [ErrorHandler::error()]. This is `false` by default.
+  pub fn recovery(&self) -> bool {
+    self.recovery
+  }
+
+  pub fn set_recovery(&mut self, recovery: bool) {
+    self.recovery = recovery;
+  }
+
+  /// The limits on the expansion of internal entities. See [EntityLimits] for the defaults.
+  pub fn entity_limits(&self) -> &EntityLimits {
+    &self.entity_limits
+  }
+
+  pub fn set_entity_limits(&mut self, entity_limits: EntityLimits) {
+    self.entity_limits = entity_limits;
+  }
+
+  /// The resolver that reads external entities. The default is [DenyAllResolver] that refuses all external access.
+  pub fn entity_resolver(&self) -> &Rc<dyn EntityResolver> {
+    &self.resolver
+  }
+
+  pub fn set_entity_resolver(&mut self, resolver: Rc<dyn EntityResolver>) {
+    self.resolver = resolver;
+  }
+
+  pub fn parse<H>(&mut self, input: InputSource, handler: &mut H) -> Result<()>
+  where
+    H: ContentHandler + LexicalHandler + DeclHandler + DTDHandler + ErrorHandler,
+  {
+    let mut parser = PullParser::with_input_source(input);
+    parser.set_namespace_aware(self.namespace_aware);
+    parser.set_recovery(self.recovery);
+    parser.set_entity_limits(self.entity_limits.clone());
+    parser.set_entity_resolver(self.resolver.clone());
+    let locator = Rc::new(SAXLocator {
+      location: RefCell::new(parser.location()),
+      public_id: RefCell::new(parser.public_id().map(|id| id.to_string())),
+      system_id: RefCell::new(parser.system_id().map(|id| id.to_string())),
+    });
+    handler.set_document_locator(locator.clone());
+    Self::dispatch(&mut parser, &locator, handler)
+  }
+
+  fn dispatch<H>(parser: &mut PullParser, locator: &SAXLocator, handler: &mut H) -> Result<()>
+  where
+    H: ContentHandler + LexicalHandler + DeclHandler + DTDHandler + ErrorHandler,
+  {
+    handler.start_document()?;
+    let mut prefixes = Vec::new();
+    // the names of the elements declared with element content, and whether each open element is one of them
+    let mut element_content = HashSet::new();
+    let mut in_element_content = Vec::new();
+    let (mut warnings, mut diagnostics) = (0, 0);
+    loop {
+      let event = parser.next_event();
+      for warning in &parser.warnings()[warnings..] {
+        warnings += 1;
+        handler.warning(warning)?;
+      }
+      for error in &parser.diagnostics()[diagnostics..] {
+        diagnostics += 1;
+        handler.error(error)?;
+      }
+      let event = event.inspect_err(|err| handler.fatal_error(err))?;
+      locator.update(parser, event.location);
+      match event.kind {
+        EventKind::XMLDecl { .. } => (),
+        EventKind::DocType { name, public_id, system_id, declarations, .. } => {
+          handler.start_dtd(&name, public_id.as_deref(), system_id.as_deref())?;
+          for decl in declarations {
+            if let MarkupDecl::Element { name, content_spec: ContentSpec::Children(_) } = &decl {
+              element_content.insert(name.clone());
+            }
+            Self::dispatch_decl(decl, handler)?;
+          }
+          handler.end_dtd()?;
+        }
+        EventKind::StartElement { name, namespace_uri, local_name, mut attributes } => {
+          let mut declared = Vec::new();
+          if parser.namespace_aware() {
+            for attr in attributes.iter().filter(|attr| attr.namespace_uri.as_deref() == Some(XMLNS_NAMESPACE_URI)) {
+              let prefix = if attr.name == "xmlns" { "" } else { attr.local_name.as_deref().unwrap_or_default() };
+              handler.start_prefix_mapping(prefix, &attr.value)?;
+              declared.push(prefix.to_string());
+            }
+            attributes.retain(|attr| attr.namespace_uri.as_deref() != Some(XMLNS_NAMESPACE_URI));
+          }
+          prefixes.push(declared);
+          in_element_content.push(element_content.contains(&name));
+          handler.start_element(namespace_uri.as_deref(), local_name.as_deref(), &name, &attributes)?
+        }
+        EventKind::EndElement { name, namespace_uri, local_name } => {
+          handler.end_element(namespace_uri.as_deref(), local_name.as_deref(), &name)?;
+          for prefix in prefixes.pop().unwrap_or_default() {
+            handler.end_prefix_mapping(&prefix)?;
+          }
+          in_element_content.pop();
+        }
+        EventKind::Text(text) if in_element_content.last() == Some(&true) && text.chars().all(is_whitespace) => {
+          handler.ignorable_whitespace(&text)?
+        }
+        EventKind::Text(text) => handler.characters(&text)?,
+        EventKind::StartEntity(name) => handler.start_entity(&name)?,
+        EventKind::EndEntity(name) => handler.end_entity(&name)?,
+        EventKind::SkippedEntity(name) => handler.skipped_entity(&name)?,
+        EventKind::CDATA(text) => {
+          handler.start_cdata()?;
+          handler.characters(&text)?;
+          handler.end_cdata()?;
+        }
+        EventKind::Comment(text) => handler.comment(&text)?,
+        EventKind::ProcessingInstruction { target, data } => {
+          handler.processing_instruction(&target, data.as_deref().unwrap_or_default())?
+        }
+        EventKind::EndDocument => return handler.end_document(),
+      }
+    }
+  }
+
+  fn dispatch_decl<H>(decl: MarkupDecl, handler: &mut H) -> Result<()>
+  where
+    H: ContentHandler + LexicalHandler + DeclHandler + DTDHandler + ErrorHandler,
+  {
+    match decl {
+      MarkupDecl::Element { name, content_spec } => handler.element_decl(&name, &content_spec.to_string()),
+      MarkupDecl::Attlist { element, definitions } => {
+        for def in definitions {
+          let att_type = def.att_type.to_string();
+          handler.attribute_decl(&element, &def.name, &att_type, def.default.mode(), def.default.value())?;
+        }
+        Ok(())
+      }
+      MarkupDecl::Entity(decl) => {
+        let name = if decl.parameter { format!("%{}", decl.name) } else { decl.name };
+        match (decl.value, decl.system_id, decl.notation_name) {
+          (Some(value), _, _) => handler.internal_entity_decl(&name, &value),
+          (None, Some(system_id), Some(notation_name)) => {
+            handler.unparsed_entity_decl(&name, decl.public_id.as_deref(), &system_id, &notation_name)
+          }
+          (None, system_id, _) => {
+            handler.external_entity_decl(&name, decl.public_id.as_deref(), system_id.as_deref().unwrap_or_default())
+          }
+        }
+      }
+      MarkupDecl::Notation { name, public_id, system_id } => {
+        handler.notation_decl(&name, public_id.as_deref(), system_id.as_deref())
+      }
+      MarkupDecl::ProcessingInstruction { target, data } => {
+        handler.processing_instruction(&target, data.as_deref().unwrap_or_default())
+      }
+      MarkupDecl::Comment(text) => handler.comment(&text),
+    }
+  }
+}
+
+impl Default for SAXParser {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+/// The [Locator] of the document entity or the external entity that the current event has been read from.
+struct SAXLocator {
+  location: RefCell<Location>,
+  public_id: RefCell<Option<String>>,
+  system_id: RefCell<Option<String>>,
+}
+
+impl SAXLocator {
+  fn update(&self, parser: &PullParser, location: Location) {
+    *self.location.borrow_mut() = location;
+    let mut public_id = self.public_id.borrow_mut();
+    if public_id.as_deref() != parser.public_id() {
+      *public_id = parser.public_id().map(|id| id.to_string());
+    }
+    let mut system_id = self.system_id.borrow_mut();
+    if system_id.as_deref() != parser.system_id() {
+      *system_id = parser.system_id().map(|id| id.to_string());
+    }
+  }
+}
+
+impl Locator for SAXLocator {
+  fn location(&self) -> Location {
+    self.location.borrow().clone()
+  }
+  fn public_id(&self) -> Option<String> {
+    self.public_id.borrow().clone()
+  }
+  fn system_id(&self) -> Option<String> {
+    self.system_id.borrow().clone()
+  }
+}
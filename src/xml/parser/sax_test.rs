@@ -0,0 +1,356 @@
+use std::rc::Rc;
+
+use crate::io::StringReader;
+use crate::test::compatibility::java;
+use crate::xml::parser::{
+  Attribute, ContentHandler, DTDHandler, DeclHandler, ErrorCode, ErrorHandler, InputSource, LexicalHandler, Locator,
+  MapResolver, SAXParser,
+};
+use crate::{Error, Result};
+
+/// A handler that records callbacks in the same format as `Recorder` in Java code of [java_recorder()].
+#[derive(Default)]
+struct Recorder {
+  locator: Option<Rc<dyn Locator>>,
+  calls: Vec<String>,
+}
+
+impl Recorder {
+  fn record(&mut self, call: String) -> Result<()> {
+    self.calls.push(call);
+    Ok(())
+  }
+}
+
+impl ContentHandler for Recorder {
+  fn set_document_locator(&mut self, locator: Rc<dyn Locator>) {
+    self.locator = Some(locator);
+  }
+  fn start_document(&mut self) -> Result<()> {
+    self.record("startDocument()".to_string())
+  }
+  fn end_document(&mut self) -> Result<()> {
+    self.record("endDocument()".to_string())
+  }
+  fn start_prefix_mapping(&mut self, prefix: &str, uri: &str) -> Result<()> {
+    self.record(format!("startPrefixMapping({},{})", prefix, uri))
+  }
+  fn end_prefix_mapping(&mut self, prefix: &str) -> Result<()> {
+    self.record(format!("endPrefixMapping({})", prefix))
+  }
+  fn start_element(
+    &mut self, namespace_uri: Option<&str>, local_name: Option<&str>, qname: &str, attributes: &[Attribute],
+  ) -> Result<()> {
+    let attrs = attributes
+      .iter()
+      .map(|a| format!("{{{}}}{}={}", a.namespace_uri.as_deref().unwrap_or_default(), a.name, a.value))
+      .collect::<Vec<_>>()
+      .join(",");
+    let uri = namespace_uri.unwrap_or_default();
+    self.record(format!("startElement({{{}}}{},{},[{}])", uri, local_name.unwrap_or_default(), qname, attrs))
+  }
+  fn end_element(&mut self, namespace_uri: Option<&str>, local_name: Option<&str>, qname: &str) -> Result<()> {
+    let uri = namespace_uri.unwrap_or_default();
+    self.record(format!("endElement({{{}}}{},{})", uri, local_name.unwrap_or_default(), qname))
+  }
+  fn characters(&mut self, text: &str) -> Result<()> {
+    self.record(format!("characters({})", text))
+  }
+  fn ignorable_whitespace(&mut self, text: &str) -> Result<()> {
+    self.record(format!("ignorableWhitespace({})", text))
+  }
+  fn processing_instruction(&mut self, target: &str, data: &str) -> Result<()> {
+    self.record(format!("processingInstruction({},{})", target, data))
+  }
+}
+
+impl LexicalHandler for Recorder {
+  fn start_dtd(&mut self, name: &str, public_id: Option<&str>, system_id: Option<&str>) -> Result<()> {
+    self.record(format!("startDTD({},{:?},{:?})", name, public_id, system_id))
+  }
+  fn end_dtd(&mut self) -> Result<()> {
+    self.record("endDTD()".to_string())
+  }
+  fn start_cdata(&mut self) -> Result<()> {
+    self.record("startCDATA()".to_string())
+  }
+  fn end_cdata(&mut self) -> Result<()> {
+    self.record("endCDATA()".to_string())
+  }
+  fn start_entity(&mut self, name: &str) -> Result<()> {
+    self.record(format!("startEntity({})", name))
+  }
+  fn end_entity(&mut self, name: &str) -> Result<()> {
+    self.record(format!("endEntity({})", name))
+  }
+  fn comment(&mut self, text: &str) -> Result<()> {
+    self.record(format!("comment({})", text))
+  }
+}
+
+impl DeclHandler for Recorder {
+  fn element_decl(&mut self, name: &str, model: &str) -> Result<()> {
+    self.record(format!("elementDecl({},{})", name, model))
+  }
+  fn attribute_decl(
+    &mut self, element_name: &str, attribute_name: &str, attribute_type: &str, mode: Option<&str>, value: Option<&str>,
+  ) -> Result<()> {
+    self.record(format!("attributeDecl({},{},{},{:?},{:?})", element_name, attribute_name, attribute_type, mode, value))
+  }
+  fn internal_entity_decl(&mut self, name: &str, value: &str) -> Result<()> {
+    self.record(format!("internalEntityDecl({},{})", name, value))
+  }
+  fn external_entity_decl(&mut self, name: &str, public_id: Option<&str>, system_id: &str) -> Result<()> {
+    self.record(format!("externalEntityDecl({},{:?},{})", name, public_id, system_id))
+  }
+}
+
+impl DTDHandler for Recorder {
+  fn notation_decl(&mut self, name: &str, public_id: Option<&str>, system_id: Option<&str>) -> Result<()> {
+    self.record(format!("notationDecl({},{:?},{:?})", name, public_id, system_id))
+  }
+  fn unparsed_entity_decl(
+    &mut self, name: &str, public_id: Option<&str>, system_id: &str, notation_name: &str,
+  ) -> Result<()> {
+    self.record(format!("unparsedEntityDecl({},{:?},{},{})", name, public_id, system_id, notation_name))
+  }
+}
+
+impl ErrorHandler for Recorder {
+  fn warning(&mut self, error: &Error) -> Result<()> {
+    self.record(format!("warning({})", code(error)))
+  }
+  fn error(&mut self, error: &Error) -> Result<()> {
+    self.record(format!("error({})", code(error)))
+  }
+  fn fatal_error(&mut self, _error: &Error) {
+    self.calls.push("fatalError()".to_string());
+  }
+}
+
+fn code(error: &Error) -> u16 {
+  match error {
+    Error::Parse { code, .. } => code.code(),
+    _ => 0,
+  }
+}
+
+fn parse(xml: &str) -> (Recorder, Result<()>) {
+  let mut recorder = Recorder::default();
+  let result = SAXParser::new().parse(InputSource::new(StringReader::new(xml)), &mut recorder);
+  (recorder, result)
+}
+
+/// Java code that parses `xml` with the JDK's SAX parser and prints callbacks one per line.
+fn java_recorder(xml: &str) -> String {
+  format!(
+    r#"
+    String xml = "{}";
+    class Recorder extends org.xml.sax.ext.DefaultHandler2 {{
+      void p(String s) {{ System.out.println(s); }}
+      String o(String s) {{ return s == null ? "None" : "Some(\"" + s + "\")"; }}
+      public void startDocument() {{ p("startDocument()"); }}
+      public void endDocument() {{ p("endDocument()"); }}
+      public void startPrefixMapping(String p, String u) {{ p("startPrefixMapping(" + p + "," + u + ")"); }}
+      public void endPrefixMapping(String p) {{ p("endPrefixMapping(" + p + ")"); }}
+      public void startElement(String u, String l, String q, Attributes a) {{
+        StringBuilder s = new StringBuilder();
+        for(int i = 0; i < a.getLength(); i++) {{
+          s.append(i == 0 ? "" : ",").append("{{" + a.getURI(i) + "}}" + a.getQName(i) + "=" + a.getValue(i));
+        }}
+        p("startElement({{" + u + "}}" + l + "," + q + ",[" + s + "])");
+      }}
+      public void endElement(String u, String l, String q) {{ p("endElement({{" + u + "}}" + l + "," + q + ")"); }}
+      public void characters(char[] ch, int s, int l) {{ p("characters(" + new String(ch, s, l) + ")"); }}
+      public void ignorableWhitespace(char[] ch, int s, int l) {{
+        p("ignorableWhitespace(" + new String(ch, s, l) + ")");
+      }}
+      public void processingInstruction(String t, String d) {{ p("processingInstruction(" + t + "," + d + ")"); }}
+      public void startDTD(String n, String pub, String sys) {{ p("startDTD(" + n + "," + o(pub) + "," + o(sys) + ")"); }}
+      public void endDTD() {{ p("endDTD()"); }}
+      public void startCDATA() {{ p("startCDATA()"); }}
+      public void endCDATA() {{ p("endCDATA()"); }}
+      public void startEntity(String n) {{ p("startEntity(" + n + ")"); }}
+      public void endEntity(String n) {{ p("endEntity(" + n + ")"); }}
+      public void comment(char[] ch, int s, int l) {{ p("comment(" + new String(ch, s, l) + ")"); }}
+      public void elementDecl(String n, String m) {{ p("elementDecl(" + n + "," + m + ")"); }}
+      public void attributeDecl(String e, String n, String t, String m, String v) {{
+        p("attributeDecl(" + e + "," + n + "," + t + "," + o(m) + "," + o(v) + ")");
+      }}
+      public void internalEntityDecl(String n, String v) {{ p("internalEntityDecl(" + n + "," + v + ")"); }}
+      public void externalEntityDecl(String n, String pub, String sys) {{
+        p("externalEntityDecl(" + n + "," + o(pub) + "," + sys + ")");
+      }}
+      public void notationDecl(String n, String pub, String sys) {{
+        p("notationDecl(" + n + "," + o(pub) + "," + o(sys) + ")");
+      }}
+      public void unparsedEntityDecl(String n, String pub, String sys, String nt) {{
+        p("unparsedEntityDecl(" + n + "," + o(pub) + "," + sys + "," + nt + ")");
+      }}
+    }}
+    Recorder recorder = new Recorder();
+    SAXParserFactory factory = SAXParserFactory.newInstance();
+    factory.setNamespaceAware(true);
+    factory.setFeature("http://apache.org/xml/features/nonvalidating/load-external-dtd", false);
+    SAXParser parser = factory.newSAXParser();
+    parser.setProperty("http://xml.org/sax/properties/lexical-handler", recorder);
+    parser.setProperty("http://xml.org/sax/properties/declaration-handler", recorder);
+    parser.parse(new InputSource(new StringReader(xml)), recorder);
+    "#,
+    xml.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+  )
+}
+
+#[test]
+fn callbacks_compatible_with_java() {
+  let xml = r#"<?xml version="1.0"?><!DOCTYPE root PUBLIC "-//P//EN" "root.dtd" []><!--c--><root a='1' b="2" xmlns:p="urn:p"><x>text</x><![CDATA[<cdata>]]><?pi data?><p:y xmlns="urn:d" p:z="3"/></root>"#;
+  let (recorder, result) = parse(xml);
+  result.unwrap();
+  let expected = java::run("callbacks_compatible_with_java", &java_recorder(xml));
+  // the Java code is set not to load the external subset, which is left unread with a warning here
+  let calls = recorder.calls.iter().filter(|call| *call != "warning(502)").collect::<Vec<_>>();
+  assert_eq!(expected.lines().collect::<Vec<_>>(), calls);
+}
+
+#[test]
+fn declarations_compatible_with_java() {
+  let xml = r#"<!DOCTYPE a [
+  <!ELEMENT a (b, (c | d)*)+>
+  <!ELEMENT b EMPTY>
+  <!ELEMENT d (#PCDATA | b)*>
+  <!ATTLIST a id ID #REQUIRED n NOTATION (png) #IMPLIED>
+  <!ATTLIST d kind (x|y) "x" v CDATA #FIXED "1">
+  <!ENTITY e2 "<b/>">
+  <!ENTITY e1 "&#x41;<b/>&e2;">
+  <!ENTITY ext PUBLIC "-//E//EN" "http://example.com/ext.xml">
+  <!ENTITY img SYSTEM "http://example.com/a.png" NDATA png>
+  <!NOTATION png PUBLIC "image/png">
+  <!-- comment -->
+]><a id="1"> <b/>&e1; <d> </d></a>"#;
+  let (recorder, result) = parse(xml);
+  result.unwrap();
+  let expected = java::run("declarations_compatible_with_java", &java_recorder(xml));
+  assert_eq!(expected.lines().collect::<Vec<_>>(), recorder.calls);
+}
+
+#[test]
+fn locator() {
+  struct LocationRecorder(Option<Rc<dyn Locator>>, Vec<String>);
+  impl ContentHandler for LocationRecorder {
+    fn set_document_locator(&mut self, locator: Rc<dyn Locator>) {
+      self.0 = Some(locator);
+    }
+    fn start_element(&mut self, _: Option<&str>, _: Option<&str>, qname: &str, _: &[Attribute]) -> Result<()> {
+      let locator = self.0.as_ref().unwrap();
+      self.1.push(format!("{}{}{:?}", qname, locator.location(), locator.system_id()));
+      Ok(())
+    }
+  }
+  impl LexicalHandler for LocationRecorder {}
+  impl DeclHandler for LocationRecorder {}
+  impl DTDHandler for LocationRecorder {}
+  impl ErrorHandler for LocationRecorder {}
+
+  let mut input = InputSource::new(StringReader::new("<a>\n <b/></a>"));
+  input.system_id = Some("file:///a.xml".to_string());
+  let mut handler = LocationRecorder(None, Vec::new());
+  SAXParser::new().parse(input, &mut handler).unwrap();
+  assert_eq!(vec!["a(1,1)Some(\"file:///a.xml\")", "b(2,2)Some(\"file:///a.xml\")"], handler.1);
+
+  // the system identifier is that of the external entity being read
+  let mut resolver = MapResolver::new();
+  resolver.insert("file:///c.xml", "\n<c/>");
+  let mut input = InputSource::new(StringReader::new("<!DOCTYPE a [<!ENTITY c SYSTEM 'c.xml'>]><a>&c;<b/></a>"));
+  input.system_id = Some("file:///a.xml".to_string());
+  let mut parser = SAXParser::new();
+  parser.set_entity_resolver(Rc::new(resolver));
+  let mut handler = LocationRecorder(None, Vec::new());
+  parser.parse(input, &mut handler).unwrap();
+  let expected = ["a(1,42)Some(\"file:///a.xml\")", "c(2,1)Some(\"file:///c.xml\")", "b(1,48)Some(\"file:///a.xml\")"];
+  assert_eq!(expected.to_vec(), handler.1);
+}
+
+#[test]
+fn fatal_error_stops_parsing() {
+  let (recorder, result) = parse("<a><b></a>");
+  assert!(matches!(result, Err(Error::Parse { .. })));
+  assert_eq!(
+    vec!["startDocument()", "startElement({}a,a,[])", "startElement({}b,b,[])", "fatalError()"],
+    recorder.calls
+  );
+  assert!(recorder.locator.is_some());
+}
+
+#[test]
+fn warnings_and_recovered_errors() {
+  let xml = "<?xml version='1.2'?><!DOCTYPE a SYSTEM 'a.dtd' [<!ENTITY e SYSTEM 'e.xml'>]><a>&e;&f;</a>";
+  let (recorder, result) = parse(xml);
+  result.unwrap();
+  let expected = vec![
+    "startDocument()",
+    "warning(501)",
+    "warning(502)",
+    "startDTD(a,None,Some(\"a.dtd\"))",
+    "externalEntityDecl(e,None,e.xml)",
+    "endDTD()",
+    "startElement({}a,a,[])",
+    "warning(503)",
+    "warning(503)",
+    "endElement({}a,a)",
+    "endDocument()",
+  ];
+  assert_eq!(expected, recorder.calls);
+
+  let mut recorder = Recorder::default();
+  let mut parser = SAXParser::new();
+  parser.set_recovery(true);
+  parser.parse(InputSource::new(StringReader::new("<a>R&D</a>")), &mut recorder).unwrap();
+  assert_eq!(
+    vec![
+      "startDocument()",
+      "startElement({}a,a,[])",
+      "error(151)",
+      "characters(R&D)",
+      "endElement({}a,a)",
+      "endDocument()"
+    ],
+    recorder.calls
+  );
+}
+
+#[test]
+fn errors_of_handler_are_not_fatal_errors() {
+  struct Abort(Vec<String>);
+  impl ContentHandler for Abort {
+    fn start_element(&mut self, _: Option<&str>, _: Option<&str>, _: &str, _: &[Attribute]) -> Result<()> {
+      Err(Error::IO(std::io::Error::other("aborted")))
+    }
+  }
+  impl LexicalHandler for Abort {}
+  impl DeclHandler for Abort {}
+  impl DTDHandler for Abort {}
+  impl ErrorHandler for Abort {
+    fn warning(&mut self, error: &Error) -> Result<()> {
+      match error {
+        Error::Parse { code, location, system_id, message } => Err(Error::Parse {
+          code: *code,
+          location: location.clone(),
+          system_id: system_id.clone(),
+          message: message.clone(),
+        }),
+        _ => Ok(()),
+      }
+    }
+    fn fatal_error(&mut self, error: &Error) {
+      self.0.push(error.to_string());
+    }
+  }
+
+  let mut handler = Abort(Vec::new());
+  let result = SAXParser::new().parse(InputSource::new(StringReader::new("<a/>")), &mut handler);
+  assert!(matches!(result, Err(Error::IO(_))));
+  let result =
+    SAXParser::new().parse(InputSource::new(StringReader::new("<!DOCTYPE a SYSTEM 'a.dtd'><a/>")), &mut handler);
+  assert!(matches!(result, Err(Error::Parse { code: ErrorCode::ExternalSubsetNotRead, .. })));
+  assert!(handler.0.is_empty());
+}
@@ -0,0 +1,91 @@
+use super::chars::{is_char, is_restricted_char};
+
+/// The bytes examined at a time. A word is loaded from 8 bytes and its bytes are tested in parallel with arithmetic
+/// on the whole word, which needs no platform-specific instructions.
+const WORD: usize = std::mem::size_of::<u64>();
+
+/// The lowest bit of each byte in a word.
+const LO: u64 = u64::from_le_bytes([0x01; WORD]);
+
+/// The highest bit of each byte in a word.
+const HI: u64 = u64::from_le_bytes([0x80; WORD]);
+
+/// Returns the index of the first byte in `haystack` that is one of the `needles`.
+pub(crate) fn find_byte(haystack: &[u8], needles: &[u8]) -> Option<usize> {
+  find(haystack, |word| needles.iter().any(|needle| has_zero(word ^ (LO * *needle as u64))), |b| needles.contains(&b))
+}
+
+/// Returns the number of occurrences of `byte` in `haystack`.
+pub(crate) fn count_byte(haystack: &[u8], byte: u8) -> usize {
+  let (words, tail) = split_words(haystack);
+  let count = words.map(|word| zero_bytes(word ^ (LO * byte as u64)).count_ones() as usize).sum::<usize>();
+  count + tail.iter().filter(|b| **b == byte).count()
+}
+
+/// Returns the number of characters in `text`, that is, the bytes that are not continuation bytes `10xxxxxx`.
+pub(crate) fn count_chars(text: &str) -> usize {
+  let (words, tail) = split_words(text.as_bytes());
+  let continuations = words.map(|word| (word & !(word << 1) & HI).count_ones() as usize).sum::<usize>();
+  text.len() - continuations - tail.iter().filter(|b| **b & 0xC0 == 0x80).count()
+}
+
+/// Whether all characters of `text` match `[2] Char`.
+pub(crate) fn all_chars(text: &str) -> bool {
+  find_illegal_char(text, false).is_none()
+}
+
+/// Returns the index of the first character of `text` that doesn't match `[2] Char`, or that matches
+/// `[2a] RestrictedChar` if `restricted`. Only control characters and U+FFFE and U+FFFF are out of the range in a
+/// string, so the bytes are skipped a word at a time until a byte below `#x20` or the first byte `#xEF` of U+FFFE and
+/// U+FFFF, and also `#x7F` and the first byte `#xC2` of `#x80`-`#x9F` if `restricted`, and the characters are checked
+/// from there.
+pub(crate) fn find_illegal_char(text: &str, restricted: bool) -> Option<usize> {
+  let suspicious = |b: u8| b < 0x20 || b == 0xEF || (restricted && (b == 0x7F || b == 0xC2));
+  let word = |word: u64| {
+    has_less(word, 0x20)
+      || has_zero(word ^ (LO * 0xEF))
+      || (restricted && (has_zero(word ^ (LO * 0x7F)) || has_zero(word ^ (LO * 0xC2))))
+  };
+  let mut offset = 0;
+  while let Some(i) = find(&text.as_bytes()[offset..], word, suspicious) {
+    let ch = text[offset + i..].chars().next().expect("a suspicious byte must start a character");
+    if !is_char(ch) || (restricted && is_restricted_char(ch)) {
+      return Some(offset + i);
+    }
+    offset += i + ch.len_utf8();
+  }
+  None
+}
+
+/// Returns the index of the first byte that satisfies `byte`, skipping the words for which `word` is `false`. `word`
+/// must be `true` for a word that contains such a byte.
+fn find(haystack: &[u8], word: impl Fn(u64) -> bool, byte: impl Fn(u8) -> bool) -> Option<usize> {
+  let (words, _) = split_words(haystack);
+  let offset = words.take_while(|w| !word(*w)).count() * WORD;
+  haystack[offset..].iter().position(|b| byte(*b)).map(|i| offset + i)
+}
+
+/// Splits `bytes` into the words and the remaining bytes that are less than a word. With the feature `scalar-scan`,
+/// all bytes are left as the remaining bytes, so that they're examined a byte at a time as the baseline of the
+/// benchmarks.
+fn split_words(bytes: &[u8]) -> (impl Iterator<Item = u64> + '_, &[u8]) {
+  let len = if cfg!(feature = "scalar-scan") { 0 } else { bytes.len() - bytes.len() % WORD };
+  let (words, tail) = bytes.split_at(len);
+  (words.chunks_exact(WORD).map(|chunk| u64::from_le_bytes(chunk.try_into().expect("a chunk must be a word"))), tail)
+}
+
+/// Whether any byte of the `word` is zero. A byte above a zero byte may be falsely flagged by the borrow, which
+/// doesn't matter for the answer.
+fn has_zero(word: u64) -> bool {
+  word.wrapping_sub(LO) & !word & HI != 0
+}
+
+/// Whether any byte of the `word` is less than `n`, which must not exceed `#x80`.
+fn has_less(word: u64, n: u8) -> bool {
+  word.wrapping_sub(LO * n as u64) & !word & HI != 0
+}
+
+/// The highest bit of each zero byte in the `word`, exactly.
+fn zero_bytes(word: u64) -> u64 {
+  !(((word & !HI) + !HI) | word) & HI
+}
@@ -0,0 +1,36 @@
+use crate::xml::parser::chars::{is_char, is_restricted_char};
+use crate::xml::parser::scan::{all_chars, count_byte, count_chars, find_byte, find_illegal_char};
+
+/// Texts whose special bytes appear at every position relative to the word boundaries.
+fn texts() -> Vec<String> {
+  let mut texts = Vec::new();
+  for len in 0..40 {
+    for i in 0..len {
+      for special in
+        ["<", "&", "\r", "\n", "\u{1}", "é", "日", "\u{FFFE}", "\u{FFFD}", "\u{10000}", "\u{7F}", "\u{85}", "\u{9F}"]
+      {
+        let mut text = "a".repeat(len);
+        text.insert_str(i, special);
+        texts.push(text);
+      }
+    }
+  }
+  texts
+}
+
+#[test]
+fn same_as_bytewise_scan() {
+  for text in texts() {
+    let bytes = text.as_bytes();
+    for needles in [&b"<"[..], b"<&", b"\r\n", b"\xC2\xE2", b"\x80", b""] {
+      assert_eq!(bytes.iter().position(|b| needles.contains(b)), find_byte(bytes, needles), "{:?} {:?}", text, needles);
+    }
+    for byte in [b'a', b'\n', 0x80, 0] {
+      assert_eq!(bytes.iter().filter(|b| **b == byte).count(), count_byte(bytes, byte), "{:?}", text);
+    }
+    assert_eq!(text.chars().count(), count_chars(&text), "{:?}", text);
+    assert_eq!(text.chars().all(is_char), all_chars(&text), "{:?}", text);
+    let restricted = text.char_indices().find(|(_, ch)| !is_char(*ch) || is_restricted_char(*ch)).map(|(i, _)| i);
+    assert_eq!(restricted, find_illegal_char(&text, true), "{:?}", text);
+  }
+}
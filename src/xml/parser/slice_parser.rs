@@ -0,0 +1,689 @@
+use std::borrow::Cow;
+use std::rc::Rc;
+
+use crate::io::{StringReader, Utf8Reader};
+use crate::xml::dom::{XMLNS_NAMESPACE_URI, XML_NAMESPACE_URI};
+use crate::{Error, Location, Result};
+
+use super::chars::{is_char, is_name_char, is_name_start_char, is_whitespace};
+use super::pull_parser::PREDEFINED_ENTITIES;
+use super::scan;
+use super::{DenyAllResolver, EntityLimits, EntityResolver, PullParser, SliceAttribute, SliceEvent, SliceEventKind};
+
+/// A parser that reads a UTF-8 document held in memory and returns [SliceEvent]s whose strings borrow from it.
+///
+/// Names, attribute values, text, comments and so on are slices of the document as long as they appear in it as they
+/// are, so a string is allocated only when references are replaced or line ends and attribute values are normalized.
+/// The events and errors are the same as [PullParser]: the parser reads the common case itself, and hands the rest
+/// of the document over to a [PullParser] when it meets something it doesn't read, such as a document type
+/// declaration, an entity reference, an XML 1.1 document, or an error. The events after that are copies.
+///
+/// ```
+/// use std::borrow::Cow;
+/// use xenolith::xml::parser::{SliceEventKind, SliceParser};
+///
+/// let xml = "<greeting lang='en'>hello &amp; bye</greeting>";
+/// let mut parser = SliceParser::new(xml);
+/// let SliceEventKind::StartElement { name, attributes, .. } = parser.next_event().unwrap().kind else { panic!() };
+/// assert!(matches!(name, Cow::Borrowed("greeting")));
+/// assert!(matches!(attributes[0].value, Cow::Borrowed("en")));
+/// let SliceEventKind::Text(text) = parser.next_event().unwrap().kind else { panic!() };
+/// assert!(matches!(text, Cow::Owned(text) if text == "hello & bye"));
+/// ```
+///
+pub struct SliceParser<'a> {
+  bytes: &'a [u8],
+  /// The document, or `None` if it's read by [PullParser] from the start.
+  text: Option<&'a str>,
+  position: usize,
+  cursor: Cursor,
+  state: State,
+  /// The names of the open elements, and the number of namespace bindings outside of each.
+  elements: Vec<(&'a str, usize)>,
+  /// The namespace prefixes and the namespace URIs they are bound to, in the order they are declared.
+  bindings: Vec<(Cow<'a, str>, Option<Cow<'a, str>>)>,
+  pending: Option<SliceEvent<'a>>,
+  /// The number of events that have been returned.
+  events: usize,
+  fallback: Option<PullParser>,
+  namespace_aware: bool,
+  entity_limits: EntityLimits,
+  resolver: Rc<dyn EntityResolver>,
+  recovery: bool,
+}
+
+/// The namespace URI and local name of an element.
+type ExpandedName<'a> = (Option<Cow<'a, str>>, Option<Cow<'a, str>>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+  Start,
+  Prolog,
+  Content,
+  Epilog,
+  Finished,
+}
+
+/// The location of a byte offset in the document, which is moved forward as the parser reads.
+struct Cursor {
+  offset: usize,
+  location: Location,
+  /// Whether the preceding character is CR, so that LF following it doesn't start another line.
+  cr: bool,
+}
+
+impl<'a> SliceParser<'a> {
+  pub fn new(text: &'a str) -> SliceParser<'a> {
+    let readable = scan::all_chars(text);
+    Self::with_text(text.as_bytes(), Some(text).filter(|_| readable))
+  }
+
+  /// Creates a parser for the UTF-8 `bytes`. If they are not valid UTF-8, the events are read by [PullParser] and
+  /// the error is reported where the malformed sequence appears.
+  pub fn from_bytes(bytes: &'a [u8]) -> SliceParser<'a> {
+    match std::str::from_utf8(bytes) {
+      Ok(text) => Self::new(text),
+      Err(_) => Self::with_text(bytes, None),
+    }
+  }
+
+  fn with_text(bytes: &'a [u8], text: Option<&'a str>) -> SliceParser<'a> {
+    SliceParser {
+      bytes,
+      text,
+      position: 0,
+      cursor: Cursor { offset: 0, location: Location::default(), cr: false },
+      state: State::Start,
+      elements: Vec::new(),
+      bindings: vec![(Cow::Borrowed("xml"), Some(Cow::Borrowed(XML_NAMESPACE_URI)))],
+      pending: None,
+      events: 0,
+      fallback: None,
+      namespace_aware: true,
+      entity_limits: EntityLimits::default(),
+      resolver: Rc::new(DenyAllResolver),
+      recovery: false,
+    }
+  }
+
+  /// Whether the parser performs namespace processing.
+  pub fn namespace_aware(&self) -> bool {
+    self.namespace_aware
+  }
+
+  /// Enables or disables namespace processing. This must be set before the first event is read.
+  pub fn set_namespace_aware(&mut self, namespace_aware: bool) {
+    self.namespace_aware = namespace_aware;
+  }
+
+  /// Replaces the limits on the expansion of internal entities. This must be set before the first event is read.
+  pub fn set_entity_limits(&mut self, entity_limits: EntityLimits) {
+    self.entity_limits = entity_limits;
+  }
+
+  /// Replaces the resolver that reads external entities. This must be set before the first event is read.
+  pub fn set_entity_resolver(&mut self, resolver: Rc<dyn EntityResolver>) {
+    self.resolver = resolver;
+  }
+
+  /// Enables or disables recovery mode. This must be set before the first event is read.
+  pub fn set_recovery(&mut self, recovery: bool) {
+    self.recovery = recovery;
+  }
+
+  /// The errors that have been repaired in recovery mode, in the order they occurred.
+  pub fn diagnostics(&self) -> &[Error] {
+    self.fallback.as_ref().map(|parser| parser.diagnostics()).unwrap_or_default()
+  }
+
+  /// The warnings about what the parser has left unread, as [PullParser::warnings()].
+  pub fn warnings(&self) -> &[Error] {
+    self.fallback.as_ref().map(|parser| parser.warnings()).unwrap_or_default()
+  }
+
+  /// Whether the rest of the document is read by [PullParser], so that the events no longer borrow from it.
+  pub fn is_fallen_back(&self) -> bool {
+    self.fallback.is_some()
+  }
+
+  /// Reads the next event. After the [SliceEventKind::EndDocument] has been reported, this will keep returning
+  /// `EndDocument`.
+  pub fn next_event(&mut self) -> Result<SliceEvent<'a>> {
+    let mut event = self.event()?;
+    if let (None, Some(text)) = (&self.fallback, self.text) {
+      event.end = self.location_at(text, self.position);
+    }
+    self.events += 1;
+    Ok(event)
+  }
+
+  fn event(&mut self) -> Result<SliceEvent<'a>> {
+    if let Some(parser) = &mut self.fallback {
+      return parser.next_event().map(SliceEvent::from);
+    }
+    if let Some(event) = self.pending.take() {
+      return Ok(event);
+    }
+    let text = match self.text {
+      Some(text) => text,
+      None => return self.fall_back(),
+    };
+    let event = match self.state {
+      State::Start => self.start(text),
+      State::Prolog | State::Epilog => self.misc(text),
+      State::Content => self.content(text),
+      State::Finished => Some(SliceEvent::new(self.location_at(text, text.len()), SliceEventKind::EndDocument)),
+    };
+    match event {
+      Some(event) => Ok(event),
+      None => self.fall_back(),
+    }
+  }
+
+  /// Reads the document again with [PullParser] up to the events that have been returned, and returns the next event
+  /// from it.
+  fn fall_back(&mut self) -> Result<SliceEvent<'a>> {
+    let mut parser = match self.text {
+      Some(text) => PullParser::new(StringReader::new(text)),
+      None => PullParser::new(Utf8Reader::new(std::io::Cursor::new(self.bytes.to_vec()))),
+    };
+    parser.set_namespace_aware(self.namespace_aware);
+    parser.set_entity_limits(self.entity_limits.clone());
+    parser.set_entity_resolver(self.resolver.clone());
+    parser.set_recovery(self.recovery);
+    for _ in 0..self.events {
+      parser.next_event()?;
+    }
+    let event = parser.next_event().map(SliceEvent::from);
+    self.fallback = Some(parser);
+    event
+  }
+
+  /// The location of the byte `offset`, which must not precede the offsets asked before.
+  fn location_at(&mut self, text: &str, offset: usize) -> Location {
+    debug_assert!(offset >= self.cursor.offset);
+    let cursor = &mut self.cursor;
+    let mut rest = &text[cursor.offset..offset];
+    cursor.location.byte_offset = offset as u64;
+    cursor.location.char_offset += scan::count_chars(rest) as u64;
+    while let Some(i) = scan::find_byte(rest.as_bytes(), b"\r\n") {
+      if !(i == 0 && cursor.cr && rest.starts_with('\n')) {
+        cursor.location.line_number += 1;
+        cursor.location.column_number = 0;
+      }
+      cursor.cr = rest.as_bytes()[i] == b'\r';
+      rest = &rest[i + 1..];
+    }
+    if !rest.is_empty() {
+      cursor.location.column_number += scan::count_chars(rest) as u64;
+      cursor.cr = false;
+    }
+    cursor.offset = offset;
+    cursor.location.clone()
+  }
+
+  fn start(&mut self, text: &'a str) -> Option<SliceEvent<'a>> {
+    self.state = State::Prolog;
+    if text.starts_with('\u{FEFF}') {
+      self.position = '\u{FEFF}'.len_utf8();
+    }
+    let rest = &text[self.position..];
+    if rest.starts_with("<?xml") && rest[5..].starts_with(is_whitespace) {
+      self.xml_decl(text)
+    } else {
+      self.misc(text)
+    }
+  }
+
+  /// The XML declaration of version 1.0, whose pseudo-attributes are read as [PullParser] does.
+  fn xml_decl(&mut self, text: &'a str) -> Option<SliceEvent<'a>> {
+    let location = self.location_at(text, self.position);
+    self.position += "<?xml".len();
+    let mut version = None;
+    let mut encoding = None;
+    let mut standalone = None;
+    loop {
+      let space = self.skip_whitespace(text);
+      if self.skip(text, "?>") {
+        break;
+      }
+      if !space {
+        return None;
+      }
+      let name = self.name(text)?;
+      self.skip_whitespace(text);
+      if !self.skip(text, "=") {
+        return None;
+      }
+      self.skip_whitespace(text);
+      let quote = text[self.position..].chars().next().filter(|ch| *ch == '"' || *ch == '\'')?;
+      let length = text[self.position + 1..].find(quote)?;
+      let value = &text[self.position + 1..self.position + 1 + length];
+      self.position += length + 2;
+      match name {
+        "version" if version.is_none() && encoding.is_none() && standalone.is_none() && value == "1.0" => {
+          version = Some(value);
+        }
+        "encoding" if version.is_some() && encoding.is_none() && standalone.is_none() => {
+          let mut chars = value.chars();
+          let valid = chars.next().map(|ch| ch.is_ascii_alphabetic()).unwrap_or(false)
+            && chars.all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '.' | '_' | '-'));
+          if !valid {
+            return None;
+          }
+          encoding = Some(value);
+        }
+        "standalone" if version.is_some() && standalone.is_none() => {
+          standalone = Some(match value {
+            "yes" => true,
+            "no" => false,
+            _ => return None,
+          });
+        }
+        _ => return None,
+      }
+    }
+    let kind = SliceEventKind::XMLDecl { version: version?.into(), encoding: encoding.map(Cow::Borrowed), standalone };
+    Some(SliceEvent::new(location, kind))
+  }
+
+  /// Comments, processing instructions and whitespace before and after the document element.
+  fn misc(&mut self, text: &'a str) -> Option<SliceEvent<'a>> {
+    self.skip_whitespace(text);
+    let location = self.location_at(text, self.position);
+    let rest = &text[self.position..];
+    if rest.is_empty() {
+      if self.state != State::Epilog {
+        return None;
+      }
+      self.state = State::Finished;
+      Some(SliceEvent::new(location, SliceEventKind::EndDocument))
+    } else if rest.starts_with("<!--") {
+      self.comment(text, location)
+    } else if rest.starts_with("<?") {
+      self.pi(text, location)
+    } else if self.state == State::Prolog && rest.starts_with('<') && !rest.starts_with("<!") {
+      self.state = State::Content;
+      self.start_tag(text, location)
+    } else {
+      None
+    }
+  }
+
+  fn content(&mut self, text: &'a str) -> Option<SliceEvent<'a>> {
+    let location = self.location_at(text, self.position);
+    let rest = &text[self.position..];
+    if rest.starts_with("</") {
+      self.end_tag(text, location)
+    } else if rest.starts_with("<!--") {
+      self.comment(text, location)
+    } else if rest.starts_with("<![CDATA[") {
+      self.cdata(text, location)
+    } else if rest.starts_with("<?") {
+      self.pi(text, location)
+    } else if rest.starts_with("<!") {
+      None
+    } else if rest.starts_with('<') {
+      self.start_tag(text, location)
+    } else if rest.is_empty() || (rest.starts_with('&') && !at_character_reference(rest)) {
+      None
+    } else {
+      self.text(text, location)
+    }
+  }
+
+  /// `<name attr="value">` or `<name/>`, whose end is reported by the next event.
+  fn start_tag(&mut self, text: &'a str, location: Location) -> Option<SliceEvent<'a>> {
+    self.position += "<".len();
+    let name = self.name(text)?;
+    let mut attributes: Vec<SliceAttribute<'a>> = Vec::new();
+    let empty = loop {
+      let space = self.skip_whitespace(text);
+      if self.skip(text, ">") {
+        break false;
+      } else if self.skip(text, "/>") {
+        break true;
+      } else if !space {
+        return None;
+      }
+      let attr_location = self.location_at(text, self.position);
+      let attr_name = self.name(text)?;
+      if attributes.iter().any(|attr| attr.name == attr_name) {
+        return None;
+      }
+      self.skip_whitespace(text);
+      if !self.skip(text, "=") {
+        return None;
+      }
+      self.skip_whitespace(text);
+      let value = self.attribute_value(text)?;
+      attributes.push(SliceAttribute {
+        location: attr_location,
+        end: self.location_at(text, self.position),
+        name: Cow::Borrowed(attr_name),
+        namespace_uri: None,
+        local_name: None,
+        value,
+        specified: true,
+      });
+    };
+    let scope = self.bindings.len();
+    let (namespace_uri, local_name) = self.start_scope(name, &mut attributes)?;
+    if empty {
+      let end = SliceEventKind::EndElement {
+        name: Cow::Borrowed(name),
+        namespace_uri: namespace_uri.clone(),
+        local_name: local_name.clone(),
+      };
+      self.pending = Some(SliceEvent::new(location.clone(), end));
+      self.bindings.truncate(scope);
+      if self.elements.is_empty() {
+        self.state = State::Epilog;
+      }
+    } else {
+      self.elements.push((name, scope));
+    }
+    let start = SliceEventKind::StartElement { name: Cow::Borrowed(name), namespace_uri, local_name, attributes };
+    Some(SliceEvent::new(location, start))
+  }
+
+  /// `</name>` that closes the innermost open element.
+  fn end_tag(&mut self, text: &'a str, location: Location) -> Option<SliceEvent<'a>> {
+    self.position += "</".len();
+    let name = self.name(text)?;
+    self.skip_whitespace(text);
+    if !self.skip(text, ">") || self.elements.last()?.0 != name {
+      return None;
+    }
+    let (namespace_uri, local_name) = self.element_name(name)?;
+    let (_, scope) = self.elements.pop()?;
+    self.bindings.truncate(scope);
+    if self.elements.is_empty() {
+      self.state = State::Epilog;
+    }
+    let end = SliceEventKind::EndElement { name: Cow::Borrowed(name), namespace_uri, local_name };
+    Some(SliceEvent::new(location, end))
+  }
+
+  /// Declares the namespaces of the `attributes` and resolves the names of the element and attributes. The namespace
+  /// URI and local name of the element are returned.
+  fn start_scope(&mut self, name: &'a str, attributes: &mut [SliceAttribute<'a>]) -> Option<ExpandedName<'a>> {
+    if !self.namespace_aware {
+      return Some((None, None));
+    }
+    for attr in attributes.iter_mut() {
+      let (prefix, local_name) = split_qname(borrowed(&attr.name)?)?;
+      let declared = match prefix {
+        None if local_name == "xmlns" => Some(""),
+        Some("xmlns") => Some(local_name),
+        _ => None,
+      };
+      if let Some(declared) = declared {
+        check_declaration(declared, &attr.value)?;
+        let namespace_uri = Some(attr.value.clone()).filter(|uri| !uri.is_empty());
+        self.bindings.push((Cow::Borrowed(declared), namespace_uri));
+        attr.namespace_uri = Some(Cow::Borrowed(XMLNS_NAMESPACE_URI));
+      }
+      attr.local_name = Some(Cow::Borrowed(local_name));
+    }
+    for attr in attributes.iter_mut() {
+      match split_qname(borrowed(&attr.name)?)?.0 {
+        None | Some("xmlns") => (),
+        Some(prefix) => attr.namespace_uri = Some(self.resolve(prefix)?.clone()),
+      }
+    }
+    for (i, attr) in attributes.iter().enumerate() {
+      let duplicated = attributes[..i].iter().any(|other| {
+        attr.namespace_uri.is_some()
+          && (&other.namespace_uri, &other.local_name) == (&attr.namespace_uri, &attr.local_name)
+      });
+      if duplicated {
+        return None;
+      }
+    }
+    self.element_name(name)
+  }
+
+  /// Resolves the name of an element with the namespace declarations in scope.
+  fn element_name(&self, name: &'a str) -> Option<ExpandedName<'a>> {
+    if !self.namespace_aware {
+      return Some((None, None));
+    }
+    let (prefix, local_name) = split_qname(name)?;
+    if prefix == Some("xmlns") {
+      return None;
+    }
+    let namespace_uri = self.resolve(prefix.unwrap_or_default()).cloned();
+    if prefix.is_some() && namespace_uri.is_none() {
+      return None;
+    }
+    Some((namespace_uri, Some(Cow::Borrowed(local_name))))
+  }
+
+  /// The namespace URI that the `prefix` is bound to, or `None` if it's not declared or undeclared.
+  fn resolve(&self, prefix: &str) -> Option<&Cow<'a, str>> {
+    self.bindings.iter().rev().find(|(declared, _)| declared == prefix).and_then(|(_, uri)| uri.as_ref())
+  }
+
+  /// The quoted attribute value, whose references are replaced and whitespace characters are normalized to spaces.
+  fn attribute_value(&mut self, text: &'a str) -> Option<Cow<'a, str>> {
+    let quote = text[self.position..].chars().next().filter(|ch| *ch == '"' || *ch == '\'')?;
+    self.position += 1;
+    let start = self.position;
+    let mut value: Option<String> = None;
+    loop {
+      let rest = &text[self.position..];
+      let length = scan::find_byte(rest.as_bytes(), &[quote as u8, b'<', b'&', b'\t', b'\n', b'\r'])?;
+      if let Some(value) = &mut value {
+        value.push_str(&rest[..length]);
+      }
+      let end = self.position + length;
+      self.position = end;
+      let ch = match rest.as_bytes()[length] {
+        b'<' => return None,
+        b'&' => self.reference(text)?,
+        b'\r' => {
+          self.position += if rest[length + 1..].starts_with('\n') { 2 } else { 1 };
+          ' '
+        }
+        b'\t' | b'\n' => {
+          self.position += 1;
+          ' '
+        }
+        _ => {
+          self.position += 1;
+          return Some(value.map(Cow::Owned).unwrap_or(Cow::Borrowed(&text[start..end])));
+        }
+      };
+      value.get_or_insert_with(|| text[start..end].to_string()).push(ch);
+    }
+  }
+
+  /// Character data up to the next markup or entity reference, with character references and predefined entity
+  /// references replaced.
+  fn text(&mut self, text: &'a str, location: Location) -> Option<SliceEvent<'a>> {
+    let start = self.position;
+    let mut data: Option<String> = None;
+    loop {
+      let rest = &text[self.position..];
+      let length = scan::find_byte(rest.as_bytes(), b"<&]\r").unwrap_or(rest.len());
+      if let Some(data) = &mut data {
+        data.push_str(&rest[..length]);
+      }
+      let end = self.position + length;
+      self.position = end;
+      let ch = match rest.as_bytes().get(length) {
+        Some(b']') if rest[length..].starts_with("]]>") => return None,
+        Some(b']') => {
+          self.position += 1;
+          if let Some(data) = &mut data {
+            data.push(']');
+          }
+          continue;
+        }
+        Some(b'\r') => {
+          self.position += if rest[length + 1..].starts_with('\n') { 2 } else { 1 };
+          '\n'
+        }
+        Some(b'&') if at_character_reference(&rest[length..]) => self.reference(text)?,
+        _ => break,
+      };
+      data.get_or_insert_with(|| text[start..end].to_string()).push(ch);
+    }
+    let data = data.map(Cow::Owned).unwrap_or(Cow::Borrowed(&text[start..self.position]));
+    Some(SliceEvent::new(location, SliceEventKind::Text(data)))
+  }
+
+  /// The character that the character reference or predefined entity reference at the position stands for.
+  fn reference(&mut self, text: &'a str) -> Option<char> {
+    let rest = &text[self.position..];
+    if let Some((reference, ch)) = PREDEFINED_ENTITIES.iter().find(|(reference, _)| rest.starts_with(reference)) {
+      self.position += reference.len();
+      return Some(*ch);
+    }
+    let (radix, digits) = match rest.strip_prefix("&#x") {
+      Some(digits) => (16, digits),
+      None => (10, rest.strip_prefix("&#")?),
+    };
+    let length = digits.find(|ch: char| !ch.is_digit(radix)).unwrap_or(digits.len());
+    if length == 0 || !digits[length..].starts_with(';') {
+      return None;
+    }
+    let ch = u32::from_str_radix(&digits[..length], radix).ok().and_then(char::from_u32).filter(|ch| is_char(*ch))?;
+    self.position += rest.len() - digits.len() + length + 1;
+    Some(ch)
+  }
+
+  /// `<!--...-->`
+  fn comment(&mut self, text: &'a str, location: Location) -> Option<SliceEvent<'a>> {
+    self.position += "<!--".len();
+    let length = text[self.position..].find("--")?;
+    let data = &text[self.position..self.position + length];
+    self.position += length + "--".len();
+    if !self.skip(text, ">") {
+      return None;
+    }
+    Some(SliceEvent::new(location, SliceEventKind::Comment(normalize(data))))
+  }
+
+  /// `<?target data?>`
+  fn pi(&mut self, text: &'a str, location: Location) -> Option<SliceEvent<'a>> {
+    self.position += "<?".len();
+    let target = self.name(text)?;
+    if target.eq_ignore_ascii_case("xml") {
+      return None;
+    }
+    let data = if self.skip(text, "?>") {
+      None
+    } else if self.skip_whitespace(text) {
+      let length = text[self.position..].find("?>")?;
+      let data = &text[self.position..self.position + length];
+      self.position += length + "?>".len();
+      Some(normalize(data))
+    } else {
+      return None;
+    };
+    Some(SliceEvent::new(location, SliceEventKind::ProcessingInstruction { target: Cow::Borrowed(target), data }))
+  }
+
+  /// `<![CDATA[...]]>`
+  fn cdata(&mut self, text: &'a str, location: Location) -> Option<SliceEvent<'a>> {
+    self.position += "<![CDATA[".len();
+    let length = text[self.position..].find("]]>")?;
+    let data = &text[self.position..self.position + length];
+    self.position += length + "]]>".len();
+    Some(SliceEvent::new(location, SliceEventKind::CDATA(normalize(data))))
+  }
+
+  /// `[5] Name ::= NameStartChar (NameChar)*`
+  fn name(&mut self, text: &'a str) -> Option<&'a str> {
+    let rest = &text[self.position..];
+    if !rest.starts_with(is_name_start_char) {
+      return None;
+    }
+    let length = rest.find(|ch| !is_name_char(ch)).unwrap_or(rest.len());
+    self.position += length;
+    Some(&rest[..length])
+  }
+
+  /// Skips `[3] S` and returns whether any whitespace has been skipped.
+  fn skip_whitespace(&mut self, text: &str) -> bool {
+    let rest = &text[self.position..];
+    let length = rest.find(|ch| !is_whitespace(ch)).unwrap_or(rest.len());
+    self.position += length;
+    length > 0
+  }
+
+  fn skip(&mut self, text: &str, prefix: &str) -> bool {
+    let matched = text[self.position..].starts_with(prefix);
+    if matched {
+      self.position += prefix.len();
+    }
+    matched
+  }
+}
+
+impl<'a> Iterator for SliceParser<'a> {
+  type Item = Result<SliceEvent<'a>>;
+
+  /// Returns events up to and including [SliceEventKind::EndDocument], or the first error.
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.state == State::Finished {
+      return None;
+    }
+    let event = self.next_event();
+    if matches!(event, Err(_) | Ok(SliceEvent { kind: SliceEventKind::EndDocument, .. })) {
+      self.state = State::Finished;
+    }
+    Some(event)
+  }
+}
+
+/// Whether the `text` starts with a character reference or predefined entity reference rather than a reference to
+/// another entity, which is left to [PullParser].
+fn at_character_reference(text: &str) -> bool {
+  text.starts_with("&#") || PREDEFINED_ENTITIES.iter().any(|(reference, _)| text.starts_with(reference))
+}
+
+/// Replaces the line ends of the `text` by LF, borrowing it if it has no CR.
+fn normalize(text: &str) -> Cow<'_, str> {
+  if text.contains('\r') {
+    Cow::Owned(text.replace("\r\n", "\n").replace('\r', "\n"))
+  } else {
+    Cow::Borrowed(text)
+  }
+}
+
+/// The name that borrows from the document. Names are never copied while the document is read by [SliceParser].
+fn borrowed<'a>(name: &Cow<'a, str>) -> Option<&'a str> {
+  match name {
+    Cow::Borrowed(name) => Some(name),
+    Cow::Owned(_) => None,
+  }
+}
+
+/// Splits `[7] QName ::= PrefixedName | UnprefixedName` into the prefix and the local part.
+fn split_qname(name: &str) -> Option<(Option<&str>, &str)> {
+  match name.split_once(':') {
+    None => Some((None, name)),
+    Some((prefix, local_name))
+      if !prefix.is_empty() && local_name.starts_with(is_name_start_char) && !local_name.contains(':') =>
+    {
+      Some((Some(prefix), local_name))
+    }
+    Some(_) => None,
+  }
+}
+
+/// Checks the constraints on the reserved prefixes and namespace names of XML 1.0 for the declaration that binds the
+/// `prefix` to the `namespace_uri`.
+fn check_declaration(prefix: &str, namespace_uri: &str) -> Option<()> {
+  match prefix {
+    "xmlns" => None,
+    "xml" if namespace_uri == XML_NAMESPACE_URI => Some(()),
+    "xml" => None,
+    _ if namespace_uri == XML_NAMESPACE_URI || namespace_uri == XMLNS_NAMESPACE_URI => None,
+    _ if !prefix.is_empty() && namespace_uri.is_empty() => None,
+    _ => Some(()),
+  }
+}
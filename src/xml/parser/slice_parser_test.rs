@@ -0,0 +1,84 @@
+use std::borrow::Cow;
+
+use crate::io::StringReader;
+use crate::xml::parser::{PullParser, SliceEventKind, SliceParser};
+use crate::{Error, Result};
+
+fn summary<E: std::fmt::Debug>(events: impl Iterator<Item = Result<E>>) -> Vec<String> {
+  events
+    .map(|event| match event {
+      Ok(event) => format!("{:?}", event),
+      Err(Error::Parse { code, location, .. }) => format!("{:?} at {}", code, location),
+      Err(err) => err.to_string(),
+    })
+    .collect()
+}
+
+#[test]
+fn same_events_as_pull_parser() {
+  for (i, xml) in [
+    "\u{FEFF}<?xml version='1.0' encoding=\"UTF-8\" standalone='yes'?>\r\n<!-- c\r\n -->\n<?pi?><a/>\r\n<?pi \r\n d?>",
+    "<?xml version=\"1.0\"?><p:a xmlns:p='urn:p' xmlns='urn:d' p:x=' 1\t&amp;\r\n&#x263A; '>日本語&lt;\r\n\rtext<b \
+     xmlns=''/><![CDATA[<>&\r\n]]><!---->&#65;&gt;</p:a>",
+    "<a x='1'\ny=\"2\" ><b>]]</b >&#x10000;</a>",
+    "<!DOCTYPE a [<!ENTITY e 'entity'>]><a>text &e; <b/></a>",
+    "<?xml version='1.1'?><a>\u{85}</a>",
+    "<a>text<b/>&undeclared;</a>",
+    // errors
+    "<a><b></a>",
+    "<a x='1' x='2'/>",
+    "<p:a/>",
+    "<a xmlns:p=''/>",
+    "<a>]]></a>",
+    "<a>&#0;</a>",
+    "<a>\u{1}</a>",
+    "<a x='<'/>",
+    "<a><!-- -- --></a>",
+    "<?xml version='2.0'?><a/>",
+    "<?xml encoding='UTF-8'?><a/>",
+    "<a>",
+    "<a/><b/>",
+    "<a/>text",
+    "",
+  ]
+  .into_iter()
+  .enumerate()
+  {
+    let expected = summary(PullParser::new(StringReader::new(xml)));
+    let actual = summary(SliceParser::new(xml).map(|event| event.map(|event| event.into_owned())));
+    assert_eq!(expected, actual, "{}", xml);
+
+    // the first documents are read without falling back to PullParser
+    let mut parser = SliceParser::new(xml);
+    while let Some(Ok(_)) = parser.next() {}
+    assert_eq!(i >= 3, parser.is_fallen_back(), "{}", xml);
+  }
+
+  let mut parser = SliceParser::from_bytes(b"<a>\xFF</a>");
+  assert!(parser.next_event().is_ok());
+  assert!(matches!(parser.next_event(), Err(Error::IO(_))));
+}
+
+#[test]
+fn borrow_unless_changed() {
+  let xml = "<a x='1' y='&lt;'>text<!--comment--><![CDATA[\r\n]]>&amp;</a>";
+  let mut parser = SliceParser::new(xml);
+  let SliceEventKind::StartElement { name, local_name, attributes, .. } = parser.next_event().unwrap().kind else {
+    panic!()
+  };
+  assert!(matches!(name, Cow::Borrowed("a")));
+  assert!(matches!(local_name, Some(Cow::Borrowed("a"))));
+  assert!(matches!(attributes[0].value, Cow::Borrowed("1")));
+  assert!(matches!(&attributes[1].value, Cow::Owned(value) if value == "<"));
+  assert!(matches!(parser.next_event().unwrap().kind, SliceEventKind::Text(Cow::Borrowed("text"))));
+  assert!(matches!(parser.next_event().unwrap().kind, SliceEventKind::Comment(Cow::Borrowed("comment"))));
+  assert!(matches!(parser.next_event().unwrap().kind, SliceEventKind::CDATA(Cow::Owned(text)) if text == "\n"));
+  assert!(matches!(parser.next_event().unwrap().kind, SliceEventKind::Text(Cow::Owned(text)) if text == "&"));
+  assert!(!parser.is_fallen_back());
+
+  let mut parser = SliceParser::new("<a>text&e;</a>");
+  parser.next_event().unwrap();
+  assert!(matches!(parser.next_event().unwrap().kind, SliceEventKind::Text(Cow::Borrowed("text"))));
+  assert!(parser.next_event().is_err());
+  assert!(parser.is_fallen_back());
+}
@@ -0,0 +1,561 @@
+use crate::io::CharReader;
+use crate::{Error, Location, Result};
+
+use super::chars::is_restricted_char;
+use super::scan;
+use super::{ErrorCode, InputSource};
+
+/// A buffered character source that the parser reads markup from. The characters are kept in a UTF-8 buffer so that
+/// the parser can look ahead and take substrings without copying each character, and the location of the cursor is
+/// maintained as the cursor advances.
+///
+/// Characters that don't match `[2] Char`, and also `[2a] RestrictedChar` in an XML 1.1 document, are rejected when the
+/// cursor reaches them.
+///
+/// Line ends read from the document entity and external entities are translated to `#xA` as defined in
+/// [§2.11 End-of-Line Handling](https://www.w3.org/TR/xml/#sec-line-ends): `#xD #xA` and `#xD` alone, and also
+/// `#xD #x85`, `#x85` and `#x2028` in an XML 1.1 document. The parser tells the version with [Source::set_xml11()]
+/// once it has read the XML declaration; until then, the characters are translated only up to the first `#x85` or
+/// `#x2028`.
+///
+/// The replacement text of an entity being expanded is pushed onto the source with [Source::push_entity()] or
+/// [Source::push_external()], and the cursor reads it until its end as if it were the whole stream; the parser pops
+/// it with [Source::pop_entity()] to resume reading the referring entity. The location of the characters in an
+/// internal entity is the location of the reference, and an external entity has its own locations. The offsets of a
+/// location count the characters as they were before their line ends were translated, and the bytes in the stream
+/// that they were decoded from if the reader reports them with [CharReader::byte_lengths()].
+///
+/// A transparent entity, such as a parameter entity referred to within a markup declaration, is popped automatically
+/// at its end so that the parser reads on into the referring entity.
+///
+pub(crate) struct Source {
+  chars: Vec<char>,
+  inputs: Vec<Input>,
+  /// Whether the document is XML 1.1, or `None` if the version is not known yet.
+  xml11: Option<bool>,
+}
+
+/// The cursor positions in the entities being read, saved by [Source::checkpoint()].
+pub(crate) struct Checkpoint(Vec<(usize, Location)>);
+
+/// A document entity or an entity that is being expanded.
+struct Input {
+  name: Option<String>,
+  reader: Option<Box<dyn CharReader>>,
+  public_id: Option<String>,
+  system_id: Option<String>,
+  buffer: String,
+  position: usize,
+  /// The length of the head of the buffer whose line ends have been translated. Only this part is read by the cursor.
+  normalized: usize,
+  /// The number of characters read from the reader.
+  read: usize,
+  eof: bool,
+  illegal: Option<char>,
+  location: Location,
+  /// The positions in the buffer of the translated line ends that were longer than `#xA`, with the numbers of bytes
+  /// and characters by which they were shortened, in ascending order of the positions.
+  shortened: Vec<(usize, u64, u64)>,
+  /// The total differences between the numbers of bytes in the stream and in UTF-8 of the characters before each
+  /// character offset from `deltas_base`, if the reader reports the numbers of bytes.
+  deltas: Vec<i64>,
+  deltas_base: u64,
+  transparent: bool,
+}
+
+impl Source {
+  const CHUNK_SIZE: usize = 4 * 1024;
+
+  pub fn new(input: InputSource) -> Source {
+    let document = Input {
+      name: None,
+      reader: Some(input.reader),
+      public_id: input.public_id,
+      system_id: input.system_id,
+      buffer: String::with_capacity(Self::CHUNK_SIZE * 2),
+      position: 0,
+      normalized: 0,
+      read: 0,
+      eof: false,
+      illegal: None,
+      location: Location::default(),
+      shortened: Vec::new(),
+      deltas: Vec::new(),
+      deltas_base: 0,
+      transparent: false,
+    };
+    Source { chars: vec!['\0'; Self::CHUNK_SIZE], inputs: vec![document], xml11: None }
+  }
+
+  /// Sets the version of the document to decide which characters are legal and which line ends are translated.
+  pub fn set_xml11(&mut self, xml11: bool) {
+    self.xml11 = Some(xml11);
+    for input in self.inputs.iter_mut().filter(|input| input.reader.is_some()) {
+      if xml11 {
+        if let Some(i) = input.buffer[input.position..].find(is_restricted_char) {
+          let end = input.position + i;
+          input.illegal = input.buffer[end..].chars().next();
+          input.buffer.truncate(end);
+          input.normalized = input.normalized.min(end);
+          input.shortened.retain(|(position, _, _)| *position < end);
+        }
+      }
+      input.normalize(Some(xml11));
+    }
+  }
+
+  /// Whether the document is XML 1.1.
+  pub fn xml11(&self) -> bool {
+    self.xml11 == Some(true)
+  }
+
+  fn input(&self) -> &Input {
+    self.inputs.last().expect("the document entity must remain at the bottom")
+  }
+
+  fn input_mut(&mut self) -> &mut Input {
+    self.inputs.last_mut().expect("the document entity must remain at the bottom")
+  }
+
+  /// The location of the character under the cursor.
+  pub fn location(&self) -> Location {
+    self.input().location.clone()
+  }
+
+  /// The document entity or the innermost external entity being read.
+  fn entity(&self) -> &Input {
+    self.inputs.iter().rev().find(|input| input.reader.is_some()).expect("the document entity must have a reader")
+  }
+
+  /// The public identifier of the document entity or the innermost external entity being read.
+  pub fn public_id(&self) -> Option<&str> {
+    self.entity().public_id.as_deref()
+  }
+
+  /// The system identifier of the document entity or the innermost external entity being read.
+  pub fn system_id(&self) -> Option<&str> {
+    self.entity().system_id.as_deref()
+  }
+
+  /// Whether the cursor is in an external entity rather than in the document entity, ignoring internal entities.
+  pub fn in_external_entity(&self) -> bool {
+    !std::ptr::eq(self.entity(), &self.inputs[0])
+  }
+
+  /// Starts reading the replacement text of the internal entity `name`.
+  pub fn push_entity(&mut self, name: &str, text: &str, transparent: bool) {
+    let location = self.location();
+    let input = Input {
+      name: Some(name.to_string()),
+      reader: None,
+      public_id: None,
+      system_id: None,
+      buffer: text.to_string(),
+      position: 0,
+      normalized: text.len(),
+      read: 0,
+      eof: true,
+      illegal: None,
+      location,
+      shortened: Vec::new(),
+      deltas: Vec::new(),
+      deltas_base: 0,
+      transparent,
+    };
+    self.inputs.push(input);
+  }
+
+  /// Starts reading the external entity `name` from the `input`.
+  pub fn push_external(&mut self, name: &str, input: InputSource, transparent: bool) {
+    let input = Input {
+      name: Some(name.to_string()),
+      reader: Some(input.reader),
+      public_id: input.public_id,
+      system_id: input.system_id,
+      buffer: String::with_capacity(Self::CHUNK_SIZE),
+      position: 0,
+      normalized: 0,
+      read: 0,
+      eof: false,
+      illegal: None,
+      location: Location::default(),
+      shortened: Vec::new(),
+      deltas: Vec::new(),
+      deltas_base: 0,
+      transparent,
+    };
+    self.inputs.push(input);
+  }
+
+  /// Finishes reading the innermost entity and returns its name. `None` is returned if no entity is being expanded.
+  pub fn pop_entity(&mut self) -> Option<String> {
+    if self.inputs.len() > 1 {
+      self.inputs.pop().and_then(|input| input.name)
+    } else {
+      None
+    }
+  }
+
+  /// The number of entities that are being expanded.
+  pub fn entity_depth(&self) -> usize {
+    self.inputs.len() - 1
+  }
+
+  /// Whether the entity `name` is being expanded, that is, a reference to it would be recursive.
+  pub fn is_expanding(&self, name: &str) -> bool {
+    self.inputs.iter().any(|input| input.name.as_deref() == Some(name))
+  }
+
+  /// The number of characters that have been read from the document entity, including those not parsed yet.
+  pub fn document_chars(&self) -> usize {
+    self.inputs[0].read
+  }
+
+  /// Marks the cursor position in the document entity to take the text read after it with [Source::text_since()].
+  /// The mark is valid until the next [Source::compact()].
+  pub fn mark(&self) -> usize {
+    self.inputs[0].position
+  }
+
+  /// The text of the document entity between the `mark` and the cursor position in it.
+  pub fn text_since(&self, mark: usize) -> &str {
+    &self.inputs[0].buffer[mark..self.inputs[0].position]
+  }
+
+  /// Saves the cursor positions so that the cursor can go back to them with [Source::rewind()]. This should be
+  /// called only between tokens, and is valid until the next [Source::compact()].
+  pub fn checkpoint(&self) -> Checkpoint {
+    Checkpoint(self.inputs.iter().map(|input| (input.position, input.location.clone())).collect())
+  }
+
+  /// Moves the cursor back to the `checkpoint`, discarding the entities pushed after it.
+  pub fn rewind(&mut self, checkpoint: Checkpoint) {
+    debug_assert!(self.inputs.len() >= checkpoint.0.len(), "an entity has been popped after the checkpoint");
+    self.inputs.truncate(checkpoint.0.len());
+    for (input, (position, location)) in self.inputs.iter_mut().zip(checkpoint.0) {
+      input.position = position;
+      input.location = location;
+    }
+  }
+
+  /// Discards the characters that have already been read. This should be called only between tokens.
+  pub fn compact(&mut self) {
+    let input = self.input_mut();
+    if input.position >= Self::CHUNK_SIZE {
+      input.buffer.drain(..input.position);
+      input.normalized -= input.position;
+      let position = input.position;
+      input.shortened.retain_mut(|(p, _, _)| match p.checked_sub(position) {
+        Some(shifted) => {
+          *p = shifted;
+          true
+        }
+        None => false,
+      });
+      input.position = 0;
+      let passed = input.location.char_offset.saturating_sub(input.deltas_base) as usize;
+      let passed = passed.min(input.deltas.len().saturating_sub(1));
+      input.deltas.drain(..passed);
+      input.deltas_base += passed as u64;
+    }
+  }
+
+  /// Reads the next chunk from the underlying reader. `false` is returned if the stream has reached its end.
+  ///
+  /// The chunk is stored only up to an illegal character, if any, and the error is raised when the parser requires
+  /// characters beyond it.
+  fn fill_more(&mut self) -> Result<bool> {
+    let Source { chars, inputs, xml11 } = self;
+    let input = inputs.last_mut().expect("the document entity must remain at the bottom");
+    if let Some(ch) = input.illegal {
+      let location = input.location_at(input.buffer.len());
+      let message = format!("illegal character U+{:04X}", ch as u32);
+      let system_id = input.system_id.clone();
+      return Err(Error::Parse { code: ErrorCode::InvalidChar, location, system_id, message });
+    }
+    let reader = match (&mut input.reader, input.eof) {
+      (Some(reader), false) => reader,
+      _ => return Ok(false),
+    };
+    let len = reader.read(chars)?;
+    if len == 0 {
+      input.eof = true;
+      input.normalize(*xml11);
+      return Ok(false);
+    }
+    let lengths = reader.byte_lengths();
+    if lengths.is_some() || !input.deltas.is_empty() {
+      if input.deltas.is_empty() {
+        input.deltas_base = input.read as u64;
+        input.deltas.push(0);
+      }
+      let mut delta = *input.deltas.last().unwrap();
+      for (i, ch) in chars[..len].iter().enumerate() {
+        delta += lengths.map(|lengths| lengths[i] as i64 - ch.len_utf8() as i64).unwrap_or(0);
+        input.deltas.push(delta);
+      }
+    }
+    input.read += len;
+    let start = input.buffer.len();
+    input.buffer.extend(&chars[..len]);
+    if let Some(i) = scan::find_illegal_char(&input.buffer[start..], *xml11 == Some(true)) {
+      input.illegal = input.buffer[start + i..].chars().next();
+      input.buffer.truncate(start + i);
+    }
+    input.normalize(*xml11);
+    Ok(true)
+  }
+
+  /// Makes sure that at least `len` bytes are available after the cursor unless the stream has reached its end. A
+  /// transparent entity that has been read to the end is popped here.
+  fn fill(&mut self, len: usize) -> Result<bool> {
+    while self.input().normalized - self.input().position < len {
+      if !self.fill_more()? {
+        if self.rest().is_empty() && self.input().transparent && self.inputs.len() > 1 {
+          self.inputs.pop();
+          continue;
+        }
+        return Ok(false);
+      }
+    }
+    Ok(true)
+  }
+
+  /// Returns the unread part of the buffer whose line ends have been translated.
+  fn rest(&self) -> &str {
+    let input = self.input();
+    &input.buffer[input.position..input.normalized]
+  }
+
+  pub fn is_eof(&mut self) -> Result<bool> {
+    Ok(!self.fill(1)?)
+  }
+
+  pub fn peek(&mut self) -> Result<Option<char>> {
+    self.fill(1)?;
+    Ok(self.rest().chars().next())
+  }
+
+  /// Whether the cursor is positioned at `prefix`. Characters are read only as long as they match it.
+  pub fn starts_with(&mut self, prefix: &str) -> Result<bool> {
+    loop {
+      let rest = self.rest();
+      if rest.len() >= prefix.len() || !prefix.starts_with(rest) {
+        return Ok(rest.starts_with(prefix));
+      }
+      if !self.fill(rest.len() + 1)? {
+        return Ok(false);
+      }
+    }
+  }
+
+  /// Returns the `n`-th (0-origin) character after the cursor without consuming it.
+  pub fn peek_nth(&mut self, n: usize) -> Result<Option<char>> {
+    loop {
+      if let Some(ch) = self.rest().chars().nth(n) {
+        return Ok(Some(ch));
+      }
+      if !self.fill(self.rest().len() + 1)? {
+        return Ok(None);
+      }
+    }
+  }
+
+  /// Returns up to `len` bytes after the cursor in the current entity without consuming them, for guessing how to
+  /// repair an error. Unlike the other methods, this stops quietly before an illegal character.
+  pub fn lookahead(&mut self, len: usize) -> Result<&str> {
+    while self.input().normalized - self.input().position < len && self.input().illegal.is_none() {
+      if !self.fill_more()? {
+        break;
+      }
+    }
+    let rest = self.rest();
+    let mut end = rest.len().min(len);
+    while !rest.is_char_boundary(end) {
+      end -= 1;
+    }
+    Ok(&rest[..end])
+  }
+
+  pub fn next_char(&mut self) -> Result<Option<char>> {
+    let ch = self.peek()?;
+    if let Some(ch) = ch {
+      self.advance(ch.len_utf8());
+    }
+    Ok(ch)
+  }
+
+  /// Consumes the specified string if the cursor is positioned at it.
+  pub fn skip_if(&mut self, prefix: &str) -> Result<bool> {
+    if self.starts_with(prefix)? {
+      self.advance(prefix.len());
+      Ok(true)
+    } else {
+      Ok(false)
+    }
+  }
+
+  /// Consumes characters while they satisfy the specified condition, and appends them to `out`.
+  pub fn read_while<F: Fn(char) -> bool>(&mut self, f: F, out: &mut String) -> Result<usize> {
+    let mut count = 0;
+    loop {
+      let rest = self.rest();
+      let len = rest.find(|ch| !f(ch)).unwrap_or(rest.len());
+      count += len;
+      out.push_str(&rest[..len]);
+      self.advance(len);
+      if !self.rest().is_empty() || !self.fill(1)? {
+        return Ok(count);
+      }
+    }
+  }
+
+  /// Consumes characters up to any of the ASCII `delimiters`, and appends them to `out`. The bytes are scanned a word
+  /// at a time, which is much faster than [Source::read_while()] for long runs of characters.
+  pub fn read_until_any(&mut self, delimiters: &[u8], out: &mut String) -> Result<()> {
+    debug_assert!(delimiters.is_ascii());
+    loop {
+      let rest = self.rest();
+      let len = scan::find_byte(rest.as_bytes(), delimiters).unwrap_or(rest.len());
+      out.push_str(&rest[..len]);
+      self.advance(len);
+      if !self.rest().is_empty() || !self.fill(1)? {
+        return Ok(());
+      }
+    }
+  }
+
+  /// Consumes characters up to the specified delimiter and appends them to `out`. The delimiter itself is consumed
+  /// but not appended. `false` is returned if the stream has reached its end without the delimiter.
+  pub fn read_until(&mut self, delimiter: &str, out: &mut String) -> Result<bool> {
+    let mut from = self.input().position;
+    loop {
+      let input = self.input();
+      if let Some(i) = input.buffer[from..input.normalized].find(delimiter) {
+        let end = from + i;
+        out.push_str(&input.buffer[input.position..end]);
+        self.advance(end - input.position + delimiter.len());
+        return Ok(true);
+      }
+      from = std::cmp::max(input.position, input.normalized.saturating_sub(delimiter.len()));
+      while !input.buffer.is_char_boundary(from) {
+        from -= 1;
+      }
+      if !self.fill_more()? {
+        out.push_str(self.rest());
+        self.advance(self.rest().len());
+        return Ok(false);
+      }
+    }
+  }
+
+  /// Moves the cursor forward by `len` bytes, counting lines and columns of the characters passed. The location
+  /// doesn't move in an internal entity.
+  fn advance(&mut self, len: usize) {
+    let input = self.input_mut();
+    let end = input.position + len;
+    if input.reader.is_some() {
+      input.location = input.location_at(end);
+    }
+    input.position = end;
+  }
+}
+
+impl Input {
+  /// The location of the position `end` in the buffer, which must not precede the cursor.
+  fn location_at(&self, end: usize) -> Location {
+    let mut location = self.location.clone();
+    forward(&mut location, &self.buffer[self.position..end]);
+    let from = self.shortened.partition_point(|(position, _, _)| *position < self.position);
+    for (_, bytes, chars) in self.shortened[from..].iter().take_while(|(position, _, _)| *position < end) {
+      location.byte_offset += bytes;
+      location.char_offset += chars;
+    }
+    if !self.deltas.is_empty() {
+      let delta = self.delta_at(location.char_offset) - self.delta_at(self.location.char_offset);
+      location.byte_offset = location.byte_offset.saturating_add_signed(delta);
+    }
+    location
+  }
+
+  /// The difference between the numbers of bytes in the stream and in UTF-8 of the characters before `char_offset`.
+  fn delta_at(&self, char_offset: u64) -> i64 {
+    match char_offset.checked_sub(self.deltas_base) {
+      Some(i) => self.deltas.get(i as usize).or(self.deltas.last()).copied().unwrap_or(0),
+      None => 0,
+    }
+  }
+
+  /// Translates the line ends in the buffer after the normalized head. `#xD` at the end of the buffer is left as it
+  /// is until the next character is read, and so is the part from the first `#x85` or `#x2028` if `xml11` is `None`.
+  fn normalize(&mut self, xml11: Option<bool>) {
+    // the candidates are CR and the first bytes of NEL and LS
+    let special = |ch: char| ch == '\r' || (xml11 != Some(false) && matches!(ch, '\u{85}' | '\u{2028}'));
+    let candidates: &[u8] = if xml11 == Some(false) { b"\r" } else { b"\r\xC2\xE2" };
+    let mut from = self.normalized;
+    loop {
+      match scan::find_byte(&self.buffer.as_bytes()[from..], candidates) {
+        Some(i) if self.buffer[from + i..].starts_with(special) => {
+          self.normalized = from + i;
+          break;
+        }
+        Some(i) => from += i + 1,
+        None => {
+          self.normalized = self.buffer.len();
+          return;
+        }
+      }
+    }
+    let tail = &self.buffer[self.normalized..];
+    let mut translated = String::with_capacity(tail.len());
+    let mut end = tail.len();
+    let mut chars = tail.char_indices().peekable();
+    while let Some((i, ch)) = chars.next() {
+      let next = chars.peek().map(|(_, next)| *next);
+      match (ch, next, xml11) {
+        ('\r', None, _) if !self.eof && self.illegal.is_none() => {
+          end = i;
+          break;
+        }
+        ('\r', Some('\u{85}'), None) | ('\u{85}' | '\u{2028}', _, None) => {
+          end = i;
+          break;
+        }
+        ('\r', Some(next @ ('\n' | '\u{85}')), _) if next == '\n' || xml11 == Some(true) => {
+          chars.next();
+          let position = self.normalized + translated.len();
+          self.shortened.push((position, next.len_utf8() as u64, 1));
+          translated.push('\n');
+        }
+        ('\r', _, _) => translated.push('\n'),
+        (ch @ ('\u{85}' | '\u{2028}'), _, Some(true)) => {
+          let position = self.normalized + translated.len();
+          self.shortened.push((position, ch.len_utf8() as u64 - 1, 0));
+          translated.push('\n');
+        }
+        (ch, _, _) => translated.push(ch),
+      }
+    }
+    let start = self.normalized;
+    self.buffer.replace_range(start..start + end, &translated);
+    self.normalized += translated.len();
+  }
+}
+
+/// Moves the `location` forward over the `text`.
+pub(crate) fn forward(location: &mut Location, text: &str) {
+  location.byte_offset += text.len() as u64;
+  match text.rfind('\n') {
+    Some(i) => {
+      let (head, tail) = text.split_at(i + 1);
+      location.line_number += scan::count_byte(head.as_bytes(), b'\n') as u64;
+      location.column_number = scan::count_chars(tail) as u64;
+      location.char_offset += scan::count_chars(head) as u64 + location.column_number;
+    }
+    None => {
+      let count = scan::count_chars(text) as u64;
+      location.column_number += count;
+      location.char_offset += count;
+    }
+  }
+}
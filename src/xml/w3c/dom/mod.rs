@@ -19,7 +19,16 @@
 //!
 //! Note that all indices in the W3C specification are **0-origin**.
 //!
+//! ## Ownership
+//!
+//! Nodes are shared by reference counting. The factory methods and accessors return nodes as `Rc` or [NodeRef], and
+//! the methods that modify a node take `&self`, so that a node can be modified through any of its references. Values
+//! that may be modified, such as [Node::node_value()] and [CharacterData::data()], are returned as owned `String`s
+//! rather than borrowed. Nullable namespace URIs, qualified names, document types and reference children are
+//! `Option`s, so that [DOMImplementation::create_document()] can create a document without document element.
+//!
 
+use std::any::Any;
 use std::rc::Rc;
 use thiserror::Error;
 
@@ -28,7 +37,7 @@ mod test;
 
 /// [Definition group *NodeType*](https://www.w3.org/TR/DOM-Level-3-Core/core.html#ID-1841493061)
 ///
-#[derive(PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum NodeType {
   ElementNode = 1,
   AttributeNode = 2,
@@ -76,45 +85,52 @@ pub trait DOMImplementation: Sized {
   type Entity: Entity<Self>;
 
   fn has_feature(&self, feature: &str, version: Option<&str>) -> bool;
-  fn create_document_type(&self, qualified_name: &str, public_id: &str, system_id: &str) -> Result<Self::DocumentType>;
-  fn create_document(&self, namespace_uri: &str, qualified_name: &str, doctype: Self::DocumentType) -> Self::Document;
+  fn create_document_type(
+    &self, qualified_name: &str, public_id: &str, system_id: &str,
+  ) -> Result<Rc<Self::DocumentType>>;
+  fn create_document(
+    &self, namespace_uri: Option<&str>, qualified_name: Option<&str>, doctype: Option<Rc<Self::DocumentType>>,
+  ) -> Result<Rc<Self::Document>>;
   fn get_feature(&self, feature: &str, version: Option<&str>) -> Option<DOMObject>;
 }
 
 /// [Interface *DocumentFragment*](https://www.w3.org/TR/DOM-Level-2-Core/#core-ID-B63ED1A3)
 ///
 pub trait DocumentFragment<IMPL: DOMImplementation>: Node<IMPL> {
-  fn clone_document_fragment(&self, deep: bool) -> Self;
+  fn clone_document_fragment(&self, deep: bool) -> Rc<Self>;
 }
 
 /// [Interface *Document*](https://www.w3.org/TR/DOM-Level-2-Core/#core-i-Document)
 ///
 pub trait Document<IMPL: DOMImplementation>: Node<IMPL> {
-  fn doctype(&self) -> &IMPL::DocumentType;
+  fn doctype(&self) -> Option<Rc<IMPL::DocumentType>>;
   fn implementation(&self) -> &IMPL;
-  fn document_element(&self) -> &IMPL::Element;
-  fn create_element(&self, tag_name: &str) -> Result<IMPL::Element>;
-  fn create_document_fragment(&self) -> IMPL::DocumentFragment;
-  fn create_text_node(&self, data: &str) -> IMPL::Text;
-  fn create_comment(data: &str) -> IMPL::Comment;
-  fn create_cdata_section(data: &str) -> IMPL::CDATASection;
-  fn create_processing_instruction(&self, target: &str, data: &str) -> IMPL::ProcessingInstruction;
-  fn create_attribute(&self, name: &str) -> Result<IMPL::Attr>;
-  fn create_entity_reference(&self, name: &str) -> Result<IMPL::EntityReference>;
+  fn document_element(&self) -> Option<Rc<IMPL::Element>>;
+  fn create_element(&self, tag_name: &str) -> Result<Rc<IMPL::Element>>;
+  fn create_document_fragment(&self) -> Rc<IMPL::DocumentFragment>;
+  fn create_text_node(&self, data: &str) -> Rc<IMPL::Text>;
+  fn create_comment(&self, data: &str) -> Rc<IMPL::Comment>;
+  fn create_cdata_section(&self, data: &str) -> Result<Rc<IMPL::CDATASection>>;
+  fn create_processing_instruction(&self, target: &str, data: &str) -> Result<Rc<IMPL::ProcessingInstruction>>;
+  fn create_attribute(&self, name: &str) -> Result<Rc<IMPL::Attr>>;
+  fn create_entity_reference(&self, name: &str) -> Result<Rc<IMPL::EntityReference>>;
   fn get_elements_by_tag_name(&self, tagname: &str) -> IMPL::NodeList;
-  fn import_node(&mut self, imported_node: NodeRef<IMPL>, deep: bool) -> NodeRef<IMPL>;
-  fn create_element_ns(&self, namespace_uri: &str, qualified_name: &str) -> Result<IMPL::Element>;
-  fn create_attribute_ns(&self, namespace_uri: &str, qualified_name: &str) -> Result<IMPL::Attr>;
+  fn import_node(&self, imported_node: NodeRef<IMPL>, deep: bool) -> Result<NodeRef<IMPL>>;
+  fn create_element_ns(&self, namespace_uri: Option<&str>, qualified_name: &str) -> Result<Rc<IMPL::Element>>;
+  fn create_attribute_ns(&self, namespace_uri: Option<&str>, qualified_name: &str) -> Result<Rc<IMPL::Attr>>;
   fn get_elements_by_tag_name_ns(&self, namespace_uri: &str, local_name: &str) -> IMPL::NodeList;
-  fn get_element_by_id(&self, element_id: &str) -> Option<IMPL::Element>;
+  fn get_element_by_id(&self, element_id: &str) -> Option<Rc<IMPL::Element>>;
 
-  fn clone_document(&self, deep: bool) -> Self;
+  fn clone_document(&self, deep: bool) -> Rc<Self>;
 }
 
 /// [Interface *Node*](https://www.w3.org/TR/DOM-Level-2-Core/#core-ID-1950641247)
 ///
+/// Nodes are shared by [NodeRef]s, so the methods that modify a node take `&self`. The values that may be modified
+/// are returned as owned values.
+///
 pub trait Node<IMPL: DOMImplementation> {
-  fn attributes(&self) -> Option<IMPL::NamedNodeMap>;
+  fn attributes(&self) -> Option<&IMPL::NamedNodeMap>;
   fn base_uri(&self) -> Option<String>;
   fn child_nodes(&self) -> &IMPL::NodeList;
   fn first_child(&self) -> Option<NodeRef<IMPL>>;
@@ -124,37 +140,36 @@ pub trait Node<IMPL: DOMImplementation> {
   fn next_sibling(&self) -> Option<NodeRef<IMPL>>;
   fn node_name(&self) -> &str;
   fn node_type(&self) -> NodeType;
-  fn node_value(&self) -> Result<Option<&str>>;
-  fn set_node_value(&mut self, value: &str) -> Result<()>;
-  fn owner_document(&self) -> Option<&IMPL::Document>;
+  fn node_value(&self) -> Result<Option<String>>;
+  fn set_node_value(&self, value: &str) -> Result<()>;
+  fn owner_document(&self) -> Option<Rc<IMPL::Document>>;
   fn parent_node(&self) -> Option<NodeRef<IMPL>>;
   fn prefix(&self) -> Option<String>;
-  fn set_prefix(&self, prefix:Option<String>) -> Result<()>;
+  fn set_prefix(&self, prefix: Option<String>) -> Result<()>;
   fn previous_sibling(&self) -> Option<NodeRef<IMPL>>;
   fn text_content(&self) -> Option<String>;
   fn set_text_content(&self, text_content: &str) -> Result<()>;
 
-  fn append_child(&mut self, new_child: NodeRef<IMPL>) -> Result<&NodeRef<IMPL>>;
-  // fn clone_node(deep: bool) -> Self;
-  fn compare_document_position(&self, other: &NodeRef<IMPL>) -> DocumentPosition {
+  fn append_child(&self, new_child: NodeRef<IMPL>) -> Result<NodeRef<IMPL>>;
+  fn compare_document_position(&self, _other: &NodeRef<IMPL>) -> DocumentPosition {
     todo!()
   }
   fn get_feature(&self, feature: &str, version: Option<&str>) -> Option<DOMObject>;
   fn get_user_data(&self, key: &str) -> Option<DOMUserData>;
   fn has_attributes(&self) -> bool;
   fn has_child_nodes(&self) -> bool;
-  fn insert_before(&mut self, new_child: NodeRef<IMPL>, ref_child: NodeRef<IMPL>) -> Result<NodeRef<IMPL>>;
-  fn is_default_namespace(&self, namespace_uri: &str) -> bool;
+  fn insert_before(&self, new_child: NodeRef<IMPL>, ref_child: Option<&NodeRef<IMPL>>) -> Result<NodeRef<IMPL>>;
+  fn is_default_namespace(&self, namespace_uri: Option<&str>) -> bool;
   fn is_equal_node(&self, arg: &NodeRef<IMPL>) -> bool;
   fn is_same_node(&self, other: &NodeRef<IMPL>) -> bool;
   fn is_supported(&self, feature: &str, version: &str) -> bool;
-  fn lookup_namespace_uri(&self, prefix: &str) -> Option<&str>;
-  fn lookup_prefix(&self, namespace_uri: &str) -> Option<&str>;
-  fn normalize(&mut self);
-  fn remove_child(&mut self, old_child: &NodeRef<IMPL>) -> Result<NodeRef<IMPL>>;
-  fn replace_child(&mut self, new_child: NodeRef<IMPL>, old_child: NodeRef<IMPL>) -> Result<NodeRef<IMPL>>;
+  fn lookup_namespace_uri(&self, prefix: Option<&str>) -> Option<String>;
+  fn lookup_prefix(&self, namespace_uri: &str) -> Option<String>;
+  fn normalize(&self);
+  fn remove_child(&self, old_child: &NodeRef<IMPL>) -> Result<NodeRef<IMPL>>;
+  fn replace_child(&self, new_child: NodeRef<IMPL>, old_child: &NodeRef<IMPL>) -> Result<NodeRef<IMPL>>;
   fn set_user_data(
-    &mut self, key: &str, data: DOMUserData, handler: Box<dyn UserDataHandler<IMPL>>,
+    &self, key: &str, data: Option<DOMUserData>, handler: Option<Box<dyn UserDataHandler<IMPL>>>,
   ) -> Option<DOMUserData>;
 }
 
@@ -171,23 +186,27 @@ pub trait NamedNodeMap<IMPL: DOMImplementation> {
   fn length(&self) -> usize;
 
   fn get_named_item(&self, name: &str) -> Option<NodeRef<IMPL>>;
-  fn get_named_item_ns(&self, namespace_uri: &str, local_name: &str) -> Result<Option<NodeRef<IMPL>>>;
+  fn get_named_item_ns(&self, namespace_uri: Option<&str>, local_name: &str) -> Result<Option<NodeRef<IMPL>>>;
   fn item(&self, index: usize) -> Option<NodeRef<IMPL>>;
-  fn remove_named_item(&mut self, name: &str) -> Result<NodeRef<IMPL>>;
-  fn remove_named_item_ns(&mut self, namespace_uri: &str, local_name: &str) -> Result<NodeRef<IMPL>>;
-  fn set_named_item(&mut self, arg: NodeRef<IMPL>) -> Result<Option<NodeRef<IMPL>>>;
-  fn set_named_item_ns(&mut self, arg: NodeRef<IMPL>) -> Result<Option<NodeRef<IMPL>>>;
+  fn remove_named_item(&self, name: &str) -> Result<NodeRef<IMPL>>;
+  fn remove_named_item_ns(&self, namespace_uri: Option<&str>, local_name: &str) -> Result<NodeRef<IMPL>>;
+  fn set_named_item(&self, arg: NodeRef<IMPL>) -> Result<Option<NodeRef<IMPL>>>;
+  fn set_named_item_ns(&self, arg: NodeRef<IMPL>) -> Result<Option<NodeRef<IMPL>>>;
 }
 
 /// [Interface *CharacterData*](https://www.w3.org/TR/DOM-Level-2-Core/#core-ID-FF21A306)
 ///
+/// Note that offsets and counts are in characters, not in UTF-16 code units.
+///
 pub trait CharacterData<IMPL: DOMImplementation>: Node<IMPL> {
-  fn data(&self) -> &str;
-  fn substring_data(&self, offset: usize, count: usize) -> String;
-  fn append_data(&mut self, arg: &str);
-  fn insert_data(&mut self, offset: usize, arg: &str) -> Result<()>;
-  fn delete_data(&mut self, offset: usize, count: usize) -> Result<()>;
-  fn replace_data(&mut self, offset: usize, count: usize) -> Result<()>;
+  fn data(&self) -> String;
+  fn set_data(&self, data: &str) -> Result<()>;
+  fn length(&self) -> usize;
+  fn substring_data(&self, offset: usize, count: usize) -> Result<String>;
+  fn append_data(&self, arg: &str) -> Result<()>;
+  fn insert_data(&self, offset: usize, arg: &str) -> Result<()>;
+  fn delete_data(&self, offset: usize, count: usize) -> Result<()>;
+  fn replace_data(&self, offset: usize, count: usize, arg: &str) -> Result<()>;
 }
 
 /// [Interface *Attr*](https://www.w3.org/TR/DOM-Level-2-Core/#core-ID-637646024)
@@ -195,54 +214,54 @@ pub trait CharacterData<IMPL: DOMImplementation>: Node<IMPL> {
 pub trait Attr<IMPL: DOMImplementation>: Node<IMPL> {
   fn name(&self) -> &str;
   fn specified(&self) -> bool;
-  fn value(&self) -> &str;
-  fn set_value(&mut self, value: &str) -> Result<()>;
-  fn owner_element(&self) -> &IMPL::Element;
+  fn value(&self) -> String;
+  fn set_value(&self, value: &str) -> Result<()>;
+  fn owner_element(&self) -> Option<Rc<IMPL::Element>>;
 
-  fn clone_attr(&self, deep: bool) -> Self;
+  fn clone_attr(&self, deep: bool) -> Rc<Self>;
 }
 
 /// [Interface *Element*](https://www.w3.org/TR/DOM-Level-2-Core/#core-ID-745549614)
 ///
 pub trait Element<IMPL: DOMImplementation>: Node<IMPL> {
   fn tag_name(&self) -> &str;
-  fn get_attribute(&self, name: &str) -> Option<&str>;
-  fn set_attribute(&mut self, name: &str, value: &str) -> Result<()>;
-  fn remove_attribute(&mut self, name: &str) -> Result<()>;
-  fn get_attribute_node(&self, name: &str) -> Option<IMPL::Attr>;
-  fn set_attribute_node(&mut self, new_attr: IMPL::Attr) -> Result<Option<IMPL::Attr>>;
-  fn remove_attribute_node(&mut self, old_attr: IMPL::Attr) -> Result<Option<IMPL::Attr>>;
+  fn get_attribute(&self, name: &str) -> Option<String>;
+  fn set_attribute(&self, name: &str, value: &str) -> Result<()>;
+  fn remove_attribute(&self, name: &str) -> Result<()>;
+  fn get_attribute_node(&self, name: &str) -> Option<Rc<IMPL::Attr>>;
+  fn set_attribute_node(&self, new_attr: Rc<IMPL::Attr>) -> Result<Option<Rc<IMPL::Attr>>>;
+  fn remove_attribute_node(&self, old_attr: &Rc<IMPL::Attr>) -> Result<Rc<IMPL::Attr>>;
   fn get_elements_by_tag_name(&self, name: &str) -> IMPL::NodeList;
-  fn get_attribute_ns(&self, namespace_uri: &str, local_name: &str) -> Option<&str>;
-  fn set_attribute_ns(&self, namespace_uri: &str, local_name: &str, value: &str) -> Result<()>;
-  fn remove_attribute_ns(&mut self, namespace_uri: &str, local_name: &str) -> Result<()>;
-  fn get_attribute_node_ns(&self, namespace_uri: &str, local_name: &str) -> Option<&IMPL::Attr>;
-  fn set_attribute_node_ns(&mut self, new_attr: IMPL::Attr) -> Result<Option<IMPL::Attr>>;
+  fn get_attribute_ns(&self, namespace_uri: Option<&str>, local_name: &str) -> Option<String>;
+  fn set_attribute_ns(&self, namespace_uri: Option<&str>, qualified_name: &str, value: &str) -> Result<()>;
+  fn remove_attribute_ns(&self, namespace_uri: Option<&str>, local_name: &str) -> Result<()>;
+  fn get_attribute_node_ns(&self, namespace_uri: Option<&str>, local_name: &str) -> Option<Rc<IMPL::Attr>>;
+  fn set_attribute_node_ns(&self, new_attr: Rc<IMPL::Attr>) -> Result<Option<Rc<IMPL::Attr>>>;
   fn get_elements_by_tag_name_ns(&self, namespace_uri: &str, local_name: &str) -> IMPL::NodeList;
   fn has_attribute(&self, name: &str) -> bool;
-  fn has_attribute_ns(namespace_uri: &str, local_name: &str) -> bool;
+  fn has_attribute_ns(&self, namespace_uri: Option<&str>, local_name: &str) -> bool;
 
-  fn clone_element(&self, deep: bool) -> Self;
+  fn clone_element(&self, deep: bool) -> Rc<Self>;
 }
 
 /// [Interface *Text*](https://www.w3.org/TR/DOM-Level-2-Core/#core-ID-1312295772)
 ///
 pub trait Text<IMPL: DOMImplementation>: CharacterData<IMPL> {
-  fn split_text(&self, offset: usize) -> Result<IMPL::Text>;
+  fn split_text(&self, offset: usize) -> Result<Rc<Self>>;
 
-  fn clone_text(&self, deep: bool) -> Self;
+  fn clone_text(&self, deep: bool) -> Rc<Self>;
 }
 
 /// [Interface *Text*](https://www.w3.org/TR/DOM-Level-2-Core/#core-ID-1728279322)
 ///
 pub trait Comment<IMPL: DOMImplementation>: CharacterData<IMPL> {
-  fn clone_comment(&self, deep: bool) -> Self;
+  fn clone_comment(&self, deep: bool) -> Rc<Self>;
 }
 
 /// [Interface *CDATASection*](https://www.w3.org/TR/DOM-Level-2-Core/#core-ID-667469212)
 ///
 pub trait CDATASection<IMPL: DOMImplementation>: Text<IMPL> {
-  fn clone_cdata_section(&self, deep: bool) -> Self;
+  fn clone_cdata_section(&self, deep: bool) -> Rc<Self>;
 }
 
 /// [Interface *DocumentType*](https://www.w3.org/TR/DOM-Level-2-Core/#core-ID-412266927)
@@ -250,51 +269,54 @@ pub trait CDATASection<IMPL: DOMImplementation>: Text<IMPL> {
 pub trait DocumentType<IMPL: DOMImplementation>: Node<IMPL> {
   fn name(&self) -> &str;
   fn entities(&self) -> &IMPL::NamedNodeMap;
-  fn notations(&self) -> IMPL::NamedNodeMap;
-  fn public_id(&self) -> &str;
-  fn system_id(&self) -> &str;
+  fn notations(&self) -> &IMPL::NamedNodeMap;
+  fn public_id(&self) -> Option<&str>;
+  fn system_id(&self) -> Option<&str>;
   fn internal_subset(&self) -> Option<&str>;
 
-  fn clone_document_type(&self, deep: bool) -> Self;
+  fn clone_document_type(&self, deep: bool) -> Rc<Self>;
 }
 
 /// [Interface *Notation*](https://www.w3.org/TR/DOM-Level-2-Core/#core-ID-5431D1B9)
 ///
 pub trait Notation<IMPL: DOMImplementation>: Node<IMPL> {
-  fn public_id(&self) -> &str;
-  fn system_id(&self) -> &str;
+  fn public_id(&self) -> Option<&str>;
+  fn system_id(&self) -> Option<&str>;
 
-  fn clone_notation(&self, deep: bool) -> Self;
+  fn clone_notation(&self, deep: bool) -> Rc<Self>;
 }
 
 /// [Interface *Entity*](https://www.w3.org/TR/DOM-Level-2-Core/#core-ID-527DCFF2)
 ///
 pub trait Entity<IMPL: DOMImplementation>: Node<IMPL> {
-  fn public_id(&self) -> &str;
-  fn system_id(&self) -> &str;
-  fn notation_name(&self) -> &str;
+  fn public_id(&self) -> Option<&str>;
+  fn system_id(&self) -> Option<&str>;
+  fn notation_name(&self) -> Option<&str>;
 
-  fn clone_entity(&self, deep: bool) -> Self;
+  fn clone_entity(&self, deep: bool) -> Rc<Self>;
 }
 
 /// [Interface *EntityReference*](https://www.w3.org/TR/DOM-Level-2-Core/#core-ID-11C98490)
 ///
 pub trait EntityReference<IMPL: DOMImplementation>: Node<IMPL> {
-  fn clone_entity_reference(&self, deep: bool) -> Self;
+  fn clone_entity_reference(&self, deep: bool) -> Rc<Self>;
 }
 
 /// [Interface *ProcessingInstruction*](https://www.w3.org/TR/DOM-Level-2-Core/#core-ID-1004215813)
 ///
 pub trait ProcessingInstruction<IMPL: DOMImplementation>: Node<IMPL> {
   fn target(&self) -> &str;
-  fn data(&self) -> &str;
-  fn set_data(&mut self, data: &str) -> Result<()>;
+  fn data(&self) -> String;
+  fn set_data(&self, data: &str) -> Result<()>;
 
-  fn clone_processing_instruction(&self, deep: bool) -> Self;
+  fn clone_processing_instruction(&self, deep: bool) -> Rc<Self>;
 }
 
+/// A reference to a node in the tree. Cloning a `NodeRef` shares the same node; use [NodeRef::clone_node()] to
+/// duplicate the node itself.
+///
 pub enum NodeRef<IMPL: DOMImplementation> {
-  DocumentFragment(IMPL::DocumentFragment),
+  DocumentFragment(Rc<IMPL::DocumentFragment>),
   Document(Rc<IMPL::Document>),
   Attr(Rc<IMPL::Attr>),
   Element(Rc<IMPL::Element>),
@@ -312,48 +334,82 @@ impl<IMPL: DOMImplementation> NodeRef<IMPL> {
   pub fn clone_node(&self, deep: bool) -> Self {
     match self {
       NodeRef::DocumentFragment(n) => NodeRef::DocumentFragment(n.clone_document_fragment(deep)),
-      NodeRef::Document(n) => NodeRef::Document(Rc::new(n.clone_document(deep))),
-      NodeRef::Attr(n) => NodeRef::Attr(Rc::new(n.clone_attr(deep))),
-      NodeRef::Element(n) => NodeRef::Element(Rc::new(n.clone_element(deep))),
-      NodeRef::Text(n) => NodeRef::Text(Rc::new(n.clone_text(deep))),
-      NodeRef::Comment(n) => NodeRef::Comment(Rc::new(n.clone_comment(deep))),
-      NodeRef::CDATASection(n) => NodeRef::CDATASection(Rc::new(n.clone_cdata_section(deep))),
-      NodeRef::DocumentType(n) => NodeRef::DocumentType(Rc::new(n.clone_document_type(deep))),
-      NodeRef::Notation(n) => NodeRef::Notation(Rc::new(n.clone_notation(deep))),
-      NodeRef::Entity(n) => NodeRef::Entity(Rc::new(n.clone_entity(deep))),
-      NodeRef::EntityReference(n) => NodeRef::EntityReference(Rc::new(n.clone_entity_reference(deep))),
-      NodeRef::ProcessingInstruction(n) => {
-        NodeRef::ProcessingInstruction(Rc::new(n.clone_processing_instruction(deep)))
-      }
+      NodeRef::Document(n) => NodeRef::Document(n.clone_document(deep)),
+      NodeRef::Attr(n) => NodeRef::Attr(n.clone_attr(deep)),
+      NodeRef::Element(n) => NodeRef::Element(n.clone_element(deep)),
+      NodeRef::Text(n) => NodeRef::Text(n.clone_text(deep)),
+      NodeRef::Comment(n) => NodeRef::Comment(n.clone_comment(deep)),
+      NodeRef::CDATASection(n) => NodeRef::CDATASection(n.clone_cdata_section(deep)),
+      NodeRef::DocumentType(n) => NodeRef::DocumentType(n.clone_document_type(deep)),
+      NodeRef::Notation(n) => NodeRef::Notation(n.clone_notation(deep)),
+      NodeRef::Entity(n) => NodeRef::Entity(n.clone_entity(deep)),
+      NodeRef::EntityReference(n) => NodeRef::EntityReference(n.clone_entity_reference(deep)),
+      NodeRef::ProcessingInstruction(n) => NodeRef::ProcessingInstruction(n.clone_processing_instruction(deep)),
     }
   }
 
   pub fn as_node(&self) -> &dyn Node<IMPL> {
     let node: &dyn Node<IMPL> = match self {
-      NodeRef::DocumentFragment(n) => n,
-      _ => todo!(),
+      NodeRef::DocumentFragment(n) => n.as_ref(),
+      NodeRef::Document(n) => n.as_ref(),
+      NodeRef::Attr(n) => n.as_ref(),
+      NodeRef::Element(n) => n.as_ref(),
+      NodeRef::Text(n) => n.as_ref(),
+      NodeRef::Comment(n) => n.as_ref(),
+      NodeRef::CDATASection(n) => n.as_ref(),
+      NodeRef::DocumentType(n) => n.as_ref(),
+      NodeRef::Notation(n) => n.as_ref(),
+      NodeRef::Entity(n) => n.as_ref(),
+      NodeRef::EntityReference(n) => n.as_ref(),
+      NodeRef::ProcessingInstruction(n) => n.as_ref(),
     };
     node
   }
 
-  pub fn as_mut_node(&mut self) -> &mut dyn Node<IMPL> {
-    let node: &mut dyn Node<IMPL> = match self {
-      NodeRef::DocumentFragment(n) => n,
-      _ => todo!(),
-    };
-    node
+  /// Returns `true` if the two references point to the same node.
+  pub fn ptr_eq(&self, other: &NodeRef<IMPL>) -> bool {
+    match (self, other) {
+      (NodeRef::DocumentFragment(a), NodeRef::DocumentFragment(b)) => Rc::ptr_eq(a, b),
+      (NodeRef::Document(a), NodeRef::Document(b)) => Rc::ptr_eq(a, b),
+      (NodeRef::Attr(a), NodeRef::Attr(b)) => Rc::ptr_eq(a, b),
+      (NodeRef::Element(a), NodeRef::Element(b)) => Rc::ptr_eq(a, b),
+      (NodeRef::Text(a), NodeRef::Text(b)) => Rc::ptr_eq(a, b),
+      (NodeRef::Comment(a), NodeRef::Comment(b)) => Rc::ptr_eq(a, b),
+      (NodeRef::CDATASection(a), NodeRef::CDATASection(b)) => Rc::ptr_eq(a, b),
+      (NodeRef::DocumentType(a), NodeRef::DocumentType(b)) => Rc::ptr_eq(a, b),
+      (NodeRef::Notation(a), NodeRef::Notation(b)) => Rc::ptr_eq(a, b),
+      (NodeRef::Entity(a), NodeRef::Entity(b)) => Rc::ptr_eq(a, b),
+      (NodeRef::EntityReference(a), NodeRef::EntityReference(b)) => Rc::ptr_eq(a, b),
+      (NodeRef::ProcessingInstruction(a), NodeRef::ProcessingInstruction(b)) => Rc::ptr_eq(a, b),
+      _ => false,
+    }
   }
 }
 
 impl<IMPL: DOMImplementation> Clone for NodeRef<IMPL> {
   fn clone(&self) -> Self {
-    self.clone_node(true)
+    match self {
+      NodeRef::DocumentFragment(n) => NodeRef::DocumentFragment(n.clone()),
+      NodeRef::Document(n) => NodeRef::Document(n.clone()),
+      NodeRef::Attr(n) => NodeRef::Attr(n.clone()),
+      NodeRef::Element(n) => NodeRef::Element(n.clone()),
+      NodeRef::Text(n) => NodeRef::Text(n.clone()),
+      NodeRef::Comment(n) => NodeRef::Comment(n.clone()),
+      NodeRef::CDATASection(n) => NodeRef::CDATASection(n.clone()),
+      NodeRef::DocumentType(n) => NodeRef::DocumentType(n.clone()),
+      NodeRef::Notation(n) => NodeRef::Notation(n.clone()),
+      NodeRef::Entity(n) => NodeRef::Entity(n.clone()),
+      NodeRef::EntityReference(n) => NodeRef::EntityReference(n.clone()),
+      NodeRef::ProcessingInstruction(n) => NodeRef::ProcessingInstruction(n.clone()),
+    }
   }
 }
 
 /// [Type Definition *DOMUserData*](https://www.w3.org/TR/DOM-Level-3-Core/core.html#DOMUserData)
 ///
-pub type DOMUserData = (); // FIXME: how to express `any`?
+/// The stored value can be taken out with `Rc::downcast()`.
+///
+pub type DOMUserData = Rc<dyn Any>;
 
 pub enum UserDataHandlerOperationType {
   Cloned,
@@ -365,10 +421,78 @@ pub enum UserDataHandlerOperationType {
 pub trait UserDataHandler<IMPL: DOMImplementation> {
   fn handle(
     &self, operation: UserDataHandlerOperationType, key: &str, data: &DOMUserData, src: &NodeRef<IMPL>,
-    dst: &NodeRef<IMPL>,
+    dst: Option<&NodeRef<IMPL>>,
   );
 }
 
+/// [Interface *DOMConfiguration*](https://www.w3.org/TR/DOM-Level-3-Core/core.html#DOMConfiguration)
+///
+/// The parameters are identified by the names defined in the specification, such as `"comments"` or
+/// `"error-handler"`, which are case-insensitive. [DOMConfiguration::set_parameter()] raises
+/// [DOMException::NotFound] for an unknown name, [DOMException::TypeMismatch] for a value of the wrong type, and
+/// [DOMException::NotSupported] for a value that the implementation doesn't support.
+///
+pub trait DOMConfiguration<IMPL: DOMImplementation> {
+  fn set_parameter(&self, name: &str, value: DOMParameter<IMPL>) -> Result<()>;
+  fn get_parameter(&self, name: &str) -> Result<DOMParameter<IMPL>>;
+  fn can_set_parameter(&self, name: &str, value: &DOMParameter<IMPL>) -> bool;
+  fn parameter_names(&self) -> Vec<String>;
+}
+
+/// The value of a [DOMConfiguration] parameter, which is `DOMUserData` in the specification.
+///
+pub enum DOMParameter<IMPL: DOMImplementation> {
+  Boolean(bool),
+  ErrorHandler(Option<Rc<dyn DOMErrorHandler<IMPL>>>),
+}
+
+impl<IMPL: DOMImplementation> Clone for DOMParameter<IMPL> {
+  fn clone(&self) -> Self {
+    match self {
+      DOMParameter::Boolean(value) => DOMParameter::Boolean(*value),
+      DOMParameter::ErrorHandler(handler) => DOMParameter::ErrorHandler(handler.clone()),
+    }
+  }
+}
+
+/// [Interface *DOMErrorHandler*](https://www.w3.org/TR/DOM-Level-3-Core/core.html#ERROR-Interfaces-DOMErrorHandler)
+///
+pub trait DOMErrorHandler<IMPL: DOMImplementation> {
+  /// Returns `true` if the processing should continue after the `error`, or `false` to stop it.
+  fn handle_error(&self, error: &DOMError<IMPL>) -> bool;
+}
+
+/// [Interface *DOMError*](https://www.w3.org/TR/DOM-Level-3-Core/core.html#ERROR-Interfaces-DOMError)
+///
+pub struct DOMError<IMPL: DOMImplementation> {
+  pub severity: DOMErrorSeverity,
+  pub message: String,
+  /// The type of the error defined in the specification, such as `"wf-invalid-character"`.
+  pub error_type: String,
+  pub related_data: Option<NodeRef<IMPL>>,
+  pub location: DOMLocator<IMPL>,
+}
+
+/// [Definition group *ErrorSeverity*](https://www.w3.org/TR/DOM-Level-3-Core/core.html#ERROR-DOMError-severity)
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DOMErrorSeverity {
+  Warning = 1,
+  Error = 2,
+  FatalError = 3,
+}
+
+/// [Interface *DOMLocator*](https://www.w3.org/TR/DOM-Level-3-Core/core.html#Interfaces-DOMLocator)
+///
+/// The line and column numbers are 1-origin as the specification, and `None` if they are not available.
+///
+pub struct DOMLocator<IMPL: DOMImplementation> {
+  pub line_number: Option<u64>,
+  pub column_number: Option<u64>,
+  pub related_node: Option<NodeRef<IMPL>>,
+  pub uri: Option<String>,
+}
+
 pub type Result<T> = std::result::Result<T, DOMException>;
 
 /// [Exception *DOMException*](https://www.w3.org/TR/DOM-Level-3-Core/core.html#ID-17189187)